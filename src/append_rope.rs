@@ -0,0 +1,278 @@
+use std;
+
+use crlf;
+use rope::Rope;
+use rope_builder::RopeBuilder;
+use str_utils::{count_chars, count_line_breaks};
+use tree::MAX_BYTES;
+
+/// A `Rope` optimized for repeatedly appending small pieces of text, as
+/// in logs and terminal scrollback.
+///
+/// `Rope::insert()` at the end of a `Rope` has to walk down the tree and
+/// update it on every call, and allocates whenever the last leaf fills
+/// up.  An `AppendRope` instead keeps its last chunk of text in a separate
+/// tail buffer, which pushed text is simply copied onto.  Only once the
+/// tail is full is a chunk of it folded into the tree, so most pushes do
+/// no allocation or tree work at all.
+///
+/// Get the text out again with `to_rope()` or `into_rope()`.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::AppendRope;
+/// let mut log = AppendRope::new();
+/// for i in 0..1000 {
+///     log.push_str("line ");
+///     log.push_str(&i.to_string());
+///     log.push_char('\n');
+/// }
+///
+/// assert_eq!(1001, log.len_lines());
+/// assert_eq!("line 999\n", log.to_rope().line(999));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AppendRope {
+    rope: Rope,
+    tail: String,
+}
+
+impl AppendRope {
+    /// Creates an empty `AppendRope`.
+    #[inline]
+    pub fn new() -> Self {
+        AppendRope::with_chunk_size(MAX_BYTES)
+    }
+
+    /// Creates an empty `AppendRope` with the given target chunk size, in
+    /// bytes.
+    ///
+    /// See [`Rope::with_chunk_size()`](struct.Rope.html#method.with_chunk_size)
+    /// for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is less than 8.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        AppendRope {
+            rope: Rope::with_chunk_size(chunk_size),
+            tail: String::with_capacity(chunk_size),
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Informational methods
+
+    /// Total number of bytes in the `AppendRope`.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn len_bytes(&self) -> usize {
+        self.rope.len_bytes() + self.tail.len()
+    }
+
+    /// Total number of chars in the `AppendRope`.
+    ///
+    /// Runs in O(M) time, where M is the length of the tail (normally at
+    /// most the chunk size).
+    #[inline]
+    pub fn len_chars(&self) -> usize {
+        self.rope.len_chars() + count_chars(&self.tail)
+    }
+
+    /// Total number of lines in the `AppendRope`.
+    ///
+    /// Runs in O(M) time, where M is the length of the tail (normally at
+    /// most the chunk size).
+    #[inline]
+    pub fn len_lines(&self) -> usize {
+        self.rope.len_lines() + count_line_breaks(&self.tail)
+    }
+
+    /// The target chunk size of the `AppendRope`, in bytes.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn chunk_size(&self) -> usize {
+        self.rope.chunk_size()
+    }
+
+    //-----------------------------------------------------------------------
+    // Edit methods
+
+    /// Appends `text` to the end of the `AppendRope`.
+    ///
+    /// Runs in amortized O(M) time, where M is the length of `text`.
+    pub fn push_str(&mut self, text: &str) {
+        self.tail.push_str(text);
+
+        let chunk_size = self.rope.chunk_size();
+        if self.tail.len() <= chunk_size {
+            return;
+        }
+
+        // Fold full chunks of the tail into the tree.  Each chunk is split
+        // off before the end of the tail, so the split can't be affected
+        // by later pushes (e.g. a CRLF pair completed by the next push).
+        let mut start = 0;
+        while self.tail.len() - start > chunk_size {
+            let rest = &self.tail[start..];
+            let split_idx = crlf::find_good_split(chunk_size, rest.as_bytes(), true);
+            if split_idx == 0 || split_idx == rest.len() {
+                // A single grapheme longer than the chunk size, which
+                // stays in the tail until it's complete.
+                break;
+            }
+            self.rope
+                .append(RopeBuilder::with_chunk_size(chunk_size).build_at_once(&rest[..split_idx]));
+            start += split_idx;
+        }
+        self.tail.drain(..start);
+    }
+
+    /// Appends `ch` to the end of the `AppendRope`.
+    ///
+    /// Runs in amortized O(1) time.
+    #[inline]
+    pub fn push_char(&mut self, ch: char) {
+        let mut buf = [0u8; 4];
+        self.push_str(ch.encode_utf8(&mut buf));
+    }
+
+    /// Removes all text from the `AppendRope`.
+    ///
+    /// Runs in O(log N) time.
+    pub fn clear(&mut self) {
+        self.rope.remove(..);
+        self.tail.clear();
+    }
+
+    //-----------------------------------------------------------------------
+    // Conversion methods
+
+    /// Returns the text of the `AppendRope` as a `Rope`.
+    ///
+    /// The `Rope` shares its data with the `AppendRope`, so this is cheap.
+    ///
+    /// Runs in O(log N + M) time, where M is the length of the tail.
+    pub fn to_rope(&self) -> Rope {
+        self.clone().into_rope()
+    }
+
+    /// Converts the `AppendRope` into a `Rope`.
+    ///
+    /// Runs in O(log N + M) time, where M is the length of the tail.
+    pub fn into_rope(self) -> Rope {
+        let mut rope = self.rope;
+        let chunk_size = rope.chunk_size();
+        rope.append(RopeBuilder::with_chunk_size(chunk_size).build_at_once(&self.tail));
+        rope.clear_dirty_range();
+        rope
+    }
+}
+
+impl std::default::Default for AppendRope {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reopens the `Rope`'s last chunk as the tail, so that text pushed onto
+/// it is stored and joined up with it correctly.
+///
+/// Runs in O(log N) time.
+impl std::convert::From<Rope> for AppendRope {
+    fn from(mut rope: Rope) -> Self {
+        let (last_chunk, _, chunk_char_idx, _) =
+            rope.slice(..).chunk_at_byte(rope.len_bytes());
+        let mut tail = String::with_capacity(rope.chunk_size().max(last_chunk.len()));
+        tail.push_str(last_chunk);
+
+        rope.remove(chunk_char_idx..);
+        AppendRope { rope, tail }
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 127 bytes, 103 chars, 1 line
+    const TEXT: &str = "Hello there!  How're you doing?  It's \
+                        a fine day, isn't it?  Aren't you glad \
+                        we're alive?  こんにちは、みんなさん！";
+
+    #[test]
+    fn push_str_01() {
+        let mut a = AppendRope::new();
+        let mut s = String::new();
+        for (i, c) in TEXT.char_indices() {
+            let piece = &TEXT[..(i + c.len_utf8())];
+            a.push_str(piece);
+            s.push_str(piece);
+            assert_eq!(s.len(), a.len_bytes());
+            assert!(a.tail.len() <= a.chunk_size());
+        }
+
+        let r = a.to_rope();
+        assert_eq!(r, s);
+        assert_eq!(r.len_chars(), a.len_chars());
+        r.assert_integrity();
+        r.assert_invariants();
+
+        let r = a.into_rope();
+        assert_eq!(r, s);
+    }
+
+    #[test]
+    fn push_str_02() {
+        // CRLF pairs pushed in two halves are never split.
+        let mut a = AppendRope::new();
+        for _ in 0..50 {
+            a.push_str("abc\r");
+            a.push_char('\n');
+        }
+        assert_eq!(51, a.len_lines());
+
+        let r = a.to_rope();
+        assert_eq!(r, "abc\r\n".repeat(50));
+        assert_eq!(51, r.len_lines());
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn push_str_03() {
+        let mut a = AppendRope::new();
+        a.push_str("Hi!");
+        a.push_str(TEXT);
+        a.push_str("");
+        assert_eq!(a.to_rope(), format!("Hi!{}", TEXT));
+
+        a.clear();
+        assert_eq!(0, a.len_bytes());
+        assert_eq!(a.to_rope(), "");
+    }
+
+    #[test]
+    fn from_rope_01() {
+        let mut a = AppendRope::from(Rope::from_str("Hello\r"));
+        a.push_str("\nworld!");
+        assert_eq!(2, a.len_lines());
+        assert_eq!(a.to_rope(), "Hello\r\nworld!");
+
+        let mut a = AppendRope::from(Rope::from_str(TEXT));
+        a.push_str(TEXT);
+        let r = a.into_rope();
+        assert_eq!(r, TEXT.repeat(2));
+        r.assert_integrity();
+        r.assert_invariants();
+
+        let a = AppendRope::from(Rope::new());
+        assert_eq!(a.to_rope(), "");
+    }
+}
@@ -0,0 +1,37 @@
+use unicode_bidi::{bidi_class, BidiClass};
+
+use slice::RopeSlice;
+
+/// The base direction of a paragraph of bidirectional text.
+///
+/// Requires the `bidi` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left.
+    Rtl,
+}
+
+/// Finds the direction of the first strong char of `text`, per rules P2
+/// and P3 of the Unicode Bidirectional Algorithm.
+///
+/// Chars between an isolate initiator and its matching pop directional
+/// isolate are skipped.  Returns `None` if there are no strong chars.
+pub(crate) fn first_strong_direction(text: &RopeSlice) -> Option<Direction> {
+    let mut isolate_depth = 0usize;
+    for chunk in text.chunks() {
+        for c in chunk.chars() {
+            match bidi_class(c) {
+                BidiClass::LRI | BidiClass::RLI | BidiClass::FSI => isolate_depth += 1,
+                BidiClass::PDI => isolate_depth = isolate_depth.saturating_sub(1),
+                BidiClass::L if isolate_depth == 0 => return Some(Direction::Ltr),
+                BidiClass::R | BidiClass::AL if isolate_depth == 0 => {
+                    return Some(Direction::Rtl)
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
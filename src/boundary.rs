@@ -0,0 +1,59 @@
+use std;
+use std::fmt;
+use std::ops::Range;
+
+/// How a checked edit handles an index that lands inside a grapheme
+/// cluster.
+///
+/// Used by `Rope::insert_checked()` and `Rope::remove_checked()`.
+///
+/// Without the `graphemes` feature, the only multi-char clusters
+/// recognized are CRLF pairs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum BoundaryPolicy {
+    /// Fail with a `BoundaryError`.
+    Reject,
+
+    /// Move the index back to the start of the cluster.
+    SnapBackward,
+
+    /// Move the index forward to the end of the cluster.
+    SnapForward,
+}
+
+impl BoundaryPolicy {
+    /// Applies the policy to `char_idx`, which is inside `cluster`.
+    pub(crate) fn resolve(
+        self,
+        char_idx: usize,
+        cluster: Range<usize>,
+    ) -> Result<usize, BoundaryError> {
+        match self {
+            BoundaryPolicy::Reject => Err(BoundaryError { char_idx, cluster }),
+            BoundaryPolicy::SnapBackward => Ok(cluster.start),
+            BoundaryPolicy::SnapForward => Ok(cluster.end),
+        }
+    }
+}
+
+/// An error for an edit index that lands inside a grapheme cluster.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BoundaryError {
+    /// The offending char index.
+    pub char_idx: usize,
+
+    /// The char range of the cluster that `char_idx` is inside of.
+    pub cluster: Range<usize>,
+}
+
+impl fmt::Display for BoundaryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "char index {} is inside the grapheme cluster at {}..{}",
+            self.char_idx, self.cluster.start, self.cluster.end
+        )
+    }
+}
+
+impl std::error::Error for BoundaryError {}
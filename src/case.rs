@@ -0,0 +1,55 @@
+use rewrite::ChunkRewriter;
+
+/// Replaces each char with the chars returned by a user-supplied function.
+pub(crate) struct MapChars<F> {
+    f: F,
+}
+
+impl<F> MapChars<F> {
+    pub fn new(f: F) -> MapChars<F> {
+        MapChars { f }
+    }
+}
+
+impl<F, I> ChunkRewriter for MapChars<F>
+where
+    F: FnMut(char) -> I,
+    I: IntoIterator<Item = char>,
+{
+    fn rewrite(&mut self, chunk: &str, out: &mut String) {
+        for c in chunk.chars() {
+            out.extend((self.f)(c));
+        }
+    }
+}
+
+/// Uppercases the first char of each word and lowercases the rest.
+pub(crate) struct TitleCase {
+    in_word: bool,
+}
+
+impl TitleCase {
+    pub fn new() -> TitleCase {
+        TitleCase { in_word: false }
+    }
+}
+
+impl ChunkRewriter for TitleCase {
+    fn rewrite(&mut self, chunk: &str, out: &mut String) {
+        for c in chunk.chars() {
+            if c.is_alphanumeric() {
+                if self.in_word {
+                    out.extend(c.to_lowercase());
+                } else {
+                    out.extend(c.to_uppercase());
+                }
+                self.in_word = true;
+            } else {
+                out.push(c);
+                // Apostrophes don't split words, so that e.g. "isn't"
+                // doesn't become "Isn'T".
+                self.in_word = self.in_word && (c == '\'' || c == '\u{2019}');
+            }
+        }
+    }
+}
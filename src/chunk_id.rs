@@ -0,0 +1,54 @@
+use std;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Weak};
+
+use tree::Node;
+
+/// An opaque identifier for a chunk of a `Rope`.
+///
+/// A chunk keeps its `ChunkId` for as long as its text is unchanged, even
+/// as other parts of the `Rope` are edited, and clones of the `Rope` share
+/// the ids of their chunks.  A chunk whose text is changed gets a new id,
+/// and ids are never reused, even once their chunk has been freed.  This
+/// makes `ChunkId`s suitable as keys for caching data derived from chunks,
+/// such as shaped glyph runs or per-chunk search indexes.
+///
+/// Note that two chunks with identical text can still have different ids.
+/// Holding on to a `ChunkId` keeps a chunk-sized allocation alive, but
+/// not the chunk's `Rope`.
+///
+/// Created by [`Chunks::with_ids()`](iter/struct.Chunks.html#method.with_ids).
+#[derive(Clone)]
+pub struct ChunkId {
+    leaf: Weak<Node>,
+}
+
+impl ChunkId {
+    pub(crate) fn new(leaf: &Arc<Node>) -> ChunkId {
+        ChunkId {
+            leaf: Arc::downgrade(leaf),
+        }
+    }
+}
+
+impl std::fmt::Debug for ChunkId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ChunkId({:p})", self.leaf.as_ptr())
+    }
+}
+
+impl PartialEq for ChunkId {
+    #[inline]
+    fn eq(&self, other: &ChunkId) -> bool {
+        Weak::ptr_eq(&self.leaf, &other.leaf)
+    }
+}
+
+impl Eq for ChunkId {}
+
+impl Hash for ChunkId {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.leaf.as_ptr() as usize).hash(state);
+    }
+}
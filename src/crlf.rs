@@ -1,7 +1,17 @@
+#[cfg(feature = "graphemes")]
+use std::str;
+
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::{GraphemeCursor, GraphemeIncomplete};
+
 /// Returns whether the given byte index in `text` is a valid
 /// splitting point.  Valid splitting point in this case means
 /// that it _is_ a utf8 code point boundary and _is not_ the
 /// middle of a CRLF grapheme.
+///
+/// With the `graphemes` feature enabled, it additionally must not be
+/// the middle of any other grapheme, in which case `text` must be
+/// valid utf8.
 #[inline]
 pub fn is_break(byte_idx: usize, text: &[u8]) -> bool {
     debug_assert!(byte_idx <= text.len());
@@ -9,18 +19,73 @@ pub fn is_break(byte_idx: usize, text: &[u8]) -> bool {
     if byte_idx == 0 || byte_idx == text.len() {
         true
     } else {
-        (text[byte_idx] >> 6 != 0b10) && ((text[byte_idx - 1] != 0x0D) | (text[byte_idx] != 0x0A))
+        (text[byte_idx] >> 6 != 0b10)
+            && ((text[byte_idx - 1] != 0x0D) | (text[byte_idx] != 0x0A))
+            && is_grapheme_break(byte_idx, text)
     }
 }
 
 /// Returns whether the seam between `left` and `right` is a valid
 /// splitting point.  Valid splitting point in this case means
 /// that it _is_ a utf8 code point boundary and _is not_ the middle
-/// of a CRLF grapheme.
+/// of a CRLF grapheme (or any grapheme, with the `graphemes` feature).
 #[inline]
 pub fn seam_is_break(left: &[u8], right: &[u8]) -> bool {
     debug_assert!(!left.is_empty() && !right.is_empty());
-    (right[0] >> 6 != 0b10) && ((left[left.len() - 1] != 0x0D) | (right[0] != 0x0A))
+    (right[0] >> 6 != 0b10)
+        && ((left[left.len() - 1] != 0x0D) | (right[0] != 0x0A))
+        && seam_is_grapheme_break(left, right)
+}
+
+#[cfg(not(feature = "graphemes"))]
+#[inline(always)]
+fn is_grapheme_break(_byte_idx: usize, _text: &[u8]) -> bool {
+    true
+}
+
+#[cfg(not(feature = "graphemes"))]
+#[inline(always)]
+fn seam_is_grapheme_break(_left: &[u8], _right: &[u8]) -> bool {
+    true
+}
+
+#[cfg(feature = "graphemes")]
+#[inline]
+fn is_grapheme_break(byte_idx: usize, text: &[u8]) -> bool {
+    // Between two ASCII chars, only CRLF is a non-break, and that's
+    // already handled by the caller.
+    if text[byte_idx - 1] < 0x80 && text[byte_idx] < 0x80 {
+        return true;
+    }
+
+    // The callers only ever pass in utf8 text with `byte_idx` on a code
+    // point boundary.
+    let text = unsafe { str::from_utf8_unchecked(text) };
+    GraphemeCursor::new(byte_idx, text.len(), true)
+        .is_boundary(text, 0)
+        .unwrap_or(true)
+}
+
+#[cfg(feature = "graphemes")]
+#[inline]
+fn seam_is_grapheme_break(left: &[u8], right: &[u8]) -> bool {
+    if left[left.len() - 1] < 0x80 && right[0] < 0x80 {
+        return true;
+    }
+
+    let left = unsafe { str::from_utf8_unchecked(left) };
+    let right = unsafe { str::from_utf8_unchecked(right) };
+    let mut cursor = GraphemeCursor::new(left.len(), left.len() + right.len(), true);
+    loop {
+        match cursor.is_boundary(right, left.len()) {
+            Ok(is_break) => return is_break,
+            Err(GraphemeIncomplete::PreContext(end)) => {
+                cursor.provide_context(&left[..end], 0);
+            }
+            // Not enough context available, so assume it's a break.
+            Err(_) => return true,
+        }
+    }
 }
 
 /// Returns the segment break before (but not including) the given byte
@@ -0,0 +1,282 @@
+use std;
+
+use rope::Rope;
+use rope_builder::RopeBuilder;
+use str_utils::count_chars;
+
+/// An invalid utf8 sequence replaced with U+FFFD during lossy decoding.
+///
+/// Returned by `LossyDecoder`, `Rope::from_reader_lossy()`, and a
+/// `RopeWriter` using `Utf8Policy::Replace`, so that editors can warn
+/// about and highlight the damaged parts of a file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// The byte offset of the invalid sequence in the input.
+    pub byte_offset: usize,
+
+    /// The length in bytes of the invalid sequence.
+    pub byte_len: usize,
+
+    /// The char index of the replacement char in the decoded text.
+    pub char_idx: usize,
+}
+
+/// A streaming, lossy utf8 decoder that builds a `Rope`.
+///
+/// Bytes can be pushed in pieces of any size, and code points split
+/// across pushes are decoded correctly.  Invalid utf8 is replaced with
+/// U+FFFD REPLACEMENT CHARACTER, following the same policy as
+/// `String::from_utf8_lossy()`, and each replacement is recorded.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::LossyDecoder;
+/// let mut decoder = LossyDecoder::new();
+/// decoder.push(b"Hello \xE3\x81");
+/// decoder.push(b"\x93\xFFworld!");
+///
+/// let (rope, replacements) = decoder.finish();
+/// assert_eq!(rope, "Hello こ\u{FFFD}world!");
+/// assert_eq!(replacements.len(), 1);
+/// assert_eq!(replacements[0].byte_offset, 9);
+/// assert_eq!(replacements[0].char_idx, 7);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LossyDecoder {
+    builder: RopeBuilder,
+    pending: [u8; 4],
+    pending_len: usize,
+    bytes_in: usize,
+    chars_out: usize,
+    replacements: Vec<Replacement>,
+}
+
+impl LossyDecoder {
+    /// Creates a new decoder, ready for input.
+    pub fn new() -> Self {
+        LossyDecoder::from_builder(RopeBuilder::new())
+    }
+
+    /// Creates a new decoder that builds a `Rope` with the given target
+    /// chunk size, in bytes.
+    ///
+    /// See [`Rope::with_chunk_size()`](struct.Rope.html#method.with_chunk_size)
+    /// for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is less than 8.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        LossyDecoder::from_builder(RopeBuilder::with_chunk_size(chunk_size))
+    }
+
+    fn from_builder(builder: RopeBuilder) -> Self {
+        LossyDecoder {
+            builder,
+            pending: [0; 4],
+            pending_len: 0,
+            bytes_in: 0,
+            chars_out: 0,
+            replacements: Vec::new(),
+        }
+    }
+
+    /// Decodes `bytes` and appends them to the in-progress `Rope`.
+    ///
+    /// A code point cut off at the end of `bytes` is held back until the
+    /// next push completes it.
+    pub fn push(&mut self, mut bytes: &[u8]) {
+        // First complete a code point left over from the last push, one
+        // byte at a time.
+        while self.pending_len > 0 && !bytes.is_empty() {
+            self.pending[self.pending_len] = bytes[0];
+            self.pending_len += 1;
+            self.bytes_in += 1;
+            bytes = &bytes[1..];
+
+            let (pending, len) = (self.pending, self.pending_len);
+            let start = self.bytes_in - len;
+            match std::str::from_utf8(&pending[..len]) {
+                Ok(text) => {
+                    self.pending_len = 0;
+                    self.append(text);
+                }
+                Err(e) => {
+                    // The pending bytes start a code point, so the error is
+                    // always at their start.
+                    if let Some(n) = e.error_len() {
+                        self.pending_len = 0;
+                        self.replace(start, n);
+                        self.decode(&pending[n..len], start + n);
+                    }
+                }
+            }
+        }
+
+        let start = self.bytes_in;
+        self.bytes_in += bytes.len();
+        self.decode(bytes, start);
+    }
+
+    /// Returns the replacements made so far.
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    /// Finishes decoding, and returns the `Rope` along with all the
+    /// replacements made.
+    ///
+    /// A code point still cut off at the end of the input is replaced.
+    pub fn finish(mut self) -> (Rope, Vec<Replacement>) {
+        if self.pending_len > 0 {
+            let (start, len) = (self.bytes_in - self.pending_len, self.pending_len);
+            self.pending_len = 0;
+            self.replace(start, len);
+        }
+        (self.builder.finish(), self.replacements)
+    }
+
+    //-----------------------------------------------------------------------
+
+    /// Decodes `bytes`, which start at byte `offset` of the input, holding
+    /// back a trailing incomplete code point.
+    fn decode(&mut self, mut bytes: &[u8], mut offset: usize) {
+        loop {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => {
+                    self.append(text);
+                    return;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    self.append(unsafe { std::str::from_utf8_unchecked(&bytes[..valid]) });
+                    match e.error_len() {
+                        Some(n) => {
+                            self.replace(offset + valid, n);
+                            bytes = &bytes[(valid + n)..];
+                            offset += valid + n;
+                        }
+                        None => {
+                            let rest = &bytes[valid..];
+                            self.pending[..rest.len()].copy_from_slice(rest);
+                            self.pending_len = rest.len();
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, text: &str) {
+        if !text.is_empty() {
+            self.builder.append(text);
+            self.chars_out += count_chars(text);
+        }
+    }
+
+    fn replace(&mut self, byte_offset: usize, byte_len: usize) {
+        self.replacements.push(Replacement {
+            byte_offset,
+            byte_len,
+            char_idx: self.chars_out,
+        });
+        self.append("\u{FFFD}");
+    }
+}
+
+impl std::default::Default for LossyDecoder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYTES: &[u8] = b"Hello \xE3\x81\x93\xE3\x82\x93\xFF\xF0\x9F\x98! \xED\xA0\x80\xC3";
+
+    #[test]
+    fn lossy_decoder_01() {
+        let mut decoder = LossyDecoder::new();
+        decoder.push(BYTES);
+        let (rope, replacements) = decoder.finish();
+
+        assert_eq!(rope, String::from_utf8_lossy(BYTES));
+        rope.assert_integrity();
+        rope.assert_invariants();
+        assert_eq!(
+            replacements,
+            vec![
+                Replacement {
+                    byte_offset: 12,
+                    byte_len: 1,
+                    char_idx: 8,
+                },
+                Replacement {
+                    byte_offset: 13,
+                    byte_len: 3,
+                    char_idx: 9,
+                },
+                Replacement {
+                    byte_offset: 18,
+                    byte_len: 1,
+                    char_idx: 12,
+                },
+                Replacement {
+                    byte_offset: 19,
+                    byte_len: 1,
+                    char_idx: 13,
+                },
+                Replacement {
+                    byte_offset: 20,
+                    byte_len: 1,
+                    char_idx: 14,
+                },
+                Replacement {
+                    byte_offset: 21,
+                    byte_len: 1,
+                    char_idx: 15,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn lossy_decoder_02() {
+        let (expected, expected_replacements) = {
+            let mut decoder = LossyDecoder::new();
+            decoder.push(BYTES);
+            decoder.finish()
+        };
+
+        // Every way of splitting the input into three pushes.
+        for i in 0..=BYTES.len() {
+            for j in i..=BYTES.len() {
+                let mut decoder = LossyDecoder::new();
+                decoder.push(&BYTES[..i]);
+                decoder.push(&BYTES[i..j]);
+                decoder.push(&BYTES[j..]);
+                let (rope, replacements) = decoder.finish();
+                assert_eq!(rope, expected);
+                assert_eq!(replacements, expected_replacements);
+            }
+        }
+    }
+
+    #[test]
+    fn lossy_decoder_03() {
+        // Byte by byte.
+        let mut decoder = LossyDecoder::new();
+        for b in BYTES {
+            decoder.push(&[*b]);
+        }
+        let (rope, _) = decoder.finish();
+        assert_eq!(rope, String::from_utf8_lossy(BYTES));
+    }
+}
@@ -0,0 +1,726 @@
+use std::ops::Range;
+
+use rope::Rope;
+
+/// A single element of a `Delta`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeltaElement {
+    /// Retains the given char range of the base text.
+    Copy(usize, usize),
+
+    /// Inserts new text.
+    Insert(Rope),
+}
+
+/// An edit to a text, represented as a sequence of regions retained from
+/// the base text interleaved with inserted text.
+///
+/// Retained regions must be in order and may not overlap, so every `Delta`
+/// is a combination of deletions and insertions.  Deltas can be applied,
+/// composed and inverted, which makes them a building block for undo
+/// history, operational transform and syncing edits between devices.
+///
+/// Elements are kept normalized: there are no empty elements, no two
+/// adjacent inserts, and no two copies that could be merged into one.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Delta, Rope};
+/// let base = Rope::from_str("Hello world!");
+/// let a = Delta::simple_edit(base.len_chars(), 0..5, "Goodbye");
+/// let b = Delta::simple_edit(a.new_len(), 13..13, " cruel");
+///
+/// let ab = a.compose(&b);
+/// let text = ab.apply(&base);
+/// assert_eq!(text, "Goodbye world cruel!");
+///
+/// // And back again.
+/// assert_eq!(ab.invert(&base).apply(&text), base);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    els: Vec<DeltaElement>,
+    base_len: usize,
+}
+
+impl Delta {
+    /// Creates a `Delta` from its elements, over a base text of `base_len`
+    /// chars.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a `Copy` range is reversed, out of bounds (i.e. beyond
+    /// `base_len`), or doesn't come after all previous `Copy` ranges.
+    pub fn new(base_len: usize, elements: Vec<DeltaElement>) -> Delta {
+        let mut delta = Delta {
+            els: Vec::with_capacity(elements.len()),
+            base_len,
+        };
+        let mut copy_end = 0;
+        for el in elements {
+            if let DeltaElement::Copy(start, end) = el {
+                assert!(
+                    copy_end <= start && start <= end && end <= base_len,
+                    "Invalid delta copy range {}..{}: previous copy ends at {}, base length {}",
+                    start,
+                    end,
+                    copy_end,
+                    base_len
+                );
+                copy_end = end;
+            }
+            delta.push(el);
+        }
+        delta
+    }
+
+    /// Creates a `Delta` that leaves a text of `base_len` chars unchanged.
+    pub fn identity(base_len: usize) -> Delta {
+        Delta::new(base_len, vec![DeltaElement::Copy(0, base_len)])
+    }
+
+    /// Creates a `Delta` that replaces the chars in `range` with `text`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > base_len`).
+    pub fn simple_edit(base_len: usize, range: Range<usize>, text: &str) -> Delta {
+        Delta::new(
+            base_len,
+            vec![
+                DeltaElement::Copy(0, range.start),
+                DeltaElement::Insert(Rope::from_str(text)),
+                DeltaElement::Copy(range.end, base_len),
+            ],
+        )
+    }
+
+    /// Length of the base text, in chars.
+    pub fn base_len(&self) -> usize {
+        self.base_len
+    }
+
+    /// Length of the text after applying the `Delta`, in chars.
+    pub fn new_len(&self) -> usize {
+        self.els.iter().map(element_len).sum()
+    }
+
+    /// The elements of the `Delta`.
+    pub fn elements(&self) -> &[DeltaElement] {
+        &self.els
+    }
+
+    /// Returns whether the `Delta` leaves its base text unchanged.
+    pub fn is_identity(&self) -> bool {
+        match self.els.as_slice() {
+            [] => self.base_len == 0,
+            [DeltaElement::Copy(start, end)] => *start == 0 && *end == self.base_len,
+            _ => false,
+        }
+    }
+
+    /// Applies the `Delta` to `base`, returning the edited text.
+    ///
+    /// Retained text is shared with `base` rather than copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` isn't `base_len()` chars long.
+    pub fn apply(&self, base: &Rope) -> Rope {
+        self.check_base(base);
+
+        let mut rope = Rope::with_chunk_size(base.chunk_size());
+        for el in &self.els {
+            match *el {
+                DeltaElement::Copy(start, end) => rope.append_slice(&base.slice(start..end)),
+                DeltaElement::Insert(ref text) => rope.append_slice(&text.slice(..)),
+            }
+        }
+        rope.clear_dirty_range();
+        rope
+    }
+
+    /// Composes two deltas, where `other` is an edit of the text that
+    /// results from applying `self`.
+    ///
+    /// Applying the returned `Delta` is the same as applying `self` and
+    /// then `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other.base_len()` isn't `self.new_len()`.
+    pub fn compose(&self, other: &Delta) -> Delta {
+        assert_eq!(
+            self.new_len(),
+            other.base_len,
+            "Composed delta must be based on the result of the first delta"
+        );
+
+        let mut delta = Delta {
+            els: Vec::new(),
+            base_len: self.base_len,
+        };
+
+        // Since copies are in order, we can walk through `self`'s elements
+        // just once.
+        let mut i = 0;
+        let mut el_start = 0;
+        for el in &other.els {
+            match *el {
+                DeltaElement::Insert(ref text) => delta.push(DeltaElement::Insert(text.clone())),
+                DeltaElement::Copy(start, end) => {
+                    while i < self.els.len() {
+                        let el_len = element_len(&self.els[i]);
+                        let el_end = el_start + el_len;
+                        if el_end > start && el_start < end {
+                            let from = start.max(el_start) - el_start;
+                            let to = end.min(el_end) - el_start;
+                            delta.push(match self.els[i] {
+                                DeltaElement::Copy(s, _) => DeltaElement::Copy(s + from, s + to),
+                                DeltaElement::Insert(ref text) => {
+                                    DeltaElement::Insert(text.sub_rope(from..to))
+                                }
+                            });
+                        }
+                        if el_end > end {
+                            break;
+                        }
+                        el_start = el_end;
+                        i += 1;
+                    }
+                }
+            }
+        }
+        delta
+    }
+
+    /// Creates the inverse of the `Delta`, which turns the edited text
+    /// back into `base`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `base` isn't `base_len()` chars long.
+    pub fn invert(&self, base: &Rope) -> Delta {
+        self.check_base(base);
+
+        let mut delta = Delta {
+            els: Vec::new(),
+            base_len: self.new_len(),
+        };
+        let mut base_pos = 0;
+        let mut new_pos = 0;
+        for el in &self.els {
+            if let DeltaElement::Copy(start, end) = *el {
+                delta.push(DeltaElement::Insert(base.sub_rope(base_pos..start)));
+                delta.push(DeltaElement::Copy(new_pos, new_pos + end - start));
+                base_pos = end;
+            }
+            new_pos += element_len(el);
+        }
+        delta.push(DeltaElement::Insert(base.sub_rope(base_pos..)));
+        delta
+    }
+
+    /// Returns the chars of the base text that the `Delta` deletes.
+    pub fn deleted(&self) -> Subset {
+        let mut subset = Subset::new(self.base_len);
+        let mut base_pos = 0;
+        for el in &self.els {
+            if let DeltaElement::Copy(start, end) = *el {
+                subset.add_range(base_pos..start);
+                base_pos = end;
+            }
+        }
+        subset.add_range(base_pos..self.base_len);
+        subset
+    }
+
+    /// Returns the chars of the edited text that the `Delta` inserts.
+    pub fn inserted(&self) -> Subset {
+        let mut subset = Subset::new(self.new_len());
+        let mut new_pos = 0;
+        for el in &self.els {
+            let len = element_len(el);
+            if let DeltaElement::Insert(_) = *el {
+                subset.add_range(new_pos..(new_pos + len));
+            }
+            new_pos += len;
+        }
+        subset
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Returns the inserts of the `Delta`, along with the base char index
+    /// they're inserted at.
+    fn inserts(&self) -> Vec<(usize, &Rope)> {
+        let mut inserts = Vec::new();
+        let mut base_pos = 0;
+        for el in &self.els {
+            match *el {
+                DeltaElement::Copy(_, end) => base_pos = end,
+                DeltaElement::Insert(ref text) => inserts.push((base_pos, text)),
+            }
+        }
+        inserts
+    }
+
+    fn check_base(&self, base: &Rope) {
+        assert_eq!(
+            base.len_chars(),
+            self.base_len,
+            "Delta applied to a text of the wrong length"
+        );
+    }
+
+    /// Pushes an element, keeping the elements normalized.
+    pub(crate) fn push(&mut self, el: DeltaElement) {
+        if element_len(&el) == 0 {
+            return;
+        }
+        match (self.els.last_mut(), el) {
+            (Some(&mut DeltaElement::Copy(_, ref mut last_end)), DeltaElement::Copy(start, end))
+                if *last_end == start =>
+            {
+                *last_end = end;
+            }
+            (Some(&mut DeltaElement::Insert(ref mut last_text)), DeltaElement::Insert(text)) => {
+                last_text.append(text);
+                last_text.clear_dirty_range();
+            }
+            (_, el) => self.els.push(el),
+        }
+    }
+}
+
+fn element_len(el: &DeltaElement) -> usize {
+    match *el {
+        DeltaElement::Copy(start, end) => end - start,
+        DeltaElement::Insert(ref text) => text.len_chars(),
+    }
+}
+
+/// Transforms two concurrent deltas against each other, so that both
+/// edits can be applied.
+///
+/// `a` and `b` must be edits of the same base text.  Returns `(a', b')`,
+/// where `a'` is `a` rebased onto the result of applying `b`, and `b'` is
+/// `b` rebased onto the result of applying `a`.  Applying `a` then `b'`
+/// gives the same text as applying `b` then `a'`.
+///
+/// Text deleted by either delta is deleted in the result.  When both
+/// deltas insert at the same position, `a`'s insert comes first.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{transform, Delta, Rope};
+/// let base = Rope::from_str("Hello world!");
+/// let a = Delta::simple_edit(12, 0..5, "Goodbye");
+/// let b = Delta::simple_edit(12, 11..11, ", and more");
+///
+/// let (a2, b2) = transform(&a, &b);
+/// let text = a.compose(&b2).apply(&base);
+/// assert_eq!(text, b.compose(&a2).apply(&base));
+/// assert_eq!(text, "Goodbye world, and more!");
+/// ```
+///
+/// # Panics
+///
+/// Panics if `a` and `b` have different base lengths.
+pub fn transform(a: &Delta, b: &Delta) -> (Delta, Delta) {
+    assert_eq!(
+        a.base_len, b.base_len,
+        "Transformed deltas must have the same base length"
+    );
+    let a_inserts = a.inserts();
+    let b_inserts = b.inserts();
+    let a_deleted = a.deleted();
+    let b_deleted = b.deleted();
+
+    // Split the base text into segments that are each either kept or
+    // deleted as a whole by both deltas, with inserts only at their starts.
+    let mut bounds = vec![0, a.base_len];
+    bounds.extend(a_inserts.iter().chain(&b_inserts).map(|ins| ins.0));
+    for r in a_deleted.ranges().iter().chain(b_deleted.ranges()) {
+        bounds.push(r.start);
+        bounds.push(r.end);
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut a2 = Delta {
+        els: Vec::new(),
+        base_len: b.new_len(),
+    };
+    let mut b2 = Delta {
+        els: Vec::new(),
+        base_len: a.new_len(),
+    };
+
+    // Positions in the results of `a` and `b`.
+    let mut a_pos = 0;
+    let mut b_pos = 0;
+    let mut a_inserts = a_inserts.into_iter().peekable();
+    let mut b_inserts = b_inserts.into_iter().peekable();
+    for (i, &pos) in bounds.iter().enumerate() {
+        if let Some((_, text)) = a_inserts.next_if(|ins| ins.0 == pos) {
+            let len = text.len_chars();
+            a2.push(DeltaElement::Insert(text.clone()));
+            b2.push(DeltaElement::Copy(a_pos, a_pos + len));
+            a_pos += len;
+        }
+        if let Some((_, text)) = b_inserts.next_if(|ins| ins.0 == pos) {
+            let len = text.len_chars();
+            a2.push(DeltaElement::Copy(b_pos, b_pos + len));
+            b2.push(DeltaElement::Insert(text.clone()));
+            b_pos += len;
+        }
+        if let Some(&next) = bounds.get(i + 1) {
+            let len = next - pos;
+            let in_a = !a_deleted.contains(pos);
+            let in_b = !b_deleted.contains(pos);
+            if in_a && in_b {
+                a2.push(DeltaElement::Copy(b_pos, b_pos + len));
+                b2.push(DeltaElement::Copy(a_pos, a_pos + len));
+            }
+            if in_a {
+                a_pos += len;
+            }
+            if in_b {
+                b_pos += len;
+            }
+        }
+    }
+
+    (a2, b2)
+}
+
+//===========================================================
+
+/// A set of chars of a text, stored as sorted, non-overlapping char ranges.
+///
+/// Used to describe e.g. the chars deleted or inserted by a `Delta`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subset {
+    ranges: Vec<Range<usize>>,
+    len: usize,
+}
+
+impl Subset {
+    /// Creates an empty `Subset` of a text of `len` chars.
+    pub fn new(len: usize) -> Subset {
+        Subset {
+            ranges: Vec::new(),
+            len,
+        }
+    }
+
+    /// Length of the text the `Subset` is over, in chars.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the `Subset` contains no chars.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of chars in the `Subset`.
+    pub fn count(&self) -> usize {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    /// Returns whether the char at `char_idx` is in the `Subset`.
+    pub fn contains(&self, char_idx: usize) -> bool {
+        let i = self.ranges.partition_point(|r| r.end <= char_idx);
+        i < self.ranges.len() && self.ranges[i].start <= char_idx
+    }
+
+    /// The char ranges of the `Subset`, sorted and non-overlapping.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Adds the chars in `range` to the `Subset`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len()`).
+    pub fn add_range(&mut self, range: Range<usize>) {
+        // Bounds check
+        assert!(range.start <= range.end);
+        assert!(
+            range.end <= self.len,
+            "Attempt to add range past end of Subset: range end {}, Subset length {}",
+            range.end,
+            self.len
+        );
+        if range.start == range.end {
+            return;
+        }
+
+        // Merge with any ranges that overlap or touch the new one.
+        let lo = self.ranges.partition_point(|r| r.end < range.start);
+        let hi = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if lo < hi {
+            merged.start = merged.start.min(self.ranges[lo].start);
+            merged.end = merged.end.max(self.ranges[hi - 1].end);
+        }
+        self.ranges.splice(lo..hi, Some(merged));
+    }
+
+    /// Returns a `Subset` with all the chars not in this one.
+    pub fn complement(&self) -> Subset {
+        let mut subset = Subset::new(self.len);
+        let mut pos = 0;
+        for r in &self.ranges {
+            subset.add_range(pos..r.start);
+            pos = r.end;
+        }
+        subset.add_range(pos..self.len);
+        subset
+    }
+
+    /// Returns a `Subset` with the chars of both this one and `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two `Subset`s are over texts of different lengths.
+    pub fn union(&self, other: &Subset) -> Subset {
+        assert_eq!(self.len, other.len, "Subsets have different lengths");
+        let mut subset = self.clone();
+        for r in &other.ranges {
+            subset.add_range(r.clone());
+        }
+        subset
+    }
+
+    /// Returns a copy of `text` with the chars in the `Subset` removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` isn't `len()` chars long.
+    pub fn delete_from(&self, text: &Rope) -> Rope {
+        assert_eq!(
+            text.len_chars(),
+            self.len,
+            "Subset applied to a text of the wrong length"
+        );
+        let mut rope = Rope::with_chunk_size(text.chunk_size());
+        for r in self.complement().ranges() {
+            rope.append_slice(&text.slice(r.clone()));
+        }
+        rope.clear_dirty_range();
+        rope
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(text: &str) -> DeltaElement {
+        DeltaElement::Insert(Rope::from_str(text))
+    }
+
+    #[test]
+    fn new_01() {
+        let d = Delta::new(
+            10,
+            vec![
+                DeltaElement::Copy(0, 2),
+                DeltaElement::Copy(2, 4),
+                insert(""),
+                insert("ab"),
+                insert("cd"),
+                DeltaElement::Copy(6, 6),
+                DeltaElement::Copy(8, 10),
+            ],
+        );
+        assert_eq!(
+            &[
+                DeltaElement::Copy(0, 4),
+                insert("abcd"),
+                DeltaElement::Copy(8, 10),
+            ],
+            d.elements()
+        );
+        assert_eq!(10, d.new_len());
+        assert!(!d.is_identity());
+        assert!(Delta::identity(10).is_identity());
+        assert!(Delta::identity(0).is_identity());
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_02() {
+        Delta::new(10, vec![DeltaElement::Copy(4, 6), DeltaElement::Copy(5, 8)]);
+    }
+
+    #[test]
+    fn apply_01() {
+        let base = Rope::from_str("Hello world!\r\nGoodbye world!");
+        let d = Delta::new(
+            base.len_chars(),
+            vec![
+                insert(">> "),
+                DeltaElement::Copy(0, 13),
+                insert("\n"),
+                DeltaElement::Copy(22, 28),
+            ],
+        );
+        let text = d.apply(&base);
+        text.assert_integrity();
+        text.assert_invariants();
+        assert_eq!(text, ">> Hello world!\r\nworld!");
+    }
+
+    #[test]
+    fn compose_01() {
+        let base = Rope::from_str("Hello world!");
+        let a = Delta::simple_edit(12, 5..5, ", big");
+        let b = Delta::new(
+            a.new_len(),
+            vec![
+                DeltaElement::Copy(0, 2),
+                insert("X"),
+                DeltaElement::Copy(7, 9),
+                DeltaElement::Copy(12, 17),
+            ],
+        );
+        let ab = a.compose(&b);
+        assert_eq!(b.apply(&a.apply(&base)), ab.apply(&base));
+        assert_eq!(ab.apply(&base), "HeXbiorld!");
+        assert_eq!(
+            &[
+                DeltaElement::Copy(0, 2),
+                insert("Xbi"),
+                DeltaElement::Copy(7, 12),
+            ],
+            ab.elements()
+        );
+    }
+
+    #[test]
+    fn invert_01() {
+        let base = Rope::from_str("Hello world! How are you?");
+        let d = Delta::new(
+            base.len_chars(),
+            vec![
+                insert("Oh, "),
+                DeltaElement::Copy(6, 12),
+                insert("!!"),
+                DeltaElement::Copy(16, 20),
+            ],
+        );
+        let text = d.apply(&base);
+        assert_eq!(text, "Oh, world!!! are");
+
+        let inv = d.invert(&base);
+        assert_eq!(inv.apply(&text), base);
+        assert_eq!(d.compose(&inv).apply(&base), base);
+    }
+
+    #[test]
+    fn deleted_inserted_01() {
+        let d = Delta::new(
+            10,
+            vec![
+                insert("ab"),
+                DeltaElement::Copy(2, 5),
+                insert("c"),
+                DeltaElement::Copy(7, 9),
+            ],
+        );
+        assert_eq!(&[0..2, 5..7, 9..10], d.deleted().ranges());
+        assert_eq!(&[0..2, 5..6], d.inserted().ranges());
+        assert_eq!(8, d.inserted().len());
+    }
+
+    fn assert_converges(base: &Rope, a: &Delta, b: &Delta, expected: &str) {
+        let (a2, b2) = transform(a, b);
+        let ab = a.compose(&b2).apply(base);
+        let ba = b.compose(&a2).apply(base);
+        assert_eq!(ab, expected);
+        assert_eq!(ba, expected);
+    }
+
+    #[test]
+    fn transform_01() {
+        // Inserts at the same position.
+        let base = Rope::from_str("Hello world!");
+        let a = Delta::simple_edit(12, 5..5, " big");
+        let b = Delta::simple_edit(12, 5..5, " wide");
+        assert_converges(&base, &a, &b, "Hello big wide world!");
+        assert_converges(&base, &b, &a, "Hello wide big world!");
+    }
+
+    #[test]
+    fn transform_02() {
+        // Overlapping deletions, with an insert inside the deleted text.
+        let base = Rope::from_str("Hello world! How are you?");
+        let a = Delta::simple_edit(25, 5..16, "");
+        let b = Delta::new(
+            25,
+            vec![
+                DeltaElement::Copy(0, 11),
+                insert(" there"),
+                DeltaElement::Copy(11, 12),
+                DeltaElement::Copy(20, 25),
+            ],
+        );
+        assert_converges(&base, &a, &b, "Hello there you?");
+        assert_converges(&base, &b, &a, "Hello there you?");
+    }
+
+    #[test]
+    fn transform_03() {
+        let base = Rope::from_str("abc");
+        let a = Delta::identity(3);
+        let b = Delta::simple_edit(3, 0..3, "xyz");
+        let (a2, b2) = transform(&a, &b);
+        assert!(a2.is_identity());
+        assert_eq!(b, b2);
+        assert_converges(&base, &a, &b, "xyz");
+    }
+
+    #[test]
+    fn subset_01() {
+        let mut s = Subset::new(20);
+        s.add_range(2..4);
+        s.add_range(8..10);
+        s.add_range(12..14);
+        s.add_range(4..5);
+        assert_eq!(&[2..5, 8..10, 12..14], s.ranges());
+        s.add_range(9..12);
+        assert_eq!(&[2..5, 8..14], s.ranges());
+        assert_eq!(9, s.count());
+        assert!(s.contains(2));
+        assert!(!s.contains(5));
+        assert!(s.contains(13));
+        assert!(!s.contains(14));
+
+        assert_eq!(&[0..2, 5..8, 14..20], s.complement().ranges());
+        let mut t = Subset::new(20);
+        t.add_range(0..3);
+        assert_eq!(&[0..5, 8..14], s.union(&t).ranges());
+        assert!(Subset::new(5).is_empty());
+    }
+
+    #[test]
+    fn subset_02() {
+        let text = Rope::from_str("Hello world!");
+        let mut s = Subset::new(text.len_chars());
+        s.add_range(0..6);
+        s.add_range(11..12);
+        assert_eq!(s.delete_from(&text), "world");
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+
+use edit::Edit;
+use rope::Rope;
+use str_utils::count_chars;
+use tree::Node;
+
+/// Maps lines of text to small integer IDs, so that line sequences can be
+/// diffed without repeatedly comparing the lines' contents.
+pub(crate) struct LineTable {
+    ids: HashMap<String, usize>,
+}
+
+impl LineTable {
+    pub fn new() -> LineTable {
+        LineTable {
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Returns the IDs of the lines of `rope` in `line_range`.  Each line
+    /// includes its line break, if any.
+    pub fn lines(&mut self, rope: &Rope, line_range: Range<usize>) -> Vec<usize> {
+        let mut lines = Vec::with_capacity(line_range.end - line_range.start);
+        for line_idx in line_range {
+            let line = rope.line(line_idx).to_string();
+            let next_id = self.ids.len();
+            lines.push(*self.ids.entry(line).or_insert(next_id));
+        }
+        lines
+    }
+}
+
+//===========================================================
+
+/// Returns the number of leading lines that are certainly the same in `a`
+/// and `b`.
+pub(crate) fn shared_prefix_lines(a: &Rope, b: &Rope) -> usize {
+    let (bytes, _) = Node::shared_prefix_bytes(&a.root, &b.root);
+    if bytes == 0 {
+        return 0;
+    }
+
+    // Only count lines whose line break is followed by at least one more
+    // shared char, so that e.g. a CR followed by an LF on one side only
+    // isn't counted.
+    let chars = a.byte_to_char(bytes);
+    a.char_to_line(chars.saturating_sub(1))
+        .min(b.char_to_line(chars.saturating_sub(1)))
+}
+
+/// Returns the number of trailing lines that are certainly the same in `a`
+/// and `b`.
+pub(crate) fn shared_suffix_lines(a: &Rope, b: &Rope) -> usize {
+    let (bytes, _) = Node::shared_suffix_bytes(&a.root, &b.root);
+
+    // Only count lines whose preceding line break is entirely shared.
+    let lines_after = |rope: &Rope| {
+        let start_byte = rope.len_bytes() - bytes;
+        let mut start_char = rope.byte_to_char(start_byte);
+        if rope.char_to_byte(start_char) < start_byte {
+            start_char += 1;
+        }
+        if start_char == rope.len_chars() {
+            0
+        } else {
+            rope.len_lines() - 1 - rope.char_to_line(start_char)
+        }
+    };
+    lines_after(a).min(lines_after(b))
+}
+
+//===========================================================
+
+/// Writes a unified diff of the lines of `a` and `b` to `writer`.
+///
+/// See [`unified_diff()`](fn.unified_diff.html) for details.
+pub fn write_unified_diff<W: io::Write>(
+    mut writer: W,
+    a: &Rope,
+    b: &Rope,
+    context_lines: usize,
+) -> io::Result<()> {
+    let changes = line_changes(a, b);
+    let a_count = content_lines(a);
+
+    let mut i = 0;
+    while i < changes.len() {
+        // Group together changes whose context would overlap.
+        let mut j = i + 1;
+        while j < changes.len()
+            && changes[j].0.start - changes[j - 1].0.end <= context_lines * 2
+        {
+            j += 1;
+        }
+        let group = &changes[i..j];
+        i = j;
+
+        let first = &group[0];
+        let last = &group[group.len() - 1];
+        let before = first.0.start.min(context_lines);
+        let after = (a_count - last.0.end).min(context_lines);
+        let a_range = (first.0.start - before)..(last.0.end + after);
+        let b_range = (first.1.start - before)..(last.1.end + after);
+        writeln!(
+            writer,
+            "@@ -{} +{} @@",
+            hunk_range(&a_range),
+            hunk_range(&b_range)
+        )?;
+
+        let mut a_idx = a_range.start;
+        for change in group {
+            for line_idx in a_idx..change.0.start {
+                write_line(&mut writer, ' ', a, line_idx)?;
+            }
+            for line_idx in change.0.clone() {
+                write_line(&mut writer, '-', a, line_idx)?;
+            }
+            for line_idx in change.1.clone() {
+                write_line(&mut writer, '+', b, line_idx)?;
+            }
+            a_idx = change.0.end;
+        }
+        for line_idx in a_idx..a_range.end {
+            write_line(&mut writer, ' ', a, line_idx)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates a unified diff of the lines of `a` and `b`, with
+/// `context_lines` lines of unchanged context around each change.
+///
+/// Only the hunks are included, without the `---`/`+++` file header
+/// lines.  Lines are written with their own line breaks, and a last line
+/// without one is followed by a `\ No newline at end of file` marker, as
+/// is conventional.  Leading and trailing subtrees shared between `a` and
+/// `b` are skipped without being compared.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{unified_diff, Rope};
+/// let a = Rope::from_str("one\ntwo\nthree\nfour\n");
+/// let b = Rope::from_str("one\n2\nthree\nfour\n");
+///
+/// assert_eq!(
+///     unified_diff(&a, &b, 1),
+///     "@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n"
+/// );
+/// ```
+pub fn unified_diff(a: &Rope, b: &Rope, context_lines: usize) -> String {
+    let mut out = Vec::new();
+    write_unified_diff(&mut out, a, b, context_lines).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Computes the changed line ranges between `a` and `b`, as pairs of
+/// line ranges where the lines of `a` were replaced by those of `b`.
+///
+/// The trailing empty line after a final line break isn't included.
+pub(crate) fn line_changes(a: &Rope, b: &Rope) -> Vec<(Range<usize>, Range<usize>)> {
+    let a_count = content_lines(a);
+    let b_count = content_lines(b);
+    let prefix = shared_prefix_lines(a, b).min(a_count).min(b_count);
+    let mut suffix = shared_suffix_lines(a, b);
+    if suffix > 0 && a_count < a.len_lines() {
+        // Both end with the same empty line, which isn't counted.
+        suffix -= 1;
+    }
+    let suffix = suffix.min(a_count - prefix).min(b_count - prefix);
+
+    let mut table = LineTable::new();
+    let a_lines = table.lines(a, prefix..(a_count - suffix));
+    let b_lines = table.lines(b, prefix..(b_count - suffix));
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let end = (a_lines.len(), b_lines.len());
+    for (mi, mj) in diff_matches(&a_lines, &b_lines).into_iter().chain(Some(end)) {
+        if mi > i || mj > j {
+            changes.push(((prefix + i)..(prefix + mi), (prefix + j)..(prefix + mj)));
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    changes
+}
+
+/// Number of lines in `rope`, not counting the empty line after a final
+/// line break.
+pub(crate) fn content_lines(rope: &Rope) -> usize {
+    let last = rope.len_lines() - 1;
+    if rope.line_to_char(last) == rope.len_chars() {
+        last
+    } else {
+        last + 1
+    }
+}
+
+/// Computes the edits that turn `rope` into `text`.
+///
+/// The common prefix and suffix are found by comparing the `Rope`'s
+/// chunks directly against `text`, and only the lines in between are
+/// diffed.
+pub(crate) fn str_edits(rope: &Rope, text: &str) -> Vec<Edit> {
+    let slice = rope.slice(..);
+    let bytes = text.as_bytes();
+
+    // Common prefix.
+    let mut prefix = 0;
+    for chunk in rope.chunks() {
+        let shared = chunk
+            .bytes()
+            .zip(&bytes[prefix..])
+            .take_while(|&(a, &b)| a == b)
+            .count();
+        prefix += shared;
+        if shared < chunk.len() {
+            break;
+        }
+    }
+    while !text.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+
+    // Common suffix, not overlapping the prefix.
+    let max_suffix = rope.len_bytes().min(text.len()) - prefix;
+    let mut suffix = 0;
+    while suffix < max_suffix {
+        let end = rope.len_bytes() - suffix;
+        let (chunk, chunk_start, _, _) = slice.chunk_at_byte(end - 1);
+        let shared = chunk.as_bytes()[..(end - chunk_start)]
+            .iter()
+            .rev()
+            .zip(bytes[..(text.len() - suffix)].iter().rev())
+            .take(max_suffix - suffix)
+            .take_while(|&(a, b)| a == b)
+            .count();
+        suffix += shared;
+        if shared < end - chunk_start {
+            break;
+        }
+    }
+    while !text.is_char_boundary(text.len() - suffix) {
+        suffix -= 1;
+    }
+
+    if prefix == rope.len_bytes() && prefix == text.len() {
+        return Vec::new();
+    }
+
+    // Diff the lines in between.
+    let old_mid = rope
+        .slice(rope.byte_to_char(prefix)..rope.byte_to_char(rope.len_bytes() - suffix))
+        .to_string();
+    let new_mid = &text[prefix..(text.len() - suffix)];
+    let old_lines: Vec<&str> = old_mid.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new_mid.split_inclusive('\n').collect();
+
+    let mut edits = Vec::new();
+    let mut char_idx = count_chars(&text[..prefix]);
+    let (mut i, mut j) = (0, 0);
+    let end = (old_lines.len(), new_lines.len());
+    for (mi, mj) in diff_matches(&old_lines, &new_lines).into_iter().chain(Some(end)) {
+        if mi > i || mj > j {
+            let old_len: usize = old_lines[i..mi].iter().map(|l| count_chars(l)).sum();
+            let new_len: usize = new_lines[j..mj].iter().map(|l| count_chars(l)).sum();
+            edits.push(Edit::replace(char_idx..(char_idx + old_len), new_len));
+            char_idx += new_len;
+        }
+        if mj < new_lines.len() {
+            char_idx += count_chars(new_lines[mj]);
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    edits
+}
+
+fn hunk_range(range: &Range<usize>) -> String {
+    match range.end - range.start {
+        0 => format!("{},0", range.start),
+        1 => format!("{}", range.start + 1),
+        n => format!("{},{}", range.start + 1, n),
+    }
+}
+
+fn write_line<W: io::Write>(
+    writer: &mut W,
+    prefix: char,
+    rope: &Rope,
+    line_idx: usize,
+) -> io::Result<()> {
+    write!(writer, "{}", prefix)?;
+    for chunk in rope.line(line_idx).chunks() {
+        writer.write_all(chunk.as_bytes())?;
+    }
+    if line_idx + 1 == rope.len_lines() {
+        writer.write_all(b"\n\\ No newline at end of file\n")?;
+    }
+    Ok(())
+}
+
+//===========================================================
+
+/// Computes a longest common subsequence of `a` and `b`, returned as the
+/// pairs of indices of the matching items, in order.
+///
+/// Uses Myers' O(ND) algorithm, after trimming any common prefix and
+/// suffix.
+pub(crate) fn diff_matches<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y)
+        .count();
+
+    let mut matches: Vec<_> = (0..prefix).map(|i| (i, i)).collect();
+    let a_mid = &a[prefix..(a.len() - suffix)];
+    let b_mid = &b[prefix..(b.len() - suffix)];
+    matches.extend(
+        myers(a_mid, b_mid)
+            .into_iter()
+            .map(|(i, j)| (i + prefix, j + prefix)),
+    );
+    matches.extend((0..suffix).map(|i| (a.len() - suffix + i, b.len() - suffix + i)));
+    matches
+}
+
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = n + m;
+
+    // `v[k + max + 1]` is the furthest x reached on diagonal k.  For each
+    // round d we keep the diagonals -d-1..=d+1 of `v` from before that
+    // round, to backtrack through afterwards.
+    let idx = |k: isize| (k + max + 1) as usize;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'outer: for d in 0..(max + 1) {
+        trace.push(v[idx(-d - 1)..(idx(d + 1) + 1)].to_vec());
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                break 'outer;
+            }
+            k += 2;
+        }
+    }
+
+    // Backtrack to find the snakes, i.e. the matching items.
+    let mut matches = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matches.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    matches.reverse();
+    matches
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_matches(a: &str, b: &str, lcs_len: usize) {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        let matches = diff_matches(&a, &b);
+        assert_eq!(lcs_len, matches.len());
+        for pair in matches.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+        }
+        for &(i, j) in &matches {
+            assert_eq!(a[i], b[j]);
+        }
+    }
+
+    #[test]
+    fn diff_matches_01() {
+        check_matches("abcabba", "cbabac", 4);
+        check_matches("", "abc", 0);
+        check_matches("abc", "", 0);
+        check_matches("abc", "abc", 3);
+        check_matches("xaxbxcx", "abc", 3);
+        check_matches("abcdef", "fedcba", 1);
+    }
+
+    #[test]
+    fn unified_diff_01() {
+        let a: String = (1..21).map(|i| format!("{}\n", i)).collect();
+        let mut b = Rope::from_str(&a);
+        b.remove(2..4);
+        b.insert(b.line_to_char(6), "x\n");
+        b.insert(b.line_to_char(17), "y\n");
+        let a = Rope::from_str(&a);
+        assert_eq!(
+            unified_diff(&a, &b, 2),
+            "@@ -1,4 +1,3 @@\n 1\n-2\n 3\n 4\n\
+             @@ -6,4 +5,5 @@\n 6\n 7\n+x\n 8\n 9\n\
+             @@ -16,4 +16,5 @@\n 16\n 17\n+y\n 18\n 19\n"
+        );
+        assert_eq!(unified_diff(&a, &b, 3).matches("@@ -").count(), 2);
+    }
+
+    #[test]
+    fn unified_diff_02() {
+        let a = Rope::from_str("a\nb");
+        let b = Rope::from_str("new\na\nb\n");
+        assert_eq!(
+            unified_diff(&a, &b, 0),
+            "@@ -0,0 +1 @@\n+new\n@@ -2 +3 @@\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(unified_diff(&a, &a, 3), "");
+        assert_eq!(unified_diff(&Rope::new(), &Rope::new(), 3), "");
+        assert_eq!(unified_diff(&Rope::new(), &a, 3).lines().next(), Some("@@ -0,0 +1,2 @@"));
+    }
+
+    #[test]
+    fn line_table_01() {
+        let mut table = LineTable::new();
+        let a = table.lines(&Rope::from_str("a\nb\na\nc"), 0..4);
+        let b = table.lines(&Rope::from_str("c\na\n"), 0..3);
+        assert_eq!(vec![0, 1, 0, 2], a);
+        assert_eq!(vec![3, 0, 4], b);
+    }
+}
@@ -0,0 +1,174 @@
+use str_utils::is_line_break;
+
+/// A summary of a text's contents, as shown in e.g. an editor's file
+/// info panel.
+///
+/// Created by [`Rope::stats()`](struct.Rope.html#method.stats) and
+/// [`RopeSlice::stats()`](struct.RopeSlice.html#method.stats), which
+/// compute all of it in a single pass over the text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// The number of bytes.
+    pub bytes: usize,
+
+    /// The number of chars.
+    pub chars: usize,
+
+    /// The number of lines, counted the same way as `len_lines()`.
+    pub lines: usize,
+
+    /// The length in chars of the longest line, not counting its line
+    /// break.
+    pub longest_line: usize,
+
+    /// The number of each kind of line break.
+    pub line_endings: LineEndings,
+
+    /// Whether there are any tab chars.
+    pub has_tabs: bool,
+
+    /// Whether the text starts with a byte order mark (U+FEFF).
+    pub has_bom: bool,
+
+    /// Whether there are any control chars other than tabs and line
+    /// breaks, e.g. NUL or escape chars.
+    pub has_control_chars: bool,
+}
+
+/// The number of each kind of line break in a text.
+///
+/// A CRLF pair counts as a single line break.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct LineEndings {
+    /// Line feeds (U+000A) not preceded by a carriage return.
+    pub lf: usize,
+
+    /// CRLF pairs.
+    pub crlf: usize,
+
+    /// Carriage returns (U+000D) not followed by a line feed.
+    pub cr: usize,
+
+    /// All other line breaks: vertical tabs, form feeds, next line
+    /// (U+0085), and the line and paragraph separators (U+2028, U+2029).
+    pub other: usize,
+}
+
+impl LineEndings {
+    /// The total number of line breaks.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.lf + self.crlf + self.cr + self.other
+    }
+}
+
+impl DocumentStats {
+    /// Computes the stats of the text made up of `chunks`, in one pass.
+    pub(crate) fn from_chunks<'a, I>(chunks: I) -> DocumentStats
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let mut stats = DocumentStats {
+            bytes: 0,
+            chars: 0,
+            lines: 1,
+            longest_line: 0,
+            line_endings: LineEndings::default(),
+            has_tabs: false,
+            has_bom: false,
+            has_control_chars: false,
+        };
+        let mut line_len = 0;
+        // Whether the last char was a CR, which may be followed by a LF
+        // in the next chunk.
+        let mut after_cr = false;
+
+        for chunk in chunks {
+            stats.bytes += chunk.len();
+            for c in chunk.chars() {
+                if stats.chars == 0 && c == '\u{FEFF}' {
+                    stats.has_bom = true;
+                }
+                stats.chars += 1;
+
+                if after_cr {
+                    after_cr = false;
+                    if c == '\n' {
+                        stats.line_endings.crlf += 1;
+                        continue;
+                    }
+                    stats.line_endings.cr += 1;
+                }
+
+                if is_line_break(c) {
+                    match c {
+                        '\r' => after_cr = true,
+                        '\n' => stats.line_endings.lf += 1,
+                        _ => stats.line_endings.other += 1,
+                    }
+                    stats.longest_line = stats.longest_line.max(line_len);
+                    line_len = 0;
+                } else {
+                    if c == '\t' {
+                        stats.has_tabs = true;
+                    } else if c.is_control() {
+                        stats.has_control_chars = true;
+                    }
+                    line_len += 1;
+                }
+            }
+        }
+
+        if after_cr {
+            stats.line_endings.cr += 1;
+        }
+        stats.longest_line = stats.longest_line.max(line_len);
+        stats.lines += stats.line_endings.total();
+        stats
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_chunks_01() {
+        let chunks = ["\u{FEFF}ab\r", "\ncd\r", "\r\n\u{2028}", "こんにちは\n", "\tx\u{1B}"];
+        let stats = DocumentStats::from_chunks(chunks.iter().cloned());
+
+        assert_eq!(stats.bytes, chunks.concat().len());
+        assert_eq!(stats.chars, chunks.concat().chars().count());
+        assert_eq!(stats.lines, 6);
+        assert_eq!(stats.longest_line, 5);
+        assert_eq!(
+            stats.line_endings,
+            LineEndings {
+                lf: 1,
+                crlf: 2,
+                cr: 1,
+                other: 1,
+            }
+        );
+        assert!(stats.has_tabs);
+        assert!(stats.has_bom);
+        assert!(stats.has_control_chars);
+    }
+
+    #[test]
+    fn from_chunks_02() {
+        let stats = DocumentStats::from_chunks(["a\r", "\r", "b\r"].iter().cloned());
+        assert_eq!(stats.lines, 4);
+        assert_eq!(stats.longest_line, 1);
+        assert_eq!(stats.line_endings.cr, 3);
+        assert!(!stats.has_tabs && !stats.has_bom && !stats.has_control_chars);
+
+        let stats = DocumentStats::from_chunks(["x", "\u{FEFF}"].iter().cloned());
+        assert!(!stats.has_bom);
+
+        let stats = DocumentStats::from_chunks(None.into_iter());
+        assert_eq!((stats.bytes, stats.lines, stats.longest_line), (0, 1, 0));
+    }
+}
@@ -0,0 +1,233 @@
+use std;
+use std::ops::Range;
+
+/// A record of a single edit to a text, in char indices.
+///
+/// The chars `start..old_end` of the text before the edit were replaced
+/// by the chars `start..new_end` of the text after the edit.  A sequence
+/// of edits applies in order, each to the text resulting from the ones
+/// before it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Edit {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+impl Edit {
+    /// Creates an `Edit` for an insertion of `len` chars at `char_idx`.
+    pub fn insert(char_idx: usize, len: usize) -> Edit {
+        Edit {
+            start: char_idx,
+            old_end: char_idx,
+            new_end: char_idx + len,
+        }
+    }
+
+    /// Creates an `Edit` for a removal of the chars in `range`.
+    pub fn remove(range: Range<usize>) -> Edit {
+        Edit {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start,
+        }
+    }
+
+    /// Creates an `Edit` for a replacement of the chars in `range` with
+    /// `len` new chars.
+    pub fn replace(range: Range<usize>, len: usize) -> Edit {
+        Edit {
+            start: range.start,
+            old_end: range.end,
+            new_end: range.start + len,
+        }
+    }
+
+    /// The replaced char range, in the text before the edit.
+    pub fn old_range(&self) -> Range<usize> {
+        self.start..self.old_end
+    }
+
+    /// The replacement char range, in the text after the edit.
+    pub fn new_range(&self) -> Range<usize> {
+        self.start..self.new_end
+    }
+
+    /// Maps a char index after the replaced range from the text before the
+    /// edit to the text after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is inside the replaced range (i.e. `start <
+    /// char_idx < old_end`).
+    pub(crate) fn shift(&self, char_idx: usize) -> usize {
+        if char_idx <= self.start {
+            char_idx
+        } else {
+            assert!(char_idx >= self.old_end);
+            char_idx - self.old_end + self.new_end
+        }
+    }
+
+    /// Maps a char index from the text before the edit to the text after
+    /// it.  See `EditMap::map_old_to_new()`.
+    pub(crate) fn map(&self, char_idx: usize, affinity: Affinity) -> usize {
+        if char_idx < self.start || (char_idx == self.start && affinity == Affinity::Before) {
+            char_idx
+        } else if char_idx >= self.old_end && char_idx > self.start {
+            char_idx - self.old_end + self.new_end
+        } else {
+            // At an insertion point, or inside the replaced range.
+            match affinity {
+                Affinity::Before => self.start,
+                Affinity::After => self.new_end,
+            }
+        }
+    }
+}
+
+//===========================================================
+
+/// Which side of an edit a mapped index sticks to.
+///
+/// This matters for indices where text was inserted, and for indices
+/// inside text that was replaced or removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Affinity {
+    /// Stick to the text before the index: it stays in front of inserted
+    /// text, and moves to the start of a replacement.
+    Before,
+
+    /// Stick to the text after the index: it moves past inserted text, and
+    /// to the end of a replacement.
+    After,
+}
+
+/// Maps char indices across a sequence of edits.
+///
+/// This lets data keyed by char indices into a text (folds, breakpoints,
+/// diagnostics, etc.) be carried over to the text after the edits.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Affinity, Edit, EditMap};
+/// // "Hello world!" -> "Hello, world!" -> "Hello, there!"
+/// let map: EditMap = vec![Edit::insert(5, 1), Edit::replace(7..12, 5)].into();
+///
+/// assert_eq!(map.map_old_to_new(6, Affinity::Before), 7);
+/// assert_eq!(map.map_old_to_new(8, Affinity::Before), 7);
+/// assert_eq!(map.map_old_to_new(8, Affinity::After), 12);
+/// assert_eq!(map.map_old_to_new(5, Affinity::Before), 5);
+/// assert_eq!(map.map_old_to_new(5, Affinity::After), 6);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditMap {
+    edits: Vec<Edit>,
+}
+
+impl EditMap {
+    /// Creates an empty `EditMap`, which maps every index to itself.
+    pub fn new() -> EditMap {
+        EditMap { edits: Vec::new() }
+    }
+
+    /// Adds an edit, applying to the text resulting from the edits
+    /// already in the map.
+    pub fn push(&mut self, edit: Edit) {
+        self.edits.push(edit);
+    }
+
+    /// Returns the edits in the map, in order.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Maps a char index in the text before all of the edits to the text
+    /// after them.
+    ///
+    /// Indices in unchanged text keep pointing at the same char.  Indices
+    /// where text was inserted, or inside text that was replaced or
+    /// removed, are resolved according to `affinity`.
+    ///
+    /// Runs in O(M) time, where M is the number of edits.
+    pub fn map_old_to_new(&self, char_idx: usize, affinity: Affinity) -> usize {
+        self.edits
+            .iter()
+            .fold(char_idx, |idx, edit| edit.map(idx, affinity))
+    }
+}
+
+impl From<Vec<Edit>> for EditMap {
+    #[inline]
+    fn from(edits: Vec<Edit>) -> Self {
+        EditMap { edits }
+    }
+}
+
+impl From<Edit> for EditMap {
+    #[inline]
+    fn from(edit: Edit) -> Self {
+        EditMap { edits: vec![edit] }
+    }
+}
+
+impl std::iter::FromIterator<Edit> for EditMap {
+    fn from_iter<T: IntoIterator<Item = Edit>>(iter: T) -> Self {
+        EditMap {
+            edits: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl std::iter::Extend<Edit> for EditMap {
+    fn extend<T: IntoIterator<Item = Edit>>(&mut self, iter: T) {
+        self.edits.extend(iter);
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Rope;
+
+    #[test]
+    fn map_old_to_new_01() {
+        let map = EditMap::from(Edit::replace(4..8, 2));
+        let before: Vec<usize> = (0..=10)
+            .map(|i| map.map_old_to_new(i, Affinity::Before))
+            .collect();
+        let after: Vec<usize> = (0..=10)
+            .map(|i| map.map_old_to_new(i, Affinity::After))
+            .collect();
+        assert_eq!(before, vec![0, 1, 2, 3, 4, 4, 4, 4, 6, 7, 8]);
+        assert_eq!(after, vec![0, 1, 2, 3, 6, 6, 6, 6, 6, 7, 8]);
+    }
+
+    #[test]
+    fn map_old_to_new_02() {
+        let mut map = EditMap::new();
+        map.push(Edit::insert(2, 3));
+        map.push(Edit::remove(0..1));
+        map.push(Edit::insert(4, 1));
+
+        assert_eq!(map.map_old_to_new(0, Affinity::Before), 0);
+        assert_eq!(map.map_old_to_new(1, Affinity::Before), 0);
+        assert_eq!(map.map_old_to_new(2, Affinity::Before), 1);
+        assert_eq!(map.map_old_to_new(2, Affinity::After), 5);
+        assert_eq!(map.map_old_to_new(3, Affinity::Before), 6);
+    }
+
+    #[test]
+    fn map_old_to_new_03() {
+        // Mapping through `Rope`'s edit records matches the edited text.
+        let mut rope = Rope::from_str("a  b\t\n  c  \n");
+        let text = rope.to_string();
+        let map: EditMap = rope.trim_trailing_whitespace_edits().into();
+        let c_idx = text.find('c').unwrap();
+        let new_idx = map.map_old_to_new(c_idx, Affinity::Before);
+        assert_eq!(rope.char(new_idx), 'c');
+    }
+}
@@ -0,0 +1,33 @@
+use std;
+use std::fmt;
+
+/// An error from a fallible `Rope` edit, such as
+/// [`Rope::try_insert()`](struct.Rope.html#method.try_insert) or
+/// [`Rope::try_remove()`](struct.Rope.html#method.try_remove).
+///
+/// The `Rope` is left unchanged when an edit fails.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RopeError {
+    /// A char index is past the end of the `Rope`.
+    CharIndexOutOfBounds { char_idx: usize, len_chars: usize },
+
+    /// The start of a char index range is greater than its end.
+    InvalidRange { start: usize, end: usize },
+}
+
+impl fmt::Display for RopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RopeError::CharIndexOutOfBounds { char_idx, len_chars } => write!(
+                f,
+                "char index {} is past the end of the Rope, whose length is {}",
+                char_idx, len_chars
+            ),
+            RopeError::InvalidRange { start, end } => {
+                write!(f, "range start {} is greater than its end {}", start, end)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RopeError {}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use slice::RopeSlice;
+
+/// Returns whether `c` is rendered as an escape by `EscapeControl`.
+///
+/// That's control chars other than line feeds, the byte order mark, and
+/// the bidi formatting chars.
+fn is_escaped(c: char) -> bool {
+    match c {
+        '\n' => false,
+        '\u{FEFF}'
+        | '\u{061C}'
+        | '\u{200E}'
+        | '\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2066}'..='\u{2069}' => true,
+        _ => c.is_control(),
+    }
+}
+
+/// A `Display` adaptor that renders invisible chars as visible escapes.
+///
+/// Control chars are written as `\t`, `\r`, `\0`, or `\u{..}`, and the
+/// byte order mark and bidi formatting chars (e.g. `\u{202e}`) are
+/// written as `\u{..}`.  Line feeds are kept as-is, so the output keeps
+/// its line structure, and everything else is written unchanged.
+///
+/// The text is written chunk by chunk, without copying it.
+///
+/// Created by [`Rope::escape_control()`](struct.Rope.html#method.escape_control)
+/// and [`RopeSlice::escape_control()`](struct.RopeSlice.html#method.escape_control).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// let rope = Rope::from_str("\u{FEFF}Hello\tworld!\r\n");
+/// assert_eq!(
+///     rope.escape_control().to_string(),
+///     "\\u{feff}Hello\\tworld!\\r\n"
+/// );
+/// ```
+#[derive(Copy, Clone)]
+pub struct EscapeControl<'a> {
+    slice: RopeSlice<'a>,
+}
+
+impl<'a> EscapeControl<'a> {
+    pub(crate) fn new(slice: RopeSlice<'a>) -> EscapeControl<'a> {
+        EscapeControl { slice }
+    }
+}
+
+impl<'a> fmt::Display for EscapeControl<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.slice.chunks() {
+            // Write runs of unescaped text in one go.
+            let mut run_start = 0;
+            for (i, c) in chunk.char_indices() {
+                if !is_escaped(c) {
+                    continue;
+                }
+                f.write_str(&chunk[run_start..i])?;
+                match c {
+                    '\t' => f.write_str("\\t")?,
+                    '\r' => f.write_str("\\r")?,
+                    '\0' => f.write_str("\\0")?,
+                    _ => write!(f, "\\u{{{:x}}}", c as u32)?,
+                }
+                run_start = i + c.len_utf8();
+            }
+            f.write_str(&chunk[run_start..])?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for EscapeControl<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use Rope;
+
+    #[test]
+    fn escape_control_01() {
+        let r = Rope::from_str("a\u{7}b\0c\u{1b}[0md\u{85}e\u{2028}f\\g\n");
+        assert_eq!(
+            "a\\u{7}b\\0c\\u{1b}[0md\\u{85}e\u{2028}f\\g\n",
+            r.escape_control().to_string()
+        );
+    }
+
+    #[test]
+    fn escape_control_02() {
+        let text = "\u{202E}abc\u{202C}\u{200E}\r\n\u{2067}x\u{2069}\u{FEFF}";
+        let r = Rope::from_str(&text.repeat(4));
+        assert!(r.chunks().count() > 1);
+        let expected = "\\u{202e}abc\\u{202c}\\u{200e}\\r\n\\u{2067}x\\u{2069}\\u{feff}";
+        assert_eq!(expected.repeat(4), r.escape_control().to_string());
+
+        let s = r.slice(1..8);
+        assert_eq!("abc\\u{202c}\\u{200e}\\r\n", s.escape_control().to_string());
+    }
+}
@@ -0,0 +1,211 @@
+use std::ops::Range;
+
+use spans::Spans;
+
+/// A stable identifier for an inserted range of text.
+///
+/// IDs are made of a site ID, identifying who made the insertion, and a
+/// sequence number that's unique per site.  This keeps IDs from different
+/// collaborators from colliding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InsertId {
+    site: u32,
+    seq: u64,
+}
+
+impl InsertId {
+    /// Creates an `InsertId` from its parts.
+    pub fn new(site: u32, seq: u64) -> InsertId {
+        InsertId { site, seq }
+    }
+
+    /// The ID of the site that made the insertion.
+    pub fn site(self) -> u32 {
+        self.site
+    }
+
+    /// The sequence number of the insertion within its site.
+    pub fn seq(self) -> u64 {
+        self.seq
+    }
+}
+
+/// Tracks stable IDs for inserted ranges of a text.
+///
+/// This is an opt-in layer to keep alongside a `Rope`: every insertion
+/// made through it gets an `InsertId`, which keeps referring to the same
+/// text no matter what other edits are made around or inside it.  This
+/// gives plugins and collaboration layers positions that survive
+/// arbitrary concurrent editing.
+///
+/// Text that was present when the `InsertIds` was created has no ID.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{InsertIds, Rope};
+/// let mut rope = Rope::from_str("Hello world!");
+/// let mut ids = InsertIds::new(rope.len_chars(), 0);
+///
+/// rope.insert(5, " big");
+/// let id = ids.insert(5, 4);
+///
+/// rope.insert(0, "Oh, ");
+/// ids.insert(0, 4);
+/// rope.remove(9..10);
+/// ids.remove(9..10);
+///
+/// let range = ids.resolve(id).unwrap();
+/// assert_eq!("big", rope.slice(range));
+/// ```
+#[derive(Debug, Clone)]
+pub struct InsertIds {
+    spans: Spans<Option<InsertId>>,
+    site: u32,
+    next_seq: u64,
+}
+
+impl InsertIds {
+    /// Creates an `InsertIds` for a text of `len` chars, assigning new IDs
+    /// with the given site ID.
+    pub fn new(len: usize, site: u32) -> InsertIds {
+        InsertIds {
+            spans: Spans::new(len, None),
+            site,
+            next_seq: 0,
+        }
+    }
+
+    /// Total length of the tracked text, in chars.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns whether the tracked text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Records an insertion of `len` chars at `char_idx`, returning its
+    /// new ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len()`).
+    pub fn insert(&mut self, char_idx: usize, len: usize) -> InsertId {
+        let id = InsertId::new(self.site, self.next_seq);
+        self.next_seq += 1;
+        self.insert_with_id(char_idx, len, id);
+        id
+    }
+
+    /// Records an insertion of `len` chars at `char_idx` with an existing
+    /// ID, e.g. an insertion received from another site.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len()`).
+    pub fn insert_with_id(&mut self, char_idx: usize, len: usize, id: InsertId) {
+        self.spans.edit_with(char_idx..char_idx, len, Some(id));
+    }
+
+    /// Records the removal of the chars in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len()`).
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.spans.edit(range, 0);
+    }
+
+    /// Returns the ID of the insertion that the char at `char_idx` came
+    /// from, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len()`).
+    pub fn id_at(&self, char_idx: usize) -> Option<InsertId> {
+        *self.spans.value_at(char_idx)
+    }
+
+    /// Returns the current char range of the insertion with the given ID.
+    ///
+    /// The range spans from the first to the last char of the insertion
+    /// that still exists, so it includes any text that was inserted inside
+    /// it since.  Returns `None` if the insertion was removed entirely.
+    ///
+    /// Runs in O(N) time, where N is the number of distinct runs of IDs.
+    pub fn resolve(&self, id: InsertId) -> Option<Range<usize>> {
+        let mut range: Option<Range<usize>> = None;
+        for (r, value) in self.spans.iter() {
+            if *value == Some(id) {
+                range = Some(match range {
+                    Some(prev) => prev.start..r.end,
+                    None => r,
+                });
+            }
+        }
+        range
+    }
+
+    /// Returns the current char ranges of the insertion with the given ID,
+    /// excluding any text that was inserted inside it since.
+    pub fn resolve_all(&self, id: InsertId) -> Vec<Range<usize>> {
+        self.spans
+            .iter()
+            .filter(|&(_, value)| *value == Some(id))
+            .map(|(r, _)| r)
+            .collect()
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_01() {
+        let mut ids = InsertIds::new(10, 3);
+        let a = ids.insert(5, 4);
+        let b = ids.insert(0, 2);
+        assert_eq!(InsertId::new(3, 0), a);
+        assert_eq!(InsertId::new(3, 1), b);
+        assert_eq!(16, ids.len());
+        assert_eq!(Some(7..11), ids.resolve(a));
+        assert_eq!(Some(0..2), ids.resolve(b));
+        assert_eq!(None, ids.id_at(2));
+        assert_eq!(Some(a), ids.id_at(7));
+    }
+
+    #[test]
+    fn insert_02() {
+        // Inserting inside an insertion splits it.
+        let mut ids = InsertIds::new(0, 0);
+        let a = ids.insert(0, 10);
+        let b = ids.insert(4, 3);
+        assert_eq!(Some(0..13), ids.resolve(a));
+        assert_eq!(vec![0..4, 7..13], ids.resolve_all(a));
+        assert_eq!(Some(4..7), ids.resolve(b));
+
+        let remote = InsertId::new(9, 0);
+        ids.insert_with_id(13, 1, remote);
+        assert_eq!(Some(13..14), ids.resolve(remote));
+    }
+
+    #[test]
+    fn remove_01() {
+        let mut ids = InsertIds::new(5, 0);
+        let a = ids.insert(2, 6);
+        ids.remove(0..4);
+        assert_eq!(Some(0..4), ids.resolve(a));
+        ids.remove(1..5);
+        assert_eq!(Some(0..1), ids.resolve(a));
+        ids.remove(0..1);
+        assert_eq!(None, ids.resolve(a));
+        assert!(ids.resolve_all(a).is_empty());
+        assert_eq!(2, ids.len());
+    }
+}
@@ -0,0 +1,117 @@
+use std;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::{Arc, Mutex, Weak};
+
+use tree::Node;
+
+/// A hash-consing table for deduplicating identical leaf chunks.
+///
+/// Ropes built or interned with the same `LeafInterner` share the storage
+/// of leaf chunks with identical contents, which can save a lot of memory
+/// for documents with massive repetition, such as generated code or logs.
+/// Editing a shared chunk transparently copies it first, so interning
+/// never affects a `Rope`'s contents.
+///
+/// The table only holds weak references, so it never keeps chunks alive
+/// on its own.  `LeafInterner` is a cheap handle: clones of it refer to
+/// the same table, and it can be shared between threads.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{LeafInterner, Rope, RopeBuilder};
+/// let interner = LeafInterner::new();
+/// let text = "Hello world!\n\n\n\n".repeat(1000);
+///
+/// let mut builder = RopeBuilder::with_chunk_size(64);
+/// builder.set_interner(&interner);
+/// builder.append(&text);
+/// let rope = builder.finish();
+///
+/// assert_eq!(rope, text.as_str());
+/// assert_eq!(1, interner.len());
+/// ```
+#[derive(Clone, Default)]
+pub struct LeafInterner {
+    table: Arc<Mutex<HashMap<u64, Vec<Weak<Node>>>>>,
+}
+
+impl LeafInterner {
+    /// Creates a new, empty `LeafInterner`.
+    pub fn new() -> Self {
+        LeafInterner::default()
+    }
+
+    /// The number of live distinct chunks in the table.
+    pub fn len(&self) -> usize {
+        let table = self.table.lock().unwrap();
+        table
+            .values()
+            .map(|bucket| bucket.iter().filter(|w| w.upgrade().is_some()).count())
+            .sum()
+    }
+
+    /// Returns whether there are no live chunks in the table.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes entries for chunks that are no longer used by any `Rope`.
+    pub fn purge(&self) {
+        let mut table = self.table.lock().unwrap();
+        table.retain(|_, bucket| {
+            bucket.retain(|w| w.upgrade().is_some());
+            !bucket.is_empty()
+        });
+    }
+
+    /// Returns the canonical leaf node with the same text as `leaf`,
+    /// registering `leaf` as canonical if there isn't one yet.
+    pub(crate) fn intern(&self, leaf: Arc<Node>) -> Arc<Node> {
+        debug_assert!(leaf.is_leaf());
+        let text = leaf.leaf_text();
+        let mut table = self.table.lock().unwrap();
+        let bucket = table.entry(text_hash(text)).or_default();
+
+        bucket.retain(|w| w.upgrade().is_some());
+        for weak in bucket.iter() {
+            if let Some(node) = weak.upgrade() {
+                if node.leaf_text() == text {
+                    return node;
+                }
+            }
+        }
+
+        bucket.push(Arc::downgrade(&leaf));
+        leaf
+    }
+
+    /// Interns all of the leaves in the given tree.
+    pub(crate) fn intern_tree(&self, node: &mut Arc<Node>) {
+        if node.is_leaf() {
+            let leaf = Arc::clone(node);
+            *node = self.intern(leaf);
+        } else {
+            for child in Arc::make_mut(node).children().nodes_mut() {
+                self.intern_tree(child);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for LeafInterner {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("LeafInterner")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Hashes a chunk's text, for its bucket in the table.
+fn text_hash(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
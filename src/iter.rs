@@ -6,9 +6,18 @@
 //! of the first and last yielded item will be truncated to match the
 //! `RopeSlice`.
 
+use std::iter::Peekable;
+use std::ops::Range;
 use std::str;
 use std::sync::Arc;
 
+use unicode_width::UnicodeWidthChar;
+
+use chunk_id::ChunkId;
+use pattern::PatternMatcher;
+use search::{ChunkSearcher, ExactSearcher, SearchOptions, Searcher};
+use spans::{tree_len, SpanNode, SpanTree};
+use str_utils::is_line_break;
 use tree::Node;
 use slice::RopeSlice;
 
@@ -96,6 +105,53 @@ impl<'a> Iterator for Chars<'a> {
 
 //==========================================================
 
+/// An iterator over a `Rope`'s text encoded as UTF-16 code units.
+///
+/// Encodes a chunk at a time, without collecting the text first.
+pub struct EncodeUtf16<'a> {
+    chunk_iter: Chunks<'a>,
+    cur_chunk: str::EncodeUtf16<'a>,
+}
+
+impl<'a> EncodeUtf16<'a> {
+    pub(crate) fn new(node: &'a Arc<Node>) -> EncodeUtf16<'a> {
+        EncodeUtf16 {
+            chunk_iter: Chunks::new(node),
+            cur_chunk: "".encode_utf16(),
+        }
+    }
+
+    pub(crate) fn new_with_range(
+        node: &'a Arc<Node>,
+        start_char: usize,
+        end_char: usize,
+    ) -> EncodeUtf16<'a> {
+        EncodeUtf16 {
+            chunk_iter: Chunks::new_with_range(node, start_char, end_char),
+            cur_chunk: "".encode_utf16(),
+        }
+    }
+}
+
+impl<'a> Iterator for EncodeUtf16<'a> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        loop {
+            if let Some(cu) = self.cur_chunk.next() {
+                return Some(cu);
+            } else if let Some(chunk) = self.chunk_iter.next() {
+                self.cur_chunk = chunk.encode_utf16();
+                continue;
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+//==========================================================
+
 /// An iterator over a `Rope`'s lines.
 ///
 /// The returned lines include the line-break at the end.
@@ -127,6 +183,22 @@ impl<'a> Lines<'a> {
             line_idx: node.char_to_line(start_char),
         }
     }
+
+    /// Turns this into an iterator that yields each line with leading and
+    /// trailing whitespace (including the line break) removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("  Hello\t\n\n world!  ");
+    /// let lines: Vec<_> = rope.lines().trimmed().collect();
+    ///
+    /// assert_eq!(lines, ["Hello", "", "world!"]);
+    /// ```
+    pub fn trimmed(self) -> TrimmedLines<'a> {
+        TrimmedLines { lines: self }
+    }
 }
 
 impl<'a> Iterator for Lines<'a> {
@@ -159,6 +231,451 @@ impl<'a> Iterator for Lines<'a> {
 
 //==========================================================
 
+/// An iterator over a `Rope`'s lines, with leading and trailing whitespace
+/// removed.
+///
+/// Whitespace is as defined by `char::is_whitespace()`, the same as for
+/// `str::trim()`.  Lines that are entirely whitespace are yielded as empty
+/// slices.
+///
+/// Created by [`Lines::trimmed()`](struct.Lines.html#method.trimmed).
+pub struct TrimmedLines<'a> {
+    lines: Lines<'a>,
+}
+
+impl<'a> Iterator for TrimmedLines<'a> {
+    type Item = RopeSlice<'a>;
+
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        let line = self.lines.next()?;
+
+        // Char indices of the first non-whitespace char and just past the
+        // last one.
+        let mut start = None;
+        let mut end = 0;
+        let mut char_idx = 0;
+        for chunk in line.chunks() {
+            for c in chunk.chars() {
+                char_idx += 1;
+                if !c.is_whitespace() {
+                    start = start.or(Some(char_idx - 1));
+                    end = char_idx;
+                }
+            }
+        }
+
+        Some(line.slice(start.unwrap_or(0)..end))
+    }
+}
+
+//==========================================================
+
+/// An iterator over a `Rope`'s lines, along with the index and byte range
+/// of each line.
+///
+/// Yields `(line_idx, byte_range, line)`, where `line` is the same as
+/// the corresponding item of [`Lines`](struct.Lines.html).  The line
+/// index and byte range are relative to the start of the
+/// `Rope`/`RopeSlice` being iterated over.
+pub struct IndexedLines<'a> {
+    lines: Lines<'a>,
+    line_idx: usize,
+    byte_idx: usize,
+}
+
+impl<'a> IndexedLines<'a> {
+    pub(crate) fn new(lines: Lines<'a>) -> IndexedLines<'a> {
+        IndexedLines {
+            lines,
+            line_idx: 0,
+            byte_idx: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for IndexedLines<'a> {
+    type Item = (usize, Range<usize>, RopeSlice<'a>);
+
+    fn next(&mut self) -> Option<(usize, Range<usize>, RopeSlice<'a>)> {
+        let line = self.lines.next()?;
+        let line_idx = self.line_idx;
+        let start = self.byte_idx;
+        self.line_idx += 1;
+        self.byte_idx += line.len_bytes();
+        Some((line_idx, start..self.byte_idx, line))
+    }
+}
+
+//==========================================================
+
+/// An iterator over the matches of a pattern.
+///
+/// Yields the char index range of each match, relative to the start of
+/// the `Rope`/`RopeSlice` that was searched.
+///
+/// Created by [`RopeSlice::matches()`](../struct.RopeSlice.html#method.matches)
+/// and [`RopeSlice::matches_with()`](../struct.RopeSlice.html#method.matches_with),
+/// which yield non-overlapping matches, and by
+/// [`RopeSlice::matches_overlapping()`](../struct.RopeSlice.html#method.matches_overlapping)
+/// and its `_with()` variant, which yield overlapping ones.
+pub struct Matches<'a> {
+    inner: MatchesInner<'a>,
+}
+
+enum MatchesInner<'a> {
+    // Exact matches are searched for a whole chunk at a time.
+    Exact(SearchMatches<'a, ExactSearcher>),
+    // Otherwise the text has to be folded and matched char by char.
+    Folded {
+        chars: Chars<'a>,
+        char_idx: usize,
+        searcher: Searcher,
+    },
+}
+
+impl<'a> Matches<'a> {
+    pub(crate) fn new(
+        slice: RopeSlice<'a>,
+        pattern: &str,
+        options: SearchOptions,
+        overlapping: bool,
+    ) -> Matches<'a> {
+        let inner = if options == SearchOptions::default() {
+            MatchesInner::Exact(SearchMatches::new(
+                slice,
+                ExactSearcher::new(pattern, overlapping),
+            ))
+        } else {
+            let mut searcher = Searcher::new(pattern, options);
+            searcher.set_overlapping(overlapping);
+            MatchesInner::Folded {
+                chars: slice.chars(),
+                char_idx: 0,
+                searcher,
+            }
+        };
+        Matches { inner }
+    }
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        match self.inner {
+            MatchesInner::Exact(ref mut matches) => matches.next(),
+            MatchesInner::Folded {
+                ref mut chars,
+                ref mut char_idx,
+                ref mut searcher,
+            } => {
+                for c in chars {
+                    let idx = *char_idx;
+                    *char_idx += 1;
+                    if let Some(start) = searcher.push(c, idx) {
+                        return Some(start..(idx + 1));
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+//==========================================================
+
+/// An iterator over the matches found by a
+/// [`ChunkSearcher`](../trait.ChunkSearcher.html).
+///
+/// Yields the char index range of each match, relative to the start of
+/// the `Rope`/`RopeSlice` that was searched.  Chunks are only fed to the
+/// searcher as more matches are needed.
+///
+/// Created by [`RopeSlice::search()`](../struct.RopeSlice.html#method.search).
+pub struct SearchMatches<'a, S> {
+    slice: RopeSlice<'a>,
+    chunks: Chunks<'a>,
+    byte_idx: usize,
+    searcher: S,
+    matches: Vec<Range<usize>>,
+    match_idx: usize,
+    done: bool,
+}
+
+impl<'a, S: ChunkSearcher> SearchMatches<'a, S> {
+    pub(crate) fn new(slice: RopeSlice<'a>, searcher: S) -> SearchMatches<'a, S> {
+        SearchMatches {
+            slice,
+            chunks: slice.chunks(),
+            byte_idx: 0,
+            searcher,
+            matches: Vec::new(),
+            match_idx: 0,
+            done: false,
+        }
+    }
+}
+
+impl<'a, S: ChunkSearcher> Iterator for SearchMatches<'a, S> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        while self.match_idx == self.matches.len() {
+            if self.done {
+                return None;
+            }
+            self.matches.clear();
+            self.match_idx = 0;
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.searcher.search_chunk(chunk, self.byte_idx, &mut self.matches);
+                    self.byte_idx += chunk.len();
+                }
+                None => {
+                    self.searcher.finish(self.byte_idx, &mut self.matches);
+                    self.done = true;
+                }
+            }
+        }
+
+        let range = self.matches[self.match_idx].clone();
+        self.match_idx += 1;
+        debug_assert!(range.start <= range.end);
+        Some(self.slice.byte_to_char(range.start)..self.slice.byte_to_char(range.end))
+    }
+}
+
+//==========================================================
+
+/// An iterator over the matches of a `Pattern`.
+///
+/// Yields the char index of each match, relative to the start of the
+/// `Rope`/`RopeSlice` that was searched, along with the matched text.
+///
+/// Created by [`RopeSlice::match_indices()`](../struct.RopeSlice.html#method.match_indices).
+pub struct MatchIndices<'a, M> {
+    slice: RopeSlice<'a>,
+    chars: Chars<'a>,
+    char_idx: usize,
+    matcher: M,
+}
+
+impl<'a, M: PatternMatcher> MatchIndices<'a, M> {
+    pub(crate) fn new(slice: RopeSlice<'a>, matcher: M) -> MatchIndices<'a, M> {
+        MatchIndices {
+            slice,
+            chars: slice.chars(),
+            char_idx: 0,
+            matcher,
+        }
+    }
+}
+
+impl<'a, M: PatternMatcher> Iterator for MatchIndices<'a, M> {
+    type Item = (usize, RopeSlice<'a>);
+
+    fn next(&mut self) -> Option<(usize, RopeSlice<'a>)> {
+        for c in &mut self.chars {
+            let idx = self.char_idx;
+            self.char_idx += 1;
+            if let Some(start) = self.matcher.next_match(c, idx) {
+                return Some((start, self.slice.slice(start..(idx + 1))));
+            }
+        }
+        None
+    }
+}
+
+//==========================================================
+
+/// An iterator over the pieces of text between the matches of a
+/// `Pattern`.
+///
+/// Created by [`RopeSlice::split()`](../struct.RopeSlice.html#method.split).
+pub struct Split<'a, M> {
+    slice: RopeSlice<'a>,
+    chars: Chars<'a>,
+    char_idx: usize,
+    matcher: M,
+    piece_start: usize,
+    done: bool,
+}
+
+impl<'a, M: PatternMatcher> Split<'a, M> {
+    pub(crate) fn new(slice: RopeSlice<'a>, matcher: M) -> Split<'a, M> {
+        Split {
+            slice,
+            chars: slice.chars(),
+            char_idx: 0,
+            matcher,
+            piece_start: 0,
+            done: false,
+        }
+    }
+}
+
+impl<'a, M: PatternMatcher> Iterator for Split<'a, M> {
+    type Item = RopeSlice<'a>;
+
+    fn next(&mut self) -> Option<RopeSlice<'a>> {
+        if self.done {
+            return None;
+        }
+        for c in &mut self.chars {
+            let idx = self.char_idx;
+            self.char_idx += 1;
+            if let Some(start) = self.matcher.next_match(c, idx) {
+                let piece = self.slice.slice(self.piece_start..start);
+                self.piece_start = idx + 1;
+                return Some(piece);
+            }
+        }
+        self.done = true;
+        Some(self.slice.slice(self.piece_start..))
+    }
+}
+
+//==========================================================
+
+/// An iterator over the visual rows of soft-wrapped text.
+///
+/// Yields the char index range of each row, relative to the start of the
+/// `RopeSlice` it was created from.  Line breaks always end a row, and are
+/// included at the end of the row they terminate.
+///
+/// Created by [`RopeSlice::wrap()`](../struct.RopeSlice.html#method.wrap)
+/// and [`RopeSlice::wrap_words()`](../struct.RopeSlice.html#method.wrap_words).
+pub struct Wrap<'a> {
+    chars: Peekable<Chars<'a>>,
+    width: usize,
+    tab_width: usize,
+    word_aware: bool,
+    pending: Option<char>,
+    char_idx: usize,
+    row_start: usize,
+    col: usize,
+    break_idx: usize,
+    word: Vec<char>,
+    yielded: bool,
+    done: bool,
+}
+
+impl<'a> Wrap<'a> {
+    pub(crate) fn new(chars: Chars<'a>, width: usize, tab_width: usize, word_aware: bool) -> Wrap<'a> {
+        assert!(width > 0, "Wrap width must be non-zero.");
+        assert!(tab_width > 0, "Tab width must be non-zero.");
+        Wrap {
+            chars: chars.peekable(),
+            width,
+            tab_width,
+            word_aware,
+            pending: None,
+            char_idx: 0,
+            row_start: 0,
+            col: 0,
+            break_idx: 0,
+            word: Vec::new(),
+            yielded: false,
+            done: false,
+        }
+    }
+
+    /// Display width of `c` when placed at column `col`.
+    fn char_width(&self, c: char, col: usize) -> usize {
+        if c == '\t' {
+            self.tab_width - (col % self.tab_width)
+        } else {
+            c.width().unwrap_or(0)
+        }
+    }
+
+    fn end_row(&mut self, end: usize) -> Range<usize> {
+        let row = self.row_start..end;
+        self.row_start = end;
+        self.break_idx = end;
+        self.col = 0;
+        self.yielded = true;
+        row
+    }
+}
+
+impl<'a> Iterator for Wrap<'a> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let c = match self.pending.take().or_else(|| self.chars.next()) {
+                Some(c) => c,
+                None => {
+                    self.done = true;
+                    if self.char_idx > self.row_start || !self.yielded {
+                        let end = self.char_idx;
+                        return Some(self.end_row(end));
+                    } else {
+                        return None;
+                    }
+                }
+            };
+            let idx = self.char_idx;
+            self.char_idx += 1;
+
+            // Line breaks always end the row.
+            if is_line_break(c) {
+                if c == '\r' && self.chars.peek() == Some(&'\n') {
+                    self.chars.next();
+                    self.char_idx += 1;
+                }
+                self.word.clear();
+                let end = self.char_idx;
+                return Some(self.end_row(end));
+            }
+
+            let width = self.char_width(c, self.col);
+            let hangs = self.word_aware && c.is_whitespace();
+            if self.col > 0 && (self.col + width) > self.width && !hangs {
+                if self.word_aware && self.break_idx > self.row_start {
+                    // Break at the last word boundary, carrying the
+                    // current word over to the next row.
+                    let end = self.break_idx;
+                    let row = self.end_row(end);
+                    for i in 0..self.word.len() {
+                        let ch = self.word[i];
+                        self.col += self.char_width(ch, self.col);
+                    }
+                    self.break_idx = self.row_start;
+                    self.pending = Some(c);
+                    self.char_idx = idx;
+                    return Some(row);
+                } else {
+                    let row = self.end_row(idx);
+                    self.word.clear();
+                    self.col = self.char_width(c, 0);
+                    if self.word_aware {
+                        self.word.push(c);
+                    }
+                    return Some(row);
+                }
+            }
+
+            self.col += width;
+            if self.word_aware {
+                if c.is_whitespace() {
+                    self.break_idx = self.char_idx;
+                    self.word.clear();
+                } else {
+                    self.word.push(c);
+                }
+            }
+        }
+    }
+}
+
+//==========================================================
+
 /// An iterator over a `Rope`'s contiguous `str` chunks.
 ///
 /// Internally, each `Rope` stores text as a segemented collection of utf8
@@ -175,18 +692,23 @@ impl<'a> Iterator for Lines<'a> {
 /// are split.  For example, they may be zero-sized, they don't necessarily
 /// align with line breaks, etc.
 ///
+/// Chunks can also be iterated in reverse, from the end of the text, with
+/// `next_back()` or `rev()`.  Each chunk from the end takes O(log N) time.
+///
 /// The converse of this API is [`RopeBuilder`](../struct.RopeBuilder.html),
 /// which is useful for efficiently streaming text data _into_ a rope.
 pub struct Chunks<'a> {
+    root: &'a Arc<Node>,
     node_stack: Vec<&'a Arc<Node>>,
     start: usize,
-    end: usize,
+    end: usize, // Also moved back by iterating from the end
     idx: usize,
 }
 
 impl<'a> Chunks<'a> {
     pub(crate) fn new(node: &Arc<Node>) -> Chunks {
         Chunks {
+            root: node,
             node_stack: vec![node],
             start: 0,
             end: node.text_info().bytes as usize,
@@ -196,6 +718,7 @@ impl<'a> Chunks<'a> {
 
     pub(crate) fn new_with_range(node: &Arc<Node>, start_char: usize, end_char: usize) -> Chunks {
         Chunks {
+            root: node,
             node_stack: vec![node],
             start: node.char_to_byte(start_char),
             end: node.char_to_byte(end_char),
@@ -204,10 +727,31 @@ impl<'a> Chunks<'a> {
     }
 }
 
-impl<'a> Iterator for Chunks<'a> {
-    type Item = &'a str;
+impl<'a> Chunks<'a> {
+    /// Turns this into an iterator that also yields each chunk's
+    /// [`ChunkId`](../struct.ChunkId.html).
+    ///
+    /// When iterating over a `RopeSlice`, the first and last chunks are
+    /// truncated as usual, but their ids are those of the whole chunks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str(&"Hello world!\n".repeat(1000));
+    /// let ids: Vec<_> = rope.chunks().with_ids().map(|(id, _)| id).collect();
+    ///
+    /// // Editing the end of the text doesn't change the first chunk.
+    /// let len = rope.len_chars();
+    /// rope.insert(len, "Goodbye!");
+    /// assert_eq!(rope.chunks().with_ids().next().unwrap().0, ids[0]);
+    /// ```
+    pub fn with_ids(self) -> ChunksWithIds<'a> {
+        ChunksWithIds { chunks: self }
+    }
 
-    fn next(&mut self) -> Option<&'a str> {
+    /// Returns the next chunk along with the leaf node it's from.
+    fn next_leaf(&mut self) -> Option<(&'a Arc<Node>, &'a str)> {
         if self.idx >= self.end {
             return None;
         }
@@ -227,7 +771,7 @@ impl<'a> Iterator for Chunks<'a> {
                             self.end - self.idx
                         };
                         self.idx += text.len();
-                        return Some(&text[start_byte..end_byte]);
+                        return Some((node, &text[start_byte..end_byte]));
                     }
 
                     Node::Internal(ref children) => {
@@ -253,6 +797,188 @@ impl<'a> Iterator for Chunks<'a> {
             }
         }
     }
+
+    /// Returns the previous chunk from the end along with the leaf node
+    /// it's from.
+    fn next_back_leaf(&mut self) -> Option<(&'a Arc<Node>, &'a str)> {
+        // Everything before `front` has been yielded from the front.
+        let front = self.start.max(self.idx);
+        if self.end <= front {
+            return None;
+        }
+
+        let last_byte = self.end - 1;
+        let (leaf, before, _) = Node::find_leaf(self.root, |inf| inf.bytes as usize > last_byte);
+        let leaf_start = before.bytes as usize;
+        let start_byte = front.max(leaf_start);
+        let chunk = &leaf.leaf_text()[(start_byte - leaf_start)..(self.end - leaf_start)];
+        self.end = start_byte;
+        Some((leaf, chunk))
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.next_leaf().map(|(_, chunk)| chunk)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Chunks<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.next_back_leaf().map(|(_, chunk)| chunk)
+    }
+}
+
+//==========================================================
+
+/// An iterator over a `Rope`'s chunks along with their ids.
+///
+/// Created by [`Chunks::with_ids()`](struct.Chunks.html#method.with_ids).
+pub struct ChunksWithIds<'a> {
+    chunks: Chunks<'a>,
+}
+
+impl<'a> Iterator for ChunksWithIds<'a> {
+    type Item = (ChunkId, &'a str);
+
+    fn next(&mut self) -> Option<(ChunkId, &'a str)> {
+        self.chunks
+            .next_leaf()
+            .map(|(leaf, chunk)| (ChunkId::new(leaf), chunk))
+    }
+}
+
+impl<'a> DoubleEndedIterator for ChunksWithIds<'a> {
+    fn next_back(&mut self) -> Option<(ChunkId, &'a str)> {
+        self.chunks
+            .next_back_leaf()
+            .map(|(leaf, chunk)| (ChunkId::new(leaf), chunk))
+    }
+}
+
+//==========================================================
+
+/// An iterator over the maximal runs of chars that share a class.
+///
+/// Each char is classified by a user-supplied function, and each run of
+/// consecutive chars with equal classes is yielded as its char index range
+/// along with the class.  Ranges are relative to the start of the
+/// `Rope`/`RopeSlice` being iterated over.
+///
+/// Created by [`RopeSlice::class_runs()`](../struct.RopeSlice.html#method.class_runs).
+pub struct ClassRuns<'a, F, K> {
+    chunks: Chunks<'a>,
+    chunk: str::Chars<'a>,
+    classify: F,
+    char_idx: usize,
+    pending: Option<(usize, K)>,
+}
+
+impl<'a, F, K> ClassRuns<'a, F, K>
+where
+    F: FnMut(char) -> K,
+    K: PartialEq,
+{
+    pub(crate) fn new(chunks: Chunks<'a>, classify: F) -> ClassRuns<'a, F, K> {
+        ClassRuns {
+            chunks,
+            chunk: "".chars(),
+            classify,
+            char_idx: 0,
+            pending: None,
+        }
+    }
+
+    /// Classifies the next char, returning its index and class.
+    fn next_class(&mut self) -> Option<(usize, K)> {
+        loop {
+            if let Some(c) = self.chunk.next() {
+                let idx = self.char_idx;
+                self.char_idx += 1;
+                return Some((idx, (self.classify)(c)));
+            }
+            self.chunk = self.chunks.next()?.chars();
+        }
+    }
+}
+
+impl<'a, F, K> Iterator for ClassRuns<'a, F, K>
+where
+    F: FnMut(char) -> K,
+    K: PartialEq,
+{
+    type Item = (Range<usize>, K);
+
+    fn next(&mut self) -> Option<(Range<usize>, K)> {
+        let (start, class) = match self.pending.take() {
+            Some(pending) => pending,
+            None => self.next_class()?,
+        };
+        while let Some((idx, next_class)) = self.next_class() {
+            if next_class != class {
+                self.pending = Some((idx, next_class));
+                return Some((start..idx, class));
+            }
+        }
+        Some((start..self.char_idx, class))
+    }
+}
+
+//==========================================================
+
+/// An iterator over the runs of a `Spans`.
+///
+/// Yields each run's char range along with its value.
+pub struct Runs<'a, T: 'a> {
+    // The nodes whose runs are still to come, along with the char index
+    // each run starts at.  The next run is on top.
+    stack: Vec<(&'a SpanNode<T>, usize)>,
+}
+
+impl<'a, T> Runs<'a, T> {
+    /// Creates an iterator starting at the run containing `char_idx`.
+    pub(crate) fn new(root: &'a SpanTree<T>, char_idx: usize) -> Runs<'a, T> {
+        let mut stack = Vec::new();
+        let mut tree = root;
+        let mut pos = 0;
+        while let Some(ref node) = *tree {
+            let start = pos + tree_len(&node.left);
+            let end = start + node.run.0;
+            if char_idx < start {
+                stack.push((&**node, start));
+                tree = &node.left;
+            } else if char_idx < end {
+                stack.push((&**node, start));
+                break;
+            } else {
+                pos = end;
+                tree = &node.right;
+            }
+        }
+        Runs { stack }
+    }
+
+    /// Pushes the nodes down the left edge of `tree`, which starts at
+    /// `pos`.
+    fn push_left_edge(&mut self, mut tree: &'a SpanTree<T>, pos: usize) {
+        while let Some(ref node) = *tree {
+            self.stack.push((&**node, pos + tree_len(&node.left)));
+            tree = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Runs<'a, T> {
+    type Item = (Range<usize>, &'a T);
+
+    fn next(&mut self) -> Option<(Range<usize>, &'a T)> {
+        let (node, start) = self.stack.pop()?;
+        let end = start + node.run.0;
+        self.push_left_edge(&node.right, end);
+        Some((start..end, &node.run.1))
+    }
 }
 
 //===========================================================
@@ -344,6 +1070,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encode_utf16_01() {
+        let text = format!("{}\u{1F600}a\u{10FFFF}\u{1F468}\u{200D}\u{1F469}", TEXT);
+        let r = Rope::from_str(&text);
+        assert!(r.encode_utf16().eq(text.encode_utf16()));
+        assert_eq!(r.encode_utf16().count(), r.len_utf16_cu());
+
+        let s = r.slice(100..r.len_chars() - 2);
+        let slice_text = s.to_string();
+        assert!(s.encode_utf16().eq(slice_text.encode_utf16()));
+        assert_eq!(0, Rope::new().encode_utf16().count());
+    }
+
     #[test]
     fn lines_01() {
         let r = Rope::from_str(TEXT);
@@ -384,6 +1123,93 @@ mod tests {
         assert!(lines.next().is_none());
     }
 
+    #[test]
+    fn indexed_lines_01() {
+        let r = Rope::from_str(TEXT);
+
+        let mut count = 0;
+        for ((line_idx, byte_range, line), line2) in r.indexed_lines().zip(r.lines()) {
+            assert_eq!(line_idx, count);
+            assert_eq!(line, line2);
+            assert_eq!(byte_range.start, r.line_to_byte(line_idx));
+            assert_eq!(&TEXT[byte_range], line);
+            count += 1;
+        }
+        assert_eq!(count, 34);
+    }
+
+    #[test]
+    fn indexed_lines_sliced_01() {
+        let r = Rope::from_str("One\nTwo ☺\nThree\n");
+        let s = r.slice(5..13);
+        let lines: Vec<_> = s
+            .indexed_lines()
+            .map(|(idx, range, line)| (idx, range, line.to_string()))
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                (0, 0..7, "wo ☺\n".to_string()),
+                (1, 7..10, "Thr".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn trimmed_lines_01() {
+        let r = Rope::from_str(TEXT);
+
+        let mut count = 0;
+        for (line, trimmed) in r.lines().zip(r.lines().trimmed()) {
+            assert_eq!(line.to_string().trim(), trimmed);
+            count += 1;
+        }
+        assert_eq!(count, 34);
+    }
+
+    #[test]
+    fn trimmed_lines_02() {
+        let r = Rope::from_str(" \t\r\n  One two\u{3000}\r\n\u{2028}\n Three");
+        let lines: Vec<_> = r.lines().trimmed().collect();
+        assert_eq!(lines, ["", "One two", "", "", "Three"]);
+
+        let s = r.slice(1..11);
+        let lines: Vec<_> = s.lines().trimmed().collect();
+        assert_eq!(lines, ["", "One t"]);
+    }
+
+    #[test]
+    fn class_runs_01() {
+        let r = Rope::from_str(TEXT);
+
+        let mut idx = 0;
+        let mut prev = None;
+        for (range, is_space) in r.class_runs(char::is_whitespace) {
+            assert_eq!(range.start, idx);
+            assert!(range.end > range.start);
+            assert_ne!(prev, Some(is_space));
+            for c in r.slice(range.clone()).chars() {
+                assert_eq!(c.is_whitespace(), is_space);
+            }
+            idx = range.end;
+            prev = Some(is_space);
+        }
+        assert_eq!(idx, r.len_chars());
+    }
+
+    #[test]
+    fn class_runs_sliced_01() {
+        let r = Rope::from_str("foo  bar\t\tbaz");
+        let runs: Vec<_> = r.slice(1..12).class_runs(char::is_alphabetic).collect();
+        assert_eq!(
+            runs,
+            vec![(0..2, true), (2..4, false), (4..7, true), (7..9, false), (9..11, true)]
+        );
+
+        assert_eq!(r.slice(3..3).class_runs(char::is_alphabetic).count(), 0);
+    }
+
     #[test]
     fn chunks_01() {
         let r = Rope::from_str(TEXT);
@@ -395,6 +1221,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn chunks_with_ids_01() {
+        let mut r = Rope::from_str(TEXT);
+        let r2 = r.clone();
+        let chunks: Vec<_> = r.chunks().collect();
+        let with_ids: Vec<_> = r.chunks().with_ids().map(|(_, chunk)| chunk).collect();
+        assert_eq!(with_ids, chunks);
+
+        // Editing the text only changes the ids of the edited chunks.
+        let ids: Vec<_> = r.chunks().with_ids().map(|(id, _)| id).collect();
+        r.insert(0, "Hi! ");
+        let new_ids: Vec<_> = r.chunks().with_ids().map(|(id, _)| id).collect();
+        assert_ne!(new_ids[0], ids[0]);
+        let tail = ids.len() - 10;
+        for (old, new) in ids[tail..].iter().zip(&new_ids[(new_ids.len() - 10)..]) {
+            assert_eq!(old, new);
+        }
+
+        // A chunk edited in place gets a new id.
+        drop(r2);
+        let first = r.chunks().with_ids().next().unwrap().0;
+        r.insert(1, "o");
+        assert_ne!(r.chunks().with_ids().next().unwrap().0, first);
+    }
+
+    #[test]
+    fn chunks_with_ids_sliced_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(3..r.len_chars() - 3);
+        let ids: Vec<_> = r.chunks().with_ids().map(|(id, _)| id).collect();
+        let s_ids: Vec<_> = s.chunks().with_ids().map(|(id, _)| id).collect();
+        assert_eq!(ids, s_ids);
+        assert_eq!(s.chunks().with_ids().next().unwrap().1, &r.chunks().next().unwrap()[3..]);
+    }
+
     #[test]
     fn bytes_sliced_01() {
         let r = Rope::from_str(TEXT);
@@ -464,4 +1325,105 @@ mod tests {
             idx += chunk.len();
         }
     }
+
+    #[test]
+    fn chunks_rev_01() {
+        let r = Rope::from_str(TEXT);
+        let forward: Vec<_> = r.chunks().collect();
+        let mut backward: Vec<_> = r.chunks().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let s = r.slice(116..331);
+        let forward: Vec<_> = s.chunks().collect();
+        let mut backward: Vec<_> = s.chunks().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+
+        let ids: Vec<_> = s.chunks().with_ids().map(|(id, _)| id).collect();
+        let mut rev_ids: Vec<_> = s.chunks().with_ids().rev().map(|(id, _)| id).collect();
+        rev_ids.reverse();
+        assert_eq!(ids, rev_ids);
+
+        assert_eq!(None, Rope::new().chunks().next_back());
+        assert_eq!(None, r.slice(5..5).chunks().next_back());
+        assert_eq!(Some("b"), Rope::from_str("ab").slice(1..).chunks().next_back());
+    }
+
+    #[test]
+    fn chunks_rev_02() {
+        // Alternating between the two ends meets in the middle.
+        let r = Rope::from_str(TEXT);
+        for &(start, end) in &[(0, r.len_chars()), (116, 331), (3, 4)] {
+            let s = r.slice(start..end);
+            let mut chunks = s.chunks();
+            let (mut front, mut back) = (String::new(), Vec::new());
+            loop {
+                match chunks.next() {
+                    Some(chunk) => front.push_str(chunk),
+                    None => break,
+                }
+                match chunks.next_back() {
+                    Some(chunk) => back.push(chunk),
+                    None => break,
+                }
+            }
+            assert_eq!(None, chunks.next());
+            assert_eq!(None, chunks.next_back());
+            for chunk in back.iter().rev() {
+                front.push_str(chunk);
+            }
+            assert_eq!(s, front.as_str());
+        }
+    }
+
+    #[test]
+    fn wrap_01() {
+        let r = Rope::from_str("Hello there!  How're you doing?\n");
+        let rows: Vec<_> = r.line(0).wrap(10, 4).collect();
+
+        assert_eq!(vec![0..10, 10..20, 20..30, 30..32], rows);
+    }
+
+    #[test]
+    fn wrap_02() {
+        // Wide chars and tabs
+        let r = Rope::from_str("\tこんにちは\tab");
+        let rows: Vec<_> = r.slice(..).wrap(8, 4).collect();
+
+        assert_eq!(vec![0..3, 3..7, 7..9], rows);
+    }
+
+    #[test]
+    fn wrap_03() {
+        let r = Rope::from_str("");
+        let rows: Vec<_> = r.slice(..).wrap(8, 4).collect();
+
+        assert_eq!(vec![0..0], rows);
+    }
+
+    #[test]
+    fn wrap_04() {
+        let r = Rope::from_str("ab\r\ncd\n\nef");
+        let rows: Vec<_> = r.slice(..).wrap(8, 4).collect();
+
+        assert_eq!(vec![0..4, 4..7, 7..8, 8..10], rows);
+    }
+
+    #[test]
+    fn wrap_words_01() {
+        let r = Rope::from_str("Hello there!  How're you doing?");
+        let rows: Vec<_> = r.slice(..).wrap_words(10, 4).collect();
+
+        assert_eq!(vec![0..6, 6..14, 14..25, 25..31], rows);
+    }
+
+    #[test]
+    fn wrap_words_02() {
+        // Words longer than the width get broken.
+        let r = Rope::from_str("a abcdefghij b");
+        let rows: Vec<_> = r.slice(..).wrap_words(4, 4).collect();
+
+        assert_eq!(vec![0..2, 2..6, 6..10, 10..14], rows);
+    }
 }
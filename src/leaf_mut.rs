@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+use tree::NodeText;
+
+/// Mutable access to the text of one of a `Rope`'s leaves, for edits that
+/// don't change its length, such as changing the case of ASCII letters or
+/// masking out text.
+///
+/// Only ASCII bytes that are printable chars or spaces can be changed, and
+/// only to other such bytes.  That keeps the text valid utf8,
+/// and keeps its line breaks and grapheme clusters as they were, so none
+/// of the `Rope`'s tree needs updating.  The leaf is changed in place,
+/// unless it was shared with another `Rope` (e.g. a clone), in which case
+/// it's copied first.
+///
+/// Created by [`Rope::leaf_mut_at()`](struct.Rope.html#method.leaf_mut_at).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// let mut rope = Rope::from_str(&"password=hunter2\n".repeat(100));
+///
+/// // Mask out all the passwords, one leaf at a time.
+/// let mut byte_idx = 0;
+/// while byte_idx < rope.len_bytes() {
+///     let mut leaf = rope.leaf_mut_at(byte_idx);
+///     let len = leaf.as_str().len();
+///     leaf.map_ascii(0..len, |b| if b.is_ascii_alphanumeric() { b'*' } else { b });
+///     byte_idx += len;
+/// }
+///
+/// assert_eq!(rope.line(42), "********=*******\n");
+/// ```
+pub struct LeafMut<'a> {
+    text: &'a mut NodeText,
+    byte_start: usize,
+}
+
+impl<'a> LeafMut<'a> {
+    pub(crate) fn new(text: &'a mut NodeText, byte_start: usize) -> LeafMut<'a> {
+        LeafMut { text, byte_start }
+    }
+
+    /// The byte index in the `Rope` of the start of the leaf's text.
+    #[inline]
+    pub fn byte_start(&self) -> usize {
+        self.byte_start
+    }
+
+    /// The leaf's text.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.text
+    }
+
+    /// Replaces each changeable byte in `byte_range` with the result of
+    /// calling `f` on it.
+    ///
+    /// `byte_range` is relative to the start of the leaf, and needn't be on
+    /// char boundaries.  Bytes that can't be changed (line breaks, other
+    /// control chars, and parts of non-ASCII chars) are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_range` is out of bounds, or if `f` returns a byte
+    /// that isn't a printable ASCII char or space.
+    pub fn map_ascii<F>(&mut self, byte_range: Range<usize>, mut f: F)
+    where
+        F: FnMut(u8) -> u8,
+    {
+        // Changing only "inert" bytes, to other inert bytes, keeps the text
+        // valid utf8 and its text info unchanged.
+        let bytes = unsafe { self.text.as_bytes_mut() };
+        for byte in &mut bytes[byte_range] {
+            if is_inert(*byte) {
+                let new_byte = f(*byte);
+                assert!(
+                    is_inert(new_byte),
+                    "Attempt to change byte {:#04x} to non-printable byte {:#04x}",
+                    *byte,
+                    new_byte
+                );
+                *byte = new_byte;
+            }
+        }
+    }
+
+    /// Converts the ASCII letters in the leaf to upper case.
+    pub fn make_ascii_uppercase(&mut self) {
+        let len = self.text.len();
+        self.map_ascii(0..len, |b| b.to_ascii_uppercase());
+    }
+
+    /// Converts the ASCII letters in the leaf to lower case.
+    pub fn make_ascii_lowercase(&mut self) {
+        let len = self.text.len();
+        self.map_ascii(0..len, |b| b.to_ascii_lowercase());
+    }
+}
+
+/// Whether `byte` is a printable ASCII char or space.
+///
+/// Tabs aren't included: they're control chars, which a following
+/// combining mark doesn't join into a grapheme cluster, unlike the chars
+/// here (e.g. `"\t\u{301}"` is two clusters, but `"a\u{301}"` is one).
+#[inline]
+fn is_inert(byte: u8) -> bool {
+    (0x20..0x7F).contains(&byte)
+}
@@ -68,17 +68,108 @@
 #![cfg_attr(feature = "cargo-clippy", allow(inline_always))]
 #![cfg_attr(feature = "cargo-clippy", allow(needless_return))]
 
+#[cfg(feature = "digest")]
+extern crate digest;
+extern crate memchr;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
 extern crate smallvec;
+#[cfg(feature = "bidi")]
+extern crate unicode_bidi;
+extern crate unicode_normalization;
+#[cfg(feature = "graphemes")]
+extern crate unicode_segmentation;
+extern crate unicode_width;
+#[cfg(feature = "checksum")]
+extern crate xxhash_rust;
 
+mod append_rope;
+#[cfg(feature = "bidi")]
+mod bidi;
+mod boundary;
+mod case;
+mod chunk_id;
 mod crlf;
+mod decoder;
+mod delta;
+mod doc_stats;
+mod diff;
+mod edit;
+mod error;
+mod escape;
+mod insert_ids;
+mod interner;
+mod leaf_mut;
+mod line_writer;
+mod mem_usage;
+mod merge;
+mod patch;
+mod pos_hint;
+mod reader;
+mod rewrite;
 mod rope_builder;
+mod rope_cow;
 mod rope;
+mod search;
+#[cfg(feature = "serde")]
+mod serde_impls;
+mod shape;
+mod snapshot;
 mod tree;
 mod slice;
+mod spans;
+mod stats;
 mod str_utils;
+mod tabs;
+mod text;
+mod trim;
+mod utf8_error;
+mod validate;
+mod weak_rope;
+mod writer;
 
 pub mod iter;
+pub mod pattern;
+#[cfg(feature = "serde")]
+pub mod serde_chunked;
 
+pub use append_rope::AppendRope;
+#[cfg(feature = "bidi")]
+pub use bidi::Direction;
+pub use boundary::{BoundaryError, BoundaryPolicy};
+pub use chunk_id::ChunkId;
+pub use decoder::{LossyDecoder, Replacement};
+pub use delta::{transform, Delta, DeltaElement, Subset};
+pub use diff::{unified_diff, write_unified_diff};
+pub use doc_stats::{DocumentStats, LineEndings};
+pub use edit::{Affinity, Edit, EditMap};
+pub use error::RopeError;
+pub use escape::EscapeControl;
+pub use insert_ids::{InsertId, InsertIds};
+pub use interner::LeafInterner;
+pub use leaf_mut::LeafMut;
+pub use line_writer::LineWriter;
+pub use mem_usage::MemUsage;
+pub use merge::{merge3, Conflict, MergeResult};
+pub use patch::PatchError;
+pub use pattern::Pattern;
+pub use pos_hint::PosHint;
+pub use reader::RopeReader;
 pub use rope::Rope;
 pub use rope_builder::RopeBuilder;
+pub use rope_cow::RopeCow;
+pub use search::{ChunkSearcher, SearchOptions};
+pub use shape::TreeShape;
 pub use slice::RopeSlice;
+pub use snapshot::RopeSnapshot;
+pub use spans::Spans;
+#[cfg(feature = "stats")]
+pub use stats::EditStats;
+pub use text::RopeText;
+pub use tree::CHUNK_ALIGNMENT;
+pub use utf8_error::{Utf8Error, Utf8Policy};
+pub use validate::{InvariantReport, InvariantViolation, TextStats};
+pub use weak_rope::WeakRope;
+pub use writer::RopeWriter;
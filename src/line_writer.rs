@@ -0,0 +1,212 @@
+use std;
+use std::ops::Range;
+
+use rope::Rope;
+use slice::RopeSlice;
+use str_utils::is_line_break;
+
+/// An adapter that appends text to the end of a `Rope`, and reports each
+/// line as it's completed.
+///
+/// Text can be pushed in pieces of any size, and lines can span any
+/// number of pushes.  Each time a line break is pushed, the line it ends
+/// is passed to the callback along with its char index range in the
+/// `Rope`, line break included.  A line ending in a CR is only reported
+/// once the next char shows whether it's part of a CRLF pair.
+///
+/// If the `Rope` doesn't end with a line break to begin with, the first
+/// line reported includes the text of its last line.
+///
+/// Call `finish()` once all the text is pushed, to report the final line.
+/// `LineWriter` also implements `fmt::Write`, so `write!()` can be used to
+/// push formatted text.
+///
+/// Created by [`Rope::line_writer()`](struct.Rope.html#method.line_writer).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// let mut rope = Rope::new();
+/// let mut lines = Vec::new();
+/// {
+///     let mut writer = rope.line_writer(|range, text| lines.push((range, text.to_string())));
+///     writer.push_str("Hello");
+///     writer.push_str(" world!\r");
+///     writer.push_str("\nHow are");
+///     writer.push_str(" you?");
+///     writer.finish();
+/// }
+///
+/// assert_eq!(lines[0], (0..14, "Hello world!\r\n".to_string()));
+/// assert_eq!(lines[1], (14..26, "How are you?".to_string()));
+/// ```
+pub struct LineWriter<'a, F> {
+    rope: &'a mut Rope,
+    on_line: F,
+    // Char index of the start of the current, incomplete, line.
+    line_start: usize,
+    // Whether the text so far ends in a CR.
+    after_cr: bool,
+}
+
+impl<'a, F> LineWriter<'a, F>
+where
+    F: FnMut(Range<usize>, RopeSlice),
+{
+    pub(crate) fn new(rope: &'a mut Rope, on_line: F) -> LineWriter<'a, F> {
+        let len = rope.len_chars();
+        let after_cr = len > 0 && rope.char(len - 1) == '\r';
+        let line_start = rope.line_boundary_before(if after_cr { len - 1 } else { len });
+        LineWriter {
+            rope,
+            on_line,
+            line_start,
+            after_cr,
+        }
+    }
+
+    /// The char index range in the `Rope` of the incomplete line at its
+    /// end, which hasn't been reported yet.
+    pub fn partial_line(&self) -> Range<usize> {
+        self.line_start..self.rope.len_chars()
+    }
+
+    /// Appends `text` to the end of the `Rope`, and reports the lines it
+    /// completes.
+    ///
+    /// Runs in O(M + log N) time, where M is the length of `text`.
+    pub fn push_str(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        let start = self.rope.len_chars();
+        self.rope.insert(start, text);
+
+        for (char_idx, c) in (start..).zip(text.chars()) {
+            if self.after_cr {
+                self.after_cr = false;
+                if c == '\n' {
+                    self.emit_line(char_idx + 1);
+                    continue;
+                }
+                self.emit_line(char_idx);
+            }
+
+            if c == '\r' {
+                self.after_cr = true;
+            } else if is_line_break(c) {
+                self.emit_line(char_idx + 1);
+            }
+        }
+    }
+
+    /// Appends `c` to the end of the `Rope`, and reports the line it
+    /// completes, if any.
+    #[inline]
+    pub fn push_char(&mut self, c: char) {
+        let mut buf = [0u8; 4];
+        self.push_str(c.encode_utf8(&mut buf));
+    }
+
+    /// Reports the final line, if it isn't empty.
+    ///
+    /// Dropping the writer without calling this leaves the final line
+    /// unreported.
+    pub fn finish(mut self) {
+        let len = self.rope.len_chars();
+        if self.line_start < len {
+            self.emit_line(len);
+        }
+    }
+
+    fn emit_line(&mut self, end: usize) {
+        let range = self.line_start..end;
+        self.line_start = end;
+        (self.on_line)(range.clone(), self.rope.slice(range));
+    }
+}
+
+impl<'a, F> std::fmt::Write for LineWriter<'a, F>
+where
+    F: FnMut(Range<usize>, RopeSlice),
+{
+    #[inline]
+    fn write_str(&mut self, text: &str) -> std::fmt::Result {
+        self.push_str(text);
+        Ok(())
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    fn collect_lines(rope: &mut Rope, pieces: &[&str]) -> Vec<(Range<usize>, String)> {
+        let mut lines = Vec::new();
+        {
+            let mut writer = rope.line_writer(|range, text| lines.push((range, text.to_string())));
+            for piece in pieces {
+                writer.push_str(piece);
+            }
+            writer.finish();
+        }
+        lines
+    }
+
+    #[test]
+    fn line_writer_01() {
+        let mut r = Rope::new();
+        let lines = collect_lines(&mut r, &["a\r", "\r", "\nb", "\u{2028}こん\n", "\r", "x"]);
+        assert_eq!(
+            lines,
+            vec![
+                (0..2, "a\r".to_string()),
+                (2..4, "\r\n".to_string()),
+                (4..6, "b\u{2028}".to_string()),
+                (6..9, "こん\n".to_string()),
+                (9..10, "\r".to_string()),
+                (10..11, "x".to_string()),
+            ]
+        );
+        assert_eq!(r, "a\r\r\nb\u{2028}こん\n\rx");
+        assert_eq!(r.len_lines(), lines.len());
+    }
+
+    #[test]
+    fn line_writer_02() {
+        // Continues the `Rope`'s last line.
+        let mut r = Rope::from_str("Hello\nwor");
+        let lines = collect_lines(&mut r, &["ld!\n", "Bye\r"]);
+        assert_eq!(
+            lines,
+            vec![(6..13, "world!\n".to_string()), (13..17, "Bye\r".to_string())]
+        );
+
+        // Completes a CRLF pair at the end of the `Rope`.
+        let mut r = Rope::from_str("Hello\nworld!\r");
+        let lines = collect_lines(&mut r, &["\n", ""]);
+        assert_eq!(lines, vec![(6..14, "world!\r\n".to_string())]);
+    }
+
+    #[test]
+    fn line_writer_03() {
+        let mut r = Rope::new();
+        let mut count = 0;
+        {
+            let mut writer = r.line_writer(|_, _| count += 1);
+            for i in 0..100 {
+                writeln!(writer, "line {}", i).unwrap();
+            }
+            writer.push_char('x');
+            assert_eq!(writer.partial_line(), 790..791);
+            writer.finish();
+        }
+        assert_eq!(count, 101);
+        assert_eq!(r.line(42), "line 42\n");
+    }
+}
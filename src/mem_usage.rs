@@ -0,0 +1,72 @@
+use std::mem::size_of;
+use std::sync::Arc;
+
+use tree::Node;
+
+/// A breakdown of the memory used by a `Rope`.
+///
+/// Returned by [`Rope::mem_usage()`](struct.Rope.html#method.mem_usage).
+/// All sizes are in bytes, and are estimates: they don't account for
+/// allocator overhead.
+///
+/// Since clones of a `Rope` share unmodified parts of their trees, memory
+/// is also split into what is owned uniquely by the `Rope` and what is
+/// shared with other ropes.  Shared memory is only freed once all ropes
+/// sharing it are dropped.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MemUsage {
+    /// Text content.
+    pub text: usize,
+
+    /// Unoccupied leaf text buffer space.
+    pub slack: usize,
+
+    /// Tree nodes and reference counts, excluding leaf text buffers.
+    pub node_overhead: usize,
+
+    /// The part of the total that only this `Rope` refers to.
+    pub unique: usize,
+
+    /// The part of the total that is shared with other ropes.
+    pub shared: usize,
+}
+
+impl MemUsage {
+    /// Total memory used, i.e. `text + slack + node_overhead`, which is
+    /// also `unique + shared`.
+    pub fn total(&self) -> usize {
+        self.text + self.slack + self.node_overhead
+    }
+
+    /// Adds the memory of `node` and its descendants.  Everything below a
+    /// node with more than one reference is counted as shared.
+    pub(crate) fn add_node(&mut self, node: &Arc<Node>, shared: bool) {
+        let shared = shared || Arc::strong_count(node) > 1;
+
+        // Arc allocation: two reference counts plus the node itself, which
+        // includes leaf text stored inline.
+        let mut total = size_of::<usize>() * 2 + size_of::<Node>();
+        match **node {
+            Node::Leaf(ref text) => {
+                if text.spilled() {
+                    total += text.capacity();
+                }
+                self.text += text.len();
+                self.slack += text.capacity() - text.len();
+                self.node_overhead += total - text.capacity();
+            }
+            Node::Internal(ref children) => {
+                self.node_overhead += total;
+                for child in children.nodes() {
+                    self.add_node(child, shared);
+                }
+            }
+        }
+
+        if shared {
+            self.shared += total;
+        } else {
+            self.unique += total;
+        }
+    }
+}
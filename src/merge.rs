@@ -0,0 +1,284 @@
+use std::ops::Range;
+
+use diff::{diff_matches, shared_prefix_lines, shared_suffix_lines, LineTable};
+use rope::Rope;
+
+/// A region of a three-way merge where both sides changed the same lines
+/// in different ways.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// The char range of the conflicting region in the merged rope, which
+    /// holds our version of the text.
+    pub range: Range<usize>,
+
+    /// The base version of the text.
+    pub base: Rope,
+
+    /// Our version of the text.
+    pub ours: Rope,
+
+    /// Their version of the text.
+    pub theirs: Rope,
+}
+
+/// The result of a three-way merge.
+///
+/// Returned by [`merge3()`](fn.merge3.html).
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// The merged text.  Conflicting regions hold our version of the text.
+    pub merged: Rope,
+
+    /// The conflicting regions, in order.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    /// Returns whether the merge had no conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges the changes made in `ours` and `theirs`, relative to their common
+/// ancestor `base`, line by line.
+///
+/// Lines changed on only one side take that side's version.  Lines changed
+/// on both sides are conflicts unless both made the same change: the merged
+/// rope keeps our version there, and the conflict is reported in the
+/// result.
+///
+/// Leading and trailing subtrees that a rope shares with `base` (e.g.
+/// because it was cloned from `base` and then edited) are skipped without
+/// being compared, so merging small edits to a large text is fast.  Text
+/// that wasn't changed is also shared with the inputs rather than copied.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{merge3, Rope};
+/// let base = Rope::from_str("one\ntwo\nthree\n");
+/// let mut ours = base.clone();
+/// ours.insert(0, "zero\n");
+/// let mut theirs = base.clone();
+/// theirs.remove(8..14);
+///
+/// let result = merge3(&base, &ours, &theirs);
+/// assert!(result.is_clean());
+/// assert_eq!(result.merged, "zero\none\ntwo\n");
+/// ```
+pub fn merge3(base: &Rope, ours: &Rope, theirs: &Rope) -> MergeResult {
+    // Skip the lines at the start and end that are unchanged on both sides.
+    let prefix_lines = shared_prefix_lines(base, ours).min(shared_prefix_lines(base, theirs));
+    let mut suffix_lines = shared_suffix_lines(base, ours).min(shared_suffix_lines(base, theirs));
+    for rope in &[base, ours, theirs] {
+        suffix_lines = suffix_lines.min(rope.len_lines() - prefix_lines);
+    }
+
+    // Diff the remaining lines against the base.
+    let mut table = LineTable::new();
+    let mut line_ids =
+        |rope: &Rope| table.lines(rope, prefix_lines..(rope.len_lines() - suffix_lines));
+    let base_lines = line_ids(base);
+    let our_lines = line_ids(ours);
+    let their_lines = line_ids(theirs);
+    let mut our_map = vec![None; base_lines.len()];
+    for (i, j) in diff_matches(&base_lines, &our_lines) {
+        our_map[i] = Some(j);
+    }
+    let mut their_map = vec![None; base_lines.len()];
+    for (i, j) in diff_matches(&base_lines, &their_lines) {
+        their_map[i] = Some(j);
+    }
+
+    let mut builder = MergeBuilder {
+        base,
+        ours,
+        theirs,
+        line_offset: prefix_lines,
+        merged: Rope::with_chunk_size(ours.chunk_size()),
+        conflicts: Vec::new(),
+    };
+    builder.merged.append_slice(&ours.slice(..ours.line_to_char(prefix_lines)));
+
+    // Walk through the lines, alternating between stable regions, where
+    // both sides match the base, and unstable regions in between.
+    let (mut b, mut o, mut t) = (0, 0, 0);
+    loop {
+        let stable = (b..base_lines.len())
+            .take_while(|&i| our_map[i] == Some(o + i - b) && their_map[i] == Some(t + i - b))
+            .count();
+        if stable > 0 {
+            builder.take(Side::Ours, o..(o + stable));
+            b += stable;
+            o += stable;
+            t += stable;
+            continue;
+        }
+
+        // Find the next base line that's matched on both sides.
+        let next = (b..base_lines.len()).find(|&i| our_map[i].is_some() && their_map[i].is_some());
+        let (b_end, o_end, t_end) = match next {
+            Some(i) => (i, our_map[i].unwrap(), their_map[i].unwrap()),
+            None => (base_lines.len(), our_lines.len(), their_lines.len()),
+        };
+        if b == b_end && o == o_end && t == t_end {
+            break;
+        }
+
+        let base_chunk = &base_lines[b..b_end];
+        let our_chunk = &our_lines[o..o_end];
+        let their_chunk = &their_lines[t..t_end];
+        if our_chunk == base_chunk || our_chunk == their_chunk {
+            builder.take(Side::Theirs, t..t_end);
+        } else if their_chunk == base_chunk {
+            builder.take(Side::Ours, o..o_end);
+        } else {
+            builder.conflict(b..b_end, o..o_end, t..t_end);
+        }
+        b = b_end;
+        o = o_end;
+        t = t_end;
+    }
+
+    let suffix_start = ours.line_to_char(ours.len_lines() - suffix_lines);
+    builder.merged.append_slice(&ours.slice(suffix_start..));
+    builder.merged.clear_dirty_range();
+
+    MergeResult {
+        merged: builder.merged,
+        conflicts: builder.conflicts,
+    }
+}
+
+//===========================================================
+
+#[derive(Copy, Clone)]
+enum Side {
+    Ours,
+    Theirs,
+}
+
+/// Builds the merged rope from line ranges of the inputs.  Line indices
+/// are relative to `line_offset`.
+struct MergeBuilder<'a> {
+    base: &'a Rope,
+    ours: &'a Rope,
+    theirs: &'a Rope,
+    line_offset: usize,
+    merged: Rope,
+    conflicts: Vec<Conflict>,
+}
+
+impl<'a> MergeBuilder<'a> {
+    fn take(&mut self, side: Side, lines: Range<usize>) {
+        let rope = match side {
+            Side::Ours => self.ours,
+            Side::Theirs => self.theirs,
+        };
+        let range = self.char_range(rope, lines);
+        self.merged.append_slice(&rope.slice(range));
+    }
+
+    fn conflict(&mut self, base: Range<usize>, ours: Range<usize>, theirs: Range<usize>) {
+        let start = self.merged.len_chars();
+        let base = self.base.sub_rope(self.char_range(self.base, base));
+        let ours_range = self.char_range(self.ours, ours);
+        let theirs = self.theirs.sub_rope(self.char_range(self.theirs, theirs));
+        self.merged.append_slice(&self.ours.slice(ours_range.clone()));
+        self.conflicts.push(Conflict {
+            range: start..self.merged.len_chars(),
+            base,
+            ours: self.ours.sub_rope(ours_range),
+            theirs,
+        });
+    }
+
+    fn char_range(&self, rope: &Rope, lines: Range<usize>) -> Range<usize> {
+        rope.line_to_char(self.line_offset + lines.start)
+            ..rope.line_to_char(self.line_offset + lines.end)
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge3_01() {
+        let base = Rope::from_str("a\nb\nc\nd\ne\n");
+        let ours = Rope::from_str("a\nB\nc\nd\ne\n");
+        let theirs = Rope::from_str("a\nb\nc\nD\ne\nf\n");
+        let result = merge3(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        assert_eq!(result.merged, "a\nB\nc\nD\ne\nf\n");
+    }
+
+    #[test]
+    fn merge3_02() {
+        let base = Rope::from_str("a\nb\nc\n");
+        let ours = Rope::from_str("a\nX\nc\n");
+        let theirs = Rope::from_str("a\nY\nZ\nc\n");
+        let result = merge3(&base, &ours, &theirs);
+        assert_eq!(result.merged, "a\nX\nc\n");
+        assert_eq!(1, result.conflicts.len());
+        let conflict = &result.conflicts[0];
+        assert_eq!(2..4, conflict.range);
+        assert_eq!(conflict.base, "b\n");
+        assert_eq!(conflict.ours, "X\n");
+        assert_eq!(conflict.theirs, "Y\nZ\n");
+    }
+
+    #[test]
+    fn merge3_03() {
+        // The same change on both sides isn't a conflict.
+        let base = Rope::from_str("a\nb\nc");
+        let ours = Rope::from_str("a\nx\nc");
+        let result = merge3(&base, &ours, &ours.clone());
+        assert!(result.is_clean());
+        assert_eq!(result.merged, "a\nx\nc");
+
+        let result = merge3(&base, &base, &base);
+        assert!(result.is_clean());
+        assert_eq!(result.merged, base);
+    }
+
+    #[test]
+    fn merge3_04() {
+        // Large text with shared subtrees, and edits without a trailing
+        // line break.
+        let text: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        let base = Rope::from_str(&text);
+        let mut ours = base.clone();
+        ours.insert(ours.line_to_char(20), "ours\n");
+        ours.insert(ours.len_chars(), "end");
+        let mut theirs = base.clone();
+        let start = theirs.line_to_char(150);
+        let end = theirs.line_to_char(151);
+        theirs.remove(start..end);
+
+        let result = merge3(&base, &ours, &theirs);
+        assert!(result.is_clean());
+        let mut expected = base.clone();
+        expected.remove(start..end);
+        expected.insert(expected.line_to_char(20), "ours\n");
+        expected.insert(expected.len_chars(), "end");
+        assert_eq!(result.merged, expected);
+        result.merged.assert_integrity();
+        result.merged.assert_invariants();
+    }
+
+    #[test]
+    fn merge3_05() {
+        // Conflicting CRLF changes around a shared prefix.
+        let base = Rope::from_str("a\r\nb\r\n");
+        let ours = Rope::from_str("a\r\nb\n");
+        let theirs = Rope::from_str("a\r\nb\r");
+        let result = merge3(&base, &ours, &theirs);
+        assert_eq!(result.merged, "a\r\nb\n");
+        assert_eq!(1, result.conflicts.len());
+        assert_eq!(3..5, result.conflicts[0].range);
+    }
+}
@@ -0,0 +1,333 @@
+use std;
+use std::fmt;
+
+use diff::content_lines;
+use rope::Rope;
+
+/// An error from applying a unified diff to a `Rope`.
+///
+/// Returned by [`Rope::apply_patch()`](struct.Rope.html#method.apply_patch).
+/// Hunks are numbered from zero, in the order they appear in the patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// The patch couldn't be parsed.  `line` is the zero-based line of the
+    /// patch text where parsing failed.
+    Malformed { line: usize, reason: &'static str },
+
+    /// A hunk starts before the end of the previous hunk.
+    HunkOutOfOrder { hunk: usize },
+
+    /// A hunk refers to lines past the end of the text.
+    OutOfBounds { hunk: usize, line_idx: usize },
+
+    /// A context or removed line of a hunk doesn't match the text.
+    ContextMismatch {
+        hunk: usize,
+        line_idx: usize,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PatchError::Malformed { line, reason } => {
+                write!(f, "malformed patch at line {}: {}", line + 1, reason)
+            }
+            PatchError::HunkOutOfOrder { hunk } => {
+                write!(f, "hunk {} overlaps or precedes the previous hunk", hunk)
+            }
+            PatchError::OutOfBounds { hunk, line_idx } => {
+                write!(f, "hunk {}: line {} is past the end of the text", hunk, line_idx)
+            }
+            PatchError::ContextMismatch {
+                hunk,
+                line_idx,
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "hunk {}: line {} is {:?}, expected {:?}",
+                hunk, line_idx, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+//===========================================================
+
+/// A parsed hunk: the zero-based line index it applies at, the old lines
+/// it expects there, and the new lines that replace them.
+struct Hunk<'a> {
+    line_idx: usize,
+    old: Vec<&'a str>,
+    new: Vec<&'a str>,
+}
+
+/// Applies the unified diff `patch` to `rope`, returning the patched text.
+pub(crate) fn apply(rope: &Rope, patch: &str) -> Result<Rope, PatchError> {
+    let hunks = parse(patch)?;
+
+    let mut patched = Rope::with_chunk_size(rope.chunk_size());
+    let content_lines = content_lines(rope);
+    let mut line_idx = 0;
+    for (hunk_i, hunk) in hunks.iter().enumerate() {
+        if hunk.line_idx < line_idx {
+            return Err(PatchError::HunkOutOfOrder { hunk: hunk_i });
+        }
+
+        // Verify the old lines.
+        for (i, line) in hunk.old.iter().enumerate() {
+            let idx = hunk.line_idx + i;
+            if idx >= content_lines {
+                return Err(PatchError::OutOfBounds {
+                    hunk: hunk_i,
+                    line_idx: idx,
+                });
+            }
+            let actual = rope.line(idx);
+            if actual != *line {
+                return Err(PatchError::ContextMismatch {
+                    hunk: hunk_i,
+                    line_idx: idx,
+                    expected: (*line).into(),
+                    actual: actual.to_string(),
+                });
+            }
+        }
+        if hunk.line_idx > content_lines {
+            return Err(PatchError::OutOfBounds {
+                hunk: hunk_i,
+                line_idx: hunk.line_idx,
+            });
+        }
+
+        // Copy the unchanged lines before the hunk, then the new lines.
+        let start = rope.line_to_char(line_idx);
+        let end = rope.line_to_char(hunk.line_idx);
+        patched.append_slice(&rope.slice(start..end));
+        for line in &hunk.new {
+            patched.append(Rope::from_str(line));
+        }
+        line_idx = hunk.line_idx + hunk.old.len();
+    }
+    let start = rope.line_to_char(line_idx);
+    patched.append_slice(&rope.slice(start..));
+
+    Ok(patched)
+}
+
+fn parse(patch: &str) -> Result<Vec<Hunk<'_>>, PatchError> {
+    let mut hunks = Vec::new();
+    let mut lines = patch.split_inclusive('\n').enumerate().peekable();
+
+    while let Some((line_i, line)) = lines.next() {
+        if !line.starts_with("@@") {
+            // Skip headers and anything else between hunks.
+            continue;
+        }
+        let malformed = |reason| PatchError::Malformed { line: line_i, reason };
+        let (old_start, old_len, new_len) =
+            parse_header(line).ok_or_else(|| malformed("invalid hunk header"))?;
+
+        let mut hunk = Hunk {
+            line_idx: if old_len == 0 {
+                old_start
+            } else {
+                old_start.checked_sub(1).ok_or_else(|| malformed("invalid hunk header"))?
+            },
+            old: Vec::with_capacity(old_len),
+            new: Vec::with_capacity(new_len),
+        };
+
+        // Which sides the previous line went to, for "\ No newline" markers.
+        let mut last = (false, false);
+        let mut cur_line_i = line_i;
+        loop {
+            if let Some(&(_, marker)) = lines.peek() {
+                if marker.starts_with('\\') {
+                    cur_line_i += 1;
+                    lines.next();
+                    if last.0 {
+                        strip_line_break(hunk.old.last_mut());
+                    }
+                    if last.1 {
+                        strip_line_break(hunk.new.last_mut());
+                    }
+                    continue;
+                }
+            }
+            if hunk.old.len() == old_len && hunk.new.len() == new_len {
+                break;
+            }
+
+            let (line_i, line) = match lines.next() {
+                Some(l) => l,
+                None => {
+                    return Err(PatchError::Malformed {
+                        line: cur_line_i + 1,
+                        reason: "unexpected end of patch",
+                    })
+                }
+            };
+            cur_line_i = line_i;
+            let (kind, text) = if line == "\n" || line == "\r\n" {
+                // Some tools strip the space from empty context lines.
+                (' ', line)
+            } else {
+                let mut chars = line.chars();
+                let kind = chars.next().unwrap_or(' ');
+                (kind, chars.as_str())
+            };
+            last = match kind {
+                ' ' => (true, true),
+                '-' => (true, false),
+                '+' => (false, true),
+                _ => {
+                    return Err(PatchError::Malformed {
+                        line: line_i,
+                        reason: "invalid hunk line",
+                    })
+                }
+            };
+            if last.0 {
+                hunk.old.push(text);
+            }
+            if last.1 {
+                hunk.new.push(text);
+            }
+            if hunk.old.len() > old_len || hunk.new.len() > new_len {
+                return Err(PatchError::Malformed {
+                    line: line_i,
+                    reason: "hunk is longer than its header says",
+                });
+            }
+        }
+        hunks.push(hunk);
+    }
+
+    Ok(hunks)
+}
+
+/// Parses a hunk header of the form `@@ -l,s +l,s @@`, returning the old
+/// start line, and the old and new lengths.
+fn parse_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = line.split(' ');
+    if parts.next() != Some("@@") {
+        return None;
+    }
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    if !parts.next()?.starts_with("@@") {
+        return None;
+    }
+
+    let range = |text: &str| -> Option<(usize, usize)> {
+        let mut nums = text.splitn(2, ',');
+        let start = nums.next()?.parse().ok()?;
+        let len = match nums.next() {
+            Some(len) => len.parse().ok()?,
+            None => 1,
+        };
+        Some((start, len))
+    };
+    let (old_start, old_len) = range(old)?;
+    let (_, new_len) = range(new)?;
+    Some((old_start, old_len, new_len))
+}
+
+/// Strips the line break from a line followed by a "\ No newline at end
+/// of file" marker.
+fn strip_line_break(line: Option<&mut &str>) {
+    if let Some(line) = line {
+        if line.ends_with('\n') {
+            *line = &line[..(line.len() - 1)];
+        }
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diff::unified_diff;
+
+    #[test]
+    fn apply_01() {
+        let a: String = (1..21).map(|i| format!("{}\n", i)).collect();
+        let a = Rope::from_str(&a);
+        let mut b = a.clone();
+        b.remove(2..4);
+        b.insert(b.line_to_char(6), "x\n");
+        b.insert(b.len_chars(), "end");
+        for context in 0..4 {
+            let patch = unified_diff(&a, &b, context);
+            assert_eq!(apply(&a, &patch).unwrap(), b);
+            assert_eq!(apply(&b, &unified_diff(&b, &a, context)).unwrap(), a);
+        }
+    }
+
+    #[test]
+    fn apply_02() {
+        let a = Rope::from_str("one\r\ntwo\r\n\r\nthree");
+        let patch = "--- a/file.txt\n\
+                     +++ b/file.txt\n\
+                     @@ -2,3 +2,2 @@\n\
+                     -two\r\n\
+                     \r\n\
+                     -three\n\
+                     \\ No newline at end of file\n\
+                     +3\r\n";
+        assert_eq!(apply(&a, patch).unwrap(), "one\r\n\r\n3\r\n");
+    }
+
+    #[test]
+    fn apply_03() {
+        let a = Rope::from_str("a\nb\nc\n");
+        assert_eq!(
+            apply(&a, "@@ -2 +2 @@\n-x\n+y\n").unwrap_err(),
+            PatchError::ContextMismatch {
+                hunk: 0,
+                line_idx: 1,
+                expected: "x\n".into(),
+                actual: "b\n".into(),
+            }
+        );
+        assert_eq!(
+            apply(&a, "@@ -4 +4 @@\n-x\n+y\n").unwrap_err(),
+            PatchError::OutOfBounds {
+                hunk: 0,
+                line_idx: 3,
+            }
+        );
+        assert_eq!(
+            apply(&a, "@@ -2 +2 @@\n-b\n+y\n@@ -1 +1 @@\n-a\n+z\n").unwrap_err(),
+            PatchError::HunkOutOfOrder { hunk: 1 }
+        );
+        assert_eq!(
+            apply(&a, "@@ -2 +2 @@\n-b\n").unwrap_err(),
+            PatchError::Malformed {
+                line: 2,
+                reason: "unexpected end of patch",
+            }
+        );
+        assert_eq!(
+            apply(&a, "@@ -2 +2 @@\n*b\n").unwrap_err(),
+            PatchError::Malformed {
+                line: 1,
+                reason: "invalid hunk line",
+            }
+        );
+        assert_eq!(
+            apply(&a, "@@ -x +2 @@\n").unwrap_err(),
+            PatchError::Malformed {
+                line: 0,
+                reason: "invalid hunk header",
+            }
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Patterns that text can be searched for with.
+//!
+//! See [`Pattern`](trait.Pattern.html) for details.
+
+use search::{SearchOptions, Searcher};
+
+/// Something that can be searched for in a `Rope` or `RopeSlice`.
+///
+/// Like `str`'s patterns in the standard library, this is implemented
+/// for:
+///
+/// - `char`, which matches that char.
+/// - `&[char]`, which matches any of the chars in the slice.
+/// - `FnMut(char) -> bool`, which matches any char it returns true for.
+/// - `&str` and `&String`, which match that text exactly.  An empty
+///   string never matches.
+///
+/// Patterns are accepted by `find()`, `match_indices()`, `split()`, and
+/// the `trim_*_matches()` methods of `Rope` and `RopeSlice`.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// let rope = Rope::from_str("  Hello, world!  ");
+///
+/// assert_eq!(Some(7..8), rope.find(','));
+/// assert_eq!(Some(2..3), rope.find(char::is_uppercase));
+/// assert_eq!(Some(9..14), rope.find("world"));
+/// assert_eq!("Hello, world", rope.trim_matches(&[' ', '!'][..]));
+/// ```
+pub trait Pattern {
+    /// The matcher that searches for the pattern.
+    type Matcher: PatternMatcher;
+
+    /// Creates a matcher for a new search.
+    fn into_matcher(self) -> Self::Matcher;
+}
+
+/// The state of a search for a `Pattern`, which is fed the searched text
+/// one char at a time.
+///
+/// A search runs either forward, with `next_match()`, or backward, with
+/// `next_match_back()`.  The two keep separate state, so a matcher can be
+/// used for one search in each direction.  The matches found by a search
+/// must not overlap each other.
+pub trait PatternMatcher {
+    /// Feeds the next char, `c`, at char index `char_idx`, and returns
+    /// the char index of the start of a match that ends with it, if any.
+    fn next_match(&mut self, c: char, char_idx: usize) -> Option<usize>;
+
+    /// Feeds the previous char, `c`, at char index `char_idx`, and
+    /// returns the char index of the end of a match that starts with it,
+    /// if any.
+    fn next_match_back(&mut self, c: char, char_idx: usize) -> Option<usize>;
+
+    /// The most chars that a match can span, if known.
+    ///
+    /// This lets searches for a match at a particular place (e.g. when
+    /// trimming) stop early.  Defaults to `None`.
+    fn max_len(&self) -> Option<usize> {
+        None
+    }
+}
+
+//==============================================================
+
+/// The matcher for patterns of single chars: `char`, `&[char]` and
+/// `FnMut(char) -> bool`.
+#[derive(Debug, Clone)]
+pub struct CharMatcher<C>(C);
+
+impl Pattern for char {
+    type Matcher = CharMatcher<char>;
+
+    #[inline]
+    fn into_matcher(self) -> Self::Matcher {
+        CharMatcher(self)
+    }
+}
+
+impl PatternMatcher for CharMatcher<char> {
+    #[inline]
+    fn next_match(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        if c == self.0 {
+            Some(char_idx)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn next_match_back(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        self.next_match(c, char_idx).map(|idx| idx + 1)
+    }
+
+    #[inline]
+    fn max_len(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl<'b> Pattern for &'b [char] {
+    type Matcher = CharMatcher<&'b [char]>;
+
+    #[inline]
+    fn into_matcher(self) -> Self::Matcher {
+        CharMatcher(self)
+    }
+}
+
+impl PatternMatcher for CharMatcher<&[char]> {
+    #[inline]
+    fn next_match(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        if self.0.contains(&c) {
+            Some(char_idx)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn next_match_back(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        self.next_match(c, char_idx).map(|idx| idx + 1)
+    }
+
+    #[inline]
+    fn max_len(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+impl<F: FnMut(char) -> bool> Pattern for F {
+    type Matcher = CharMatcher<F>;
+
+    #[inline]
+    fn into_matcher(self) -> Self::Matcher {
+        CharMatcher(self)
+    }
+}
+
+impl<F: FnMut(char) -> bool> PatternMatcher for CharMatcher<F> {
+    #[inline]
+    fn next_match(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        if (self.0)(c) {
+            Some(char_idx)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn next_match_back(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        self.next_match(c, char_idx).map(|idx| idx + 1)
+    }
+
+    #[inline]
+    fn max_len(&self) -> Option<usize> {
+        Some(1)
+    }
+}
+
+//==============================================================
+
+/// The matcher for string patterns: `&str` and `&String`.
+#[derive(Debug, Clone)]
+pub struct StrMatcher {
+    forward: Searcher,
+    backward: Searcher,
+    len: usize,
+}
+
+impl StrMatcher {
+    fn new(pattern: &str) -> StrMatcher {
+        let reversed: String = pattern.chars().rev().collect();
+        StrMatcher {
+            forward: Searcher::new(pattern, SearchOptions::default()),
+            backward: Searcher::new(&reversed, SearchOptions::default()),
+            len: pattern.chars().count(),
+        }
+    }
+}
+
+impl Pattern for &str {
+    type Matcher = StrMatcher;
+
+    #[inline]
+    fn into_matcher(self) -> Self::Matcher {
+        StrMatcher::new(self)
+    }
+}
+
+impl Pattern for &String {
+    type Matcher = StrMatcher;
+
+    #[inline]
+    fn into_matcher(self) -> Self::Matcher {
+        StrMatcher::new(self)
+    }
+}
+
+impl PatternMatcher for StrMatcher {
+    #[inline]
+    fn next_match(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        self.forward.push(c, char_idx)
+    }
+
+    #[inline]
+    fn next_match_back(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        // The reversed pattern's match "starts" at its last char.
+        self.backward.push(c, char_idx).map(|idx| idx + 1)
+    }
+
+    #[inline]
+    fn max_len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
@@ -0,0 +1,93 @@
+use std;
+use std::sync::{Arc, Weak};
+
+use tree::{Node, TextInfo};
+
+/// A hint for speeding up nearby index conversions.
+///
+/// Pass the same `PosHint` to a sequence of `*_with_hint()` conversions
+/// on a `Rope`, such as
+/// [`char_to_byte_with_hint()`](struct.Rope.html#method.char_to_byte_with_hint).
+/// Each conversion remembers the chunk it ended up in, and a following
+/// conversion that lands in the same chunk skips the O(log N) descent of
+/// the rope's tree.
+///
+/// A hint never affects the results.  Hints from another `Rope`, or from
+/// before the `Rope` was edited, are simply ignored.  Holding on to a hint
+/// doesn't keep any of the `Rope`'s text alive.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{PosHint, Rope};
+/// let rope = Rope::from_str("Hello みんなさん!");
+/// let mut hint = PosHint::new();
+/// let bytes: Vec<usize> = (0..rope.len_chars())
+///     .map(|i| rope.char_to_byte_with_hint(i, &mut hint))
+///     .collect();
+///
+/// assert_eq!(bytes, vec![0, 1, 2, 3, 4, 5, 6, 9, 12, 15, 18, 21]);
+/// ```
+#[derive(Clone)]
+pub struct PosHint {
+    root: Weak<Node>,
+    leaf: Weak<Node>,
+    before: TextInfo,
+    end: TextInfo,
+}
+
+impl PosHint {
+    /// Creates an empty hint.
+    pub fn new() -> PosHint {
+        PosHint {
+            root: Weak::new(),
+            leaf: Weak::new(),
+            before: TextInfo::new(),
+            end: TextInfo::new(),
+        }
+    }
+
+    /// Points the hint at `leaf` of the tree under `root`.
+    pub(crate) fn set(
+        &mut self,
+        root: &Arc<Node>,
+        leaf: &Arc<Node>,
+        before: TextInfo,
+        info: TextInfo,
+    ) {
+        self.root = Arc::downgrade(root);
+        self.leaf = Arc::downgrade(leaf);
+        self.before = before;
+        self.end = before + info;
+    }
+
+    /// Returns the hinted leaf, and the text info before and up to its
+    /// end, if the hint refers to the tree under `root`.
+    pub(crate) fn get(&self, root: &Arc<Node>) -> Option<(Arc<Node>, TextInfo, TextInfo)> {
+        // Weak references keep their allocation from being reused, and
+        // edits relocate a root that has weak references to it, so a
+        // matching pointer means the tree is unchanged.
+        if self.root.as_ptr() != Arc::as_ptr(root) {
+            return None;
+        }
+        self.leaf
+            .upgrade()
+            .map(|leaf| (leaf, self.before, self.end))
+    }
+}
+
+impl std::default::Default for PosHint {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for PosHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("PosHint")
+            .field("chars", &(self.before.chars..self.end.chars))
+            .field("bytes", &(self.before.bytes..self.end.bytes))
+            .finish()
+    }
+}
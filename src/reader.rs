@@ -0,0 +1,223 @@
+use std::io;
+
+use iter::Chunks;
+use slice::RopeSlice;
+
+/// An `io::Read`, `io::BufRead` and `io::Seek` adapter over the bytes of a
+/// `Rope` or `RopeSlice`.
+///
+/// `fill_buf()` hands out the remainder of the current chunk directly, so
+/// line-oriented readers such as `BufRead::read_line()` don't need an
+/// intermediate buffer.  Seeking uses the rope's tree to find the new
+/// position, and runs in O(log N) time.
+///
+/// As with `io::Cursor`, seeking past the end is allowed, after which reads
+/// return no data.
+///
+/// Created by [`Rope::reader()`](struct.Rope.html#method.reader) and
+/// [`RopeSlice::reader()`](struct.RopeSlice.html#method.reader).
+///
+/// # Example
+///
+/// ```
+/// # use std::io::BufRead;
+/// # use ropey::Rope;
+/// let rope = Rope::from_str("Hello\nworld!\n");
+/// let lines: Vec<String> = rope.reader().lines().map(|l| l.unwrap()).collect();
+///
+/// assert_eq!(vec!["Hello", "world!"], lines);
+/// ```
+pub struct RopeReader<'a> {
+    slice: RopeSlice<'a>,
+    chunks: Chunks<'a>,
+    cur_chunk: &'a [u8],
+    byte_idx: u64,
+}
+
+impl<'a> RopeReader<'a> {
+    pub(crate) fn new(slice: RopeSlice<'a>) -> RopeReader<'a> {
+        RopeReader {
+            slice,
+            chunks: slice.chunks(),
+            cur_chunk: &[],
+            byte_idx: 0,
+        }
+    }
+}
+
+impl<'a> io::Read for RopeReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = {
+            let available = io::BufRead::fill_buf(self)?;
+            let amount = available.len().min(buf.len());
+            buf[..amount].copy_from_slice(&available[..amount]);
+            amount
+        };
+        io::BufRead::consume(self, amount);
+        Ok(amount)
+    }
+}
+
+impl<'a> io::BufRead for RopeReader<'a> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.cur_chunk.is_empty() {
+            match self.chunks.next() {
+                Some(chunk) => self.cur_chunk = chunk.as_bytes(),
+                None => break,
+            }
+        }
+        Ok(self.cur_chunk)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        let amount = amount.min(self.cur_chunk.len());
+        self.cur_chunk = &self.cur_chunk[amount..];
+        self.byte_idx += amount as u64;
+    }
+}
+
+impl<'a> io::Seek for RopeReader<'a> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            io::SeekFrom::Start(n) => (n, 0),
+            io::SeekFrom::End(n) => (self.slice.len_bytes() as u64, n),
+            io::SeekFrom::Current(n) => (self.byte_idx, n),
+        };
+        let byte_idx = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.wrapping_neg() as u64)
+        };
+        let byte_idx = match byte_idx {
+            Some(byte_idx) => byte_idx,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid seek to a negative or overflowing position",
+                ))
+            }
+        };
+
+        // Restart the chunk iterator at the char containing the new
+        // position, and skip into that char's chunk as needed.
+        let len = self.slice.len_bytes();
+        if byte_idx < len as u64 {
+            let char_idx = self.slice.byte_to_char(byte_idx as usize);
+            let skip = byte_idx as usize - self.slice.char_to_byte(char_idx);
+            self.chunks = self.slice.slice(char_idx..).chunks();
+            self.cur_chunk = &[];
+            while self.cur_chunk.is_empty() {
+                self.cur_chunk = self.chunks.next().unwrap().as_bytes();
+            }
+            self.cur_chunk = &self.cur_chunk[skip..];
+        } else {
+            self.chunks = self.slice.slice(self.slice.len_chars()..).chunks();
+            self.cur_chunk = &[];
+        }
+        self.byte_idx = byte_idx;
+
+        Ok(byte_idx)
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, Read, Seek, SeekFrom};
+    use Rope;
+
+    // 124 bytes, 100 chars, 4 lines
+    const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's \
+                              a fine day, isn't it?\nAren't you glad \
+                              we're alive?\nこんにちは、みんなさん！";
+
+    #[test]
+    fn read_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut reader = r.reader();
+        let mut buf = [0u8; 3];
+        let mut out = Vec::new();
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+
+        assert_eq!(TEXT_LINES.as_bytes(), &out[..]);
+    }
+
+    #[test]
+    fn read_to_string_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut text = String::new();
+        r.slice(6..50).reader().read_to_string(&mut text).unwrap();
+
+        assert_eq!(r.slice(6..50), text.as_str());
+    }
+
+    #[test]
+    fn read_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut reader = r.reader();
+        let mut line = String::new();
+        for i in 0..4 {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(r.line(i), line.as_str());
+        }
+
+        line.clear();
+        assert_eq!(0, reader.read_line(&mut line).unwrap());
+    }
+
+    #[test]
+    fn seek_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut reader = r.reader();
+        let mut buf = [0u8; 5];
+
+        assert_eq!(6, reader.seek(SeekFrom::Start(6)).unwrap());
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(b"there", &buf);
+
+        assert_eq!(1, reader.seek(SeekFrom::Current(-10)).unwrap());
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(b"ello ", &buf);
+
+        assert_eq!(6, reader.seek(SeekFrom::Current(0)).unwrap());
+        assert_eq!(119, reader.seek(SeekFrom::End(-5)).unwrap());
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(&TEXT_LINES.as_bytes()[119..], &rest[..]);
+    }
+
+    #[test]
+    fn seek_02() {
+        // Seeking into the middle of a char and across every position.
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(3..97);
+        let text = s.to_string();
+        let mut reader = s.reader();
+        for i in 0..=text.len() {
+            reader.seek(SeekFrom::Start(i as u64)).unwrap();
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).unwrap();
+            assert_eq!(&text.as_bytes()[i..], &rest[..]);
+        }
+    }
+
+    #[test]
+    fn seek_03() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut reader = r.reader();
+        let mut buf = [0u8; 5];
+
+        assert!(reader.seek(SeekFrom::Current(-1)).is_err());
+        assert_eq!(500, reader.seek(SeekFrom::Start(500)).unwrap());
+        assert_eq!(0, reader.read(&mut buf).unwrap());
+        assert_eq!(500, reader.seek(SeekFrom::Current(0)).unwrap());
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use rope::Rope;
+use rope_builder::RopeBuilder;
+#[cfg(feature = "stats")]
+use stats::EditStats;
+use tree::Node;
+
+/// A streaming rewrite of a text, fed the text chunk by chunk.
+pub(crate) trait ChunkRewriter {
+    /// Writes the rewritten version of `chunk` to `out`.  Text may be held
+    /// back to be written with a later chunk.
+    fn rewrite(&mut self, chunk: &str, out: &mut String);
+
+    /// Writes any text still held back at the end of the text.
+    fn finish(&mut self, _out: &mut String) {}
+}
+
+/// A part of a rewritten rope.
+enum Piece {
+    Shared(Arc<Node>),
+    Text(String),
+}
+
+/// Rewrites the contents of `rope` in a single pass.
+///
+/// Leaves whose rewritten text is unchanged, and subtrees made up entirely
+/// of such leaves, are shared with `rope` rather than rebuilt.
+pub(crate) fn rewrite_chunks<R: ChunkRewriter>(rope: &Rope, rewriter: &mut R) -> Rope {
+    let mut buf = String::new();
+    let pieces = rewrite_node(&rope.root, rewriter, &mut buf);
+    let mut tail = String::new();
+    rewriter.finish(&mut tail);
+
+    let mut pieces = match pieces {
+        Some(pieces) => pieces,
+        None if tail.is_empty() => {
+            return Rope {
+                root: Arc::clone(&rope.root),
+                chunk_size: rope.chunk_size,
+                max_skew: rope.max_skew,
+                dirty: None,
+                #[cfg(feature = "stats")]
+                stats: EditStats::default(),
+            }
+        }
+        None => vec![Piece::Shared(Arc::clone(&rope.root))],
+    };
+    push_text(&mut pieces, &tail);
+
+    let mut new_rope = Rope::with_chunk_size(rope.chunk_size);
+    for piece in pieces {
+        let piece_rope = match piece {
+            Piece::Shared(node) => Rope {
+                root: node,
+                chunk_size: rope.chunk_size,
+                max_skew: rope.max_skew,
+                dirty: None,
+                #[cfg(feature = "stats")]
+                stats: EditStats::default(),
+            },
+            Piece::Text(text) => {
+                let mut builder = RopeBuilder::with_chunk_size(rope.chunk_size);
+                builder.append(&text);
+                builder.finish()
+            }
+        };
+        new_rope.append(piece_rope);
+    }
+    new_rope.max_skew = rope.max_skew;
+    new_rope.clear_dirty_range();
+    new_rope
+}
+
+/// Returns the rewritten pieces of `node`, or `None` if it's unchanged.
+fn rewrite_node<R: ChunkRewriter>(
+    node: &Arc<Node>,
+    rewriter: &mut R,
+    buf: &mut String,
+) -> Option<Vec<Piece>> {
+    match **node {
+        Node::Leaf(ref text) => {
+            buf.clear();
+            rewriter.rewrite(text, buf);
+            if buf.as_str() == &text[..] {
+                None
+            } else {
+                let mut pieces = Vec::new();
+                push_text(&mut pieces, buf);
+                Some(pieces)
+            }
+        }
+        Node::Internal(ref children) => {
+            let results: Vec<_> = children
+                .nodes()
+                .iter()
+                .map(|child| rewrite_node(child, rewriter, buf))
+                .collect();
+            if results.iter().all(|r| r.is_none()) {
+                return None;
+            }
+
+            let mut pieces = Vec::new();
+            for (child, result) in children.nodes().iter().zip(results) {
+                match result {
+                    None => pieces.push(Piece::Shared(Arc::clone(child))),
+                    Some(child_pieces) => {
+                        for piece in child_pieces {
+                            match piece {
+                                Piece::Text(text) => push_text(&mut pieces, &text),
+                                shared => pieces.push(shared),
+                            }
+                        }
+                    }
+                }
+            }
+            Some(pieces)
+        }
+    }
+}
+
+/// Pushes text onto `pieces`, merging it with a preceding text piece.
+fn push_text(pieces: &mut Vec<Piece>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(&mut Piece::Text(ref mut last)) = pieces.last_mut() {
+        last.push_str(text);
+        return;
+    }
+    pieces.push(Piece::Text(text.into()));
+}
@@ -1,14 +1,58 @@
 use std;
+use std::borrow::Cow;
+use std::hash::Hasher;
 use std::io;
+use std::io::IoSlice;
+use std::ops::{Bound, Range, RangeBounds};
 use std::sync::Arc;
 use std::ptr;
 
+#[cfg(feature = "bidi")]
+use bidi::{first_strong_direction, Direction};
+use boundary::{BoundaryError, BoundaryPolicy};
+use case::{MapChars, TitleCase};
 use crlf;
-use iter::{Bytes, Chars, Chunks, Lines};
+use diff;
+use doc_stats::DocumentStats;
+use decoder::{LossyDecoder, Replacement};
+use edit::Edit;
+use error::RopeError;
+use escape::EscapeControl;
+use interner::LeafInterner;
+use iter::{
+    Bytes, Chars, Chunks, ClassRuns, EncodeUtf16, IndexedLines, Lines, MatchIndices, Matches,
+    SearchMatches, Split,
+};
+use leaf_mut::LeafMut;
+use line_writer::LineWriter;
+use mem_usage::MemUsage;
+use patch::{self, PatchError};
+use pattern::Pattern;
+use pos_hint::PosHint;
+use reader::RopeReader;
+use rewrite::rewrite_chunks;
 use rope_builder::RopeBuilder;
+use search::{ChunkSearcher, SearchOptions};
+use shape::{group_sizes, TreeShape};
 use slice::{CharIdxRange, RopeSlice};
-use str_utils::char_idx_to_byte_idx;
-use tree::{Count, Node, NodeChildren, TextInfo, MAX_BYTES};
+use snapshot::RopeSnapshot;
+#[cfg(feature = "stats")]
+use stats::EditStats;
+use stats::{self, Event, Recording};
+use str_utils::{byte_idx_to_char_idx, char_idx_to_byte_idx, char_idx_to_line_idx, count_chars,
+                is_line_break};
+use tabs::{CollapseSpaces, ExpandTabs};
+use trim::TrimTrailing;
+use utf8_error::{Utf8Error, Utf8Policy};
+use tree::{
+    Count, Metric, Node, NodeChildren, NodeText, TextInfo, DEFAULT_MAX_SKEW, INLINE_BYTES,
+    MAX_BYTES, MAX_CHILDREN, MIN_CHUNK_SIZE,
+};
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+use validate::{InvariantReport, InvariantViolation};
+use weak_rope::WeakRope;
+use writer::RopeWriter;
 
 /// A utf8 text rope.
 ///
@@ -31,9 +75,10 @@ use tree::{Count, Node, NodeChildren, TextInfo, MAX_BYTES};
 /// assert_eq!(rope, "Hello world!");
 /// ```
 ///
-/// Cloning `Rope`'s is extremely cheap, taking only a few instructions and
-/// 8 bytes of memory, regardless of text size.  This is accomplished by data
-/// sharing between `Rope` clones, and the memory used by clones only grows
+/// Cloning `Rope`'s is extremely cheap, taking only a few instructions and a
+/// small, fixed amount of memory (a pointer to the shared tree plus the rope's
+/// own settings and bookkeeping), regardless of text size.  This is
+/// accomplished by data sharing between `Rope` clones, and the memory used by clones only grows
 /// incrementally as the their contents diverge due to edits.  All of this
 /// is thread safe, and clones can be sent freely between threads.
 ///
@@ -63,6 +108,11 @@ use tree::{Count, Node, NodeChildren, TextInfo, MAX_BYTES};
 #[derive(Clone)]
 pub struct Rope {
     pub(crate) root: Arc<Node>,
+    pub(crate) chunk_size: usize,
+    pub(crate) max_skew: Option<usize>,
+    pub(crate) dirty: Option<Range<usize>>,
+    #[cfg(feature = "stats")]
+    pub(crate) stats: EditStats,
 }
 
 impl Rope {
@@ -71,8 +121,47 @@ impl Rope {
 
     /// Creates an empty `Rope`.
     pub fn new() -> Self {
+        Rope::with_chunk_size(MAX_BYTES)
+    }
+
+    /// Creates an empty `Rope` with the given target chunk size, in bytes.
+    ///
+    /// The chunk size is the maximum size of the `Rope`'s leaf text chunks,
+    /// and is kept through all subsequent edits.  Larger chunks use less
+    /// memory overhead and make iteration faster, whereas smaller chunks
+    /// make edits cheaper.  The default is a little under 768 bytes.
+    ///
+    /// Note: chunks may still exceed the chunk size when a single CRLF pair
+    /// or grapheme can't otherwise be split.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::with_chunk_size(4096);
+    /// rope.insert(0, "Hello world!");
+    ///
+    /// assert_eq!(4096, rope.chunk_size());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is less than 8.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        assert!(
+            chunk_size >= MIN_CHUNK_SIZE,
+            "Chunk size too small: chunk size {}, minimum {}",
+            chunk_size,
+            MIN_CHUNK_SIZE
+        );
+
         Rope {
             root: Arc::new(Node::new()),
+            chunk_size,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
         }
     }
 
@@ -83,6 +172,126 @@ impl Rope {
         RopeBuilder::new().build_at_once(text)
     }
 
+    /// Creates a `Rope` by joining the given lines with `separator`.
+    ///
+    /// This is equivalent to `Rope::from_str(&lines.join(separator))`, but
+    /// builds the `Rope` directly without first copying everything into a
+    /// single `String`.  Note that the separator is only placed _between_
+    /// lines, so if you want a trailing line break you should append an
+    /// empty line at the end.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let lines = vec!["Hello", "world!"];
+    /// let rope = Rope::from_lines(lines, "\n");
+    ///
+    /// assert_eq!(rope, "Hello\nworld!");
+    /// ```
+    pub fn from_lines<I, S>(lines: I, separator: &str) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut builder = RopeBuilder::new();
+        for (i, line) in lines.into_iter().enumerate() {
+            if i > 0 {
+                builder.append(separator);
+            }
+            builder.append(line.as_ref());
+        }
+        builder.finish()
+    }
+
+    /// Creates a `Rope` with exactly the tree layout described by `shape`.
+    ///
+    /// This is for benchmarks and tests that need a specific, reproducible
+    /// tree shape.  See [`TreeShape`](enum.TreeShape.html) for details.
+    /// The `Rope` has the default chunk size.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, TreeShape};
+    /// let chunks = ["Hello ", "there ", "world!"];
+    /// let shape = TreeShape::from_chunks(&chunks, TreeShape::MAX_CHILDREN);
+    /// let rope = Rope::from_shape(&shape).unwrap();
+    ///
+    /// assert_eq!(rope, "Hello there world!");
+    /// assert_eq!(rope.chunks().collect::<Vec<_>>(), chunks);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the described tree would break any of the `Rope`'s invariants
+    /// (see [`validate()`](#method.validate)), a report of all of them is
+    /// returned instead.  Internal nodes with more than
+    /// `TreeShape::MAX_CHILDREN` children are also reported.
+    pub fn from_shape(shape: &TreeShape) -> Result<Self, InvariantReport> {
+        let mut violations = Vec::new();
+        let root = shape.to_node(&mut Vec::new(), &mut violations);
+        if !violations.is_empty() {
+            return Err(InvariantReport { violations });
+        }
+
+        let rope = Rope {
+            root: Arc::new(root),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        };
+        rope.validate()?;
+        Ok(rope)
+    }
+
+    /// Creates a `Rope` with each of `chunks` in its own leaf, exactly as
+    /// given.
+    ///
+    /// Unlike `from_shape()`, the chunks are not checked against the
+    /// `Rope`'s invariants, so chunk boundaries that a `Rope` would never
+    /// produce on its own (e.g. splitting a CRLF pair, or chunks longer
+    /// than the chunk size) can be set up deterministically.  This is for
+    /// reproducing chunk-boundary bugs in tests and fuzzers, and such
+    /// ropes may not behave correctly in other ways.  Empty chunks are
+    /// skipped, since leaves are never empty.
+    ///
+    /// Only available with the `testing` feature.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_chunks_exact(&["Hello\r", "\nworld!"]);
+    ///
+    /// assert_eq!(rope, "Hello\r\nworld!");
+    /// assert_eq!(rope.chunks().collect::<Vec<_>>(), ["Hello\r", "\nworld!"]);
+    /// assert!(rope.validate().is_err());
+    /// ```
+    #[cfg(feature = "testing")]
+    pub fn from_chunks_exact(chunks: &[&str]) -> Self {
+        let chunks: Vec<&str> = chunks.iter().cloned().filter(|c| !c.is_empty()).collect();
+        let shape = TreeShape::from_chunks(&chunks, MAX_CHILDREN);
+        let root = shape.to_node(&mut Vec::new(), &mut Vec::new());
+
+        Rope {
+            root: Arc::new(root),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        }
+    }
+
     /// Creates a `Rope` from the output of a reader.
     ///
     /// Runs in O(N) time.
@@ -92,78 +301,191 @@ impl Rope {
     /// - If the reader returns an error, `from_reader` stops and returns
     ///   that error.
     /// - If non-utf8 data is encountered, an IO error with kind
-    ///   `InvalidData` is returned.
+    ///   `InvalidData` is returned.  Its inner error is a
+    ///   [`Utf8Error`](struct.Utf8Error.html), giving the byte offset of
+    ///   the invalid sequence in the stream and the sequence itself.
     ///
     /// Note: some data from the reader is likely consumed even if there is
     /// an error.
-    #[allow(unused_mut)]
-    pub fn from_reader<T: io::Read>(mut reader: T) -> io::Result<Self> {
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, Utf8Error};
+    /// let data: &[u8] = b"Hello\xFFworld!";
+    /// let error = Rope::from_reader(data).unwrap_err();
+    /// let utf8_error = error.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+    /// assert_eq!(utf8_error.valid_up_to(), 5);
+    /// assert_eq!(utf8_error.invalid_bytes(), b"\xFF");
+    /// ```
+    pub fn from_reader<T: io::Read>(reader: T) -> io::Result<Self> {
+        match Rope::try_from_reader(reader)? {
+            Ok(rope) => Ok(rope),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+
+    /// Creates a `Rope` from the output of a reader, reporting exactly
+    /// where any invalid utf8 is.
+    ///
+    /// This is the same as `from_reader()`, except that invalid utf8 is
+    /// reported as a [`Utf8Error`](struct.Utf8Error.html), with the byte
+    /// offset of the invalid sequence in the stream.  Reading stops as
+    /// soon as an invalid sequence is found.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Errors
+    ///
+    /// - If the reader returns an error, `try_from_reader` stops and
+    ///   returns that error.
+    /// - If non-utf8 data is encountered, `Ok(Err(..))` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let data: &[u8] = b"Hello\xFFworld!";
+    /// let error = Rope::try_from_reader(data).unwrap().unwrap_err();
+    /// assert_eq!(error.valid_up_to(), 5);
+    /// assert_eq!(error.error_len(), Some(1));
+    /// ```
+    pub fn try_from_reader<T: io::Read>(mut reader: T) -> io::Result<Result<Self, Utf8Error>> {
         const BUFFER_SIZE: usize = MAX_BYTES * 2;
         let mut builder = RopeBuilder::new();
         let mut buffer = [0u8; BUFFER_SIZE];
         let mut fill_idx = 0; // How much `buffer` is currently filled with valid data
+        let mut consumed = 0; // How many bytes of the stream were appended so far
         loop {
-            match reader.read(&mut buffer[fill_idx..]) {
-                Ok(read_count) => {
-                    fill_idx += read_count;
-
-                    // Determine how much of the buffer is valid utf8
-                    let valid_count = match std::str::from_utf8(&buffer[..fill_idx]) {
-                        Ok(_) => fill_idx,
-                        Err(e) => e.valid_up_to(),
-                    };
-
-                    // Append the valid part of the buffer to the rope.
-                    if valid_count > 0 {
-                        builder.append(unsafe {
-                            std::str::from_utf8_unchecked(&buffer[..valid_count])
-                        });
-                    }
+            let read_count = match reader.read(&mut buffer[fill_idx..]) {
+                Ok(read_count) => read_count,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            fill_idx += read_count;
 
-                    // Shift the un-read part of the buffer to the beginning
-                    if valid_count < fill_idx {
-                        unsafe {
-                            ptr::copy(
-                                buffer.as_ptr().offset(valid_count as isize),
-                                buffer.as_mut_ptr().offset(0),
-                                fill_idx - valid_count,
-                            );
-                        }
-                    }
-                    fill_idx -= valid_count;
-
-                    if fill_idx == BUFFER_SIZE {
-                        // Buffer is full and none of it could be consumed.  Utf8
-                        // codepoints don't get that large, so it's clearly not
-                        // valid text.
-                        return Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            "stream did not contain valid UTF-8",
-                        ));
+            // Determine how much of the buffer is valid utf8
+            let valid_count = match std::str::from_utf8(&buffer[..fill_idx]) {
+                Ok(_) => fill_idx,
+                Err(e) => {
+                    if e.error_len().is_some() {
+                        // Not just a sequence cut off by the end of the buffer.
+                        return Ok(Err(Utf8Error::from_std(e, consumed, &buffer[..fill_idx])));
                     }
+                    e.valid_up_to()
+                }
+            };
 
-                    // If we're done reading
-                    if read_count == 0 {
-                        if fill_idx > 0 {
-                            // We couldn't consume all data.
-                            return Err(io::Error::new(
-                                io::ErrorKind::InvalidData,
-                                "stream did not contain valid UTF-8",
-                            ));
-                        } else {
-                            return Ok(builder.finish());
-                        }
-                    }
+            // Append the valid part of the buffer to the rope.
+            if valid_count > 0 {
+                builder.append(unsafe { std::str::from_utf8_unchecked(&buffer[..valid_count]) });
+            }
+            consumed += valid_count;
+
+            // Shift the un-read part of the buffer to the beginning
+            if valid_count < fill_idx {
+                unsafe {
+                    ptr::copy(
+                        buffer.as_ptr().offset(valid_count as isize),
+                        buffer.as_mut_ptr().offset(0),
+                        fill_idx - valid_count,
+                    );
                 }
+            }
+            fill_idx -= valid_count;
 
-                Err(e) => {
-                    // Read error
-                    return Err(e);
+            // If we're done reading
+            if read_count == 0 {
+                if fill_idx > 0 {
+                    // The stream ended in the middle of a codepoint.
+                    return Ok(Err(Utf8Error::new(consumed, None, &buffer[..fill_idx])));
+                } else {
+                    return Ok(Ok(builder.finish()));
                 }
             }
         }
     }
 
+    /// Creates a `Rope` from the output of a reader, handling utf8 that
+    /// is split across reads or invalid according to `policy`.
+    ///
+    /// `from_reader()` behaves like `Utf8Policy::Wait`.  Unlike it, any
+    /// `InvalidData` error returned here has a
+    /// [`Utf8Error`](struct.Utf8Error.html) as its inner error, giving
+    /// the offset of the bad utf8 in the stream.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Errors
+    ///
+    /// - If the reader returns an error, `from_reader_with_policy` stops
+    ///   and returns that error.
+    /// - If bad utf8 is encountered and `policy` isn't
+    ///   `Utf8Policy::Replace`, an IO error with kind `InvalidData` is
+    ///   returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, Utf8Policy};
+    /// let data: &[u8] = b"Hello\xFFworld\xE3\x81";
+    /// let rope = Rope::from_reader_with_policy(data, Utf8Policy::Replace).unwrap();
+    /// assert_eq!(rope, "Hello\u{FFFD}world\u{FFFD}");
+    /// ```
+    pub fn from_reader_with_policy<T: io::Read>(
+        mut reader: T,
+        policy: Utf8Policy,
+    ) -> io::Result<Self> {
+        let mut rope = Rope::new();
+        {
+            let mut writer = rope.writer_with_policy(policy);
+            io::copy(&mut reader, &mut writer)?;
+            writer.finish()?;
+        }
+        rope.dirty = None;
+        Ok(rope)
+    }
+
+    /// Creates a `Rope` from the output of a reader, replacing invalid
+    /// utf8 with U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// This is the same as `from_reader_with_policy()` with
+    /// `Utf8Policy::Replace`, but also returns where each replacement
+    /// was made, e.g. so that an editor can warn that a file contained
+    /// invalid utf8 and mark the spots.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Errors
+    ///
+    /// If the reader returns an error, `from_reader_lossy` stops and
+    /// returns that error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let data: &[u8] = b"Hello\xFFworld\xE3\x81";
+    /// let (rope, replacements) = Rope::from_reader_lossy(data).unwrap();
+    /// assert_eq!(rope, "Hello\u{FFFD}world\u{FFFD}");
+    /// assert_eq!(replacements.len(), 2);
+    /// assert_eq!(replacements[1].byte_offset, 11);
+    /// assert_eq!(replacements[1].byte_len, 2);
+    /// assert_eq!(replacements[1].char_idx, 11);
+    /// ```
+    pub fn from_reader_lossy<T: io::Read>(mut reader: T) -> io::Result<(Self, Vec<Replacement>)> {
+        let mut decoder = LossyDecoder::new();
+        let mut buffer = [0u8; MAX_BYTES * 2];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => return Ok(decoder.finish()),
+                Ok(n) => decoder.push(&buffer[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
     //-----------------------------------------------------------------------
     // Informational methods
 
@@ -188,6 +510,56 @@ impl Rope {
         self.root.line_break_count() + 1
     }
 
+    /// Returns a summary of the `Rope`'s contents: its length, longest
+    /// line, kinds of line endings, and whether it has tabs, a byte order
+    /// mark, or control chars.
+    ///
+    /// Everything is computed in a single pass over the text.  See
+    /// [`DocumentStats`](struct.DocumentStats.html) for details.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\n\tworld!\n");
+    /// let stats = rope.stats();
+    ///
+    /// assert_eq!(3, stats.lines);
+    /// assert_eq!(7, stats.longest_line);
+    /// assert_eq!((1, 1), (stats.line_endings.lf, stats.line_endings.crlf));
+    /// assert!(stats.has_tabs);
+    /// ```
+    pub fn stats(&self) -> DocumentStats {
+        self.slice(..).stats()
+    }
+
+    /// The target chunk size of the `Rope`, in bytes.
+    ///
+    /// Runs in O(1) time.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Total number of UTF-16 code units in the `Rope`, i.e. its length
+    /// if it were encoded as UTF-16.
+    ///
+    /// Runs in O(1) time.
+    pub fn len_utf16_cu(&self) -> usize {
+        self.root.utf16_cu_count()
+    }
+
+    /// Total number of extended grapheme clusters in the `Rope`.
+    ///
+    /// Requires the `graphemes` feature.
+    ///
+    /// Runs in O(1) time.
+    #[cfg(feature = "graphemes")]
+    pub fn len_graphemes(&self) -> usize {
+        self.root.grapheme_count()
+    }
+
     //-----------------------------------------------------------------------
     // Memory management methods
 
@@ -201,11 +573,99 @@ impl Rope {
     pub fn capacity(&self) -> usize {
         let mut byte_count = 0;
         for chunk in self.chunks() {
-            byte_count += chunk.len().max(MAX_BYTES);
+            byte_count += chunk.len().max(INLINE_BYTES);
         }
         byte_count
     }
 
+    /// Deduplicates the `Rope`'s leaf chunks through `interner`, sharing
+    /// the storage of chunks with identical contents.
+    ///
+    /// Chunks created by later edits aren't interned automatically, so
+    /// this can be called again as needed.  See
+    /// [`LeafInterner`](struct.LeafInterner.html) for details.
+    ///
+    /// Runs in O(N) time.
+    pub fn intern_leaves(&mut self, interner: &LeafInterner) {
+        interner.intern_tree(&mut self.root);
+    }
+
+    /// Creates an immutable snapshot of the current text.
+    ///
+    /// The snapshot is `Send + Sync` and unaffected by later edits to
+    /// this `Rope`, so it can be handed to background threads for
+    /// read-only work while editing continues here.
+    ///
+    /// Runs in O(1) time.
+    pub fn snapshot(&self) -> RopeSnapshot {
+        RopeSnapshot::new(self)
+    }
+
+    /// Creates a `WeakRope` referring to the current snapshot of the text.
+    ///
+    /// The `WeakRope` doesn't keep the text alive, but can be upgraded
+    /// back into a `Rope` for as long as this `Rope` or one of its clones
+    /// still holds the same, unedited text.
+    ///
+    /// Runs in O(1) time.
+    pub fn downgrade(&self) -> WeakRope {
+        WeakRope::new(self)
+    }
+
+    /// Returns a breakdown of the memory used by the `Rope`, including how
+    /// much of it is shared with clones of the `Rope`.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// assert_eq!(0, rope.mem_usage().shared);
+    ///
+    /// let clone = rope.clone();
+    /// assert_eq!(0, rope.mem_usage().unique);
+    /// assert_eq!(rope.mem_usage().total(), clone.mem_usage().shared);
+    /// ```
+    pub fn mem_usage(&self) -> MemUsage {
+        let mut usage = MemUsage::default();
+        usage.add_node(&self.root, false);
+        usage
+    }
+
+    /// Returns counts of the tree maintenance work (node splits, merges,
+    /// path copies, etc.) done by edits to the `Rope` so far.
+    ///
+    /// The counts are per-`Rope`: clones start out with a copy of them,
+    /// and ropes created by `split_off()` start out at zero.
+    ///
+    /// Only available with the `stats` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// let clone = rope.clone();
+    /// rope.insert(5, ",");
+    ///
+    /// assert!(rope.edit_stats().path_copies > 0);
+    /// assert_eq!(clone.edit_stats().path_copies, 0);
+    /// ```
+    #[cfg(feature = "stats")]
+    pub fn edit_stats(&self) -> EditStats {
+        self.stats
+    }
+
+    /// Resets the counts returned by `edit_stats()` to zero.
+    ///
+    /// Only available with the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn reset_edit_stats(&mut self) {
+        self.stats = EditStats::default();
+    }
+
     /// Shrinks the `Rope`'s capacity to the minimum possible.
     ///
     /// This will rarely result in `capacity() == len_bytes()`.  `Rope`
@@ -227,10 +687,10 @@ impl Rope {
     /// shrinking.
     pub fn shrink_to_fit(&mut self) {
         let mut node_stack = Vec::new();
-        let mut builder = RopeBuilder::new();
+        let mut builder = RopeBuilder::with_chunk_size(self.chunk_size);
 
         node_stack.push(self.root.clone());
-        *self = Rope::new();
+        self.root = Arc::new(Node::new());
 
         loop {
             if node_stack.is_empty() {
@@ -250,7 +710,122 @@ impl Rope {
             }
         }
 
-        *self = builder.finish();
+        self.root = builder.finish().root;
+    }
+
+    /// Rebuilds the `Rope`'s tree at the minimum height for its contents.
+    ///
+    /// Adjacent chunks are merged where they fit within the chunk size
+    /// together, and the tree is rebuilt above them with fully packed
+    /// nodes.  Unlike `shrink_to_fit()`, text is only copied for the
+    /// chunks that get merged, and the rest stay shared with any clones of
+    /// the `Rope`.
+    ///
+    /// This normally happens automatically, see
+    /// [`set_max_skew()`](#method.set_max_skew).
+    ///
+    /// Runs in O(N) time, where N is the number of chunks.
+    pub fn rebalance(&mut self) {
+        let recording = Recording::start();
+
+        let mut level = Vec::new();
+        let info = self.root.text_info();
+        Node::collect_leaves(&self.root, info, self.chunk_size, &mut level);
+
+        while level.len() > 1 {
+            let mut nodes = level.into_iter();
+            level = group_sizes(nodes.len(), MAX_CHILDREN)
+                .into_iter()
+                .map(|count| {
+                    let mut children = NodeChildren::new();
+                    for child in nodes.by_ref().take(count) {
+                        children.push(child);
+                    }
+                    stats::count(Event::Allocation);
+                    (children.combined_info(), Arc::new(Node::Internal(children)))
+                })
+                .collect();
+        }
+
+        self.root = match level.pop() {
+            Some((_, node)) => node,
+            None => Arc::new(Node::new()),
+        };
+        stats::count(Event::Rebalance);
+        recording.finish(self);
+    }
+
+    /// How many levels taller the `Rope`'s tree is than the minimum for its
+    /// length and chunk size.
+    ///
+    /// Trees can grow taller than needed under skewed workloads (e.g.
+    /// millions of inserts at the front, or removing most of a large
+    /// text), which makes every query slower.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// assert_eq!(0, rope.skew());
+    /// ```
+    pub fn skew(&self) -> usize {
+        let mut nodes = self.len_bytes().div_ceil(self.chunk_size).max(1);
+        let mut min_depth = 1;
+        while nodes > 1 {
+            nodes = nodes.div_ceil(MAX_CHILDREN);
+            min_depth += 1;
+        }
+        self.root.depth().saturating_sub(min_depth)
+    }
+
+    /// The maximum skew the `Rope` allows before it automatically
+    /// rebalances, if any.
+    ///
+    /// See [`set_max_skew()`](#method.set_max_skew).
+    ///
+    /// Runs in O(1) time.
+    pub fn max_skew(&self) -> Option<usize> {
+        self.max_skew
+    }
+
+    /// Sets the maximum [`skew()`](#method.skew) the `Rope` allows.
+    ///
+    /// Whenever an edit leaves the tree more skewed than this, the `Rope`
+    /// is [`rebalance()`](#method.rebalance)d.  `None` disables automatic
+    /// rebalancing.  The default is 3, which typical workloads never
+    /// reach.  The setting is kept by clones and by `Rope`s split off of
+    /// this one.
+    ///
+    /// Runs in O(1) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::new();
+    /// rope.set_max_skew(Some(2));
+    /// for _ in 0..100000 {
+    ///     rope.insert(0, "a");
+    /// }
+    ///
+    /// assert!(rope.skew() <= 2);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_skew` is less than 2.  A freshly rebalanced tree can
+    /// be a level taller than the minimum, and one edit can add another,
+    /// so lower limits would rebalance on nearly every edit.
+    pub fn set_max_skew(&mut self, max_skew: Option<usize>) {
+        assert!(
+            max_skew.is_none_or(|max_skew| max_skew >= 2),
+            "Maximum skew too small: {:?}, minimum 2",
+            max_skew
+        );
+        self.max_skew = max_skew;
     }
 
     //-----------------------------------------------------------------------
@@ -265,9 +840,6 @@ impl Rope {
     ///
     /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
     pub fn insert(&mut self, char_idx: usize, text: &str) {
-        // TODO: handle large insertions more efficiently, instead of doing a split
-        // and appends.
-
         // Bounds check
         assert!(
             char_idx <= self.len_chars(),
@@ -276,108 +848,7 @@ impl Rope {
             self.len_chars()
         );
 
-        if text.len() > MAX_BYTES * 6 {
-            // For huge insert texts, build a tree out of it and then
-            // split and join.
-            let text_rope = Rope::from_str(text);
-            let right = self.split_off(char_idx);
-            self.append(text_rope);
-            self.append(right);
-        } else {
-            // Otherwise, for small-to-medium sized inserts, iteratively insert in
-            // chunks.
-            let mut text = text;
-            while text.len() > 0 {
-                let split_idx = crlf::find_good_split(
-                    text.len() - (MAX_BYTES - 4).min(text.len()),
-                    text.as_bytes(),
-                    false,
-                );
-                let ins_text = &text[split_idx..];
-                text = &text[..split_idx];
-
-                // Do the insertion
-                let mut seam = None;
-                let (l_info, residual) = Arc::make_mut(&mut self.root).edit_char_range(
-                    char_idx,
-                    char_idx,
-                    |acc_info, cur_info, leaf_text| {
-                        debug_assert!(acc_info.chars as usize <= char_idx);
-                        let byte_idx =
-                            char_idx_to_byte_idx(leaf_text, char_idx - acc_info.chars as usize);
-                        if byte_idx == 0 {
-                            seam = Some(acc_info.bytes);
-                        } else if byte_idx == leaf_text.len() {
-                            let count = (leaf_text.len() + ins_text.len()) as Count;
-                            seam = Some(acc_info.bytes + count)
-                        } else {
-                            seam = None
-                        }
-
-                        if (leaf_text.len() + ins_text.len()) <= MAX_BYTES {
-                            // Calculate new info without doing a full re-scan of cur_text
-                            let new_info = {
-                                // Get summed info of current text and to-be-inserted text
-                                let mut info = cur_info + TextInfo::from_str(ins_text);
-                                // Check for CRLF graphemes on the insertion seams, and
-                                // adjust line break counts accordingly
-                                if !ins_text.is_empty() {
-                                    if byte_idx > 0 && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
-                                        && ins_text.as_bytes()[0] == 0x0A
-                                    {
-                                        info.line_breaks -= 1;
-                                    }
-                                    if byte_idx < leaf_text.len()
-                                        && *ins_text.as_bytes().last().unwrap() == 0x0D
-                                        && leaf_text.as_bytes()[byte_idx] == 0x0A
-                                    {
-                                        info.line_breaks -= 1;
-                                    }
-                                    if byte_idx > 0 && byte_idx < leaf_text.len()
-                                        && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
-                                        && leaf_text.as_bytes()[byte_idx] == 0x0A
-                                    {
-                                        info.line_breaks += 1;
-                                    }
-                                }
-                                info
-                            };
-                            // Insert the text and return the new info
-                            leaf_text.insert_str(byte_idx, ins_text);
-                            return (new_info, None);
-                        } else {
-                            let r_text = leaf_text.insert_str_split(byte_idx, ins_text);
-                            if r_text.len() > 0 {
-                                return (
-                                    TextInfo::from_str(leaf_text),
-                                    Some((TextInfo::from_str(&r_text), r_text)),
-                                );
-                            } else {
-                                // Leaf couldn't be validly split, so leave it oversized
-                                return (TextInfo::from_str(leaf_text), None);
-                            }
-                        }
-                    },
-                );
-
-                // Handle root splitting, if any.
-                if let Some((r_info, r_node)) = residual {
-                    let mut l_node = Arc::new(Node::new());
-                    std::mem::swap(&mut l_node, &mut self.root);
-
-                    let mut children = NodeChildren::new();
-                    children.push((l_info, l_node));
-                    children.push((r_info, r_node));
-
-                    *Arc::make_mut(&mut self.root) = Node::Internal(children);
-                }
-
-                // Handle seam, if any.
-                if let Some(byte_pos) = seam {
-                    Arc::make_mut(&mut self.root).fix_grapheme_seam(byte_pos, true);
-                }
-            }
-        }
+        self.insert_at(Metric::Chars, char_idx, text);
     }
 
     /// Inserts a single char `ch` at char index `char_idx`.
@@ -393,6 +864,108 @@ impl Rope {
         self.insert(char_idx, ch.encode_utf8(&mut buf));
     }
 
+    /// Inserts `text` at char index `char_idx`, unless that would split a
+    /// grapheme cluster.
+    ///
+    /// If `char_idx` is inside a grapheme cluster, `policy` decides
+    /// whether to insert at the start or end of the cluster instead, or
+    /// to fail.  Without the `graphemes` feature only CRLF pairs are
+    /// treated as clusters.
+    ///
+    /// Returns the char index `text` was inserted at.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of `text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{BoundaryPolicy, Rope};
+    /// let mut rope = Rope::from_str("Hello\r\nworld!");
+    /// assert!(rope.insert_checked(6, "!", BoundaryPolicy::Reject).is_err());
+    ///
+    /// let idx = rope.insert_checked(6, "!", BoundaryPolicy::SnapBackward).unwrap();
+    /// assert_eq!(idx, 5);
+    /// assert_eq!(rope, "Hello!\r\nworld!");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn insert_checked(
+        &mut self,
+        char_idx: usize,
+        text: &str,
+        policy: BoundaryPolicy,
+    ) -> Result<usize, BoundaryError> {
+        let char_idx = self.check_boundary(char_idx, policy)?;
+        self.insert(char_idx, text);
+        Ok(char_idx)
+    }
+
+    /// Inserts `text` at char index `char_idx`, or returns an error if
+    /// `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    ///
+    /// The non-panicking version of `insert()`.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of `text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, RopeError};
+    /// let mut rope = Rope::from_str("Hello!");
+    /// assert_eq!(Ok(()), rope.try_insert(5, " world"));
+    /// assert_eq!(
+    ///     Err(RopeError::CharIndexOutOfBounds { char_idx: 13, len_chars: 12 }),
+    ///     rope.try_insert(13, "?")
+    /// );
+    ///
+    /// assert_eq!("Hello world!", rope);
+    /// ```
+    pub fn try_insert(&mut self, char_idx: usize, text: &str) -> Result<(), RopeError> {
+        self.check_char_idx(char_idx)?;
+        self.insert(char_idx, text);
+        Ok(())
+    }
+
+    /// Inserts `text` at byte index `byte_idx`.
+    ///
+    /// The byte-indexed counterpart of `insert()`.  The tree is descended
+    /// by byte counts, and `byte_idx` is checked to be on a char boundary
+    /// in the leaf it's in, so no conversion to a char index is needed
+    /// first.
+    ///
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of `text`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("こんにちは!");
+    /// rope.insert_at_byte(15, ", world");
+    ///
+    /// assert_eq!("こんにちは, world!", rope);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`),
+    /// or isn't on a char boundary.
+    pub fn insert_at_byte(&mut self, byte_idx: usize, text: &str) {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to insert past end of Rope: insertion point {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        self.insert_at(Metric::Bytes, byte_idx, text);
+    }
+
     /// Removes the text in the given char index range.
     ///
     /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in `char`
@@ -425,1368 +998,6755 @@ impl Rope {
             self.len_chars()
         );
 
-        // A special case that the rest of the logic doesn't handle
-        // correctly.
-        if start == 0 && end == self.len_chars() {
-            self.root = Arc::new(Node::new());
-            return;
-        }
-
-        // Scope to contain borrow of root
-        {
-            let root = Arc::make_mut(&mut self.root);
-            let mut seam = None;
-
-            let (_text_info, _residual) = {
-                root.edit_char_range(start, end, |acc_info, cur_info, leaf_text| {
-                    let local_start = start - (acc_info.chars as usize).min(start);
-                    let local_end = (end - acc_info.chars as usize).min(cur_info.chars as usize);
-                    let byte_start = char_idx_to_byte_idx(leaf_text, local_start);
-                    let byte_end = char_idx_to_byte_idx(leaf_text, local_end);
-
-                    if local_start == 0 || local_end == cur_info.chars as usize {
-                        seam = Some(acc_info.bytes as usize + byte_start);
-                    }
-
-                    // Remove text and calculate new info
-                    let new_info = if (byte_end - byte_start) < leaf_text.len() {
-                        let rem_info = TextInfo::from_str(&leaf_text[byte_start..byte_end]);
-                        let mut info = cur_info - rem_info;
-
-                        // Check for CRLF graphemes on the insertion seams, and
-                        // adjust line break counts accordingly
-                        if byte_start != byte_end {
-                            if byte_start > 0 && leaf_text.as_bytes()[byte_start - 1] == 0x0D
-                                && leaf_text.as_bytes()[byte_start] == 0x0A
-                            {
-                                info.line_breaks += 1;
-                            }
-                            if byte_end < leaf_text.len()
-                                && leaf_text.as_bytes()[byte_end - 1] == 0x0D
-                                && leaf_text.as_bytes()[byte_end] == 0x0A
-                            {
-                                info.line_breaks += 1;
-                            }
-                            if byte_start > 0 && byte_end < leaf_text.len()
-                                && leaf_text.as_bytes()[byte_start - 1] == 0x0D
-                                && leaf_text.as_bytes()[byte_end] == 0x0A
-                            {
-                                info.line_breaks -= 1;
-                            }
-                        }
-
-                        // Remove the text
-                        leaf_text.remove_range(byte_start, byte_end);
-
-                        info
-                    } else {
-                        // Remove the text
-                        leaf_text.remove_range(byte_start, byte_end);
-
-                        TextInfo::from_str(leaf_text)
-                    };
+        self.remove_range(Metric::Chars, start, end);
+    }
 
-                    (new_info, None)
-                })
-            };
+    /// Removes the text in the given char index range, or returns an error
+    /// if the start of the range is greater than the end, or the end is out
+    /// of bounds (i.e. `end > len_chars()`).
+    ///
+    /// The non-panicking version of `remove()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, RopeError};
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// assert_eq!(Ok(()), rope.try_remove(5..11));
+    /// assert_eq!(
+    ///     Err(RopeError::InvalidRange { start: 3, end: 2 }),
+    ///     rope.try_remove(3..2)
+    /// );
+    ///
+    /// assert_eq!("Hello!", rope);
+    /// ```
+    pub fn try_remove<R: CharIdxRange>(&mut self, range: R) -> Result<(), RopeError> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
 
-            if let Some(seam_idx) = seam {
-                root.fix_grapheme_seam(seam_idx as Count, false);
-            }
-            root.zip_fix(start);
+        self.check_char_idx(start)?;
+        self.check_char_idx(end)?;
+        if start > end {
+            return Err(RopeError::InvalidRange { start, end });
         }
-
-        self.pull_up_singular_nodes();
+        self.remove(start..end);
+        Ok(())
     }
 
-    /// Splits the `Rope` at `char_idx`, returning the right part of
-    /// the split.
+    /// Removes the text in the given char index range, unless that would
+    /// split a grapheme cluster.
+    ///
+    /// Each end of the range that's inside a grapheme cluster is handled
+    /// according to `policy`, as with `insert_checked()`.  This keeps
+    /// removals from leaving half of a CRLF pair (or, with the `graphemes`
+    /// feature, of any other cluster) behind.
+    ///
+    /// Returns the char range that was removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{BoundaryPolicy, Rope};
+    /// let mut rope = Rope::from_str("one\r\ntwo\r\n");
+    /// assert!(rope.remove_checked(4..9, BoundaryPolicy::Reject).is_err());
+    ///
+    /// let removed = rope.remove_checked(4..9, BoundaryPolicy::SnapForward).unwrap();
+    /// assert_eq!(removed, 5..10);
+    /// assert_eq!(rope, "one\r\n");
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
-    pub fn split_off(&mut self, char_idx: usize) -> Self {
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    pub fn remove_checked<R: CharIdxRange>(
+        &mut self,
+        range: R,
+        policy: BoundaryPolicy,
+    ) -> Result<Range<usize>, BoundaryError> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
+
         // Bounds check
+        assert!(start <= end);
         assert!(
-            char_idx <= self.len_chars(),
-            "Attempt to split past end of Rope: split point {}, Rope length {}",
-            char_idx,
+            end <= self.len_chars(),
+            "Attempt to remove past end of Rope: removal end {}, Rope length {}",
+            end,
             self.len_chars()
         );
 
-        if char_idx == 0 {
-            // Special case 1
-            let mut new_rope = Rope::new();
-            std::mem::swap(self, &mut new_rope);
-            new_rope
-        } else if char_idx == self.len_chars() {
-            // Special case 2
-            Rope::new()
-        } else {
-            // Do the split
-            let mut new_rope_root = Arc::new(Arc::make_mut(&mut self.root).split(char_idx));
-
-            // Fix up the edges
-            Arc::make_mut(&mut self.root).zip_fix_right();
-            Arc::make_mut(&mut new_rope_root).zip_fix_left();
-            self.pull_up_singular_nodes();
-
-            while (!new_rope_root.is_leaf()) && new_rope_root.child_count() == 1 {
-                let child = if let Node::Internal(ref children) = *new_rope_root {
-                    Arc::clone(&children.nodes()[0])
-                } else {
-                    unreachable!()
-                };
-
-                new_rope_root = child;
-            }
-
-            // Return right rope
-            Rope {
-                root: new_rope_root,
-            }
-        }
+        let start = self.check_boundary(start, policy)?;
+        let end = self.check_boundary(end, policy)?;
+        self.remove(start..end);
+        Ok(start..end)
     }
 
-    /// Appends a `Rope` to the end of this one, consuming the other `Rope`.
-    pub fn append(&mut self, other: Self) {
-        if self.len_chars() == 0 {
-            let mut other = other;
-            std::mem::swap(self, &mut other);
-        } else if other.len_chars() > 0 {
-            let seam_byte_i = self.root.text_info().bytes;
-
-            let l_depth = self.root.depth();
-            let r_depth = other.root.depth();
-
-            if l_depth > r_depth {
-                let extra =
-                    Arc::make_mut(&mut self.root).append_at_depth(other.root, l_depth - r_depth);
-                if let Some(node) = extra {
-                    let mut children = NodeChildren::new();
-                    children.push((self.root.text_info(), Arc::clone(&self.root)));
-                    children.push((node.text_info(), node));
-                    self.root = Arc::new(Node::Internal(children));
-                }
-            } else {
-                let mut other = other;
-                let extra = Arc::make_mut(&mut other.root)
-                    .prepend_at_depth(Arc::clone(&self.root), r_depth - l_depth);
-                if let Some(node) = extra {
-                    let mut children = NodeChildren::new();
-                    children.push((node.text_info(), node));
-                    children.push((other.root.text_info(), Arc::clone(&other.root)));
-                    other.root = Arc::new(Node::Internal(children));
-                }
-                *self = other;
-            };
-
-            Arc::make_mut(&mut self.root).fix_grapheme_seam(seam_byte_i, true);
-        }
-    }
-
-    //-----------------------------------------------------------------------
-    // Index conversion methods
-
-    /// Returns the char index of the given byte.
+    /// Removes the text in the given byte index range.
     ///
-    /// # Panics
+    /// The byte-indexed counterpart of `remove()`.  Takes any range of
+    /// byte indices, e.g. `2..7`, `2..=6`, `2..`, etc.  As with
+    /// `insert_at_byte()`, the tree is descended by byte counts, and both
+    /// ends of the range are checked to be on char boundaries in the
+    /// leaves they're in.
     ///
-    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
-    #[allow(dead_code)]
-    pub fn byte_to_char(&self, byte_idx: usize) -> usize {
-        // Bounds check
-        assert!(
-            byte_idx <= self.len_bytes(),
-            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
-            byte_idx,
-            self.len_bytes()
-        );
-
-        self.root.byte_to_char(byte_idx)
-    }
-
-    /// Returns the line index of the given byte.
+    /// # Example
     ///
-    /// Notes:
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("こんにちは, world!");
+    /// rope.remove_byte_range(15..22);
     ///
-    /// - Lines are zero-indexed.
-    /// - If `byte_idx` is one-past-the-end, then one-past-the-end line index
-    ///   is returned.  This is mainly unintuitive for empty ropes, which will
-    ///   return a line index of 1 for a `byte_idx` of zero.  Otherwise it
-    ///   behaves as expected.
+    /// assert_eq!("こんにちは!", rope);
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
-    #[allow(dead_code)]
-    pub fn byte_to_line(&self, byte_idx: usize) -> usize {
+    /// Panics if the start of the range is greater than the end, if the end
+    /// is out of bounds (i.e. `end > len_bytes()`), or if either end isn't
+    /// on a char boundary.  The `Rope` is left unchanged if it panics.
+    pub fn remove_byte_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len_bytes(),
+        };
+
         // Bounds check
+        assert!(start <= end);
         assert!(
-            byte_idx <= self.len_bytes(),
-            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
-            byte_idx,
+            end <= self.len_bytes(),
+            "Attempt to remove past end of Rope: removal end {}, Rope byte length {}",
+            end,
             self.len_bytes()
         );
 
-        if byte_idx == self.len_bytes() {
-            self.len_lines()
-        } else {
-            self.root.byte_to_line(byte_idx)
-        }
+        self.remove_range(Metric::Bytes, start, end);
     }
 
-    /// Returns the byte index of the given char.
+    /// Removes the lines in the given line index range, along with their
+    /// line breaks.
     ///
-    /// # Panics
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in line
+    /// indices, and its end can be one-past-the-end.  Removing the last
+    /// line doesn't remove the line break before it.
     ///
-    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
-    #[allow(dead_code)]
-    pub fn char_to_byte(&self, char_idx: usize) -> usize {
-        // Bounds check
-        assert!(
-            char_idx <= self.len_chars(),
-            "Attempt to index past end of Rope: char index {}, Rope char length {}",
-            char_idx,
-            self.len_chars()
-        );
-
-        self.root.char_to_byte(char_idx)
-    }
-
-    /// Returns the line index of the given char.
+    /// Runs in O(M + log N) time, where N is the length of the `Rope` and M
+    /// is the length of the removed text.
     ///
-    /// Notes:
+    /// # Example
     ///
-    /// - Lines are zero-indexed.
-    /// - If `char_idx` is one-past-the-end, then one-past-the-end line index
-    ///   is returned.  This is mainly unintuitive for empty ropes, which will
-    ///   return a line index of 1 for a `char_idx` of zero.  Otherwise it
-    ///   behaves as expected.
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("one\ntwo\nthree\nfour");
+    /// rope.remove_lines(1..3);
+    ///
+    /// assert_eq!("one\nfour", rope);
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
-    pub fn char_to_line(&self, char_idx: usize) -> usize {
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_lines()`).
+    pub fn remove_lines<R: CharIdxRange>(&mut self, range: R) {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_lines());
+
         // Bounds check
+        assert!(start <= end);
         assert!(
-            char_idx <= self.len_chars(),
-            "Attempt to index past end of Rope: char index {}, Rope char length {}",
-            char_idx,
-            self.len_chars()
+            end <= self.len_lines(),
+            "Attempt to remove past end of Rope: removal end line {}, Rope line length {}",
+            end,
+            self.len_lines()
         );
 
-        if char_idx == self.len_chars() {
-            self.len_lines()
-        } else {
-            self.root.char_to_line(char_idx)
-        }
+        let start = self.line_to_char(start);
+        let end = self.line_to_char(end);
+        self.remove(start..end);
     }
 
-    /// Returns the byte index of the start of the given line.
+    /// Removes the first `byte_count` bytes of the `Rope`, rounded up to
+    /// the next char boundary.
     ///
-    /// Notes:
+    /// This is meant for keeping bounded buffers (e.g. scrollback) under a
+    /// size limit.  Whole leading subtrees are dropped without touching
+    /// their text.
     ///
-    /// - Lines are zero-indexed.
-    /// - `line_idx` can be one-past-the-end, which will return one-past-the-end
-    ///   byte index.
+    /// Returns the number of bytes removed.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("こんにちは!");
+    ///
+    /// assert_eq!(6, rope.trim_front_bytes(4));
+    /// assert_eq!(rope, "にちは!");
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
-    #[allow(dead_code)]
-    pub(crate) fn line_to_byte(&self, line_idx: usize) -> usize {
+    /// Panics if `byte_count` is out of bounds (i.e. `byte_count > len_bytes()`).
+    pub fn trim_front_bytes(&mut self, byte_count: usize) -> usize {
         // Bounds check
         assert!(
-            line_idx <= self.len_lines(),
-            "Attempt to index past end of Rope: line index {}, Rope line length {}",
-            line_idx,
-            self.len_lines()
+            byte_count <= self.len_bytes(),
+            "Attempt to trim past end of Rope: byte count {}, Rope byte length {}",
+            byte_count,
+            self.len_bytes()
         );
 
-        if line_idx == self.len_lines() {
-            self.len_bytes()
-        } else {
-            self.root.line_to_byte(line_idx)
+        let mut char_idx = self.byte_to_char(byte_count);
+        let mut byte_idx = self.char_to_byte(char_idx);
+        if byte_idx < byte_count {
+            char_idx += 1;
+            byte_idx = self.char_to_byte(char_idx);
         }
+        self.remove(..char_idx);
+        byte_idx
     }
 
-    /// Returns the char index of the start of the given line.
+    /// Gives mutable access to the text of the leaf containing `byte_idx`,
+    /// for edits that don't change its length.
     ///
-    /// Notes:
+    /// This is much cheaper than replacing the text with `remove()` and
+    /// `insert()`, as the leaf is changed in place unless it's shared
+    /// with another `Rope`.  The whole leaf counts as changed for
+    /// `take_dirty_range()`.  See [`LeafMut`](struct.LeafMut.html) for
+    /// which changes are allowed.
     ///
-    /// - Lines are zero-indexed.
-    /// - `line_idx` can be one-past-the-end, which will return one-past-the-end
-    ///   char index.
+    /// If `byte_idx` is one-past-the-end, the last leaf is returned.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// rope.leaf_mut_at(0).make_ascii_uppercase();
+    ///
+    /// assert_eq!(rope, "HELLO WORLD!");
+    /// ```
     ///
     /// # Panics
     ///
-    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
-    pub fn line_to_char(&self, line_idx: usize) -> usize {
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn leaf_mut_at(&mut self, byte_idx: usize) -> LeafMut<'_> {
         // Bounds check
         assert!(
-            line_idx <= self.len_lines(),
-            "Attempt to index past end of Rope: line index {}, Rope line length {}",
-            line_idx,
-            self.len_lines()
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
         );
 
-        if line_idx == self.len_lines() {
-            self.len_chars()
-        } else {
-            self.root.line_to_char(line_idx)
-        }
+        // Make the path to the leaf unshared first, so that any copies are
+        // counted and the leaf's char range can be marked dirty.
+        let recording = Recording::start();
+        let (byte_start, byte_len) = {
+            let (text, byte_start) = Node::leaf_text_mut_at_byte(&mut self.root, byte_idx);
+            (byte_start, text.len())
+        };
+        recording.finish(self);
+
+        let char_start = self.byte_to_char(byte_start);
+        let char_end = self.byte_to_char(byte_start + byte_len);
+        self.mark_dirty(Edit::replace(char_start..char_end, char_end - char_start));
+
+        let (text, byte_start) = Node::leaf_text_mut_at_byte(&mut self.root, byte_idx);
+        LeafMut::new(text, byte_start)
     }
 
-    //-----------------------------------------------------------------------
-    // Fetch methods
-
-    /// Returns the char at `char_idx`.
+    /// Splits the `Rope` at `char_idx`, returning the right part of
+    /// the split.
     ///
     /// # Panics
     ///
-    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len_chars()`).
-    pub fn char(&self, char_idx: usize) -> char {
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn split_off(&mut self, char_idx: usize) -> Self {
         // Bounds check
         assert!(
-            char_idx < self.len_chars(),
-            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx <= self.len_chars(),
+            "Attempt to split past end of Rope: split point {}, Rope length {}",
             char_idx,
             self.len_chars()
         );
 
-        let (chunk, offset) = self.root.get_chunk_at_char(char_idx);
-        let byte_idx = char_idx_to_byte_idx(chunk, offset);
-        chunk[byte_idx..].chars().nth(0).unwrap()
-    }
+        self.split_contiguous();
+        self.mark_dirty(Edit::remove(char_idx..self.len_chars()));
 
-    /// Returns the line at `line_idx`.
-    ///
-    /// Note: lines are zero-indexed.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
-    pub fn line(&self, line_idx: usize) -> RopeSlice {
-        // Bounds check
-        assert!(
-            line_idx < self.len_lines(),
-            "Attempt to index past end of Rope: line index {}, Rope line length {}",
-            line_idx,
-            self.len_lines()
-        );
+        if char_idx == 0 {
+            // Special case 1
+            let mut new_rope = Rope::with_chunk_size(self.chunk_size);
+            new_rope.max_skew = self.max_skew;
+            std::mem::swap(&mut self.root, &mut new_rope.root);
+            new_rope
+        } else if char_idx == self.len_chars() {
+            // Special case 2
+            let mut new_rope = Rope::with_chunk_size(self.chunk_size);
+            new_rope.max_skew = self.max_skew;
+            new_rope
+        } else {
+            let recording = Recording::start();
 
-        let start = self.line_to_char(line_idx);
-        let end = self.line_to_char(line_idx + 1);
+            // Do the split
+            let mut new_rope_root = Arc::new(stats::make_mut(&mut self.root).split(char_idx));
+            stats::count(Event::Allocation);
 
-        self.slice(start..end)
-    }
+            // Fix up the edges
+            stats::make_mut(&mut self.root).zip_fix_right(self.chunk_size);
+            stats::make_mut(&mut new_rope_root).zip_fix_left(self.chunk_size);
+            self.pull_up_singular_nodes();
+            self.rebalance_if_skewed();
+            recording.finish(self);
 
-    //-----------------------------------------------------------------------
-    // Slicing
+            while (!new_rope_root.is_leaf()) && new_rope_root.child_count() == 1 {
+                let child = if let Node::Internal(ref children) = *new_rope_root {
+                    Arc::clone(&children.nodes()[0])
+                } else {
+                    unreachable!()
+                };
 
-    /// Gets an immutable slice of the `Rope`.
+                new_rope_root = child;
+            }
+
+            // Return right rope
+            Rope {
+                root: new_rope_root,
+                chunk_size: self.chunk_size,
+                max_skew: self.max_skew,
+                dirty: None,
+                #[cfg(feature = "stats")]
+                stats: EditStats::default(),
+            }
+        }
+    }
+
+    /// Swaps the text in two non-overlapping char ranges.
     ///
-    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in `char`
-    /// indices.
+    /// The ranges may be given in either order, and may be different
+    /// lengths.  The text is moved by splitting and re-joining the tree,
+    /// so it's shared rather than copied.
+    ///
+    /// Runs in O(log N) time.
     ///
     /// # Example
     ///
     /// ```
     /// # use ropey::Rope;
-    /// let rope = Rope::from_str("Hello world!");
-    /// let slice = rope.slice(..5);
+    /// let mut rope = Rope::from_str("one, two, three");
+    /// rope.swap_ranges(10..15, 0..3);
     ///
-    /// assert_eq!("Hello", slice);
+    /// assert_eq!(rope, "three, two, one");
     /// ```
     ///
     /// # Panics
     ///
-    /// Panics if the start of the range is greater than the end, or if the
-    /// end is out of bounds (i.e. `end > len_chars()`).
-    pub fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice {
-        let start = range.start().unwrap_or(0);
-        let end = range.end().unwrap_or_else(|| self.len_chars());
-
+    /// Panics if either range's start is greater than its end, if the
+    /// ranges overlap, or if either range is out of bounds (i.e. `end >
+    /// len_chars()`).  An empty range overlaps a range if it's strictly
+    /// inside it, but not if it's at either end of it.
+    pub fn swap_ranges(&mut self, a: Range<usize>, b: Range<usize>) {
         // Bounds check
-        assert!(start <= end);
+        assert!(a.start <= a.end && b.start <= b.end);
+        let end = a.end.max(b.end);
         assert!(
             end <= self.len_chars(),
-            "Attempt to slice past end of Rope: slice end {}, Rope length {}",
+            "Attempt to swap past end of Rope: range end {}, Rope length {}",
             end,
             self.len_chars()
         );
 
-        RopeSlice::new_with_range(&self.root, start, end)
-    }
+        let (a, b) = if (a.start, a.end) <= (b.start, b.end) { (a, b) } else { (b, a) };
+        assert!(
+            a.end <= b.start,
+            "Attempt to swap overlapping ranges: {:?} and {:?}",
+            a,
+            b
+        );
+
+        if a.start == a.end && b.start == b.end {
+            return;
+        }
+
+        let dirty = self.dirty.take();
+        let tail = self.split_off(b.end);
+        let b_text = self.split_off(b.start);
+        let between = self.split_off(a.end);
+        let a_text = self.split_off(a.start);
+        self.append(b_text);
+        self.append(between);
+        self.append(a_text);
+        self.append(tail);
+        self.dirty = dirty;
+        self.mark_dirty(Edit::replace(a.start..b.end, b.end - a.start));
+    }
+
+    /// Rotates the text in `range` so that the char at `mid` becomes the
+    /// first char of the range.
+    ///
+    /// In other words, the text in `range.start..mid` and `mid..range.end`
+    /// is swapped.  This is handy for e.g. moving a line up or down past
+    /// its neighbor.  As with `swap_ranges()`, the text is shared rather
+    /// than copied.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("a\nb\nc\n");
+    ///
+    /// // Move line 2 up.
+    /// let start = rope.line_to_char(1);
+    /// let mid = rope.line_to_char(2);
+    /// let end = rope.line_to_char(3);
+    /// rope.rotate(start..end, mid);
+    ///
+    /// assert_eq!(rope, "a\nc\nb\n");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` isn't within `range` (i.e. `range.start <= mid <=
+    /// range.end` doesn't hold), or if the range is out of bounds (i.e.
+    /// `end > len_chars()`).
+    pub fn rotate(&mut self, range: Range<usize>, mid: usize) {
+        assert!(
+            range.start <= mid && mid <= range.end,
+            "Attempt to rotate about a point outside the range: point {}, range {:?}",
+            mid,
+            range
+        );
+        self.swap_ranges(range.start..mid, mid..range.end);
+    }
+
+    /// Sorts the lines in `line_range` with the comparator function
+    /// `compare`.
+    ///
+    /// `compare` is passed each line's text without its line break.  The
+    /// sort is stable, and the line breaks stay where they are, so an
+    /// unterminated last line can be sorted like any other.  Lines are
+    /// moved by splicing their text into a new tree rather than copying
+    /// it through `String`s, so whole subtrees within long lines are
+    /// shared.
+    ///
+    /// Note that if the `Rope` ends in a line break, its last line is
+    /// empty, so it usually shouldn't be included in the range.
+    ///
+    /// Runs in O(L + M log N) time, where L is the number of chars in the
+    /// range and M is the number of lines in it, plus the time for the
+    /// O(M log M) comparisons.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("head\ncherry\napple\nbanana");
+    /// rope.sort_lines(1..4, |a, b| a.chars().cmp(b.chars()));
+    ///
+    /// assert_eq!(rope, "head\napple\nbanana\ncherry");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_lines()`).
+    pub fn sort_lines<F>(&mut self, line_range: Range<usize>, mut compare: F)
+    where
+        F: FnMut(&RopeSlice, &RopeSlice) -> std::cmp::Ordering,
+    {
+        // Bounds check
+        assert!(line_range.start <= line_range.end);
+        assert!(
+            line_range.end <= self.len_lines(),
+            "Attempt to sort lines past end of Rope: line range end {}, Rope line length {}",
+            line_range.end,
+            self.len_lines()
+        );
+
+        let line_count = line_range.end - line_range.start;
+        let start = self.line_to_char(line_range.start);
+        let end = self.line_to_char(line_range.end);
+
+        let sorted = {
+            // Find the lines in a single pass over the text, as the char
+            // ranges of each line's text and of its line break, relative
+            // to `start`.
+            let region = self.slice(start..end);
+            let mut lines = Vec::with_capacity(line_count);
+            let mut line_start = 0;
+            let mut cr_idx = None; // Index of a CR that may start a CRLF pair
+            let mut char_idx = 0;
+            for chunk in region.chunks() {
+                for c in chunk.chars() {
+                    if let Some(cr) = cr_idx.take() {
+                        let break_end = if c == '\n' { char_idx + 1 } else { cr + 1 };
+                        lines.push((line_start..cr, cr..break_end));
+                        line_start = break_end;
+                    }
+                    if char_idx < line_start {
+                        // The LF of a CRLF pair.
+                    } else if c == '\r' {
+                        cr_idx = Some(char_idx);
+                    } else if is_line_break(c) {
+                        lines.push((line_start..char_idx, char_idx..(char_idx + 1)));
+                        line_start = char_idx + 1;
+                    }
+                    char_idx += 1;
+                }
+            }
+            if let Some(cr) = cr_idx {
+                lines.push((line_start..cr, cr..(cr + 1)));
+                line_start = cr + 1;
+            }
+            if lines.len() < line_count {
+                // An unterminated last line.
+                lines.push((line_start..char_idx, char_idx..char_idx));
+            }
+            debug_assert_eq!(line_count, lines.len());
+
+            let mut texts: Vec<_> = lines.iter().map(|l| region.slice(l.0.clone())).collect();
+            texts.sort_by(|a, b| compare(a, b));
+
+            // The line breaks stay where they are.
+            let mut sorted = Rope::with_chunk_size(self.chunk_size);
+            for (text, line) in texts.iter().zip(lines) {
+                sorted.append_slice(text);
+                sorted.append_slice(&region.slice(line.1));
+            }
+            sorted
+        };
+
+        let dirty = self.dirty.take();
+        let tail = self.split_off(end);
+        self.split_off(start);
+        self.append(sorted);
+        self.append(tail);
+        self.dirty = dirty;
+        self.mark_dirty(Edit::replace(start..end, end - start));
+    }
+
+    /// Appends a `Rope` to the end of this one, consuming the other `Rope`.
+    ///
+    /// The `Rope` keeps its own chunk size.  If `other` has chunks longer
+    /// than that (i.e. it has a larger chunk size), its text is re-split to
+    /// fit, which takes O(M) time in its length.
+    pub fn append(&mut self, other: Self) {
+        self.mark_dirty(Edit::insert(self.len_chars(), other.len_chars()));
+        let recording = Recording::start();
+
+        self.split_contiguous();
+        let mut other = other;
+        if other.chunk_size > self.chunk_size && other.root.has_oversized_leaf(self.chunk_size) {
+            other = other.rechunked(self.chunk_size);
+        } else {
+            other.split_contiguous();
+        }
+
+        if self.len_chars() == 0 {
+            self.root = other.root;
+        } else if other.len_chars() > 0 {
+            let seam_byte_i = self.root.text_info().bytes;
+
+            let l_depth = self.root.depth();
+            let r_depth = other.root.depth();
+
+            if l_depth > r_depth {
+                let extra =
+                    stats::make_mut(&mut self.root).append_at_depth(other.root, l_depth - r_depth);
+                if let Some(node) = extra {
+                    let mut children = NodeChildren::new();
+                    children.push((self.root.text_info(), Arc::clone(&self.root)));
+                    children.push((node.text_info(), node));
+                    self.root = Arc::new(Node::Internal(children));
+                    stats::count(Event::Allocation);
+                    stats::count(Event::Rebalance);
+                }
+            } else {
+                let extra = stats::make_mut(&mut other.root)
+                    .prepend_at_depth(Arc::clone(&self.root), r_depth - l_depth);
+                if let Some(node) = extra {
+                    let mut children = NodeChildren::new();
+                    children.push((node.text_info(), node));
+                    children.push((other.root.text_info(), Arc::clone(&other.root)));
+                    other.root = Arc::new(Node::Internal(children));
+                    stats::count(Event::Allocation);
+                    stats::count(Event::Rebalance);
+                }
+                self.root = other.root;
+            };
+
+            stats::make_mut(&mut self.root).fix_grapheme_seam(seam_byte_i, true);
+        }
+
+        self.rebalance_if_skewed();
+        recording.finish(self);
+    }
+
+    /// Appends the contents of a `RopeSlice` to the end of this `Rope`.
+    ///
+    /// The slice's data is shared rather than copied: only the text at the
+    /// edges of the slice is copied, and all whole subtrees in between are
+    /// reused.  This makes moving large regions of text between ropes
+    /// cheap.
+    ///
+    /// Runs in O(log N + log M) time, where N is the length of the `Rope`
+    /// and M is the length of the `Rope` the slice refers to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello ");
+    /// let other = Rope::from_str("Goodbye world!");
+    /// rope.append_slice(&other.slice(8..));
+    ///
+    /// assert_eq!(rope, "Hello world!");
+    /// ```
+    pub fn append_slice(&mut self, slice: &RopeSlice) {
+        self.append(slice.to_rope_with_chunk_size(self.chunk_size));
+    }
+
+    /// Applies a patch in unified diff format to the `Rope`.
+    ///
+    /// Each hunk's context and removed lines must match the `Rope`'s lines
+    /// exactly, at the line numbers given in the hunk's header.  Any file
+    /// header lines (`---`, `+++`, etc.) are skipped.  If the patch can't
+    /// be applied, the `Rope` is left unchanged and an error describing
+    /// the problem is returned.
+    ///
+    /// Unchanged text is shared rather than copied.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("one\ntwo\nthree\n");
+    /// rope.apply_patch("@@ -2 +2,2 @@\n-two\n+2\n+2.5\n").unwrap();
+    ///
+    /// assert_eq!(rope, "one\n2\n2.5\nthree\n");
+    /// ```
+    pub fn apply_patch(&mut self, patch: &str) -> Result<(), PatchError> {
+        let patched = patch::apply(self, patch)?;
+        self.replace_contents(patched);
+        Ok(())
+    }
+
+    /// Replaces each char in `range` with the chars returned by `f`.
+    ///
+    /// `f` may return any number of chars, so case conversions can be
+    /// passed directly (e.g. `char::to_uppercase`).  The range is
+    /// transformed in a single pass, and chunks that `f` leaves unchanged
+    /// are shared rather than copied.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in `char`
+    /// indices.
+    ///
+    /// Runs in O(M + log N) time, where M is the length of the range.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// rope.map_chars(6..11, char::to_uppercase);
+    /// rope.map_chars(..1, |c| Some(c.to_ascii_lowercase()));
+    ///
+    /// assert_eq!(rope, "hello WORLD!");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    pub fn map_chars<R, F, I>(&mut self, range: R, f: F)
+    where
+        R: CharIdxRange,
+        F: FnMut(char) -> I,
+        I: IntoIterator<Item = char>,
+    {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
+
+        // Bounds check
+        assert!(start <= end);
+        assert!(
+            end <= self.len_chars(),
+            "Attempt to map chars past end of Rope: range end {}, Rope length {}",
+            end,
+            self.len_chars()
+        );
+
+        let dirty = self.dirty.take();
+        let tail = self.split_off(end);
+        let middle = self.split_off(start);
+        let mapped = rewrite_chunks(&middle, &mut MapChars::new(f));
+        let mapped_len = mapped.len_chars();
+        self.append(mapped);
+        self.append(tail);
+        self.dirty = dirty;
+        self.mark_dirty(Edit::replace(start..end, mapped_len));
+    }
+
+    /// Returns a copy of the `Rope` with the first letter or digit of each
+    /// word uppercased and the rest lowercased.
+    ///
+    /// Words are runs of alphanumeric chars, which may contain apostrophes.
+    /// This is a simple heuristic rather than full Unicode word
+    /// segmentation, and chars are uppercased rather than titlecased, so
+    /// the few digraphs with distinct titlecase forms (e.g. "ǆ") become
+    /// fully uppercase.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("isn't it a FINE day-trip?");
+    ///
+    /// assert_eq!(rope.to_titlecase(), "Isn't It A Fine Day-Trip?");
+    /// ```
+    pub fn to_titlecase(&self) -> Rope {
+        rewrite_chunks(self, &mut TitleCase::new())
+    }
+
+    /// Returns a copy of the `Rope` with its chars in reverse order.
+    ///
+    /// This reverses individual chars, so CRLF pairs become LF CR pairs
+    /// (i.e. two line breaks), and combining marks end up before their
+    /// base chars.  See `reversed_graphemes()` for a reversal that keeps
+    /// those intact.
+    ///
+    /// The new `Rope` is built directly from the chunks of this one,
+    /// back-to-front, and keeps this one's chunk size.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nwörld!");
+    ///
+    /// assert_eq!(rope.reversed(), "!dlröw\nolleH");
+    /// ```
+    pub fn reversed(&self) -> Rope {
+        self.reversed_by(|chunk, buf| buf.extend(chunk.chars().rev()))
+    }
+
+    /// Returns a copy of the `Rope` with its extended grapheme clusters in
+    /// reverse order.
+    ///
+    /// Requires the `graphemes` feature.
+    ///
+    /// Unlike `reversed()`, this keeps CRLF pairs and multi-char graphemes
+    /// intact.  As with `char_to_grapheme()`, graphemes are segmented
+    /// within the context of the `Rope`'s own leaf chunks.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("ab\r\ne\u{301}");
+    ///
+    /// assert_eq!(rope.reversed_graphemes(), "e\u{301}\r\nba");
+    /// ```
+    #[cfg(feature = "graphemes")]
+    pub fn reversed_graphemes(&self) -> Rope {
+        self.reversed_by(|chunk, buf| buf.extend(chunk.graphemes(true).rev()))
+    }
+
+    /// Builds a reversed copy of the `Rope`, visiting its chunks
+    /// back-to-front and reversing each one with `reverse_chunk`.
+    fn reversed_by<F: FnMut(&str, &mut String)>(&self, mut reverse_chunk: F) -> Rope {
+        let chunks: Vec<&str> = self.chunks().collect();
+        let mut builder = RopeBuilder::with_chunk_size(self.chunk_size);
+        let mut buf = String::new();
+        for chunk in chunks.iter().rev() {
+            buf.clear();
+            reverse_chunk(chunk, &mut buf);
+            builder.append(&buf);
+        }
+        builder.finish()
+    }
+
+    /// Replaces every tab with spaces up to the next tab stop, where tab
+    /// stops are every `tab_width` columns.
+    ///
+    /// Columns are counted in display width, the same as for
+    /// [`RopeSlice::wrap()`](struct.RopeSlice.html#method.wrap).  This is
+    /// done in a single pass over the text, and chunks without any tabs
+    /// are shared rather than copied.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("\tfoo\nab\tc");
+    /// rope.expand_tabs(4);
+    ///
+    /// assert_eq!(rope, "    foo\nab  c");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tab_width` is zero.
+    pub fn expand_tabs(&mut self, tab_width: usize) {
+        let expanded = rewrite_chunks(self, &mut ExpandTabs::new(tab_width));
+        self.replace_contents(expanded);
+    }
+
+    /// Replaces every run of two or more spaces that ends at a tab stop
+    /// with a tab, where tab stops are every `tab_width` columns.
+    ///
+    /// This is the inverse of `expand_tabs()`, except for single spaces
+    /// before a tab stop, which are left alone.  As with `expand_tabs()`,
+    /// this is done in a single pass over the text, and unchanged chunks
+    /// are shared rather than copied.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("        foo\nab  c d");
+    /// rope.collapse_spaces_to_tabs(4);
+    ///
+    /// assert_eq!(rope, "\t\tfoo\nab\tc d");
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tab_width` is zero.
+    pub fn collapse_spaces_to_tabs(&mut self, tab_width: usize) {
+        let collapsed = rewrite_chunks(self, &mut CollapseSpaces::new(tab_width));
+        self.replace_contents(collapsed);
+    }
+
+    /// Removes trailing spaces and tabs from every line, returning the
+    /// number of lines that changed.
+    ///
+    /// All lines are trimmed in a single pass over the text, and chunks
+    /// without trailing whitespace are shared rather than copied.  Use
+    /// `trim_trailing_whitespace_edits()` to also find out where the text
+    /// was removed.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("foo  \nbar\n\t\nbaz \t");
+    ///
+    /// assert_eq!(rope.trim_trailing_whitespace(), 3);
+    /// assert_eq!(rope, "foo\nbar\n\nbaz");
+    /// ```
+    pub fn trim_trailing_whitespace(&mut self) -> usize {
+        self.trim_trailing_whitespace_edits().len()
+    }
+
+    /// Removes trailing spaces and tabs from every line, the same as
+    /// `trim_trailing_whitespace()`, returning an `Edit` for each line
+    /// that changed.
+    ///
+    /// The edits are in text order, and apply one after another, so they
+    /// can be fed in order to anything tracking positions in the text.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Edit, Rope};
+    /// let mut rope = Rope::from_str("foo  \nbar\nbaz \t");
+    /// let edits = rope.trim_trailing_whitespace_edits();
+    ///
+    /// assert_eq!(rope, "foo\nbar\nbaz");
+    /// assert_eq!(edits, vec![Edit::remove(3..5), Edit::remove(11..13)]);
+    /// ```
+    pub fn trim_trailing_whitespace_edits(&mut self) -> Vec<Edit> {
+        let mut trim = TrimTrailing::new();
+        self.root = rewrite_chunks(self, &mut trim).root;
+        for edit in &trim.edits {
+            self.mark_dirty(*edit);
+        }
+        trim.edits
+    }
+
+    /// Returns the char range of the text edited since the last call to
+    /// this method, and resets it.
+    ///
+    /// The range covers all edits since the last call, in char indices of
+    /// the current text.  Text that was only removed leaves an empty range
+    /// at the removal point.  Returns `None` if there were no edits.
+    ///
+    /// This is handy for renderers and incremental parsers that only need
+    /// to know roughly what changed since they last looked.  Edits that
+    /// rewrite the whole text, such as `expand_tabs()`, conservatively
+    /// mark everything between their first and last change as edited.
+    /// Clones of a `Rope` start out with the same edited range.
+    ///
+    /// Runs in O(1) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello world!");
+    /// assert_eq!(rope.take_dirty_range(), None);
+    ///
+    /// rope.insert(5, ",");
+    /// rope.remove(7..12);
+    /// assert_eq!(rope.take_dirty_range(), Some(5..7));
+    /// assert_eq!(rope.take_dirty_range(), None);
+    /// ```
+    pub fn take_dirty_range(&mut self) -> Option<Range<usize>> {
+        self.dirty.take()
+    }
+
+    /// Forgets the edited range, for ropes that are built from scratch and
+    /// so have no earlier version for the edits to be relative to.
+    pub(crate) fn clear_dirty_range(&mut self) {
+        self.dirty = None;
+    }
+
+    /// Extends the edited range to cover `edit`.
+    fn mark_dirty(&mut self, edit: Edit) {
+        if edit.old_end == edit.start && edit.new_end == edit.start {
+            return;
+        }
+        self.dirty = Some(match self.dirty.take() {
+            None => edit.new_range(),
+            Some(dirty) => {
+                let end = if dirty.end <= edit.start {
+                    dirty.end
+                } else if dirty.end >= edit.old_end {
+                    edit.shift(dirty.end)
+                } else {
+                    edit.new_end
+                };
+                dirty.start.min(edit.start)..end.max(edit.new_end)
+            }
+        });
+    }
+
+    /// Replaces the `Rope`'s text with `new`'s, marking the text between
+    /// the first and last differences as edited.
+    fn replace_contents(&mut self, new: Rope) {
+        let (prefix, equal) = Node::shared_prefix_bytes(&self.root, &new.root);
+        if !equal {
+            let (suffix, _) = Node::shared_suffix_bytes(&self.root, &new.root);
+            let old_len = self.len_bytes();
+            let new_len = new.len_bytes();
+            let prefix = prefix.min(old_len).min(new_len);
+            let suffix = suffix.min(old_len - prefix).min(new_len - prefix);
+
+            // Round the ends of the changed range up to char boundaries.
+            let end_char = |rope: &Rope, byte_idx: usize| {
+                let char_idx = rope.byte_to_char(byte_idx);
+                if rope.char_to_byte(char_idx) < byte_idx {
+                    char_idx + 1
+                } else {
+                    char_idx
+                }
+            };
+            let start = self.byte_to_char(prefix);
+            let old_end = end_char(self, old_len - suffix);
+            let new_end = end_char(&new, new_len - suffix);
+            self.mark_dirty(Edit {
+                start,
+                old_end,
+                new_end,
+            });
+        }
+        self.root = new.root;
+    }
+
+    /// Checks that `char_idx` is in bounds, i.e. `char_idx <= len_chars()`.
+    fn check_char_idx(&self, char_idx: usize) -> Result<(), RopeError> {
+        if char_idx > self.len_chars() {
+            Err(RopeError::CharIndexOutOfBounds {
+                char_idx,
+                len_chars: self.len_chars(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies `policy` to `char_idx` if it's inside a grapheme cluster.
+    fn check_boundary(
+        &self,
+        char_idx: usize,
+        policy: BoundaryPolicy,
+    ) -> Result<usize, BoundaryError> {
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+        match self.cluster_at(char_idx) {
+            Some(cluster) => policy.resolve(char_idx, cluster),
+            None => Ok(char_idx),
+        }
+    }
+
+    /// Returns the char range of the grapheme cluster that `char_idx` is
+    /// strictly inside of, if any.
+    fn cluster_at(&self, char_idx: usize) -> Option<Range<usize>> {
+        // Leaf boundaries are always cluster boundaries, so the leaf's own
+        // text is enough context.
+        let (chunk, chunk_char_idx) = self.root.get_chunk_at_char(char_idx);
+        let byte_idx = char_idx_to_byte_idx(chunk, chunk_char_idx);
+        let bytes = chunk.as_bytes();
+        if crlf::is_break(byte_idx, bytes) {
+            return None;
+        }
+        let chunk_start = char_idx - chunk_char_idx;
+        let start = chunk_start + count_chars(&chunk[..crlf::prev_break(byte_idx, bytes)]);
+        let end = chunk_start + count_chars(&chunk[..crlf::next_break(byte_idx, bytes)]);
+        Some(start..end)
+    }
+
+    //-----------------------------------------------------------------------
+    // Index conversion methods
+
+    /// Returns whether `byte_idx` is on a char boundary, like
+    /// `str::is_char_boundary()`.
+    ///
+    /// The start and end of the `Rope` count as boundaries.  Indices past
+    /// the end don't.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは");
+    ///
+    /// assert!(rope.is_char_boundary(3));
+    /// assert!(!rope.is_char_boundary(4));
+    /// assert!(rope.is_char_boundary(15));
+    /// assert!(!rope.is_char_boundary(16));
+    /// ```
+    pub fn is_char_boundary(&self, byte_idx: usize) -> bool {
+        if byte_idx == 0 || byte_idx == self.len_bytes() {
+            return true;
+        } else if byte_idx > self.len_bytes() {
+            return false;
+        }
+        let (text, start) = self.leaf_at_byte(byte_idx);
+        text.is_char_boundary(byte_idx - start)
+    }
+
+    /// Returns the closest char boundary at or before `byte_idx`.
+    ///
+    /// Indices past the end give the end of the `Rope`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは");
+    ///
+    /// assert_eq!(3, rope.floor_char_boundary(4));
+    /// assert_eq!(6, rope.floor_char_boundary(6));
+    /// assert_eq!(15, rope.floor_char_boundary(100));
+    /// ```
+    pub fn floor_char_boundary(&self, byte_idx: usize) -> usize {
+        if byte_idx >= self.len_bytes() {
+            return self.len_bytes();
+        }
+        let (text, start) = self.leaf_at_byte(byte_idx);
+        let mut local_idx = byte_idx - start;
+        while !text.is_char_boundary(local_idx) {
+            local_idx -= 1;
+        }
+        start + local_idx
+    }
+
+    /// Returns the closest char boundary at or after `byte_idx`.
+    ///
+    /// Indices past the end give the end of the `Rope`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは");
+    ///
+    /// assert_eq!(6, rope.ceil_char_boundary(4));
+    /// assert_eq!(6, rope.ceil_char_boundary(6));
+    /// assert_eq!(15, rope.ceil_char_boundary(100));
+    /// ```
+    pub fn ceil_char_boundary(&self, byte_idx: usize) -> usize {
+        if byte_idx >= self.len_bytes() {
+            return self.len_bytes();
+        }
+        // Leaves always end on a char boundary.
+        let (text, start) = self.leaf_at_byte(byte_idx);
+        let mut local_idx = byte_idx - start;
+        while !text.is_char_boundary(local_idx) {
+            local_idx += 1;
+        }
+        start + local_idx
+    }
+
+    /// Returns the char index of the given byte.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    #[allow(dead_code)]
+    pub fn byte_to_char(&self, byte_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        self.root.byte_to_char(byte_idx)
+    }
+
+    /// Returns the char index of the given byte, or `None` if `byte_idx` is out
+    /// of bounds (i.e. `byte_idx > len_bytes()`).
+    ///
+    /// The non-panicking version of `byte_to_char()`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは");
+    ///
+    /// assert_eq!(Some(2), rope.get_byte_to_char(6));
+    /// assert_eq!(Some(5), rope.get_byte_to_char(15));
+    /// assert_eq!(None, rope.get_byte_to_char(16));
+    /// ```
+    #[inline]
+    pub fn get_byte_to_char(&self, byte_idx: usize) -> Option<usize> {
+        if byte_idx <= self.len_bytes() {
+            Some(self.byte_to_char(byte_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the line index of the given byte.
+    ///
+    /// Notes:
+    ///
+    /// - Lines are zero-indexed.
+    /// - If `byte_idx` is one-past-the-end, then one-past-the-end line index
+    ///   is returned.  This is mainly unintuitive for empty ropes, which will
+    ///   return a line index of 1 for a `byte_idx` of zero.  Otherwise it
+    ///   behaves as expected.
+    ///
+    /// This is the same as `char_to_line(byte_to_char(byte_idx))`, but is
+    /// done in a single descent of the tree instead of two.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは\nworld!");
+    ///
+    /// assert_eq!(0, rope.byte_to_line(15));
+    /// assert_eq!(1, rope.byte_to_line(16));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    #[allow(dead_code)]
+    pub fn byte_to_line(&self, byte_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        if byte_idx == self.len_bytes() {
+            self.len_lines()
+        } else {
+            self.root.byte_to_line(byte_idx)
+        }
+    }
+
+    /// Returns the byte index of the given char.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[allow(dead_code)]
+    pub fn char_to_byte(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        self.root.char_to_byte(char_idx)
+    }
+
+    /// Returns the byte index of the given char, or `None` if `char_idx` is out
+    /// of bounds (i.e. `char_idx > len_chars()`).
+    ///
+    /// The non-panicking version of `char_to_byte()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_char_to_byte(&self, char_idx: usize) -> Option<usize> {
+        if char_idx <= self.len_chars() {
+            Some(self.char_to_byte(char_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the line index of the given char.
+    ///
+    /// Notes:
+    ///
+    /// - Lines are zero-indexed.
+    /// - If `char_idx` is one-past-the-end, then one-past-the-end line index
+    ///   is returned.  This is mainly unintuitive for empty ropes, which will
+    ///   return a line index of 1 for a `char_idx` of zero.  Otherwise it
+    ///   behaves as expected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_line(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        if char_idx == self.len_chars() {
+            self.len_lines()
+        } else {
+            self.root.char_to_line(char_idx)
+        }
+    }
+
+    /// Returns the line index of the given char, or `None` if `char_idx` is out
+    /// of bounds (i.e. `char_idx > len_chars()`).
+    ///
+    /// The non-panicking version of `char_to_line()`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    ///
+    /// assert_eq!(Some(1), rope.get_char_to_line(6));
+    /// assert_eq!(None, rope.get_char_to_line(13));
+    /// ```
+    #[inline]
+    pub fn get_char_to_line(&self, char_idx: usize) -> Option<usize> {
+        if char_idx <= self.len_chars() {
+            Some(self.char_to_line(char_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the line index of the given char, along with its column:
+    /// its char offset from the start of that line.
+    ///
+    /// This is the same as `char_to_line()` followed by subtracting
+    /// `line_to_char()` of the result from `char_idx`, but is done in a
+    /// single descent of the tree instead of two.  Unlike `char_to_line()`,
+    /// one-past-the-end is at the end of the last line, rather than on a
+    /// line of its own.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    ///
+    /// assert_eq!((0, 2), rope.char_to_line_col(2));
+    /// assert_eq!((1, 0), rope.char_to_line_col(6));
+    /// assert_eq!((1, 6), rope.char_to_line_col(12));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_line_col(&self, char_idx: usize) -> (usize, usize) {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        self.root.char_to_line_col(char_idx)
+    }
+
+    /// Returns the byte indices of the given chars.
+    ///
+    /// `char_idxs` must be sorted in ascending order.  All of the indices
+    /// are converted in a single pass over the rope's tree, which is much
+    /// faster than converting them one by one with `char_to_byte()`.
+    ///
+    /// Runs in O(M + log N) time in the best case and O(M log N) in the
+    /// worst, where M is the number of indices.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello みんなさん!");
+    ///
+    /// assert_eq!(rope.chars_to_bytes(&[0, 6, 7, 12]), vec![0, 6, 9, 22]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idxs` isn't sorted, or if any of the indices is out
+    /// of bounds (i.e. `char_idx > len_chars()`).
+    pub fn chars_to_bytes(&self, char_idxs: &[usize]) -> Vec<usize> {
+        self.convert_sorted(
+            char_idxs,
+            self.len_chars(),
+            |inf| inf.chars as usize,
+            |inf| inf.bytes as usize,
+            char_idx_to_byte_idx,
+        )
+    }
+
+    /// Returns the char indices of the given bytes.
+    ///
+    /// `byte_idxs` must be sorted in ascending order.  All of the indices
+    /// are converted in a single pass over the rope's tree, as with
+    /// `chars_to_bytes()`.
+    ///
+    /// Runs in O(M + log N) time in the best case and O(M log N) in the
+    /// worst, where M is the number of indices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idxs` isn't sorted, or if any of the indices is out
+    /// of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn bytes_to_chars(&self, byte_idxs: &[usize]) -> Vec<usize> {
+        self.convert_sorted(
+            byte_idxs,
+            self.len_bytes(),
+            |inf| inf.bytes as usize,
+            |inf| inf.chars as usize,
+            byte_idx_to_char_idx,
+        )
+    }
+
+    fn convert_sorted<I, O, C>(
+        &self,
+        idxs: &[usize],
+        len: usize,
+        metric_in: I,
+        metric_out: O,
+        convert: C,
+    ) -> Vec<usize>
+    where
+        I: Fn(&TextInfo) -> usize,
+        O: Fn(&TextInfo) -> usize,
+        C: Fn(&str, usize) -> usize,
+    {
+        assert!(
+            idxs.windows(2).all(|w| w[0] <= w[1]),
+            "Indices to convert aren't sorted"
+        );
+        if let Some(&last) = idxs.last() {
+            assert!(
+                last <= len,
+                "Attempt to index past end of Rope: index {}, Rope length {}",
+                last,
+                len
+            );
+        }
+
+        let mut out = Vec::with_capacity(idxs.len());
+        self.root.convert_sorted(idxs, (0, 0), &metric_in, &metric_out, &convert, &mut out);
+        out
+    }
+
+    /// Returns the byte index of the given char, using and updating `hint`.
+    ///
+    /// Gives the same result as `char_to_byte()`, but runs in O(1) time
+    /// when `char_idx` is in the same chunk as the previous conversion
+    /// that used `hint`.  See [`PosHint`](struct.PosHint.html).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_byte_with_hint(&self, char_idx: usize, hint: &mut PosHint) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        let (leaf, before) = self.hinted_leaf(hint, |inf| char_idx as Count <= inf.chars);
+        let local_idx = char_idx - before.chars as usize;
+        before.bytes as usize + char_idx_to_byte_idx(leaf.leaf_text(), local_idx)
+    }
+
+    /// Returns the char index of the given byte, using and updating `hint`.
+    ///
+    /// Gives the same result as `byte_to_char()`, but runs in O(1) time
+    /// when `byte_idx` is in the same chunk as the previous conversion
+    /// that used `hint`.  See [`PosHint`](struct.PosHint.html).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn byte_to_char_with_hint(&self, byte_idx: usize, hint: &mut PosHint) -> usize {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        let (leaf, before) = self.hinted_leaf(hint, |inf| byte_idx as Count <= inf.bytes);
+        let local_idx = byte_idx - before.bytes as usize;
+        before.chars as usize + byte_idx_to_char_idx(leaf.leaf_text(), local_idx)
+    }
+
+    /// Returns the line index of the given char, using and updating `hint`.
+    ///
+    /// Gives the same result as `char_to_line()`, but runs in O(1) time
+    /// when `char_idx` is in the same chunk as the previous conversion
+    /// that used `hint`.  See [`PosHint`](struct.PosHint.html).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_line_with_hint(&self, char_idx: usize, hint: &mut PosHint) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        if char_idx == self.len_chars() {
+            return self.len_lines();
+        }
+        let (leaf, before) = self.hinted_leaf(hint, |inf| char_idx as Count <= inf.chars);
+        let local_idx = char_idx - before.chars as usize;
+        before.line_breaks as usize + char_idx_to_line_idx(leaf.leaf_text(), local_idx)
+    }
+
+    /// Returns the leaf that `pred` leads to, as with `Node::find_leaf()`,
+    /// and the text info of everything before it.
+    ///
+    /// The hinted leaf is used if it's the one `pred` would lead to, and
+    /// otherwise `hint` is updated to the leaf that's found.
+    fn hinted_leaf<F>(&self, hint: &mut PosHint, pred: F) -> (Arc<Node>, TextInfo)
+    where
+        F: Fn(&TextInfo) -> bool,
+    {
+        if let Some((leaf, before, end)) = hint.get(&self.root) {
+            // `pred` must be true up to the end of the leaf, and false up
+            // to the end of everything before it (unless nothing is).
+            let first = before.chars == 0 && before.bytes == 0;
+            if pred(&end) && (first || !pred(&before)) {
+                return (leaf, before);
+            }
+        }
+        let (leaf, before, info) = Node::find_leaf(&self.root, pred);
+        hint.set(&self.root, leaf, before, info);
+        (Arc::clone(leaf), before)
+    }
+
+    /// Returns the byte index of the start of the given line.
+    ///
+    /// Notes:
+    ///
+    /// - Lines are zero-indexed.
+    /// - `line_idx` can be one-past-the-end, which will return one-past-the-end
+    ///   byte index.
+    ///
+    /// This is the same as `char_to_byte(line_to_char(line_idx))`, but is
+    /// done in a single descent of the tree instead of two.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは\nworld!");
+    ///
+    /// assert_eq!(16, rope.line_to_byte(1));
+    /// assert_eq!(22, rope.line_to_byte(2));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    pub fn line_to_byte(&self, line_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            line_idx <= self.len_lines(),
+            "Attempt to index past end of Rope: line index {}, Rope line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        if line_idx == self.len_lines() {
+            self.len_bytes()
+        } else {
+            self.root.line_to_byte(line_idx)
+        }
+    }
+
+    /// Returns the length in chars of the given line, including its line
+    /// break.
+    ///
+    /// This is the same as `line(line_idx).len_chars()`, but finds the
+    /// starts of the line and the next one in a single descent of the
+    /// tree, and doesn't create a `RopeSlice`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは\r\nworld!");
+    ///
+    /// assert_eq!(7, rope.line_len_chars(0));
+    /// assert_eq!(6, rope.line_len_chars(1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    pub fn line_len_chars(&self, line_idx: usize) -> usize {
+        self.line_len(line_idx).1
+    }
+
+    /// Returns the length in bytes of the given line, including its line
+    /// break.
+    ///
+    /// This is the same as `line(line_idx).len_bytes()`.  See
+    /// `line_len_chars()`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは\r\nworld!");
+    ///
+    /// assert_eq!(17, rope.line_len_bytes(0));
+    /// assert_eq!(6, rope.line_len_bytes(1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    pub fn line_len_bytes(&self, line_idx: usize) -> usize {
+        self.line_len(line_idx).0
+    }
+
+    /// Returns the char index of the start of the given line.
+    ///
+    /// Notes:
+    ///
+    /// - Lines are zero-indexed.
+    /// - `line_idx` can be one-past-the-end, which will return one-past-the-end
+    ///   char index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    pub fn line_to_char(&self, line_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            line_idx <= self.len_lines(),
+            "Attempt to index past end of Rope: line index {}, Rope line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        if line_idx == self.len_lines() {
+            self.len_chars()
+        } else {
+            self.root.line_to_char(line_idx)
+        }
+    }
+
+    /// Returns the char index of the start of the given line, or `None` if
+    /// `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    ///
+    /// The non-panicking version of `line_to_char()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_line_to_char(&self, line_idx: usize) -> Option<usize> {
+        if line_idx <= self.len_lines() {
+            Some(self.line_to_char(line_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the char index at column `col` (i.e. `col` chars after the
+    /// start) of the given line.
+    ///
+    /// The inverse of `char_to_line_col()`.  Like it, this only descends
+    /// the tree once.
+    ///
+    /// Note: `col` isn't checked against the length of the line, so a
+    /// column past the end of the line gives an index in a later line.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    ///
+    /// assert_eq!(2, rope.line_col_to_char(0, 2));
+    /// assert_eq!(12, rope.line_col_to_char(1, 6));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`),
+    /// or if the resulting char index is out of bounds (i.e. `> len_chars()`).
+    pub fn line_col_to_char(&self, line_idx: usize, col: usize) -> usize {
+        // Bounds check
+        assert!(
+            line_idx < self.len_lines(),
+            "Attempt to index past end of Rope: line index {}, Rope line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        let char_idx = self.line_to_char(line_idx) + col;
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        char_idx
+    }
+
+    /// Returns whether `char_idx` is at the start of a line.
+    ///
+    /// Answered from the tree's line break counts, without first finding
+    /// the line index.  The start of the `Rope` is always a line start, and
+    /// its end is one if the last char is a line break.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!\n");
+    ///
+    /// assert!(rope.is_line_start(0));
+    /// assert!(!rope.is_line_start(6));
+    /// assert!(rope.is_line_start(7));
+    /// assert!(rope.is_line_start(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn is_line_start(&self, char_idx: usize) -> bool {
+        self.line_boundary_before(char_idx) == char_idx
+    }
+
+    /// Returns whether `char_idx` is at the end of a line, i.e. just before
+    /// its line break, or at the end of the `Rope`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!\n");
+    ///
+    /// assert!(rope.is_line_end(5));
+    /// assert!(!rope.is_line_end(6));
+    /// assert!(rope.is_line_end(13));
+    /// assert!(rope.is_line_end(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn is_line_end(&self, char_idx: usize) -> bool {
+        self.line_boundary_after(char_idx) == char_idx
+    }
+
+    /// Returns the char index of the start of the line containing
+    /// `char_idx`.
+    ///
+    /// Unlike `line_to_char(char_to_line(char_idx))`, this only descends
+    /// the tree once (or twice, if the line starts in an earlier leaf).
+    /// A one-past-the-end `char_idx` belongs to the last line.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!\n");
+    ///
+    /// assert_eq!(0, rope.line_boundary_before(6));
+    /// assert_eq!(7, rope.line_boundary_before(7));
+    /// assert_eq!(7, rope.line_boundary_before(10));
+    /// assert_eq!(14, rope.line_boundary_before(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn line_boundary_before(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        char_idx - self.root.char_to_line_col(char_idx).1
+    }
+
+    /// Returns the char index of the end of the line containing
+    /// `char_idx`: just before its line break, or the end of the `Rope`
+    /// for the last line.
+    ///
+    /// Note: if `char_idx` is between the two chars of a CRLF pair, the
+    /// returned index is that of the CR, before `char_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!\n");
+    ///
+    /// assert_eq!(5, rope.line_boundary_after(0));
+    /// assert_eq!(5, rope.line_boundary_after(5));
+    /// assert_eq!(13, rope.line_boundary_after(7));
+    /// assert_eq!(14, rope.line_boundary_after(14));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn line_boundary_after(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        let line_idx = self.root.char_to_line_col(char_idx).0;
+        if line_idx + 1 >= self.len_lines() {
+            return self.len_chars();
+        }
+
+        // Back up over the line break that ends the line.
+        let next_start = self.root.line_to_char(line_idx + 1);
+        if next_start >= 2 && self.char(next_start - 1) == '\n'
+            && self.char(next_start - 2) == '\r'
+        {
+            next_start - 2
+        } else {
+            next_start - 1
+        }
+    }
+
+    /// Returns the index of the extended grapheme cluster that the given
+    /// char belongs to.
+    ///
+    /// Requires the `graphemes` feature.
+    ///
+    /// Notes:
+    ///
+    /// - If `char_idx` is one-past-the-end, then one-past-the-end grapheme
+    ///   index is returned.
+    /// - Graphemes are segmented within the context of the `Rope`'s own
+    ///   leaf chunks.  This only matters for unusually long runs of
+    ///   context-dependent graphemes, such as regional indicator (flag)
+    ///   sequences, which may be segmented slightly differently than when
+    ///   segmenting the whole text at once.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    #[cfg(feature = "graphemes")]
+    pub fn char_to_grapheme(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        if char_idx == self.len_chars() {
+            self.len_graphemes()
+        } else {
+            self.root.char_to_grapheme(char_idx)
+        }
+    }
+
+    /// Returns the UTF-16 code unit index of the given char.
+    ///
+    /// This is what e.g. the Language Server Protocol uses for columns by
+    /// default.  Chars outside the Basic Multilingual Plane take two code
+    /// units, all others one.
+    ///
+    /// Notes:
+    ///
+    /// - `char_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end UTF-16 code unit index.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a😀b");
+    ///
+    /// assert_eq!(1, rope.char_to_utf16_cu(1));
+    /// assert_eq!(3, rope.char_to_utf16_cu(2));
+    /// assert_eq!(4, rope.char_to_utf16_cu(3));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn char_to_utf16_cu(&self, char_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        if char_idx == self.len_chars() {
+            self.len_utf16_cu()
+        } else {
+            self.root.char_to_utf16_cu(char_idx)
+        }
+    }
+
+    /// Returns the index of the char containing the given UTF-16 code
+    /// unit.
+    ///
+    /// The inverse of `char_to_utf16_cu()`.  An index in the middle of a
+    /// surrogate pair gives the char that the pair encodes.
+    ///
+    /// Notes:
+    ///
+    /// - `utf16_cu_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end char index.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a😀b");
+    ///
+    /// assert_eq!(1, rope.utf16_cu_to_char(1));
+    /// assert_eq!(1, rope.utf16_cu_to_char(2));
+    /// assert_eq!(2, rope.utf16_cu_to_char(3));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `utf16_cu_idx` is out of bounds (i.e.
+    /// `utf16_cu_idx > len_utf16_cu()`).
+    pub fn utf16_cu_to_char(&self, utf16_cu_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            utf16_cu_idx <= self.len_utf16_cu(),
+            "Attempt to index past end of Rope: UTF-16 code unit index {}, Rope UTF-16 length {}",
+            utf16_cu_idx,
+            self.len_utf16_cu()
+        );
+
+        if utf16_cu_idx == self.len_utf16_cu() {
+            self.len_chars()
+        } else {
+            self.root.utf16_cu_to_char(utf16_cu_idx)
+        }
+    }
+
+    /// Returns the char index of the start of the given extended grapheme
+    /// cluster.
+    ///
+    /// Requires the `graphemes` feature.
+    ///
+    /// Notes:
+    ///
+    /// - `grapheme_idx` can be one-past-the-end, which will return
+    ///   one-past-the-end char index.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `grapheme_idx` is out of bounds (i.e.
+    /// `grapheme_idx > len_graphemes()`).
+    #[cfg(feature = "graphemes")]
+    pub fn grapheme_to_char(&self, grapheme_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            grapheme_idx <= self.len_graphemes(),
+            "Attempt to index past end of Rope: grapheme index {}, Rope grapheme length {}",
+            grapheme_idx,
+            self.len_graphemes()
+        );
+
+        if grapheme_idx == self.len_graphemes() {
+            self.len_chars()
+        } else {
+            self.root.grapheme_to_char(grapheme_idx)
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Fetch methods
+
+    /// Returns the byte at `byte_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("\u{FEFF}Hello!");
+    ///
+    /// assert_eq!(0xEF, rope.byte(0));
+    /// assert_eq!(b'!', rope.byte(8));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx >= len_bytes()`).
+    pub fn byte(&self, byte_idx: usize) -> u8 {
+        // Bounds check
+        assert!(
+            byte_idx < self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        let (chunk, start) = self.leaf_at_byte(byte_idx);
+        chunk.as_bytes()[byte_idx - start]
+    }
+
+    /// Returns the char at `char_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len_chars()`).
+    pub fn char(&self, char_idx: usize) -> char {
+        // Bounds check
+        assert!(
+            char_idx < self.len_chars(),
+            "Attempt to index past end of Rope: char index {}, Rope char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        let (chunk, offset) = self.root.get_chunk_at_char(char_idx);
+        let byte_idx = char_idx_to_byte_idx(chunk, offset);
+        chunk[byte_idx..].chars().nth(0).unwrap()
+    }
+
+    /// Returns the char at `char_idx`, or `None` if `char_idx` is out of
+    /// bounds (i.e. `char_idx >= len_chars()`).
+    ///
+    /// The non-panicking version of `char()`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello!");
+    ///
+    /// assert_eq!(Some('!'), rope.get_char(5));
+    /// assert_eq!(None, rope.get_char(6));
+    /// ```
+    #[inline]
+    pub fn get_char(&self, char_idx: usize) -> Option<char> {
+        if char_idx < self.len_chars() {
+            Some(self.char(char_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the first char of the `Rope`, or `None` if it's empty.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn first_char(&self) -> Option<char> {
+        if self.len_chars() == 0 {
+            None
+        } else {
+            Some(self.char(0))
+        }
+    }
+
+    /// Returns the last char of the `Rope`, or `None` if it's empty.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!\n");
+    ///
+    /// assert_eq!(rope.first_char(), Some('H'));
+    /// assert_eq!(rope.last_char(), Some('\n'));
+    /// assert_eq!(Rope::new().last_char(), None);
+    /// ```
+    #[inline]
+    pub fn last_char(&self) -> Option<char> {
+        if self.len_chars() == 0 {
+            None
+        } else {
+            Some(self.char(self.len_chars() - 1))
+        }
+    }
+
+    /// Returns the line at `line_idx`.
+    ///
+    /// Note: lines are zero-indexed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    pub fn line(&self, line_idx: usize) -> RopeSlice {
+        // Bounds check
+        assert!(
+            line_idx < self.len_lines(),
+            "Attempt to index past end of Rope: line index {}, Rope line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        let start = self.line_to_char(line_idx);
+        let end = self.line_to_char(line_idx + 1);
+
+        self.slice(start..end)
+    }
+
+    /// Returns the line at `line_idx`, or `None` if `line_idx` is out of
+    /// bounds (i.e. `line_idx >= len_lines()`).
+    ///
+    /// The non-panicking version of `line()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    ///
+    /// assert_eq!(rope.get_line(1).unwrap(), "world!");
+    /// assert!(rope.get_line(2).is_none());
+    /// ```
+    #[inline]
+    pub fn get_line(&self, line_idx: usize) -> Option<RopeSlice<'_>> {
+        if line_idx < self.len_lines() {
+            Some(self.line(line_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the text of the line at `line_idx`, borrowed if the whole
+    /// line is in a single chunk.
+    ///
+    /// Most lines are much shorter than a chunk, so this usually doesn't
+    /// allocate, unlike `line(line_idx).to_string()`.
+    ///
+    /// Runs in O(log N) time when borrowing, and O(M + log N) time when
+    /// allocating, where M is the length of the line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// use std::borrow::Cow;
+    ///
+    /// let rope = Rope::from_str("Hello\nworld!");
+    ///
+    /// assert_eq!(Cow::Borrowed("world!"), rope.line_to_cow(1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    pub fn line_to_cow(&self, line_idx: usize) -> Cow<'_, str> {
+        let line = self.line(line_idx);
+        let (chunk, _, _, _) = line.chunk_at_byte(0);
+        if chunk.len() == line.len_bytes() {
+            Cow::Borrowed(chunk)
+        } else {
+            Cow::Owned(line.to_string())
+        }
+    }
+
+    /// Returns the chunk containing the given byte index.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// chunk.  The return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// One-past-the-end is valid, and returns the last chunk.  An empty
+    /// `Rope` has a single empty chunk.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn chunk_at_byte(&self, byte_idx: usize) -> (&str, usize, usize, usize) {
+        self.slice(..).chunk_at_byte(byte_idx)
+    }
+
+    /// Returns the chunk containing the given char index.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// chunk.  The return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// One-past-the-end is valid, and returns the last chunk.  An empty
+    /// `Rope` has a single empty chunk.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str(&"Hello world!\n".repeat(1000));
+    ///
+    /// // Walk the text from char 5000 onward, a chunk at a time.
+    /// let (chunk, _, chunk_char_idx, _) = rope.chunk_at_char(5000);
+    /// let mut text = chunk.chars().skip(5000 - chunk_char_idx).collect::<String>();
+    /// let mut char_idx = chunk_char_idx + chunk.chars().count();
+    /// while char_idx < rope.len_chars() {
+    ///     let (chunk, _, _, _) = rope.chunk_at_char(char_idx);
+    ///     text.push_str(chunk);
+    ///     char_idx += chunk.chars().count();
+    /// }
+    ///
+    /// assert_eq!(rope.slice(5000..), text.as_str());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn chunk_at_char(&self, char_idx: usize) -> (&str, usize, usize, usize) {
+        self.slice(..).chunk_at_char(char_idx)
+    }
+
+    /// Returns the chunk containing the given line break.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// chunk.  The return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// For convenience, both the start and end of the `Rope` count as line
+    /// breaks: 0 gives the first chunk, `len_lines()` gives the last one,
+    /// and anything in between gives the chunk containing the line break
+    /// at the end of line `line_break_idx - 1`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    pub fn chunk_at_line_break(&self, line_break_idx: usize) -> (&str, usize, usize, usize) {
+        self.slice(..).chunk_at_line_break(line_break_idx)
+    }
+
+    /// Returns the base direction of the line at `line_idx`, or `None` if
+    /// it has no strongly directional chars.
+    ///
+    /// Requires the `bidi` feature.
+    ///
+    /// The direction is that of the line's first strong char, following
+    /// rules P2 and P3 of the Unicode Bidirectional Algorithm, with each
+    /// line treated as its own paragraph.  Text inside directional
+    /// isolates is skipped.  Callers usually fall back to left-to-right
+    /// (or the previous line's direction) for `None`.
+    ///
+    /// Runs in O(log N + M) time, where M is the number of chars scanned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Direction, Rope};
+    /// let rope = Rope::from_str("hello\n  שלום world\n123\n");
+    ///
+    /// assert_eq!(rope.line_direction(0), Some(Direction::Ltr));
+    /// assert_eq!(rope.line_direction(1), Some(Direction::Rtl));
+    /// assert_eq!(rope.line_direction(2), None);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx >= len_lines()`).
+    #[cfg(feature = "bidi")]
+    pub fn line_direction(&self, line_idx: usize) -> Option<Direction> {
+        first_strong_direction(&self.line(line_idx))
+    }
+
+    //-----------------------------------------------------------------------
+    // Search methods
+
+    /// Returns the char index range of the first match of `pattern`, or
+    /// `None` if there isn't one.
+    ///
+    /// Runs in O(N + M) time, where N is the length of the `Rope` and M is
+    /// the length of `pattern`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    ///
+    /// assert_eq!(Some(6..11), rope.find("world"));
+    /// assert_eq!(None, rope.find("World"));
+    /// ```
+    pub fn find<P: Pattern>(&self, pattern: P) -> Option<Range<usize>> {
+        self.slice(..).find(pattern)
+    }
+
+    /// Creates an iterator over the matches found by `searcher`, which is
+    /// fed the `Rope`'s chunks in order.
+    ///
+    /// See [`RopeSlice::search()`](struct.RopeSlice.html#method.search).
+    pub fn search<S: ChunkSearcher>(&self, searcher: S) -> SearchMatches<'_, S> {
+        self.slice(..).search(searcher)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `pattern`,
+    /// yielding the char index and text of each.
+    ///
+    /// See [`RopeSlice::match_indices()`](struct.RopeSlice.html#method.match_indices).
+    pub fn match_indices<P: Pattern>(&self, pattern: P) -> MatchIndices<'_, P::Matcher> {
+        self.slice(..).match_indices(pattern)
+    }
+
+    /// Creates an iterator over the pieces of the `Rope` between the
+    /// non-overlapping matches of `pattern`.
+    ///
+    /// See [`RopeSlice::split()`](struct.RopeSlice.html#method.split).
+    pub fn split<P: Pattern>(&self, pattern: P) -> Split<'_, P::Matcher> {
+        self.slice(..).split(pattern)
+    }
+
+    /// Returns a slice of the `Rope` with all leading and trailing
+    /// matches of `pattern` removed.
+    pub fn trim_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'_> {
+        self.slice(..).trim_matches(pattern)
+    }
+
+    /// Returns a slice of the `Rope` with all leading matches of
+    /// `pattern` removed.
+    pub fn trim_start_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'_> {
+        self.slice(..).trim_start_matches(pattern)
+    }
+
+    /// Returns a slice of the `Rope` with all trailing matches of
+    /// `pattern` removed.
+    pub fn trim_end_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'_> {
+        self.slice(..).trim_end_matches(pattern)
+    }
+
+    /// Like `find()`, but matches according to `options`.
+    pub fn find_with(&self, pattern: &str, options: SearchOptions) -> Option<Range<usize>> {
+        self.slice(..).find_with(pattern, options)
+    }
+
+    /// Creates an iterator over the char index ranges of the
+    /// non-overlapping matches of `pattern`.
+    ///
+    /// An empty pattern never matches.
+    pub fn matches(&self, pattern: &str) -> Matches<'_> {
+        self.slice(..).matches(pattern)
+    }
+
+    /// Like `matches()`, but matches according to `options`.
+    pub fn matches_with(&self, pattern: &str, options: SearchOptions) -> Matches<'_> {
+        self.slice(..).matches_with(pattern, options)
+    }
+
+    /// Creates an iterator over the char index ranges of all matches of
+    /// `pattern`, including ones that overlap each other.
+    ///
+    /// An empty pattern never matches.
+    pub fn matches_overlapping(&self, pattern: &str) -> Matches<'_> {
+        self.slice(..).matches_overlapping(pattern)
+    }
+
+    /// Like `matches_overlapping()`, but matches according to `options`.
+    pub fn matches_overlapping_with(&self, pattern: &str, options: SearchOptions) -> Matches<'_> {
+        self.slice(..).matches_overlapping_with(pattern, options)
+    }
+
+    /// Finds the delimiter that matches the one at `char_idx`.
+    ///
+    /// If the char at `char_idx` is `open`, scans forward for the matching
+    /// `close`.  If it is `close`, scans backward for the matching `open`.
+    /// Nested pairs are skipped over.  Returns `None` if the char at
+    /// `char_idx` is neither delimiter or if no match is found.
+    ///
+    /// `open` and `close` must be different chars.
+    ///
+    /// Runs in O(M + log N) time, where M is the distance scanned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("f(a, (b), c)");
+    ///
+    /// assert_eq!(Some(11), rope.find_matching(1, '(', ')'));
+    /// assert_eq!(Some(1), rope.find_matching(11, '(', ')'));
+    /// assert_eq!(None, rope.find_matching(0, '(', ')'));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len_chars()`).
+    pub fn find_matching(&self, char_idx: usize, open: char, close: char) -> Option<usize> {
+        self.find_matching_skipping(char_idx, open, close, |_| None)
+    }
+
+    /// Like `find_matching()`, but lets the caller exclude regions of text
+    /// (e.g. string literals or comments) from the scan.
+    ///
+    /// `skip` is called with the char index of each delimiter encountered
+    /// during the scan.  If it returns a char range, the delimiter is
+    /// ignored and scanning resumes on the far side of that range.  The
+    /// delimiter at `char_idx` itself is never passed to `skip`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("(a \")\" b)");
+    ///
+    /// // Treat the quoted section as opaque.
+    /// let m = rope.find_matching_skipping(0, '(', ')', |idx| {
+    ///     if idx >= 3 && idx < 6 { Some(3..6) } else { None }
+    /// });
+    /// assert_eq!(Some(8), m);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len_chars()`),
+    /// or if `skip` returns a range that doesn't contain the index passed
+    /// to it.
+    pub fn find_matching_skipping<F>(
+        &self,
+        char_idx: usize,
+        open: char,
+        close: char,
+        mut skip: F,
+    ) -> Option<usize>
+    where
+        F: FnMut(usize) -> Option<Range<usize>>,
+    {
+        debug_assert!(open != close);
+
+        let delimiter = self.char(char_idx);
+        let mut depth = 0usize;
+
+        if delimiter == open {
+            // Scan forward.
+            let mut start = char_idx;
+            'forward: while start < self.len_chars() {
+                let (chunk, offset) = self.root.get_chunk_at_char(start);
+                let byte_start = char_idx_to_byte_idx(chunk, offset);
+                let mut idx = start;
+                for c in chunk[byte_start..].chars() {
+                    if (c == open || c == close) && idx != char_idx {
+                        if let Some(range) = skip(idx) {
+                            assert!(range.start <= idx && idx < range.end);
+                            start = range.end;
+                            continue 'forward;
+                        }
+                    }
+                    if c == open {
+                        depth += 1;
+                    } else if c == close {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(idx);
+                        }
+                    }
+                    idx += 1;
+                }
+                start = idx;
+            }
+        } else if delimiter == close {
+            // Scan backward.
+            let mut end = char_idx + 1;
+            'backward: while end > 0 {
+                let (chunk, offset) = self.root.get_chunk_at_char(end - 1);
+                let byte_end = char_idx_to_byte_idx(chunk, offset + 1);
+                let mut idx = end;
+                for c in chunk[..byte_end].chars().rev() {
+                    idx -= 1;
+                    if (c == open || c == close) && idx != char_idx {
+                        if let Some(range) = skip(idx) {
+                            assert!(range.start <= idx && idx < range.end);
+                            end = range.start;
+                            continue 'backward;
+                        }
+                    }
+                    if c == close {
+                        depth += 1;
+                    } else if c == open {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(idx);
+                        }
+                    }
+                }
+                end = idx;
+            }
+        }
+
+        None
+    }
+
+    /// Returns the edits that turn the `Rope` into `text`, in order.
+    ///
+    /// This is meant for e.g. a file that was reloaded from disk, where
+    /// only a small part of it is likely to have changed.  Any common
+    /// prefix and suffix are found by comparing the `Rope`'s chunks
+    /// directly against `text`, without building a second `Rope`, and
+    /// only the lines in between are diffed.  Each changed run of lines
+    /// becomes one edit, trimmed to leave out the common prefix and
+    /// suffix.
+    ///
+    /// Runs in O(N + D²) time in the worst case, where D is the number of
+    /// lines in between the common prefix and suffix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Edit, Rope};
+    /// let rope = Rope::from_str("one\ntwo\nthree\n");
+    /// let edits = rope.diff_against_str("one\n2\nthree\n");
+    ///
+    /// assert_eq!(edits, [Edit::replace(4..7, 1)]);
+    /// ```
+    pub fn diff_against_str(&self, text: &str) -> Vec<Edit> {
+        diff::str_edits(self, text)
+    }
+
+    //-----------------------------------------------------------------------
+    // Slicing
+
+    /// Gets an immutable slice of the `Rope`.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in `char`
+    /// indices.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// let slice = rope.slice(..5);
+    ///
+    /// assert_eq!("Hello", slice);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    pub fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
+
+        // Bounds check
+        assert!(start <= end);
+        assert!(
+            end <= self.len_chars(),
+            "Attempt to slice past end of Rope: slice end {}, Rope length {}",
+            end,
+            self.len_chars()
+        );
+
+        RopeSlice::new_with_range(&self.root, start, end)
+    }
+
+    /// Gets an immutable slice of the `Rope`, or `None` if the start of
+    /// the range is greater than the end, or the end is out of bounds
+    /// (i.e. `end > len_chars()`).
+    ///
+    /// The non-panicking version of `slice()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    ///
+    /// assert_eq!(rope.get_slice(6..).unwrap(), "world!");
+    /// assert!(rope.get_slice(6..13).is_none());
+    /// assert!(rope.get_slice(7..6).is_none());
+    /// ```
+    pub fn get_slice<R: CharIdxRange>(&self, range: R) -> Option<RopeSlice<'_>> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
+
+        if start <= end && end <= self.len_chars() {
+            Some(RopeSlice::new_with_range(&self.root, start, end))
+        } else {
+            None
+        }
+    }
+
+    /// Gets an immutable slice of the `Rope`, using byte indices.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  Both ends of the range
+    /// must be on char boundaries.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("こんにちは world!");
+    /// let slice = rope.byte_slice(6..17);
+    ///
+    /// assert_eq!("にちは w", slice);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the end
+    /// is out of bounds (i.e. `end > len_bytes()`), or if either end isn't
+    /// on a char boundary.
+    pub fn byte_slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_bytes());
+
+        // Bounds check
+        assert!(start <= end);
+        assert!(
+            end <= self.len_bytes(),
+            "Attempt to slice past end of Rope: slice end {}, Rope byte length {}",
+            end,
+            self.len_bytes()
+        );
+        assert!(
+            self.is_char_boundary(start) && self.is_char_boundary(end),
+            "Attempt to slice Rope at a non-char boundary: byte range {}..{}",
+            start,
+            end
+        );
+
+        RopeSlice::new_with_range(&self.root, self.byte_to_char(start), self.byte_to_char(end))
+    }
+
+    /// Gets an immutable slice of the `Rope` made up of the lines in the
+    /// given line index range, along with their line breaks.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  The range is in line
+    /// indices, and its end can be one-past-the-end.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("one\ntwo\nthree\nfour");
+    ///
+    /// assert_eq!("two\nthree\n", rope.line_slice(1..3));
+    /// assert_eq!("four", rope.line_slice(3..));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_lines()`).
+    pub fn line_slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_lines());
+
+        // Bounds check
+        assert!(start <= end);
+        assert!(
+            end <= self.len_lines(),
+            "Attempt to slice past end of Rope: slice end line {}, Rope line length {}",
+            end,
+            self.len_lines()
+        );
+
+        RopeSlice::new_with_range(&self.root, self.line_to_char(start), self.line_to_char(end))
+    }
+
+    /// Creates a new `Rope` from the given char index range of this one,
+    /// leaving this `Rope` unchanged.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  Like `append_slice()`,
+    /// the new `Rope` shares all whole subtrees within the range with this
+    /// one, so even huge ranges are cheap to extract.
+    ///
+    /// This is equivalent to `rope.slice(range).to_rope()`, except that the
+    /// new `Rope` keeps this one's chunk size.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len_chars()`).
+    pub fn sub_rope<R: CharIdxRange>(&self, range: R) -> Rope {
+        self.slice(range).to_rope_with_chunk_size(self.chunk_size)
+    }
 
     //-----------------------------------------------------------------------
     // Iterator methods
 
-    /// Creates an iterator over the bytes of the `Rope`.
-    pub fn bytes(&self) -> Bytes {
-        Bytes::new(&self.root)
+    /// Creates an iterator over the bytes of the `Rope`.
+    pub fn bytes(&self) -> Bytes {
+        Bytes::new(&self.root)
+    }
+
+    /// Creates an iterator over the chars of the `Rope`.
+    pub fn chars(&self) -> Chars {
+        Chars::new(&self.root)
+    }
+
+    /// Creates an iterator over the text of the `Rope` encoded as UTF-16
+    /// code units.
+    ///
+    /// The text is encoded a chunk at a time, so this doesn't need to
+    /// create an intermediate `String`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a😀");
+    /// let utf16: Vec<u16> = rope.encode_utf16().collect();
+    ///
+    /// assert_eq!(vec![0x61, 0xD83D, 0xDE00], utf16);
+    /// ```
+    pub fn encode_utf16(&self) -> EncodeUtf16<'_> {
+        EncodeUtf16::new(&self.root)
+    }
+
+    /// Creates an iterator over the lines of the `Rope`.
+    pub fn lines(&self) -> Lines {
+        Lines::new(&self.root)
+    }
+
+    /// Creates an iterator over the lines of the `Rope`, along with the
+    /// index and byte range of each line.
+    ///
+    /// This is useful for feeding lines to tools that work in byte
+    /// offsets (e.g. regex engines), and mapping their results back to
+    /// the `Rope`.
+    pub fn indexed_lines(&self) -> IndexedLines<'_> {
+        IndexedLines::new(self.lines())
+    }
+
+    /// Creates an iterator over the chunks of the `Rope`.
+    ///
+    /// See [`CHUNK_ALIGNMENT`](constant.CHUNK_ALIGNMENT.html) for the
+    /// alignment the chunks start at.
+    pub fn chunks(&self) -> Chunks {
+        Chunks::new(&self.root)
+    }
+
+    /// Creates an iterator over the maximal runs of chars for which
+    /// `classify` returns equal values.
+    ///
+    /// See [`RopeSlice::class_runs()`](struct.RopeSlice.html#method.class_runs)
+    /// for details.
+    pub fn class_runs<F, K>(&self, classify: F) -> ClassRuns<'_, F, K>
+    where
+        F: FnMut(char) -> K,
+        K: PartialEq,
+    {
+        ClassRuns::new(self.chunks(), classify)
+    }
+
+    /// Creates an `io::Read`/`io::BufRead` adapter over the bytes of the
+    /// `Rope`.
+    ///
+    /// This lets the `Rope`'s text be fed to anything that reads from a
+    /// byte stream, without copying it into a `String` first.
+    pub fn reader(&self) -> RopeReader<'_> {
+        self.slice(..).reader()
+    }
+
+    /// Creates an `io::Write` adapter that appends to the end of the
+    /// `Rope`.
+    ///
+    /// This lets output from subprocesses, decompressors, formatters,
+    /// etc. be streamed straight into the `Rope`.  Code points split
+    /// across writes are handled, and invalid utf8 is reported as an
+    /// error.  See [`RopeWriter`](struct.RopeWriter.html) for details.
+    pub fn writer(&mut self) -> RopeWriter<'_> {
+        RopeWriter::new(self, Utf8Policy::Wait)
+    }
+
+    /// Creates an `io::Write` adapter that appends to the end of the
+    /// `Rope`, handling split and invalid utf8 according to `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use ropey::{Rope, Utf8Policy};
+    /// let mut rope = Rope::new();
+    /// {
+    ///     let mut writer = rope.writer_with_policy(Utf8Policy::Replace);
+    ///     writer.write_all(b"caf\xC3").unwrap();
+    ///     writer.write_all(b"\xA9 \xFF!").unwrap();
+    ///     writer.finish().unwrap();
+    /// }
+    ///
+    /// assert_eq!(rope, "café \u{FFFD}!");
+    /// ```
+    pub fn writer_with_policy(&mut self, policy: Utf8Policy) -> RopeWriter<'_> {
+        RopeWriter::new(self, policy)
+    }
+
+    /// Creates an adapter that appends text to the end of the `Rope`, and
+    /// calls `on_line` with the char index range and text of each line as
+    /// it's completed.
+    ///
+    /// This is for consumers that need line framing in the `Rope`'s own
+    /// coordinates, such as terminal emulators and log viewers.  See
+    /// [`LineWriter`](struct.LineWriter.html) for details.
+    pub fn line_writer<F>(&mut self, on_line: F) -> LineWriter<'_, F>
+    where
+        F: FnMut(Range<usize>, RopeSlice),
+    {
+        LineWriter::new(self, on_line)
+    }
+
+    /// Appends the `Rope`'s chunks to `slices` as `IoSlice`s, for use with
+    /// vectored writes (e.g. `Write::write_vectored()`).
+    ///
+    /// This doesn't copy any text: the `IoSlice`s refer directly to the
+    /// `Rope`'s internal buffers.  Empty chunks are skipped.
+    ///
+    /// Runs in O(N) time, where N is the number of chunks.
+    pub fn as_io_slices<'a>(&'a self, slices: &mut Vec<IoSlice<'a>>) {
+        self.slice(..).as_io_slices(slices)
+    }
+
+    //-----------------------------------------------------------------------
+    // Conversion methods
+
+    /// Returns the entire text of the `Rope` as a newly allocated String.
+    pub fn to_string(&self) -> String {
+        use iter::Chunks;
+        let mut text = String::with_capacity(self.len_bytes());
+        for chunk in Chunks::new(&self.root) {
+            text.push_str(chunk);
+        }
+        text
+    }
+
+    /// Returns the entire text of the `Rope` as an `Arc<str>`.
+    ///
+    /// The chunks are copied straight into the `Arc<str>`'s allocation,
+    /// without going through a `String` first.  This is still a copy,
+    /// even for a `Rope` that is a single chunk, as chunks aren't stored
+    /// as `Arc<str>`s.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// # use std::sync::Arc;
+    /// let rope = Rope::from_str("Hello world!");
+    /// let text: Arc<str> = rope.to_shared_str();
+    /// assert_eq!(&*text, "Hello world!");
+    /// ```
+    pub fn to_shared_str(&self) -> Arc<str> {
+        use iter::Chunks;
+        if let Node::Leaf(ref text) = *self.root {
+            return Arc::from(&text[..]);
+        }
+
+        // Allocate the `Arc` at its final size, and then fill it in.
+        let mut bytes: Arc<[u8]> = std::iter::repeat_n(0, self.len_bytes()).collect();
+        {
+            let buffer = Arc::get_mut(&mut bytes).unwrap();
+            let mut byte_idx = 0;
+            for chunk in Chunks::new(&self.root) {
+                buffer[byte_idx..(byte_idx + chunk.len())].copy_from_slice(chunk.as_bytes());
+                byte_idx += chunk.len();
+            }
+        }
+
+        // Safe because the bytes are the concatenated chunks, which are
+        // valid utf8, and `str` has the same layout as `[u8]`.
+        unsafe { Arc::from_raw(Arc::into_raw(bytes) as *const str) }
+    }
+
+    /// Returns the entire text of the `Rope` as a borrowed `&str`, first
+    /// coalescing it into a single chunk if needed.
+    ///
+    /// This is meant for handing small texts to APIs that only take
+    /// `&str`, without allocating a `String` each time.  Texts larger
+    /// than the chunk size end up in a single oversized chunk, which the
+    /// next edit to the `Rope` splits up again in O(N) time.
+    ///
+    /// Runs in O(1) time if the `Rope` is already a single chunk, and
+    /// O(N) time otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut rope = Rope::from_str("Hello ");
+    /// rope.insert(6, "world!");
+    /// assert_eq!(rope.make_contiguous(), "Hello world!");
+    /// assert_eq!(rope.chunks().count(), 1);
+    /// ```
+    pub fn make_contiguous(&mut self) -> &str {
+        if let Node::Internal(_) = *self.root {
+            let mut text = NodeText::with_capacity(self.len_bytes());
+            for chunk in self.chunks() {
+                text.push_str(chunk);
+            }
+            self.root = Arc::new(Node::Leaf(text));
+        }
+        match *self.root {
+            Node::Leaf(ref text) => text,
+            Node::Internal(_) => unreachable!(),
+        }
+    }
+
+    /// Returns a `Display` adaptor that writes the `Rope` with control
+    /// chars, the byte order mark, and bidi formatting chars rendered as
+    /// visible escapes.
+    ///
+    /// See [`EscapeControl`](struct.EscapeControl.html) for details.
+    pub fn escape_control(&self) -> EscapeControl<'_> {
+        EscapeControl::new(self.slice(..))
+    }
+
+    //-----------------------------------------------------------------------
+    // Hashing methods
+
+    /// Feeds the contents of the `Rope` into `hasher`, one chunk at a time.
+    ///
+    /// This passes the text's bytes to `hasher.write()` without any
+    /// framing, so for hashers that process their input as a byte stream
+    /// the result is the same as hashing the equivalent `&[u8]`, regardless
+    /// of how the text is chunked.  This makes it suitable for computing
+    /// content checksums without first copying the text into a `String`.
+    ///
+    /// Runs in O(N) time.
+    pub fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        self.slice(..).hash_into(hasher)
+    }
+
+    /// Returns a checksum of the `Rope`'s contents.
+    ///
+    /// The checksum is the 64-bit XXH64 hash (with a seed of zero) of the
+    /// text's utf8 bytes.  It depends only on the text itself, not on how
+    /// the `Rope` happens to be chunked internally, and it will remain
+    /// stable across Ropey versions and platforms.  This makes it suitable
+    /// for comparing texts between processes or machines: the checksum of
+    /// a `Rope` is the same as XXH64 applied to the equivalent `&str`.
+    ///
+    /// Only available with the `checksum` feature.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let mut r1 = Rope::from_str("Hello world!");
+    /// let r2 = Rope::from_str("Hello world!");
+    /// assert_eq!(r1.checksum(), r2.checksum());
+    ///
+    /// r1.insert(5, ",");
+    /// assert_ne!(r1.checksum(), r2.checksum());
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn checksum(&self) -> u64 {
+        self.slice(..).checksum()
+    }
+
+    /// Feeds the contents of the `Rope` into a `digest`-crate hasher (e.g.
+    /// SHA-256), one chunk at a time.
+    ///
+    /// Only available with the `digest` feature.
+    ///
+    /// Runs in O(N) time.
+    #[cfg(feature = "digest")]
+    pub fn update_digest<D: digest::Update>(&self, digest: &mut D) {
+        self.slice(..).update_digest(digest)
+    }
+
+    //-----------------------------------------------------------------------
+    // Comparison methods
+
+    /// Returns whether the `Rope` has the same text as `other`, treating
+    /// LF, CRLF and CR line endings as equivalent.
+    ///
+    /// The texts are compared a chunk at a time, without making
+    /// normalized copies of either of them.  Other line breaks (e.g.
+    /// U+2028) are compared as-is.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!\r");
+    /// let other = Rope::from_str("Hello\nworld!\n");
+    ///
+    /// assert!(rope.eq_ignore_line_endings(&other.slice(..)));
+    /// assert!(!rope.eq_ignore_line_endings(&other.slice(1..)));
+    /// ```
+    pub fn eq_ignore_line_endings(&self, other: &RopeSlice) -> bool {
+        self.slice(..).eq_ignore_line_endings(other)
+    }
+
+    //-----------------------------------------------------------------------
+    // Debugging
+
+    /// Debugging tool to make sure that all of the meta-data of the
+    /// tree is consistent with the actual data.
+    #[doc(hidden)]
+    pub fn assert_integrity(&self) {
+        self.root.assert_integrity();
+    }
+
+    /// Debugging tool to make sure that all of the following invariants
+    /// hold true throughout the tree:
+    ///
+    /// - The tree is the same height everywhere.
+    /// - All internal nodes have the minimum number of children.
+    /// - All leaf nodes are non-empty.
+    /// - CRLF pairs are never split over chunk boundaries.
+    #[doc(hidden)]
+    pub fn assert_invariants(&self) {
+        self.root.assert_balance();
+        self.root.assert_node_size(true);
+        self.assert_grapheme_seams();
+    }
+
+    /// Checks the `Rope`'s internal tree for consistency, returning a
+    /// report of every broken invariant found, if any.
+    ///
+    /// This checks that:
+    ///
+    /// - The meta-data of each node matches its actual contents.
+    /// - The tree is the same height everywhere.
+    /// - All internal nodes have the minimum number of children, and no
+    ///   more than the maximum.
+    /// - All non-root leaf nodes are non-empty, and no longer than the
+    ///   chunk size (not counting the graphemes at their ends).
+    /// - CRLF pairs are never split over chunk boundaries.
+    ///
+    /// A correctly functioning `Rope` always passes, so this is mainly
+    /// useful for fuzzing and for testing Ropey itself.
+    ///
+    /// Runs in O(N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\r\nworld!");
+    /// assert!(rope.validate().is_ok());
+    /// ```
+    pub fn validate(&self) -> Result<(), InvariantReport> {
+        let mut violations = Vec::new();
+        self.root.validate(
+            &mut Vec::new(),
+            &mut None,
+            self.chunk_size,
+            MAX_CHILDREN,
+            &mut violations,
+        );
+
+        // Chunk seams
+        let mut byte_idx = 0;
+        let mut last_chunk: &str = "";
+        for chunk in self.chunks() {
+            if !chunk.is_empty() {
+                if !last_chunk.is_empty()
+                    && !crlf::seam_is_break(last_chunk.as_bytes(), chunk.as_bytes())
+                {
+                    violations.push(InvariantViolation::SplitSeam { byte_idx });
+                }
+                last_chunk = chunk;
+            }
+            byte_idx += chunk.len();
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(InvariantReport { violations })
+        }
+    }
+
+    /// Returns the layout of the `Rope`'s internal tree.
+    ///
+    /// Passing the result to [`from_shape()`](#method.from_shape)
+    /// recreates a `Rope` with the same layout, e.g. to reproduce a tree
+    /// shape found by a fuzzer.
+    ///
+    /// Runs in O(N) time, where N is the number of nodes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, TreeShape};
+    /// let rope = Rope::from_str("Hello world!");
+    /// assert_eq!(TreeShape::Leaf("Hello world!"), rope.shape());
+    /// ```
+    pub fn shape(&self) -> TreeShape<'_> {
+        TreeShape::of_node(&self.root)
+    }
+
+    /// Checks that graphemes are never split over chunk boundaries.
+    fn assert_grapheme_seams(&self) {
+        if self.chunks().count() > 0 {
+            let mut itr = self.chunks();
+            let mut last_chunk = itr.next().unwrap();
+            for chunk in itr {
+                if !chunk.is_empty() && !last_chunk.is_empty() {
+                    assert!(crlf::seam_is_break(last_chunk.as_bytes(), chunk.as_bytes()));
+                    last_chunk = chunk;
+                } else if last_chunk.is_empty() {
+                    last_chunk = chunk;
+                }
+            }
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    /// Returns the text of the leaf containing `byte_idx`, which must be
+    /// less than `len_bytes()`, along with the byte index of its start.
+    fn leaf_at_byte(&self, byte_idx: usize) -> (&str, usize) {
+        let (leaf, before, _) = Node::find_leaf(&self.root, |inf| inf.bytes as usize > byte_idx);
+        (leaf.leaf_text(), before.bytes as usize)
+    }
+
+    /// Returns the length in bytes and chars of the given line.
+    ///
+    /// Panics if `line_idx` is out of bounds.
+    fn line_len(&self, line_idx: usize) -> (usize, usize) {
+        // Bounds check
+        assert!(
+            line_idx < self.len_lines(),
+            "Attempt to index past end of Rope: line index {}, Rope line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        if line_idx + 1 == self.len_lines() {
+            // The last line doesn't end in a line break.
+            let (bytes, chars) = self.root.line_to_byte_char(line_idx);
+            (self.len_bytes() - bytes, self.len_chars() - chars)
+        } else {
+            self.root.line_len(line_idx)
+        }
+    }
+
+    /// Inserts `text` at `idx`, in `metric` units, which has already been
+    /// bounds checked.  A byte index is checked to be on a char boundary
+    /// in the same descent of the tree as the insertion.
+    fn insert_at(&mut self, metric: Metric, idx: usize, text: &str) {
+        // TODO: handle large insertions more efficiently, instead of doing a split
+        // and appends.
+
+        self.split_contiguous();
+        let recording = Recording::start();
+        let char_count = count_chars(text);
+
+        let char_idx = if text.len() > self.chunk_size * 6 {
+            // For huge insert texts, build a tree out of it and then
+            // split and join.  Splitting takes a char index, but finding
+            // it is dwarfed by building the tree.
+            let char_idx = self.to_char_idx(metric, idx);
+            let dirty = self.dirty.take();
+            let text_rope = RopeBuilder::with_chunk_size(self.chunk_size).build_at_once(text);
+            let right = self.split_off(char_idx);
+            self.append(text_rope);
+            self.append(right);
+            self.dirty = dirty;
+            char_idx
+        } else {
+            // Otherwise, for small-to-medium sized inserts, iteratively insert in
+            // chunks.  The char index is found (and a byte index checked)
+            // in the leaf, unless there's nothing to insert.
+            let mut char_idx = None;
+            let mut text = text;
+            while text.len() > 0 {
+                let split_idx = crlf::find_good_split(
+                    text.len() - (self.chunk_size - 4).min(text.len()),
+                    text.as_bytes(),
+                    false,
+                );
+                let ins_text = &text[split_idx..];
+                text = &text[..split_idx];
+
+                // Do the insertion
+                let mut seam = None;
+                let chunk_size = self.chunk_size;
+                let (l_info, residual) = stats::make_mut(&mut self.root).edit_range(
+                    metric,
+                    idx,
+                    idx,
+                    chunk_size,
+                    |acc_info, cur_info, leaf_text| {
+                        debug_assert!(metric.of(&acc_info) <= idx);
+                        let local_idx = idx - metric.of(&acc_info);
+                        let byte_idx = match metric {
+                            Metric::Bytes => local_idx,
+                            Metric::Chars => char_idx_to_byte_idx(leaf_text, local_idx),
+                        };
+                        if char_idx.is_none() {
+                            char_idx = Some(match metric {
+                                Metric::Bytes => {
+                                    let local_char_idx = byte_idx_to_char_idx(leaf_text, byte_idx);
+                                    acc_info.chars as usize + local_char_idx
+                                }
+                                Metric::Chars => idx,
+                            });
+                        }
+                        if byte_idx == 0 {
+                            seam = Some(acc_info.bytes);
+                        } else if byte_idx == leaf_text.len() {
+                            let count = (leaf_text.len() + ins_text.len()) as Count;
+                            seam = Some(acc_info.bytes + count)
+                        } else {
+                            seam = None
+                        }
+
+                        if (leaf_text.len() + ins_text.len()) <= chunk_size {
+                            // Calculate new info without doing a full re-scan of cur_text
+                            let new_info = {
+                                // Get summed info of current text and to-be-inserted text
+                                let mut info = cur_info + TextInfo::from_str(ins_text);
+                                // Check for CRLF graphemes on the insertion seams, and
+                                // adjust line break counts accordingly
+                                if !ins_text.is_empty() {
+                                    if byte_idx > 0 && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
+                                        && ins_text.as_bytes()[0] == 0x0A
+                                    {
+                                        info.line_breaks -= 1;
+                                    }
+                                    if byte_idx < leaf_text.len()
+                                        && *ins_text.as_bytes().last().unwrap() == 0x0D
+                                        && leaf_text.as_bytes()[byte_idx] == 0x0A
+                                    {
+                                        info.line_breaks -= 1;
+                                    }
+                                    if byte_idx > 0 && byte_idx < leaf_text.len()
+                                        && leaf_text.as_bytes()[byte_idx - 1] == 0x0D
+                                        && leaf_text.as_bytes()[byte_idx] == 0x0A
+                                    {
+                                        info.line_breaks += 1;
+                                    }
+                                }
+                                info
+                            };
+                            // Insert the text and return the new info
+                            leaf_text.insert_str(byte_idx, ins_text);
+                            // Grapheme counts can't be fixed up locally, so
+                            // just re-scan.
+                            if cfg!(feature = "graphemes") {
+                                return (TextInfo::from_str(leaf_text), None);
+                            }
+                            return (new_info, None);
+                        } else {
+                            let r_text = leaf_text.insert_str_split(byte_idx, ins_text);
+                            if r_text.len() > 0 {
+                                return (
+                                    TextInfo::from_str(leaf_text),
+                                    Some((TextInfo::from_str(&r_text), r_text)),
+                                );
+                            } else {
+                                // Leaf couldn't be validly split, so leave it oversized
+                                return (TextInfo::from_str(leaf_text), None);
+                            }
+                        }
+                    },
+                );
+
+                // Handle root splitting, if any.
+                if let Some((r_info, r_node)) = residual {
+                    let mut l_node = Arc::new(Node::new());
+                    std::mem::swap(&mut l_node, &mut self.root);
+
+                    let mut children = NodeChildren::new();
+                    children.push((l_info, l_node));
+                    children.push((r_info, r_node));
+
+                    *Arc::make_mut(&mut self.root) = Node::Internal(children);
+                    stats::count(Event::Allocation);
+                    stats::count(Event::Rebalance);
+                }
+
+                // Handle seam, if any.
+                if let Some(byte_pos) = seam {
+                    stats::make_mut(&mut self.root).fix_grapheme_seam(byte_pos, true);
+                }
+            }
+            char_idx.unwrap_or_else(|| self.to_char_idx(metric, idx))
+        };
+
+        self.mark_dirty(Edit::insert(char_idx, char_count));
+        self.rebalance_if_skewed();
+        recording.finish(self);
+    }
+
+    /// Removes the text in `start..end`, in `metric` units, which has
+    /// already been bounds checked.  The ends of a byte range are checked
+    /// to be on char boundaries in the same descent of the tree as the
+    /// removal.
+    fn remove_range(&mut self, metric: Metric, start: usize, end: usize) {
+        self.split_contiguous();
+
+        // A special case that the rest of the logic doesn't handle
+        // correctly.
+        if start == 0 && end == metric.of(&self.root.text_info()) {
+            self.mark_dirty(Edit::remove(0..self.len_chars()));
+            self.root = Arc::new(Node::new());
+            return;
+        }
+
+        let recording = Recording::start();
+
+        // For large prefixes, split the prefix off instead.  That drops
+        // whole leading subtrees without descending into them or touching
+        // their text, which matters for e.g. trimming scrollback buffers.
+        // Splitting takes a char index, so this is only done for char
+        // ranges.
+        if metric == Metric::Chars && start == 0 && end > self.chunk_size {
+            self.mark_dirty(Edit::remove(start..end));
+            let dirty = self.dirty.take();
+            let right = self.split_off(end);
+            self.root = right.root;
+            self.dirty = dirty;
+            self.rebalance_if_skewed();
+            recording.finish(self);
+            return;
+        }
+
+        // The removed char range.  For a byte range, it's found (and the
+        // ends checked) in the leaves at either end.
+        let mut chars = start..end;
+
+        // Scope to contain borrow of root
+        {
+            let chunk_size = self.chunk_size;
+            let root = stats::make_mut(&mut self.root);
+            let mut seam = None;
+
+            let (_text_info, _residual) = {
+                root.edit_range(metric, start, end, chunk_size, |acc_info, cur_info, leaf_text| {
+                    let acc_idx = metric.of(&acc_info);
+                    let local_start = start - acc_idx.min(start);
+                    let local_end = (end - acc_idx).min(metric.of(&cur_info));
+                    let (byte_start, byte_end) = match metric {
+                        Metric::Bytes => (local_start, local_end),
+                        Metric::Chars => (
+                            char_idx_to_byte_idx(leaf_text, local_start),
+                            char_idx_to_byte_idx(leaf_text, local_end),
+                        ),
+                    };
+
+                    if metric == Metric::Bytes {
+                        let acc_chars = acc_info.chars as usize;
+                        if acc_idx <= start {
+                            chars.start = acc_chars + byte_idx_to_char_idx(leaf_text, byte_start);
+                        }
+                        if end <= acc_idx + metric.of(&cur_info) {
+                            chars.end = acc_chars + byte_idx_to_char_idx(leaf_text, byte_end);
+                        }
+                    }
+
+                    if local_start == 0 || local_end == metric.of(&cur_info) {
+                        seam = Some(acc_info.bytes as usize + byte_start);
+                    }
+
+                    // Remove text and calculate new info
+                    let new_info = if (byte_end - byte_start) < leaf_text.len() {
+                        let rem_info = TextInfo::from_str(&leaf_text[byte_start..byte_end]);
+                        let mut info = cur_info - rem_info;
+
+                        // Check for CRLF graphemes on the insertion seams, and
+                        // adjust line break counts accordingly
+                        if byte_start != byte_end {
+                            if byte_start > 0 && leaf_text.as_bytes()[byte_start - 1] == 0x0D
+                                && leaf_text.as_bytes()[byte_start] == 0x0A
+                            {
+                                info.line_breaks += 1;
+                            }
+                            if byte_end < leaf_text.len()
+                                && leaf_text.as_bytes()[byte_end - 1] == 0x0D
+                                && leaf_text.as_bytes()[byte_end] == 0x0A
+                            {
+                                info.line_breaks += 1;
+                            }
+                            if byte_start > 0 && byte_end < leaf_text.len()
+                                && leaf_text.as_bytes()[byte_start - 1] == 0x0D
+                                && leaf_text.as_bytes()[byte_end] == 0x0A
+                            {
+                                info.line_breaks -= 1;
+                            }
+                        }
+
+                        // Remove the text
+                        leaf_text.remove_range(byte_start, byte_end);
+
+                        // Grapheme counts can't be fixed up locally, so
+                        // just re-scan.
+                        if cfg!(feature = "graphemes") {
+                            TextInfo::from_str(leaf_text)
+                        } else {
+                            info
+                        }
+                    } else {
+                        // Remove the text
+                        leaf_text.remove_range(byte_start, byte_end);
+
+                        TextInfo::from_str(leaf_text)
+                    };
+
+                    (new_info, None)
+                })
+            };
+
+            if let Some(seam_idx) = seam {
+                root.fix_grapheme_seam(seam_idx as Count, false);
+            }
+            root.zip_fix(chars.start, chunk_size);
+        }
+
+        self.mark_dirty(Edit::remove(chars));
+        self.pull_up_singular_nodes();
+        self.rebalance_if_skewed();
+        recording.finish(self);
+    }
+
+    /// Returns the char index of `idx`, in `metric` units.  A byte index is
+    /// checked to be on a char boundary.
+    fn to_char_idx(&self, metric: Metric, idx: usize) -> usize {
+        match metric {
+            Metric::Bytes => self.byte_to_char_at_boundary(idx),
+            Metric::Chars => idx,
+        }
+    }
+
+    /// Returns the char index of `byte_idx`, in the same descent of the
+    /// tree as checking that it's on a char boundary.
+    ///
+    /// Panics if `byte_idx` is out of bounds or not on a char boundary.
+    fn byte_to_char_at_boundary(&self, byte_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of Rope: byte index {}, Rope byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+        if byte_idx == self.len_bytes() {
+            return self.len_chars();
+        }
+
+        let (leaf, before, _) = Node::find_leaf(&self.root, |inf| inf.bytes as usize > byte_idx);
+        let text = leaf.leaf_text();
+        let local_idx = byte_idx - before.bytes as usize;
+        assert!(
+            text.is_char_boundary(local_idx),
+            "Attempt to index Rope at a non-char boundary: byte index {}",
+            byte_idx
+        );
+        before.chars as usize + byte_idx_to_char_idx(text, local_idx)
+    }
+
+    /// Returns a copy of the `Rope` with its text re-split into chunks of
+    /// at most `chunk_size`, which becomes its chunk size.
+    pub(crate) fn rechunked(&self, chunk_size: usize) -> Rope {
+        let mut builder = RopeBuilder::with_chunk_size(chunk_size);
+        for chunk in self.chunks() {
+            builder.append(chunk);
+        }
+        let mut rope = builder.finish();
+        rope.max_skew = self.max_skew;
+        rope
+    }
+
+    /// Splits up the single oversized chunk left by `make_contiguous()`,
+    /// if there is one, so that edits keep chunks within the chunk size.
+    fn split_contiguous(&mut self) {
+        if self.root.is_oversized(self.chunk_size) {
+            self.root = self.rechunked(self.chunk_size).root;
+        }
+    }
+
+    /// Iteratively replaced the root node with its child if it only has
+    /// one child.
+    pub(crate) fn pull_up_singular_nodes(&mut self) {
+        while (!self.root.is_leaf()) && self.root.child_count() == 1 {
+            let child = if let Node::Internal(ref children) = *self.root {
+                Arc::clone(&children.nodes()[0])
+            } else {
+                unreachable!()
+            };
+
+            self.root = child;
+            stats::count(Event::Rebalance);
+        }
+    }
+
+    /// Rebalances the tree if its skew is over the `Rope`'s maximum.
+    fn rebalance_if_skewed(&mut self) {
+        if let Some(max_skew) = self.max_skew {
+            if self.skew() > max_skew {
+                self.rebalance();
+            }
+        }
+    }
+}
+
+//==============================================================
+
+/// The normal `{:?}` format shows the `Rope`'s text as a list of chunks.
+///
+/// The alternate `{:#?}` format instead shows the whole tree, one node per
+/// line, with each node's byte, char, and line ranges and how full it is.
+/// Leaves also show their (escaped) text.  This is useful when diagnosing
+/// bugs.
+impl std::fmt::Debug for Rope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if f.alternate() {
+            writeln!(
+                f,
+                "Rope (bytes: {}, chars: {}, lines: {}, chunk size: {})",
+                self.len_bytes(),
+                self.len_chars(),
+                self.len_lines(),
+                self.chunk_size
+            )?;
+            self.root.fmt_tree(f, 1, TextInfo::new(), self.chunk_size)
+        } else {
+            f.debug_list().entries(self.chunks()).finish()
+        }
+    }
+}
+
+impl std::fmt::Display for Rope {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for chunk in self.chunks() {
+            write!(f, "{}", chunk)?
+        }
+        Ok(())
+    }
+}
+
+impl std::default::Default for Rope {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::cmp::PartialEq<Rope> for Rope {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        self.slice(..) == other.slice(..)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<&'a str> for Rope {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        self.slice(..) == *other
+    }
+}
+
+impl<'a> std::cmp::PartialEq<Rope> for &'a str {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        *self == other.slice(..)
+    }
+}
+
+impl std::cmp::PartialEq<str> for Rope {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.slice(..) == other
+    }
+}
+
+impl std::cmp::PartialEq<Rope> for str {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        self == other.slice(..)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<String> for Rope {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self.slice(..) == other.as_str()
+    }
+}
+
+impl<'a> std::cmp::PartialEq<Rope> for String {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        self.as_str() == other.slice(..)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<std::borrow::Cow<'a, str>> for Rope {
+    #[inline]
+    fn eq(&self, other: &std::borrow::Cow<'a, str>) -> bool {
+        self.slice(..) == **other
+    }
+}
+
+impl<'a> std::cmp::PartialEq<Rope> for std::borrow::Cow<'a, str> {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        **self == other.slice(..)
+    }
+}
+
+impl std::cmp::Eq for Rope {}
+
+impl std::cmp::Ord for Rope {
+    #[inline]
+    fn cmp(&self, other: &Rope) -> std::cmp::Ordering {
+        self.slice(..).cmp(&other.slice(..))
+    }
+}
+
+impl std::cmp::PartialOrd<Rope> for Rope {
+    #[inline]
+    fn partial_cmp(&self, other: &Rope) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> std::cmp::PartialOrd<&'a str> for Rope {
+    #[inline]
+    fn partial_cmp(&self, other: &&'a str) -> Option<std::cmp::Ordering> {
+        self.slice(..).partial_cmp(other)
+    }
+}
+
+impl std::cmp::PartialOrd<Rope> for &str {
+    #[inline]
+    fn partial_cmp(&self, other: &Rope) -> Option<std::cmp::Ordering> {
+        self.partial_cmp(&other.slice(..))
+    }
+}
+
+impl std::cmp::PartialOrd<str> for Rope {
+    #[inline]
+    fn partial_cmp(&self, other: &str) -> Option<std::cmp::Ordering> {
+        self.slice(..).partial_cmp(other)
+    }
+}
+
+impl std::cmp::PartialOrd<Rope> for str {
+    #[inline]
+    fn partial_cmp(&self, other: &Rope) -> Option<std::cmp::Ordering> {
+        self.partial_cmp(&other.slice(..))
+    }
+}
+
+impl std::cmp::PartialOrd<String> for Rope {
+    #[inline]
+    fn partial_cmp(&self, other: &String) -> Option<std::cmp::Ordering> {
+        self.slice(..).partial_cmp(other.as_str())
+    }
+}
+
+impl std::cmp::PartialOrd<Rope> for String {
+    #[inline]
+    fn partial_cmp(&self, other: &Rope) -> Option<std::cmp::Ordering> {
+        self.as_str().partial_cmp(&other.slice(..))
+    }
+}
+
+impl<'a> std::convert::TryFrom<&'a [u8]> for Rope {
+    type Error = Utf8Error;
+
+    /// Creates a `Rope` from utf8 bytes, reporting where any invalid utf8
+    /// is.
+    #[inline]
+    fn try_from(bytes: &'a [u8]) -> Result<Self, Utf8Error> {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok(Rope::from_str(text)),
+            Err(e) => Err(Utf8Error::from_std(e, 0, bytes)),
+        }
+    }
+}
+
+//==============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 127 bytes, 103 chars, 1 line
+    const TEXT: &str = "Hello there!  How're you doing?  It's \
+                        a fine day, isn't it?  Aren't you glad \
+                        we're alive?  こんにちは、みんなさん！";
+    // 124 bytes, 100 chars, 4 lines
+    const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's \
+                              a fine day, isn't it?\nAren't you glad \
+                              we're alive?\nこんにちは、みんなさん！";
+
+    #[test]
+    fn new_01() {
+        let r = Rope::new();
+        assert_eq!(r, "");
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn from_str() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn from_lines_01() {
+        let lines: Vec<String> = TEXT_LINES.lines().map(|l| l.to_string()).collect();
+        let r = Rope::from_lines(&lines, "\r\n");
+        assert_eq!(r, lines.join("\r\n"));
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn from_lines_02() {
+        assert_eq!(Rope::from_lines(Vec::<&str>::new(), "\n"), "");
+        assert_eq!(Rope::from_lines(vec!["", ""], "\n"), "\n");
+        assert_eq!(Rope::from_lines(vec!["a", "b", ""], "\n"), "a\nb\n");
+    }
+
+    #[test]
+    fn len_bytes_01() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r.len_bytes(), 127);
+    }
+
+    #[test]
+    fn len_bytes_02() {
+        let r = Rope::from_str("");
+        assert_eq!(r.len_bytes(), 0);
+    }
+
+    #[test]
+    fn len_chars_01() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r.len_chars(), 103);
+    }
+
+    #[test]
+    fn len_chars_02() {
+        let r = Rope::from_str("");
+        assert_eq!(r.len_chars(), 0);
+    }
+
+    #[test]
+    fn len_lines_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        assert_eq!(r.len_lines(), 4);
+    }
+
+    #[test]
+    fn len_lines_02() {
+        let r = Rope::from_str("");
+        assert_eq!(r.len_lines(), 1);
+    }
+
+    #[test]
+    fn len_utf16_cu_01() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(r.len_utf16_cu(), 103);
+
+        let r = Rope::from_str(&"a\u{1F600}せ".repeat(20));
+        assert_eq!(r.len_utf16_cu(), 80);
+        assert_eq!(Rope::new().len_utf16_cu(), 0);
+    }
+
+    #[test]
+    fn insert_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert(3, "AA");
+
+        assert_eq!(
+            r,
+            "HelAAlo there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert(0, "AA");
+
+        assert_eq!(
+            r,
+            "AAHello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_03() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert(103, "AA");
+
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！AA"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_04() {
+        let mut r = Rope::new();
+        r.insert(0, "He");
+        r.insert(2, "l");
+        r.insert(3, "l");
+        r.insert(4, "o w");
+        r.insert(7, "o");
+        r.insert(8, "rl");
+        r.insert(10, "d!");
+        r.insert(3, "zopter");
+
+        assert_eq!("Helzopterlo world!", r);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_05() {
+        let mut r = Rope::new();
+        r.insert(0, "こんいちは、みんなさん！");
+        r.insert(7, "zopter");
+        assert_eq!("こんいちは、みzopterんなさん！", r);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_06() {
+        let mut r = Rope::new();
+        r.insert(0, "こ");
+        r.insert(1, "ん");
+        r.insert(2, "い");
+        r.insert(3, "ち");
+        r.insert(4, "は");
+        r.insert(5, "、");
+        r.insert(6, "み");
+        r.insert(7, "ん");
+        r.insert(8, "な");
+        r.insert(9, "さ");
+        r.insert(10, "ん");
+        r.insert(11, "！");
+        r.insert(7, "zopter");
+        assert_eq!("こんいちは、みzopterんなさん！", r);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_char_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert_char(3, 'A');
+        r.insert_char(12, '!');
+        r.insert_char(12, '!');
+
+        assert_eq!(
+            r,
+            "HelAlo there!!!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn insert_char_02() {
+        let mut r = Rope::new();
+
+        r.insert_char(0, '！');
+        r.insert_char(0, 'こ');
+        r.insert_char(1, 'ん');
+        r.insert_char(2, 'い');
+        r.insert_char(3, 'ち');
+        r.insert_char(4, 'は');
+        r.insert_char(5, '、');
+        r.insert_char(6, 'み');
+        r.insert_char(7, 'ん');
+        r.insert_char(8, 'な');
+        r.insert_char(9, 'さ');
+        r.insert_char(10, 'ん');
+        assert_eq!("こんいちは、みんなさん！", r);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_01() {
+        let mut r = Rope::from_str(TEXT);
+
+        r.remove(5..11);
+        r.remove(24..31);
+        r.remove(19..25);
+        r.remove(75..79);
+        assert_eq!(
+            r,
+            "Hello!  How're you \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにんなさん！"
+        );
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_02() {
+        let mut r = Rope::from_str("\r\n\r\n\r\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n");
+
+        // Make sure CRLF pairs get merged properly, via
+        // assert_invariants() below.
+        r.remove(3..6);
+        assert_eq!(r, "\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n");
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_03() {
+        let mut r = Rope::from_str(TEXT);
+
+        // Make sure crlf pairs get merged properly
+        r.remove(45..45);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn remove_04() {
+        let mut r = Rope::from_str(TEXT);
+
+        // Make sure crlf pairs get merged properly
+        r.remove(0..103);
+        assert_eq!(r, "");
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_05() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(56..55); // Wrong ordering of start/end
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_06() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(102..104); // Removing past the end
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_07() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(103..104); // Removing past the end
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_08() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(104..104); // Removing past the end
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_09() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(104..105); // Removing past the end
+    }
+
+    #[test]
+    fn remove_10() {
+        // Removing large prefixes, which are split off.
+        let r = Rope::from_str(TEXT);
+        for end in 0..104 {
+            let mut r2 = r.clone();
+            r2.remove(..end);
+            assert_eq!(r2, r.slice(end..));
+            assert_eq!(r, TEXT);
+
+            r2.assert_integrity();
+            r2.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn try_insert_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(r.try_insert(103, "!"), Ok(()));
+        assert_eq!(r.try_insert(0, ""), Ok(()));
+        assert_eq!(
+            r.try_insert(105, "!"),
+            Err(RopeError::CharIndexOutOfBounds {
+                char_idx: 105,
+                len_chars: 104,
+            })
+        );
+        assert_eq!(r, format!("{}!", TEXT));
+    }
+
+    #[test]
+    fn try_remove_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(
+            r.try_remove(104..104),
+            Err(RopeError::CharIndexOutOfBounds {
+                char_idx: 104,
+                len_chars: 103,
+            })
+        );
+        assert_eq!(r.try_remove(53..52), Err(RopeError::InvalidRange { start: 53, end: 52 }));
+        assert_eq!(
+            r.try_remove(104..),
+            Err(RopeError::CharIndexOutOfBounds {
+                char_idx: 104,
+                len_chars: 103,
+            })
+        );
+        assert_eq!(r, TEXT);
+
+        assert_eq!(r.try_remove(5..), Ok(()));
+        assert_eq!(r, "Hello");
+        assert_eq!(r.try_remove(..), Ok(()));
+        assert_eq!(r, "");
+    }
+
+    #[test]
+    fn insert_at_byte_01() {
+        let mut r = Rope::from_str(TEXT);
+        let mut s = TEXT.to_string();
+        for &byte_idx in &[127, 100, 94, 91, 64, 8, 0] {
+            r.insert_at_byte(byte_idx, "\r\nこ");
+            s.insert_str(byte_idx, "\r\nこ");
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        assert_eq!(r, s);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_byte_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert_at_byte(95, "!");
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_byte_03() {
+        let mut r = Rope::from_str(TEXT);
+        r.insert_at_byte(128, "!");
+    }
+
+    #[test]
+    fn insert_at_byte_04() {
+        // The insertion is recorded in chars.
+        let mut r = Rope::from_str(TEXT);
+        r.take_dirty_range();
+        let c = r.byte_to_char(100);
+        r.insert_at_byte(100, "ab");
+        assert_eq!(r.take_dirty_range(), Some(c..c + 2));
+        r.insert_at_byte(100, "");
+        assert_eq!(r.take_dirty_range(), None);
+    }
+
+    #[test]
+    fn remove_byte_range_01() {
+        let mut r = Rope::from_str(TEXT);
+        let mut s = TEXT.to_string();
+        for &(start, end) in &[(121, 127), (94, 100), (91, 97), (20, 70), (0, 5), (6, 6)] {
+            r.remove_byte_range(start..end);
+            s.replace_range(start..end, "");
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        assert_eq!(r, s);
+
+        r.remove_byte_range(3..=4);
+        s.replace_range(3..=4, "");
+        assert_eq!(r, s);
+        r.remove_byte_range(3..);
+        assert_eq!(r, &s[..3]);
+        r.remove_byte_range(..);
+        assert_eq!(r, "");
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_byte_range_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove_byte_range(94..99);
+    }
+
+    #[test]
+    fn remove_byte_range_03() {
+        // The removal is recorded in chars.
+        let mut r = Rope::from_str(TEXT);
+        r.take_dirty_range();
+        let (start, end) = (r.byte_to_char(91), r.byte_to_char(100));
+        r.remove_byte_range(91..100);
+        assert_eq!(r.take_dirty_range(), Some(start..start));
+        assert_eq!(r.len_chars(), TEXT.chars().count() - (end - start));
+    }
+
+    #[test]
+    fn remove_byte_range_04() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        // Ends that aren't on char boundaries, in leaves far enough apart
+        // that the nodes between them would be removed first.  The rope
+        // must be left as it was.
+        let mut r = Rope::from_str(TEXT);
+        for &(start, end) in &[(5, 95), (92, 127), (0, 107)] {
+            let result = catch_unwind(AssertUnwindSafe(|| r.remove_byte_range(start..end)));
+            assert!(result.is_err());
+            assert_eq!(r, TEXT);
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn remove_lines_01() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        let expected = format!("{}{}", &TEXT_LINES[..32], &TEXT_LINES[88..]);
+        r.remove_lines(1..3);
+        assert_eq!(r, expected);
+        r.assert_integrity();
+        r.assert_invariants();
+
+        r.remove_lines(2..2);
+        r.remove_lines(2..);
+        assert_eq!(r, expected);
+        r.remove_lines(1..);
+        assert_eq!(r, &TEXT_LINES[..32]);
+        r.remove_lines(..);
+        assert_eq!(r, "");
+    }
+
+    #[test]
+    #[should_panic]
+    fn remove_lines_02() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        r.remove_lines(3..5);
+    }
+
+    #[test]
+    fn trim_front_bytes_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(0, r.trim_front_bytes(0));
+        assert_eq!(50, r.trim_front_bytes(50));
+        assert_eq!(r, &TEXT[50..]);
+
+        // Inside a multi-byte char.
+        assert_eq!(44, r.trim_front_bytes(42));
+        assert_eq!(r, &TEXT[94..]);
+
+        assert_eq!(33, r.trim_front_bytes(33));
+        assert_eq!(r, "");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn trim_front_bytes_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.trim_front_bytes(128);
+    }
+
+    #[test]
+    fn leaf_mut_at_01() {
+        let text = format!("{}\r\n{}\tabc", TEXT_LINES, TEXT);
+        let mut r = Rope::from_str(&text);
+        let original = r.clone();
+        let mut byte_idx = 0;
+        while byte_idx < r.len_bytes() {
+            let mut leaf = r.leaf_mut_at(byte_idx);
+            assert_eq!(byte_idx, leaf.byte_start());
+            leaf.make_ascii_uppercase();
+            byte_idx += leaf.as_str().len();
+        }
+
+        assert_eq!(r, text.to_ascii_uppercase());
+        assert_eq!(original, text);
+        assert_eq!(r.len_lines(), original.len_lines());
+        r.assert_integrity();
+        r.assert_invariants();
+
+        // The last leaf, at one-past-the-end.
+        r.leaf_mut_at(r.len_bytes()).make_ascii_lowercase();
+        assert!(r.to_string().ends_with("\tabc"));
+
+        let mut r = Rope::new();
+        assert_eq!("", r.leaf_mut_at(0).as_str());
+    }
+
+    #[test]
+    fn leaf_mut_at_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.take_dirty_range();
+        {
+            let mut leaf = r.leaf_mut_at(20);
+            let start = leaf.byte_start();
+            assert!(start <= 20);
+            // Line breaks and non-ASCII bytes are left alone.
+            leaf.map_ascii(0..2, |_| b'-');
+        }
+        assert!(r.to_string().contains("--"));
+        let dirty = r.take_dirty_range().unwrap();
+        assert!(dirty.start <= 20 && dirty.end > 20);
+        r.assert_integrity();
+        r.assert_invariants();
+
+        let mut r = Rope::from_str("a\nb");
+        r.leaf_mut_at(0).map_ascii(0..3, |_| b'x');
+        assert_eq!(r, "x\nx");
+    }
+
+    #[test]
+    #[should_panic]
+    fn leaf_mut_at_03() {
+        let mut r = Rope::from_str(TEXT);
+        r.leaf_mut_at(0).map_ascii(0..1, |_| b'\n');
+    }
+
+    #[test]
+    #[should_panic]
+    fn leaf_mut_at_04() {
+        let mut r = Rope::from_str(TEXT);
+        r.leaf_mut_at(128);
+    }
+
+    #[test]
+    #[cfg(feature = "graphemes")]
+    fn leaf_mut_at_05() {
+        // Tabs before combining marks mustn't be changed, as that would
+        // change the number of grapheme clusters.
+        let text = "\t\u{301}a\u{301}\t\u{301}".repeat(20);
+        let mut r = Rope::from_str(&text);
+        let mut byte_idx = 0;
+        while byte_idx < r.len_bytes() {
+            let mut leaf = r.leaf_mut_at(byte_idx);
+            let len = leaf.as_str().len();
+            leaf.map_ascii(0..len, |_| b'a');
+            byte_idx += len;
+        }
+
+        assert_eq!(r, text.as_str());
+        assert!(r.validate().is_ok());
+        assert_graphemes(&r);
+    }
+
+    #[test]
+    fn split_off_01() {
+        let mut r = Rope::from_str(TEXT);
+
+        let r2 = r.split_off(50);
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?  It's \
+             a fine day, "
+        );
+        assert_eq!(
+            r2,
+            "isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r2.assert_integrity();
+        r.assert_invariants();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    fn split_off_02() {
+        let mut r = Rope::from_str(TEXT);
+
+        let r2 = r.split_off(1);
+        assert_eq!(r, "H");
+        assert_eq!(
+            r2,
+            "ello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r2.assert_integrity();
+        r.assert_invariants();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    fn split_off_03() {
+        let mut r = Rope::from_str(TEXT);
+
+        let r2 = r.split_off(102);
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん"
+        );
+        assert_eq!(r2, "！");
+
+        r.assert_integrity();
+        r2.assert_integrity();
+        r.assert_invariants();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    fn split_off_04() {
+        let mut r = Rope::from_str(TEXT);
+
+        let r2 = r.split_off(0);
+        assert_eq!(r, "");
+        assert_eq!(
+            r2,
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+
+        r.assert_integrity();
+        r2.assert_integrity();
+        r.assert_invariants();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    fn split_off_05() {
+        let mut r = Rope::from_str(TEXT);
+
+        let r2 = r.split_off(103);
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！"
+        );
+        assert_eq!(r2, "");
+
+        r.assert_integrity();
+        r2.assert_integrity();
+        r.assert_invariants();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_06() {
+        let mut r = Rope::from_str(TEXT);
+        r.split_off(104); // One past the end of the rope
+    }
+
+    #[test]
+    fn append_01() {
+        let mut r = Rope::from_str(
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't ",
+        );
+        let r2 = Rope::from_str(
+            "it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！",
+        );
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn append_02() {
+        let mut r = Rope::from_str(
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんに",
+        );
+        let r2 = Rope::from_str("ちは、みんなさん！");
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn append_03() {
+        let mut r = Rope::from_str(
+            "Hello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん",
+        );
+        let r2 = Rope::from_str("！");
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn append_04() {
+        let mut r = Rope::from_str("H");
+        let r2 = Rope::from_str(
+            "ello there!  How're you doing?  It's \
+             a fine day, isn't it?  Aren't you glad \
+             we're alive?  こんにちは、みんなさん！",
+        );
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn append_05() {
+        let mut r = Rope::from_str(TEXT);
+        let r2 = Rope::from_str("");
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn append_06() {
+        let mut r = Rope::from_str("");
+        let r2 = Rope::from_str(TEXT);
+
+        r.append(r2);
+        assert_eq!(r, TEXT);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn shrink_to_fit_01() {
+        let mut r = Rope::new();
+        for _ in 0..10 {
+            let len = r.len_chars();
+            r.insert(len / 2, "こ");
+            r.insert(len / 2, "ん");
+            r.insert(len / 2, "い");
+            r.insert(len / 2, "ち");
+            r.insert(len / 2, "は");
+            r.insert(len / 2, "、");
+            r.insert(len / 2, "み");
+            r.insert(len / 2, "ん");
+            r.insert(len / 2, "な");
+            r.insert(len / 2, "さ");
+            r.insert(len / 2, "ん");
+            r.insert(len / 2, "！");
+            r.insert(len / 2, "zopter");
+        }
+
+        let r2 = r.clone();
+        r.shrink_to_fit();
+
+        assert_eq!(r, r2);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    #[cfg(feature = "testing")]
+    fn from_chunks_exact_01() {
+        let chunks = ["a", "", "こ", "んにちは\r", "\n", "z"];
+        let r = Rope::from_chunks_exact(&chunks[..]);
+        assert_eq!(r, chunks.concat());
+        assert_eq!(r.chunks().collect::<Vec<_>>(), ["a", "こ", "んにちは\r", "\n", "z"]);
+        r.assert_integrity();
+
+        // Many chunks, for a multi-level tree.
+        let text = "abcdefghij".repeat(50);
+        let chunks: Vec<&str> = (0..500).map(|i| &text[i..(i + 1)]).collect();
+        let r = Rope::from_chunks_exact(&chunks);
+        assert_eq!(r, text);
+        assert_eq!(500, r.chunks().count());
+        r.assert_integrity();
+        r.assert_invariants();
+
+        assert_eq!(Rope::from_chunks_exact(&[]), "");
+    }
+
+    #[test]
+    fn rebalance_01() {
+        // One byte per leaf, and as few children per node as allowed.
+        let text = "abcdefghij".repeat(73);
+        let chunks: Vec<&str> = (0..729).map(|i| &text[i..(i + 1)]).collect();
+        let shape = TreeShape::from_chunks(&chunks, TreeShape::MIN_CHILDREN);
+        let mut r = Rope::from_shape(&shape).unwrap();
+        let r2 = r.clone();
+        assert_eq!(3, r.skew());
+
+        r.rebalance();
+        assert!(r.skew() <= 1);
+        assert_eq!(r, r2);
+        assert_eq!(r2.shape(), shape);
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn rebalance_02() {
+        let mut r = Rope::new();
+        r.set_max_skew(None);
+        r.insert(0, TEXT);
+        r.rebalance();
+        assert_eq!(r, TEXT);
+        assert!(r.skew() <= 1);
+        r.assert_integrity();
+        r.assert_invariants();
+
+        r.remove(..);
+        r.rebalance();
+        assert_eq!(r, "");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn max_skew_01() {
+        let text = "abcdefghij".repeat(73);
+        let chunks: Vec<&str> = (0..729).map(|i| &text[i..(i + 1)]).collect();
+        let shape = TreeShape::from_chunks(&chunks, TreeShape::MIN_CHILDREN);
+
+        // Not skewed enough for the default.
+        let mut r = Rope::from_shape(&shape).unwrap();
+        assert_eq!(Some(3), r.max_skew());
+        r.insert(0, "a");
+        assert_eq!(3, r.skew());
+
+        // Rebalanced by the next edit.
+        let mut r = Rope::from_shape(&shape).unwrap();
+        r.set_max_skew(Some(2));
+        r.insert(0, "a");
+        assert!(r.skew() <= 1);
+        assert_eq!(Some(2), r.split_off(100).max_skew());
+        assert_eq!(r, format!("a{}", &text[..99]));
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn max_skew_02() {
+        let mut r = Rope::new();
+        r.set_max_skew(Some(2));
+        for i in 0..2000 {
+            r.insert(0, &TEXT[(i % 91)..(i % 91 + 1)]);
+            assert!(r.skew() <= 2);
+        }
+        for _ in 0..1990 {
+            r.remove(1..2);
+            assert!(r.skew() <= 2);
+        }
+        assert_eq!(10, r.len_chars());
+
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    #[should_panic]
+    fn max_skew_03() {
+        Rope::new().set_max_skew(Some(1));
+    }
+
+    #[test]
+    fn is_char_boundary_01() {
+        let r = Rope::from_str(TEXT);
+        for i in 0..(TEXT.len() + 2) {
+            assert_eq!(TEXT.is_char_boundary(i), r.is_char_boundary(i));
+        }
+
+        assert!(Rope::new().is_char_boundary(0));
+        assert!(!Rope::new().is_char_boundary(1));
+    }
+
+    #[test]
+    fn floor_char_boundary_01() {
+        let r = Rope::from_str(TEXT);
+        for i in 0..(TEXT.len() + 2) {
+            let mut floor = i.min(TEXT.len());
+            while !TEXT.is_char_boundary(floor) {
+                floor -= 1;
+            }
+            assert_eq!(floor, r.floor_char_boundary(i));
+        }
+
+        assert_eq!(0, Rope::new().floor_char_boundary(5));
+    }
+
+    #[test]
+    fn ceil_char_boundary_01() {
+        let r = Rope::from_str(TEXT);
+        for i in 0..(TEXT.len() + 2) {
+            let mut ceil = i.min(TEXT.len());
+            while !TEXT.is_char_boundary(ceil) {
+                ceil += 1;
+            }
+            assert_eq!(ceil, r.ceil_char_boundary(i));
+        }
+
+        assert_eq!(0, Rope::new().ceil_char_boundary(0));
+    }
+
+    #[test]
+    fn ceil_char_boundary_02() {
+        let r = Rope::from_str("こんにちは");
+        assert_eq!(15, r.ceil_char_boundary(16));
+        assert_eq!(15, r.ceil_char_boundary(usize::MAX));
+        assert_eq!(0, Rope::new().ceil_char_boundary(5));
+    }
+
+    #[test]
+    fn floor_char_boundary_02() {
+        let r = Rope::from_str("こんにちは");
+        assert_eq!(15, r.floor_char_boundary(16));
+        assert_eq!(15, r.floor_char_boundary(usize::MAX));
+        assert_eq!(0, Rope::new().floor_char_boundary(5));
+    }
+
+    #[test]
+    fn byte_to_char_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(0, r.byte_to_char(0));
+        assert_eq!(1, r.byte_to_char(1));
+        assert_eq!(2, r.byte_to_char(2));
+
+        assert_eq!(91, r.byte_to_char(91));
+        assert_eq!(91, r.byte_to_char(92));
+        assert_eq!(91, r.byte_to_char(93));
+
+        assert_eq!(92, r.byte_to_char(94));
+        assert_eq!(92, r.byte_to_char(95));
+        assert_eq!(92, r.byte_to_char(96));
+
+        assert_eq!(102, r.byte_to_char(124));
+        assert_eq!(102, r.byte_to_char(125));
+        assert_eq!(102, r.byte_to_char(126));
+        assert_eq!(103, r.byte_to_char(127));
+    }
+
+    #[test]
+    fn char_to_byte_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(0, r.char_to_byte(0));
+        assert_eq!(1, r.char_to_byte(1));
+        assert_eq!(2, r.char_to_byte(2));
+
+        assert_eq!(91, r.char_to_byte(91));
+        assert_eq!(94, r.char_to_byte(92));
+        assert_eq!(97, r.char_to_byte(93));
+        assert_eq!(100, r.char_to_byte(94));
+
+        assert_eq!(124, r.char_to_byte(102));
+        assert_eq!(127, r.char_to_byte(103));
+    }
+
+    #[test]
+    fn byte_to_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        for byte_idx in 0..=r.len_bytes() {
+            let char_idx = r.byte_to_char(byte_idx);
+            assert_eq!(r.char_to_line(char_idx), r.byte_to_line(byte_idx));
+        }
+        assert_eq!(1, Rope::new().byte_to_line(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_to_line_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.byte_to_line(r.len_bytes() + 1);
+    }
+
+    #[test]
+    fn char_to_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        assert_eq!(0, r.char_to_line(0));
+        assert_eq!(0, r.char_to_line(1));
+
+        assert_eq!(0, r.char_to_line(31));
+        assert_eq!(1, r.char_to_line(32));
+        assert_eq!(1, r.char_to_line(33));
+
+        assert_eq!(1, r.char_to_line(58));
+        assert_eq!(2, r.char_to_line(59));
+        assert_eq!(2, r.char_to_line(60));
+
+        assert_eq!(2, r.char_to_line(87));
+        assert_eq!(3, r.char_to_line(88));
+        assert_eq!(3, r.char_to_line(89));
+
+        assert_eq!(4, r.char_to_line(100));
+    }
+
+    #[test]
+    fn char_to_line_02() {
+        let r = Rope::from_str("");
+        assert_eq!(1, r.char_to_line(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn char_to_line_03() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.char_to_line(101);
+    }
+
+    #[test]
+    fn line_to_byte_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        for line_idx in 0..=r.len_lines() {
+            let char_idx = r.line_to_char(line_idx);
+            assert_eq!(r.char_to_byte(char_idx), r.line_to_byte(line_idx));
+        }
+        assert_eq!(0, Rope::new().line_to_byte(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_to_byte_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_to_byte(5);
+    }
+
+    #[test]
+    fn line_to_cow_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        for (i, line) in r.lines().enumerate() {
+            let cow = r.line_to_cow(i);
+            assert_eq!(line, &*cow);
+            let (chunk, _, _, _) = line.chunk_at_byte(0);
+            assert_eq!(chunk.len() == line.len_bytes(), matches!(cow, Cow::Borrowed(_)));
+        }
+
+        let r = Rope::from_str("a\nb\n\nc");
+        assert_eq!(Cow::Borrowed("b\n"), r.line_to_cow(1));
+        assert_eq!(Cow::Borrowed(""), Rope::new().line_to_cow(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_to_cow_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_to_cow(4);
+    }
+
+    #[test]
+    fn chunk_at_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        let mut text = String::new();
+        let mut byte_idx = 0;
+        while byte_idx < r.len_bytes() {
+            let (chunk, b, c, l) = r.chunk_at_byte(byte_idx);
+            assert_eq!(b, byte_idx);
+            assert_eq!(c, r.byte_to_char(b));
+            assert_eq!(l, r.byte_to_line(b));
+            assert_eq!((chunk, b, c, l), r.chunk_at_char(c));
+            text.push_str(chunk);
+            byte_idx += chunk.len();
+        }
+        assert_eq!(text, TEXT_LINES);
+
+        for i in 1..r.len_lines() {
+            let char_idx = r.line_to_char(i) - 1;
+            assert_eq!(r.chunk_at_line_break(i), r.chunk_at_char(char_idx));
+        }
+        assert_eq!(r.chunk_at_line_break(0), r.chunk_at_byte(0));
+        assert_eq!(r.chunk_at_line_break(4), r.chunk_at_byte(r.len_bytes()));
+        assert_eq!(("", 0, 0, 0), Rope::new().chunk_at_line_break(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunk_at_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.chunk_at_line_break(5);
+    }
+
+    #[test]
+    fn line_len_01() {
+        // Lines of all lengths, spanning any number of chunks.
+        let mut text = String::new();
+        for i in 0..40 {
+            text.push_str(&"aこ\r".repeat(i));
+            text.push_str(["\n", "\r\n", "\u{2028}"][i % 3]);
+        }
+        text.push_str("end");
+
+        for r in &[Rope::from_str(&text), Rope::from_str(TEXT_LINES), Rope::new()] {
+            for line_idx in 0..r.len_lines() {
+                let line = r.line(line_idx);
+                assert_eq!(line.len_chars(), r.line_len_chars(line_idx));
+                assert_eq!(line.len_bytes(), r.line_len_bytes(line_idx));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_len_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_len_chars(4);
+    }
+
+    #[test]
+    fn line_to_char_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        assert_eq!(0, r.line_to_char(0));
+        assert_eq!(32, r.line_to_char(1));
+        assert_eq!(59, r.line_to_char(2));
+        assert_eq!(88, r.line_to_char(3));
+        assert_eq!(100, r.line_to_char(4));
+    }
+
+    #[test]
+    fn line_to_char_02() {
+        let r = Rope::from_str("");
+        assert_eq!(0, r.line_to_char(0));
+        assert_eq!(0, r.line_to_char(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_to_char_03() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_to_char(5);
+    }
+
+    #[test]
+    fn get_index_conversions_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(Some(91), r.get_byte_to_char(93));
+        assert_eq!(Some(103), r.get_byte_to_char(127));
+        assert_eq!(None, r.get_byte_to_char(128));
+
+        assert_eq!(Some(97), r.get_char_to_byte(93));
+        assert_eq!(Some(127), r.get_char_to_byte(103));
+        assert_eq!(None, r.get_char_to_byte(104));
+
+        let r = Rope::from_str(TEXT_LINES);
+        assert_eq!(Some(2), r.get_char_to_line(59));
+        assert_eq!(Some(4), r.get_char_to_line(100));
+        assert_eq!(None, r.get_char_to_line(101));
+
+        assert_eq!(Some(88), r.get_line_to_char(3));
+        assert_eq!(Some(100), r.get_line_to_char(4));
+        assert_eq!(None, r.get_line_to_char(5));
+
+        let r = Rope::new();
+        assert_eq!(Some(0), r.get_byte_to_char(0));
+        assert_eq!(Some(1), r.get_char_to_line(0));
+        assert_eq!(None, r.get_char_to_byte(1));
+        assert_eq!(Some(0), r.get_line_to_char(1));
+        assert_eq!(None, r.get_line_to_char(2));
+    }
+
+    #[test]
+    fn char_to_line_col_01() {
+        // Long lines spanning several chunks, short ones, CRLF pairs and
+        // empty lines.
+        let text = format!(
+            "{}\r\n\r\n{}\n{}\u{2028}x",
+            TEXT,
+            TEXT_LINES,
+            "abcdefghij".repeat(30)
+        );
+        for r in &[Rope::from_str(TEXT_LINES), Rope::from_str(&text), Rope::new()] {
+            for i in 0..r.len_chars() {
+                let line = r.char_to_line(i);
+                let col = i - r.line_to_char(line);
+                assert_eq!((line, col), r.char_to_line_col(i));
+                assert_eq!(i, r.line_col_to_char(line, col));
+            }
+        }
+    }
+
+    #[test]
+    fn char_to_line_col_03() {
+        // One-past-the-end is at the end of the last line.
+        let r = Rope::from_str("ab\ncd");
+        assert_eq!((1, 2), r.char_to_line_col(5));
+        assert_eq!(5, r.line_col_to_char(1, 2));
+
+        let r = Rope::from_str("ab\r\n");
+        assert_eq!((1, 0), r.char_to_line_col(4));
+        assert_eq!(4, r.line_col_to_char(1, 0));
+
+        let r = Rope::from_str(TEXT_LINES);
+        let last_line = r.len_lines() - 1;
+        let col = r.line(last_line).len_chars();
+        assert_eq!((last_line, col), r.char_to_line_col(r.len_chars()));
+        assert_eq!(r.len_chars(), r.line_col_to_char(last_line, col));
+
+        let r = Rope::new();
+        assert_eq!((0, 0), r.char_to_line_col(0));
+        assert_eq!(0, r.line_col_to_char(0, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn char_to_line_col_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.char_to_line_col(101);
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_col_to_char_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_col_to_char(3, 13);
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_col_to_char_02() {
+        let r = Rope::from_str("ab\ncd");
+        r.line_col_to_char(2, 0);
+    }
+
+    #[test]
+    fn line_boundary_01() {
+        let text = "Hi\r\nthere\r\rこんにちは\n\u{2028}a\r\n";
+        let r = Rope::from_str(&text.repeat(3));
+        r.assert_invariants();
+
+        // Reference line starts and ends, computed from the chars.
+        let chars: Vec<char> = r.chars().collect();
+        let is_break = |i: usize| {
+            i < chars.len() && is_line_break(chars[i])
+                && !(chars[i] == '\n' && i > 0 && chars[i - 1] == '\r')
+        };
+        let mut start = 0;
+        for i in 0..(chars.len() + 1) {
+            if i > 0
+                && is_line_break(chars[i - 1])
+                && !(chars[i - 1] == '\r' && chars.get(i) == Some(&'\n'))
+            {
+                start = i;
+            }
+            let mut end = start;
+            while end < chars.len() && !is_break(end) {
+                end += 1;
+            }
+
+            assert_eq!(start, r.line_boundary_before(i));
+            assert_eq!(end, r.line_boundary_after(i));
+            assert_eq!(start == i, r.is_line_start(i));
+            assert_eq!(end == i, r.is_line_end(i));
+        }
+    }
+
+    #[test]
+    fn line_boundary_02() {
+        let r = Rope::new();
+        assert!(r.is_line_start(0));
+        assert!(r.is_line_end(0));
+        assert_eq!(0, r.line_boundary_before(0));
+        assert_eq!(0, r.line_boundary_after(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_boundary_03() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_boundary_after(r.len_chars() + 1);
     }
 
-    /// Creates an iterator over the chars of the `Rope`.
-    pub fn chars(&self) -> Chars {
-        Chars::new(&self.root)
+    #[test]
+    fn byte_01() {
+        let r = Rope::from_str(TEXT);
+
+        for (i, &b) in TEXT.as_bytes().iter().enumerate() {
+            assert_eq!(r.byte(i), b);
+        }
     }
 
-    /// Creates an iterator over the lines of the `Rope`.
-    pub fn lines(&self) -> Lines {
-        Lines::new(&self.root)
+    #[test]
+    #[should_panic]
+    fn byte_02() {
+        let r = Rope::from_str(TEXT);
+        r.byte(TEXT.len());
     }
 
-    /// Creates an iterator over the chunks of the `Rope`.
-    pub fn chunks(&self) -> Chunks {
-        Chunks::new(&self.root)
+    #[test]
+    fn char_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(r.char(0), 'H');
+        assert_eq!(r.char(10), 'e');
+        assert_eq!(r.char(18), 'r');
+        assert_eq!(r.char(102), '！');
     }
 
-    //-----------------------------------------------------------------------
-    // Conversion methods
+    #[test]
+    #[should_panic]
+    fn char_02() {
+        let r = Rope::from_str(TEXT);
+        r.char(103);
+    }
 
-    /// Returns the entire text of the `Rope` as a newly allocated String.
-    pub fn to_string(&self) -> String {
-        use iter::Chunks;
-        let mut text = String::with_capacity(self.len_bytes());
-        for chunk in Chunks::new(&self.root) {
-            text.push_str(chunk);
-        }
-        text
+    #[test]
+    #[should_panic]
+    fn char_03() {
+        let r = Rope::from_str("");
+        r.char(0);
     }
 
-    //-----------------------------------------------------------------------
-    // Debugging
+    #[test]
+    fn get_char_01() {
+        let r = Rope::from_str(TEXT);
 
-    /// Debugging tool to make sure that all of the meta-data of the
-    /// tree is consistent with the actual data.
-    #[doc(hidden)]
-    pub fn assert_integrity(&self) {
-        self.root.assert_integrity();
+        assert_eq!(r.get_char(0), Some('H'));
+        assert_eq!(r.get_char(102), Some('！'));
+        assert_eq!(r.get_char(103), None);
+        assert_eq!(Rope::new().get_char(0), None);
     }
 
-    /// Debugging tool to make sure that all of the following invariants
-    /// hold true throughout the tree:
-    ///
-    /// - The tree is the same height everywhere.
-    /// - All internal nodes have the minimum number of children.
-    /// - All leaf nodes are non-empty.
-    /// - CRLF pairs are never split over chunk boundaries.
-    #[doc(hidden)]
-    pub fn assert_invariants(&self) {
-        self.root.assert_balance();
-        self.root.assert_node_size(true);
-        self.assert_grapheme_seams();
+    #[test]
+    fn line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        assert_eq!(r.line(0), "Hello there!  How're you doing?\n");
+        assert_eq!(r.line(1), "It's a fine day, isn't it?\n");
+        assert_eq!(r.line(2), "Aren't you glad we're alive?\n");
+        assert_eq!(r.line(3), "こんにちは、みんなさん！");
     }
 
-    /// Checks that graphemes are never split over chunk boundaries.
-    fn assert_grapheme_seams(&self) {
-        if self.chunks().count() > 0 {
-            let mut itr = self.chunks();
-            let mut last_chunk = itr.next().unwrap();
-            for chunk in itr {
-                if !chunk.is_empty() && !last_chunk.is_empty() {
-                    assert!(crlf::seam_is_break(last_chunk.as_bytes(), chunk.as_bytes()));
-                    last_chunk = chunk;
-                } else if last_chunk.is_empty() {
-                    last_chunk = chunk;
-                }
-            }
-        }
+    #[test]
+    fn line_02() {
+        let r = Rope::from_str("Hello there!  How're you doing?\n");
+
+        assert_eq!(r.line(0), "Hello there!  How're you doing?\n");
+        assert_eq!(r.line(1), "");
     }
 
-    //-----------------------------------------------------------------------
-    // Internal utilities
+    #[test]
+    fn line_03() {
+        let r = Rope::from_str("");
 
-    /// Iteratively replaced the root node with its child if it only has
-    /// one child.
-    pub(crate) fn pull_up_singular_nodes(&mut self) {
-        while (!self.root.is_leaf()) && self.root.child_count() == 1 {
-            let child = if let Node::Internal(ref children) = *self.root {
-                Arc::clone(&children.nodes()[0])
-            } else {
-                unreachable!()
-            };
+        assert_eq!(r.line(0), "");
+    }
 
-            self.root = child;
-        }
+    #[test]
+    #[should_panic]
+    fn line_04() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line(4);
     }
-}
 
-//==============================================================
+    #[test]
+    fn get_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
 
-impl std::fmt::Debug for Rope {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        f.debug_list().entries(self.chunks()).finish()
+        assert_eq!(r.get_line(1).unwrap(), "It's a fine day, isn't it?\n");
+        assert_eq!(r.get_line(3).unwrap(), "こんにちは、みんなさん！");
+        assert!(r.get_line(4).is_none());
+        assert_eq!(Rope::new().get_line(0).unwrap(), "");
     }
-}
 
-impl std::fmt::Display for Rope {
-    #[inline]
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        for chunk in self.chunks() {
-            write!(f, "{}", chunk)?
-        }
-        Ok(())
+    #[test]
+    fn slice_01() {
+        let r = Rope::from_str(TEXT);
+
+        let s = r.slice(0..r.len_chars());
+
+        assert_eq!(TEXT, s);
     }
-}
 
-impl std::default::Default for Rope {
-    #[inline]
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn slice_02() {
+        let r = Rope::from_str(TEXT);
+
+        let s = r.slice(5..21);
+
+        assert_eq!(&TEXT[5..21], s);
     }
-}
 
-impl std::cmp::PartialEq<Rope> for Rope {
-    #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        self.slice(..) == other.slice(..)
+    #[test]
+    fn slice_03() {
+        let r = Rope::from_str(TEXT);
+
+        let s = r.slice(31..97);
+
+        assert_eq!(&TEXT[31..109], s);
     }
-}
 
-impl<'a> std::cmp::PartialEq<&'a str> for Rope {
-    #[inline]
-    fn eq(&self, other: &&'a str) -> bool {
-        self.slice(..) == *other
+    #[test]
+    fn slice_04() {
+        let r = Rope::from_str(TEXT);
+
+        let s = r.slice(53..53);
+
+        assert_eq!("", s);
     }
-}
 
-impl<'a> std::cmp::PartialEq<Rope> for &'a str {
-    #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        *self == other.slice(..)
+    #[test]
+    #[should_panic]
+    fn slice_05() {
+        let r = Rope::from_str(TEXT);
+        r.slice(53..52);
     }
-}
 
-impl std::cmp::PartialEq<str> for Rope {
-    #[inline]
-    fn eq(&self, other: &str) -> bool {
-        self.slice(..) == other
+    #[test]
+    #[should_panic]
+    fn slice_06() {
+        let r = Rope::from_str(TEXT);
+        r.slice(102..104);
+    }
+
+    #[test]
+    fn get_slice_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(r.get_slice(5..21).unwrap(), &TEXT[5..21]);
+        assert_eq!(r.get_slice(..).unwrap(), TEXT);
+        assert_eq!(r.get_slice(103..).unwrap(), "");
+        assert!(r.get_slice(53..52).is_none());
+        assert!(r.get_slice(102..104).is_none());
+        assert!(r.get_slice(104..).is_none());
+    }
+
+    #[test]
+    fn byte_slice_01() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(r.byte_slice(5..21), &TEXT[5..21]);
+        assert_eq!(r.byte_slice(91..112), &TEXT[91..112]);
+        assert_eq!(r.byte_slice(94..), &TEXT[94..]);
+        assert_eq!(r.byte_slice(..), TEXT);
+        assert_eq!(r.byte_slice(127..), "");
+        assert_eq!(r.byte_slice(97..97), "");
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_slice_02() {
+        let r = Rope::from_str(TEXT);
+        r.byte_slice(92..100);
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_slice_03() {
+        let r = Rope::from_str(TEXT);
+        r.byte_slice(94..128);
+    }
+
+    #[test]
+    fn line_slice_01() {
+        let r = Rope::from_str(TEXT_LINES);
+
+        assert_eq!(r.line_slice(0..1), r.line(0));
+        assert_eq!(r.line_slice(1..3), r.slice(32..88));
+        assert_eq!(r.line_slice(3..), r.line(3));
+        assert_eq!(r.line_slice(..), TEXT_LINES);
+        assert_eq!(r.line_slice(4..), "");
+        assert_eq!(r.line_slice(2..2), "");
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_slice_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.line_slice(3..5);
+    }
+
+    #[test]
+    fn eq_rope_01() {
+        let r = Rope::from_str("");
+
+        assert_eq!(r, r);
+    }
+
+    #[test]
+    fn eq_rope_02() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(r, r);
+    }
+
+    #[test]
+    fn eq_rope_03() {
+        let r1 = Rope::from_str(TEXT);
+        let mut r2 = r1.clone();
+        r2.remove(26..27);
+        r2.insert(26, "z");
+
+        assert_ne!(r1, r2);
+    }
+
+    #[test]
+    fn eq_rope_04() {
+        let r = Rope::from_str("");
+
+        assert_eq!(r, "");
+        assert_eq!("", r);
     }
-}
 
-impl std::cmp::PartialEq<Rope> for str {
-    #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        self == other.slice(..)
+    #[test]
+    fn eq_rope_05() {
+        let r = Rope::from_str(TEXT);
+
+        assert_eq!(r, TEXT);
+        assert_eq!(TEXT, r);
     }
-}
 
-impl<'a> std::cmp::PartialEq<String> for Rope {
-    #[inline]
-    fn eq(&self, other: &String) -> bool {
-        self.slice(..) == other.as_str()
+    #[test]
+    fn eq_rope_06() {
+        let mut r = Rope::from_str(TEXT);
+        r.remove(26..27);
+        r.insert(26, "z");
+
+        assert_ne!(r, TEXT);
+        assert_ne!(TEXT, r);
     }
-}
 
-impl<'a> std::cmp::PartialEq<Rope> for String {
-    #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        self.as_str() == other.slice(..)
+    #[test]
+    fn eq_rope_07() {
+        let r = Rope::from_str(TEXT);
+        let s: String = TEXT.into();
+
+        assert_eq!(r, s);
+        assert_eq!(s, r);
     }
-}
 
-impl<'a> std::cmp::PartialEq<std::borrow::Cow<'a, str>> for Rope {
-    #[inline]
-    fn eq(&self, other: &std::borrow::Cow<'a, str>) -> bool {
-        self.slice(..) == **other
+    #[test]
+    fn cmp_rope_01() {
+        let r1 = Rope::from_str(TEXT);
+        let mut r2 = Rope::from_str(TEXT);
+        r2.remove(26..27);
+        r2.insert(26, "z");
+
+        assert!(r1 < r2);
+        assert!(r2 > r1);
+        assert!(r1 <= Rope::from_str(TEXT));
+        assert!(Rope::new() < r1);
+
+        let mut set = std::collections::BTreeSet::new();
+        set.insert(r2.clone());
+        set.insert(r1.clone());
+        set.insert(Rope::from_str(TEXT));
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![r1, r2]);
     }
-}
 
-impl<'a> std::cmp::PartialEq<Rope> for std::borrow::Cow<'a, str> {
-    #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        **self == other.slice(..)
+    #[test]
+    fn cmp_str_01() {
+        let r = Rope::from_str(TEXT);
+        let s: String = TEXT.into();
+
+        assert!(r <= TEXT && TEXT <= r);
+        assert!(r >= s && s >= r);
+        assert!(r > "Hello there!");
+        assert!("Hello there!" < r);
+        assert!(r < *"Help");
+        assert!(*"Help" > r);
+        assert!(String::from("Hello there!!") > r);
     }
-}
 
-//==============================================================
+    #[test]
+    fn append_slice_01() {
+        let r = Rope::from_str(TEXT);
+        let mut r2 = Rope::from_str("Hi.  ");
+        r2.append_slice(&r.slice(6..));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        assert_eq!(r2, format!("Hi.  {}", &TEXT[6..]));
 
-    // 127 bytes, 103 chars, 1 line
-    const TEXT: &str = "Hello there!  How're you doing?  It's \
-                        a fine day, isn't it?  Aren't you glad \
-                        we're alive?  こんにちは、みんなさん！";
-    // 124 bytes, 100 chars, 4 lines
-    const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's \
-                              a fine day, isn't it?\nAren't you glad \
-                              we're alive?\nこんにちは、みんなさん！";
+        r2.assert_integrity();
+        r2.assert_invariants();
+    }
 
     #[test]
-    fn new_01() {
-        let r = Rope::new();
-        assert_eq!(r, "");
+    fn append_slice_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        let mut r2 = Rope::new();
+        for line in r.lines() {
+            r2.append_slice(&line);
+        }
 
-        r.assert_integrity();
-        r.assert_invariants();
+        assert_eq!(r2, r);
+        r2.assert_integrity();
+        r2.assert_invariants();
     }
 
     #[test]
-    fn from_str() {
+    fn sub_rope_01() {
         let r = Rope::from_str(TEXT);
+        let r2 = r.sub_rope(7..98);
+
+        assert_eq!(r2, r.slice(7..98));
         assert_eq!(r, TEXT);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        r2.assert_integrity();
+        r2.assert_invariants();
     }
 
     #[test]
-    fn len_bytes_01() {
-        let r = Rope::from_str(TEXT);
-        assert_eq!(r.len_bytes(), 127);
+    fn sub_rope_02() {
+        // Leaves in the middle of the range should be shared, not copied.
+        fn leaves(node: &Arc<Node>, out: &mut Vec<*const Node>) {
+            match **node {
+                Node::Leaf(_) => out.push(&**node as *const Node),
+                Node::Internal(ref children) => for child in children.nodes() {
+                    leaves(child, out);
+                },
+            }
+        }
+
+        let text = TEXT.repeat(16);
+        let r = Rope::from_str(&text);
+        let r2 = r.sub_rope(100..1500);
+        assert_eq!(r2, r.slice(100..1500));
+
+        let mut l1 = Vec::new();
+        let mut l2 = Vec::new();
+        leaves(&r.root, &mut l1);
+        leaves(&r2.root, &mut l2);
+        let shared = l2.iter().filter(|l| l1.contains(l)).count();
+        assert!(shared >= l2.len() - 2);
     }
 
     #[test]
-    fn len_bytes_02() {
-        let r = Rope::from_str("");
-        assert_eq!(r.len_bytes(), 0);
+    fn find_matching_01() {
+        let r = Rope::from_str("fn a() { if b { c(d[0]) } else { (e) } }");
+
+        assert_eq!(Some(39), r.find_matching(7, '{', '}'));
+        assert_eq!(Some(7), r.find_matching(39, '{', '}'));
+        assert_eq!(Some(24), r.find_matching(14, '{', '}'));
+        assert_eq!(Some(22), r.find_matching(17, '(', ')'));
+        assert_eq!(Some(17), r.find_matching(22, '(', ')'));
+        assert_eq!(None, r.find_matching(0, '{', '}'));
     }
 
     #[test]
-    fn len_chars_01() {
-        let r = Rope::from_str(TEXT);
-        assert_eq!(r.len_chars(), 103);
+    fn find_matching_02() {
+        // Unbalanced
+        let r = Rope::from_str("((こんにちは)");
+
+        assert_eq!(None, r.find_matching(0, '(', ')'));
+        assert_eq!(Some(1), r.find_matching(7, '(', ')'));
     }
 
     #[test]
-    fn len_chars_02() {
-        let r = Rope::from_str("");
-        assert_eq!(r.len_chars(), 0);
+    fn find_matching_03() {
+        let r = Rope::from_str("[a ']' [b] ']' c]");
+        let quoted = |idx: usize| {
+            if idx >= 3 && idx < 6 {
+                Some(3..6)
+            } else if idx >= 11 && idx < 14 {
+                Some(11..14)
+            } else {
+                None
+            }
+        };
+
+        assert_eq!(Some(16), r.find_matching_skipping(0, '[', ']', &quoted));
+        assert_eq!(Some(0), r.find_matching_skipping(16, '[', ']', &quoted));
+        assert_eq!(Some(4), r.find_matching(0, '[', ']'));
     }
 
     #[test]
-    fn len_lines_01() {
+    fn find_01() {
         let r = Rope::from_str(TEXT_LINES);
-        assert_eq!(r.len_lines(), 4);
+
+        assert_eq!(Some(12..31), r.find("  How're you doing?"));
+        assert_eq!(Some(91..94), r.find("ちは、"));
+        assert_eq!(None, r.find("doing?\nIT'S"));
+
+        let options = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(Some(25..36), r.find_with("doing?\nIT'S", options));
     }
 
     #[test]
-    fn len_lines_02() {
-        let r = Rope::from_str("");
-        assert_eq!(r.len_lines(), 1);
+    fn matches_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let m: Vec<_> = r.matches("re").collect();
+
+        assert_eq!(vec![9..11, 18..20, 60..62, 78..80], m);
+        assert_eq!(0, r.matches("").count());
     }
 
     #[test]
-    fn insert_01() {
-        let mut r = Rope::from_str(TEXT);
-        r.insert(3, "AA");
+    fn matches_02() {
+        // Exact matches are found a chunk at a time, including ones that
+        // span several chunks.
+        let text = TEXT.repeat(5);
+        let r = Rope::from_str(&text);
+        let needles = ["e", "ん", "you", "こんにちは、みんなさん！Hello", "it?  Aren't you glad"];
+        for needle in &needles {
+            let expected: Vec<_> = text
+                .match_indices(needle)
+                .map(|(i, _)| {
+                    let start = r.byte_to_char(i);
+                    start..(start + needle.chars().count())
+                })
+                .collect();
+            assert_eq!(expected, r.matches(needle).collect::<Vec<_>>());
+
+            let expected: Vec<_> = expected
+                .iter()
+                .filter(|m| m.start >= 3)
+                .map(|m| (m.start - 3)..(m.end - 3))
+                .collect();
+            assert_eq!(expected, r.slice(3..).matches(needle).collect::<Vec<_>>());
+        }
+    }
 
-        assert_eq!(
-            r,
-            "HelAAlo there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
-        );
+    #[test]
+    fn matches_overlapping_01() {
+        let text = "abaabaaba".repeat(10);
+        let r = Rope::from_str(&text);
+        assert!(r.chunks().count() > 1);
+
+        let expected: Vec<_> = (0..(text.len() - 3))
+            .filter(|&i| &text[i..(i + 4)] == "abaa" || &text[i..(i + 4)] == "aaba")
+            .map(|i| i..(i + 4))
+            .collect();
+        let mut m: Vec<_> = r.matches_overlapping("abaa").collect();
+        m.extend(r.matches_overlapping("aaba"));
+        m.sort_by_key(|r| r.start);
+        assert_eq!(expected, m);
+
+        let m: Vec<_> = r.slice(1..10).matches_overlapping("aba").collect();
+        assert_eq!(vec![2..5, 5..8], m);
+        assert_eq!(0, r.matches_overlapping("").count());
+    }
 
-        r.assert_integrity();
-        r.assert_invariants();
+    fn check_diff_against_str(a: &str, b: &str) -> Vec<Edit> {
+        let mut r = Rope::from_str(a);
+        let edits = r.diff_against_str(b);
+        let b_chars: Vec<char> = b.chars().collect();
+        for e in &edits {
+            assert!(e.start < e.old_end || e.start < e.new_end);
+            r.remove(e.old_range());
+            let new: String = b_chars[e.new_range()].iter().collect();
+            r.insert(e.start, &new);
+        }
+        assert_eq!(r, b);
+        edits
     }
 
     #[test]
-    fn insert_02() {
-        let mut r = Rope::from_str(TEXT);
-        r.insert(0, "AA");
+    fn diff_against_str_01() {
+        assert_eq!(check_diff_against_str(TEXT_LINES, TEXT_LINES), []);
+        assert_eq!(check_diff_against_str("", ""), []);
+        assert_eq!(check_diff_against_str("", "abc"), [Edit::insert(0, 3)]);
+        assert_eq!(check_diff_against_str("abc", ""), [Edit::remove(0..3)]);
+        assert_eq!(check_diff_against_str("aaa", "aaaa"), [Edit::insert(3, 1)]);
+        assert_eq!(check_diff_against_str("aaaa", "aaa"), [Edit::remove(3..4)]);
+        // Shared leading byte of different chars.
+        assert_eq!(check_diff_against_str("xéx", "xêx"), [Edit::replace(1..2, 1)]);
+
+        let edited = TEXT_LINES.replace("fine", "nice");
+        assert_eq!(check_diff_against_str(TEXT_LINES, &edited), [Edit::replace(39..42, 3)]);
+    }
 
+    #[test]
+    fn diff_against_str_02() {
+        let a = "zero\none\ntwo\nthree\nfour\nfive\n";
+        let b = "zero\n1\ntwo\nthree\n3.5\nfour\nfive";
+        let edits = check_diff_against_str(a, b);
         assert_eq!(
-            r,
-            "AAHello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
+            edits,
+            [
+                Edit::replace(5..9, 2),
+                Edit::insert(17, 4),
+                Edit::replace(26..31, 4),
+            ]
         );
 
-        r.assert_integrity();
-        r.assert_invariants();
+        check_diff_against_str(TEXT_LINES, "");
+        check_diff_against_str(TEXT_LINES, "Hello\nこんにちは\r\n\n");
+        check_diff_against_str(&TEXT_LINES.repeat(3), &TEXT_LINES.replace("\n", "\r\n"));
     }
 
     #[test]
-    fn insert_03() {
-        let mut r = Rope::from_str(TEXT);
-        r.insert(103, "AA");
+    fn hash_into_01() {
+        use std::collections::hash_map::DefaultHasher;
 
-        assert_eq!(
-            r,
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！AA"
-        );
+        let r = Rope::from_str(TEXT);
+        assert!(r.chunks().count() > 1);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        let mut h1 = DefaultHasher::new();
+        let mut h2 = DefaultHasher::new();
+        r.hash_into(&mut h1);
+        h2.write(TEXT.as_bytes());
+
+        assert_eq!(h1.finish(), h2.finish());
     }
 
     #[test]
-    fn insert_04() {
-        let mut r = Rope::new();
-        r.insert(0, "He");
-        r.insert(2, "l");
-        r.insert(3, "l");
-        r.insert(4, "o w");
-        r.insert(7, "o");
-        r.insert(8, "rl");
-        r.insert(10, "d!");
-        r.insert(3, "zopter");
-
-        assert_eq!("Helzopterlo world!", r);
+    fn as_io_slices_01() {
+        let r = Rope::from_str(TEXT);
+        let mut slices = Vec::new();
+        r.as_io_slices(&mut slices);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        assert_eq!(r.chunks().count(), slices.len());
+        let bytes: Vec<u8> = slices.iter().flat_map(|s| s.iter().cloned()).collect();
+        assert_eq!(TEXT.as_bytes(), &bytes[..]);
     }
 
     #[test]
-    fn insert_05() {
-        let mut r = Rope::new();
-        r.insert(0, "こんいちは、みんなさん！");
-        r.insert(7, "zopter");
-        assert_eq!("こんいちは、みzopterんなさん！", r);
+    fn as_io_slices_02() {
+        let r = Rope::from_str(TEXT);
+        let mut slices = Vec::new();
+        r.slice(5..21).as_io_slices(&mut slices);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        let bytes: Vec<u8> = slices.iter().flat_map(|s| s.iter().cloned()).collect();
+        assert_eq!(&TEXT.as_bytes()[5..21], &bytes[..]);
     }
 
     #[test]
-    fn insert_06() {
-        let mut r = Rope::new();
-        r.insert(0, "こ");
-        r.insert(1, "ん");
-        r.insert(2, "い");
-        r.insert(3, "ち");
-        r.insert(4, "は");
-        r.insert(5, "、");
-        r.insert(6, "み");
-        r.insert(7, "ん");
-        r.insert(8, "な");
-        r.insert(9, "さ");
-        r.insert(10, "ん");
-        r.insert(11, "！");
-        r.insert(7, "zopter");
-        assert_eq!("こんいちは、みzopterんなさん！", r);
-
-        r.assert_integrity();
-        r.assert_invariants();
+    #[cfg(feature = "checksum")]
+    fn checksum_01() {
+        // Known XXH64 values, to catch accidental changes to the algorithm.
+        assert_eq!(0xEF46DB3751D8E999, Rope::new().checksum());
+        assert_eq!(0x44BC2CF5AD770999, Rope::from_str("abc").checksum());
     }
 
     #[test]
-    fn insert_char_01() {
-        let mut r = Rope::from_str(TEXT);
-        r.insert_char(3, 'A');
-        r.insert_char(12, '!');
-        r.insert_char(12, '!');
+    #[cfg(feature = "checksum")]
+    fn checksum_02() {
+        // Independent of chunking.
+        let r1 = Rope::from_str(TEXT);
+        let mut r2 = Rope::new();
+        for c in TEXT.chars().rev() {
+            r2.insert_char(0, c);
+        }
 
+        assert_eq!(r1, r2);
+        assert_eq!(r1.checksum(), r2.checksum());
+        assert_eq!(r1.slice(3..40).checksum(), r2.slice(3..40).checksum());
         assert_eq!(
-            r,
-            "HelAlo there!!!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
+            Rope::from_str(&TEXT[3..40]).checksum(),
+            r2.slice(3..40).checksum()
         );
-
-        r.assert_integrity();
-        r.assert_invariants();
     }
 
+    #[cfg(feature = "digest")]
     #[test]
-    fn insert_char_02() {
-        let mut r = Rope::new();
+    fn update_digest_01() {
+        struct Collect(Vec<u8>);
+        impl ::digest::Update for Collect {
+            fn update(&mut self, data: &[u8]) {
+                self.0.extend_from_slice(data);
+            }
+        }
 
-        r.insert_char(0, '！');
-        r.insert_char(0, 'こ');
-        r.insert_char(1, 'ん');
-        r.insert_char(2, 'い');
-        r.insert_char(3, 'ち');
-        r.insert_char(4, 'は');
-        r.insert_char(5, '、');
-        r.insert_char(6, 'み');
-        r.insert_char(7, 'ん');
-        r.insert_char(8, 'な');
-        r.insert_char(9, 'さ');
-        r.insert_char(10, 'ん');
-        assert_eq!("こんいちは、みんなさん！", r);
+        let r = Rope::from_str(TEXT);
+        let mut d = Collect(Vec::new());
+        r.update_digest(&mut d);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        assert_eq!(TEXT.as_bytes(), &d.0[..]);
     }
 
-    #[test]
-    fn remove_01() {
-        let mut r = Rope::from_str(TEXT);
-
-        r.remove(5..11);
-        r.remove(24..31);
-        r.remove(19..25);
-        r.remove(75..79);
-        assert_eq!(
-            r,
-            "Hello!  How're you \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにんなさん！"
-        );
-
-        r.assert_integrity();
-        r.assert_invariants();
+    fn assert_utf16(r: &Rope) {
+        let text = r.to_string();
+        assert_eq!(text.encode_utf16().count(), r.len_utf16_cu());
+        let mut utf16_cu_idx = 0;
+        for (char_idx, c) in text.chars().enumerate() {
+            assert_eq!(utf16_cu_idx, r.char_to_utf16_cu(char_idx));
+            for i in 0..c.len_utf16() {
+                assert_eq!(char_idx, r.utf16_cu_to_char(utf16_cu_idx + i));
+            }
+            utf16_cu_idx += c.len_utf16();
+        }
+        assert_eq!(r.len_utf16_cu(), r.char_to_utf16_cu(r.len_chars()));
+        assert_eq!(r.len_chars(), r.utf16_cu_to_char(r.len_utf16_cu()));
     }
 
     #[test]
-    fn remove_02() {
-        let mut r = Rope::from_str("\r\n\r\n\r\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n");
-
-        // Make sure CRLF pairs get merged properly, via
-        // assert_invariants() below.
-        r.remove(3..6);
-        assert_eq!(r, "\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n\r\n");
-
-        r.assert_integrity();
+    fn utf16_01() {
+        let text = "Hello \u{1F600}\u{1F600}!\r\nこんにちは\u{1F468}\u{200D}\u{1F469} \u{10FFFF}";
+        let r = Rope::from_str(text);
         r.assert_invariants();
+        assert_utf16(&r);
+        assert_utf16(&Rope::from_str(TEXT));
+        assert_utf16(&Rope::new());
     }
 
     #[test]
-    fn remove_03() {
-        let mut r = Rope::from_str(TEXT);
-
-        // Make sure crlf pairs get merged properly
-        r.remove(45..45);
-        assert_eq!(r, TEXT);
-
+    fn utf16_02() {
+        // Edits keep the counts up to date.
+        let mut r = Rope::new();
+        for i in 0..40 {
+            r.insert(r.len_chars() / 2, ["\u{1F600}a", "せ", "\u{10400}\u{10401}"][i % 3]);
+        }
+        r.remove(7..23);
+        r.insert_char(5, '\u{1F680}');
         r.assert_integrity();
         r.assert_invariants();
+        assert_utf16(&r);
     }
 
     #[test]
-    fn remove_04() {
-        let mut r = Rope::from_str(TEXT);
+    #[should_panic]
+    fn utf16_03() {
+        let r = Rope::from_str("a\u{1F600}b");
+        r.utf16_cu_to_char(5);
+    }
 
-        // Make sure crlf pairs get merged properly
-        r.remove(0..103);
-        assert_eq!(r, "");
+    #[cfg(feature = "graphemes")]
+    fn assert_graphemes(r: &Rope) {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let text = r.to_string();
+        let starts: Vec<usize> = text.grapheme_indices(true)
+            .map(|(i, _)| text[..i].chars().count())
+            .collect();
+        assert_eq!(starts.len(), r.len_graphemes());
+        for (g, &c) in starts.iter().enumerate() {
+            assert_eq!(c, r.grapheme_to_char(g));
+            assert_eq!(g, r.char_to_grapheme(c));
+        }
+        assert_eq!(r.len_chars(), r.grapheme_to_char(starts.len()));
+        assert_eq!(starts.len(), r.char_to_grapheme(r.len_chars()));
+    }
 
-        r.assert_integrity();
+    #[test]
+    #[cfg(feature = "graphemes")]
+    fn graphemes_01() {
+        let text = "he\u{301}llo\r\nwo\u{308}\u{301}rld \u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}!";
+        let r = Rope::from_str(text);
         r.assert_invariants();
+        assert_graphemes(&r);
+        assert_eq!(14, r.len_graphemes());
+        assert_eq!(1, r.char_to_grapheme(2));
+        assert_eq!(2, r.char_to_grapheme(3));
+        assert_eq!(3, r.grapheme_to_char(2));
     }
 
     #[test]
-    #[should_panic]
-    fn remove_05() {
-        let mut r = Rope::from_str(TEXT);
-        r.remove(56..55); // Wrong ordering of start/end
+    #[cfg(feature = "graphemes")]
+    fn graphemes_02() {
+        // Edits that create and break up graphemes across leaf seams.
+        let mut r = Rope::new();
+        for i in 0..40 {
+            r.insert(0, "e");
+            r.insert(r.len_chars() / 2, "\u{301}");
+            if i % 3 == 0 {
+                r.insert(r.len_chars(), "\r");
+                r.insert(0, "\n");
+            }
+            r.assert_invariants();
+            assert_graphemes(&r);
+        }
+        while r.len_chars() > 0 {
+            let idx = r.len_chars() / 3;
+            r.remove(idx..(idx + 2).min(r.len_chars()));
+            r.assert_invariants();
+            assert_graphemes(&r);
+        }
     }
 
     #[test]
-    #[should_panic]
-    fn remove_06() {
-        let mut r = Rope::from_str(TEXT);
-        r.remove(102..104); // Removing past the end
+    fn with_chunk_size_01() {
+        let mut r = Rope::with_chunk_size(32);
+        for _ in 0..20 {
+            let idx = r.len_chars() / 3;
+            r.insert(idx, TEXT_LINES);
+            r.remove((idx + 7)..(idx + 60));
+        }
+        r.assert_integrity();
+        r.assert_invariants();
+
+        assert_eq!(32, r.chunk_size());
+        assert!(r.chunks().all(|chunk| chunk.len() <= 32));
+        assert!(r.chunks().any(|chunk| chunk.len() > 8));
     }
 
     #[test]
-    #[should_panic]
-    fn remove_07() {
-        let mut r = Rope::from_str(TEXT);
-        r.remove(103..104); // Removing past the end
+    fn with_chunk_size_02() {
+        // The chunk size survives splitting, appending, and shrinking.
+        let mut r = Rope::with_chunk_size(16);
+        r.insert(0, TEXT);
+        let mut r2 = r.split_off(50);
+        assert_eq!(16, r.chunk_size());
+        assert_eq!(16, r2.chunk_size());
+
+        r2.append(Rope::from_str(TEXT));
+        r2.shrink_to_fit();
+        assert_eq!(16, r2.chunk_size());
+        assert_eq!(16, r2.sub_rope(3..40).chunk_size());
+        assert!(r2.chunks().all(|chunk| chunk.len() <= 16));
+
+        let mut r3 = Rope::new();
+        r3.append(r2);
+        assert_eq!(Rope::new().chunk_size(), r3.chunk_size());
     }
 
     #[test]
     #[should_panic]
-    fn remove_08() {
-        let mut r = Rope::from_str(TEXT);
-        r.remove(104..104); // Removing past the end
+    fn with_chunk_size_03() {
+        Rope::with_chunk_size(7);
     }
 
     #[test]
-    #[should_panic]
-    fn remove_09() {
-        let mut r = Rope::from_str(TEXT);
-        r.remove(104..105); // Removing past the end
+    fn validate_01() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        r.remove(20..70);
+        r.insert(5, "\r\n");
+        assert_eq!(Ok(()), r.validate());
     }
 
     #[test]
-    fn split_off_01() {
-        let mut r = Rope::from_str(TEXT);
-
-        let r2 = r.split_off(50);
+    fn validate_02() {
+
+        // Hand-build a broken tree: a root with a single child, whose
+        // children have wrong info, an empty leaf, a split CRLF pair, and
+        // uneven depths.
+        let leaf = |text: &str| Arc::new(Node::Leaf(NodeText::from_str(text)));
+        let mut inner = NodeChildren::new();
+        inner.push((TextInfo::from_str("Hi\r"), leaf("Hi\r")));
+        inner.push((TextInfo::from_str("there"), leaf("\nthe")));
+        inner.push((TextInfo::new(), leaf("")));
+        let inner = Arc::new(Node::Internal(inner));
+        let mut outer = NodeChildren::new();
+        outer.push((inner.text_info(), inner));
+        outer.push((TextInfo::from_str("!"), leaf("!")));
+        let r = Rope {
+            root: Arc::new(Node::Internal(outer)),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        };
+
+        let report = r.validate().unwrap_err();
         assert_eq!(
-            r,
-            "Hello there!  How're you doing?  It's \
-             a fine day, "
-        );
-        assert_eq!(
-            r2,
-            "isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
+            vec![
+                InvariantViolation::InfoMismatch {
+                    path: vec![0, 1],
+                    recorded: TextInfo::from_str("there").into(),
+                    actual: TextInfo::from_str("\nthe").into(),
+                },
+                InvariantViolation::EmptyLeaf { path: vec![0, 2] },
+                InvariantViolation::UnbalancedDepth {
+                    path: vec![1],
+                    expected: 3,
+                    actual: 2,
+                },
+                InvariantViolation::SplitSeam { byte_idx: 3 },
+            ],
+            report.violations
         );
-
-        r.assert_integrity();
-        r2.assert_integrity();
-        r.assert_invariants();
-        r2.assert_invariants();
     }
 
     #[test]
-    fn split_off_02() {
-        let mut r = Rope::from_str(TEXT);
-
-        let r2 = r.split_off(1);
-        assert_eq!(r, "H");
+    fn validate_03() {
+        let leaf = |text: &str| Arc::new(Node::Leaf(NodeText::from_str(text)));
+        let mut children = NodeChildren::new();
+        children.push((TextInfo::from_str("Hello world!"), leaf("Hello world!")));
+        children.push((TextInfo::from_str("123456789"), leaf("123456789")));
+        let r = Rope {
+            root: Arc::new(Node::Internal(children)),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        };
+
+        // The second leaf is only over by a char at its end.
+        let report = r.validate().unwrap_err();
         assert_eq!(
-            r2,
-            "ello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
+            vec![InvariantViolation::OversizedLeaf {
+                path: vec![0],
+                max: MAX_BYTES,
+                actual: 12,
+            }],
+            report.violations
         );
-
-        r.assert_integrity();
-        r2.assert_integrity();
-        r.assert_invariants();
-        r2.assert_invariants();
     }
 
     #[test]
-    fn split_off_03() {
+    fn validate_04() {
+        // Ways of ending up with chunks from a rope with a larger chunk
+        // size, which get re-split.
         let mut r = Rope::from_str(TEXT);
+        r.make_contiguous();
+        assert_eq!(Ok(()), r.validate());
+        r.insert(3, "abc");
+        assert_eq!(Ok(()), r.validate());
+
+        let mut big = Rope::with_chunk_size(64);
+        big.insert(0, TEXT);
+        assert!(big.chunks().any(|chunk| chunk.len() > MAX_BYTES + 8));
+        let mut r = Rope::from_str("Hi!");
+        r.append(big.clone());
+        assert_eq!(Ok(()), r.validate());
+        assert_eq!(format!("Hi!{}", TEXT), r);
+
+        let r = big.slice(1..).to_rope();
+        assert_eq!(Ok(()), r.validate());
+        assert_eq!(&TEXT[1..], r);
+    }
 
-        let r2 = r.split_off(102);
+    #[test]
+    fn debug_01() {
+        let r = Rope::from_str("Hello\nworld!");
+        assert_eq!(r#"["Hello\nwo", "rld!"]"#, format!("{:?}", r));
+    }
+
+    #[test]
+    fn debug_02() {
+        let r = Rope::from_str("Hello\nworld!\tHi");
         assert_eq!(
-            r,
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん"
+            "Rope (bytes: 15, chars: 15, lines: 2, chunk size: 8)\n    \
+             Internal (2/5 children, 40%) bytes 0..15, chars 0..15, lines 0..1\n        \
+             Leaf (8/8 bytes, 100%) bytes 0..8, chars 0..8, lines 0..1: \"Hello\\nwo\"\n        \
+             Leaf (7/8 bytes, 87%) bytes 8..15, chars 8..15, lines 1..1: \"rld!\\tHi\"\n",
+            format!("{:#?}", r)
         );
-        assert_eq!(r2, "！");
+    }
 
-        r.assert_integrity();
-        r2.assert_integrity();
-        r.assert_invariants();
-        r2.assert_invariants();
+    #[test]
+    fn mem_usage_01() {
+        let r = Rope::from_str(TEXT);
+        let usage = r.mem_usage();
+
+        assert_eq!(r.len_bytes(), usage.text);
+        assert_eq!(r.capacity() - r.len_bytes(), usage.slack);
+        assert!(usage.node_overhead > 0);
+        assert_eq!(usage.total(), usage.unique);
+        assert_eq!(0, usage.shared);
     }
 
     #[test]
-    fn split_off_04() {
-        let mut r = Rope::from_str(TEXT);
+    fn mem_usage_02() {
+        let r1 = Rope::from_str(TEXT);
+        let mut r2 = r1.clone();
+        r2.insert(r2.len_chars(), "!");
+        let usage = r2.mem_usage();
 
-        let r2 = r.split_off(0);
-        assert_eq!(r, "");
-        assert_eq!(
-            r2,
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
-        );
+        // Only the path to the edited leaf is copied.
+        assert!(usage.unique > 0);
+        assert!(usage.shared > usage.unique);
+        assert_eq!(usage.total(), usage.unique + usage.shared);
+    }
 
-        r.assert_integrity();
-        r2.assert_integrity();
-        r.assert_invariants();
-        r2.assert_invariants();
+    #[test]
+    fn intern_leaves_01() {
+        let interner = LeafInterner::new();
+        let mut r1 = Rope::from_str("abcdefgh12345678abcdefgh");
+        let r2 = Rope::from_str("12345678abcdefgh");
+        r1.intern_leaves(&interner);
+        let mut r2_interned = r2.clone();
+        r2_interned.intern_leaves(&interner);
+
+        assert_eq!(2, interner.len());
+        assert_eq!(r2, r2_interned);
+        let chunk_ptrs = |r: &Rope| r.chunks().map(|c| c.as_ptr()).collect::<Vec<_>>();
+        let p1 = chunk_ptrs(&r1);
+        let p2 = chunk_ptrs(&r2_interned);
+        assert_eq!(p1[0], p1[2]);
+        assert_eq!(p1[0], p2[1]);
+        assert_eq!(p1[1], p2[0]);
+
+        // Editing a shared chunk doesn't affect the others.
+        r1.insert(0, "!");
+        assert_eq!("!abcdefgh12345678abcdefgh", r1);
+        assert_eq!("12345678abcdefgh", r2_interned);
+        r1.assert_integrity();
+        r1.assert_invariants();
     }
 
     #[test]
-    fn split_off_05() {
-        let mut r = Rope::from_str(TEXT);
+    fn intern_leaves_02() {
+        let interner = LeafInterner::new();
+        {
+            let mut builder = RopeBuilder::new();
+            builder.set_interner(&interner);
+            builder.append(&TEXT.repeat(3));
+            let r = builder.finish();
+            assert_eq!(TEXT.repeat(3), r.to_string());
+            r.assert_integrity();
+            r.assert_invariants();
+            assert!(!interner.is_empty());
+        }
 
-        let r2 = r.split_off(103);
+        interner.purge();
+        assert!(interner.is_empty());
+    }
+
+#[test]
+    fn expand_tabs_01() {
+        let text = "\tHello world!\n  \tこんにちは\tx\r\n\t\n".repeat(20);
+        let mut r = Rope::from_str(&text);
+        r.expand_tabs(4);
+        r.assert_integrity();
+        r.assert_invariants();
         assert_eq!(
             r,
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！"
+            "    Hello world!\n    こんにちは  x\r\n    \n".repeat(20).as_str()
         );
-        assert_eq!(r2, "");
 
+        r.collapse_spaces_to_tabs(4);
         r.assert_integrity();
-        r2.assert_integrity();
         r.assert_invariants();
-        r2.assert_invariants();
+        assert_eq!(r, "\tHello world!\n\tこんにちは\tx\r\n\t\n".repeat(20).as_str());
     }
 
     #[test]
-    #[should_panic]
-    fn split_off_06() {
-        let mut r = Rope::from_str(TEXT);
-        r.split_off(104); // One past the end of the rope
-    }
-
-    #[test]
-    fn append_01() {
-        let mut r = Rope::from_str(
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't ",
-        );
-        let r2 = Rope::from_str(
-            "it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！",
-        );
-
-        r.append(r2);
-        assert_eq!(r, TEXT);
-
+    fn expand_tabs_02() {
+        // Chunks without tabs are shared.
+        let text = "Hello world!\n".repeat(100);
+        let mut r = Rope::from_str(&format!("\t{}\t", text));
+        let r2 = r.clone();
+        r.expand_tabs(8);
         r.assert_integrity();
         r.assert_invariants();
+        assert_eq!(r, format!("        {}        ", text).as_str());
+        assert!(r.mem_usage().shared > text.len() / 2);
+
+        r.collapse_spaces_to_tabs(8);
+        assert_eq!(r, r2);
+
+        let mut r4 = Rope::from_str(&text);
+        r4.collapse_spaces_to_tabs(4);
+        assert_eq!(r4, text.as_str());
     }
 
     #[test]
-    fn append_02() {
-        let mut r = Rope::from_str(
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんに",
-        );
-        let r2 = Rope::from_str("ちは、みんなさん！");
+    fn collapse_spaces_to_tabs_01() {
+        let mut r = Rope::from_str("a b  c   d    e     f");
+        r.collapse_spaces_to_tabs(4);
+        assert_eq!(r, "a b  c\t d\t  e \tf");
+    }
 
-        r.append(r2);
+        #[test]
+    fn trim_trailing_whitespace_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(r.trim_trailing_whitespace(), 0);
         assert_eq!(r, TEXT);
 
+        let mut r = Rope::from_str("  \t\r\n\ta b  \u{2028} c\t\t\t\t\t\t\t \r\n  ");
+        assert_eq!(r.trim_trailing_whitespace(), 4);
+        assert_eq!(r, "\r\n\ta b\u{2028} c\r\n");
         r.assert_integrity();
         r.assert_invariants();
     }
 
     #[test]
-    fn append_03() {
-        let mut r = Rope::from_str(
-            "Hello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん",
+    fn trim_trailing_whitespace_02() {
+        let text = "a \nb\t\t\nc\n   \nd  e ";
+        let mut r = Rope::from_str(text);
+        let edits = r.trim_trailing_whitespace_edits();
+        assert_eq!(
+            edits,
+            vec![
+                Edit::remove(1..2),
+                Edit::remove(3..5),
+                Edit::remove(6..9),
+                Edit::remove(11..12),
+            ]
         );
-        let r2 = Rope::from_str("！");
-
-        r.append(r2);
-        assert_eq!(r, TEXT);
 
-        r.assert_integrity();
-        r.assert_invariants();
+        // Applying the edits in order reproduces the trimmed text.
+        let mut r2 = Rope::from_str(text);
+        for edit in &edits {
+            r2.remove(edit.old_range());
+        }
+        assert_eq!(r2, r);
+        assert_eq!(r, "a\nb\nc\n\nd  e");
     }
 
     #[test]
-    fn append_04() {
-        let mut r = Rope::from_str("H");
-        let r2 = Rope::from_str(
-            "ello there!  How're you doing?  It's \
-             a fine day, isn't it?  Aren't you glad \
-             we're alive?  こんにちは、みんなさん！",
+    #[cfg(feature = "bidi")]
+    fn line_direction_01() {
+        let r = Rope::from_str(
+            "\u{2067}abc\u{2069} عربي\n\
+             \u{2068}שלום\u{2069}\r\n\
+             ... x\n\
+             \u{2066}abc",
         );
-
-        r.append(r2);
-        assert_eq!(r, TEXT);
-
-        r.assert_integrity();
-        r.assert_invariants();
+        assert_eq!(r.line_direction(0), Some(Direction::Rtl));
+        assert_eq!(r.line_direction(1), None);
+        assert_eq!(r.line_direction(2), Some(Direction::Ltr));
+        assert_eq!(r.line_direction(3), None);
     }
 
     #[test]
-    fn append_05() {
+    fn map_chars_01() {
         let mut r = Rope::from_str(TEXT);
-        let r2 = Rope::from_str("");
-
-        r.append(r2);
-        assert_eq!(r, TEXT);
+        r.map_chars(.., char::to_uppercase);
+        assert_eq!(r, TEXT.to_uppercase().as_str());
 
+        let mut r = Rope::from_str(TEXT);
+        r.map_chars(13..31, |c| if c == 'o' { vec![] } else { vec![c, c] });
+        assert_eq!(
+            r,
+            "Hello there!   HHww''rree  yyuu  ddiinngg??  It's a fine day, \
+             isn't it?  Aren't you glad we're alive?  こんにちは、みんなさん！"
+        );
         r.assert_integrity();
         r.assert_invariants();
     }
 
     #[test]
-    fn append_06() {
-        let mut r = Rope::from_str("");
-        let r2 = Rope::from_str(TEXT);
+    #[should_panic]
+    fn map_chars_02() {
+        let mut r = Rope::from_str(TEXT);
+        r.map_chars(100..104, char::to_uppercase);
+    }
 
-        r.append(r2);
-        assert_eq!(r, TEXT);
+    #[test]
+    fn to_titlecase_01() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(
+            r.to_titlecase(),
+            "Hello There!  How're You Doing?  It's A Fine Day, \
+             Isn't It?  Aren't You Glad We're Alive?  こんにちは、みんなさん！"
+        );
 
-        r.assert_integrity();
-        r.assert_invariants();
+        let r = Rope::from_str("ÉCOLE 'quoted' o'NEIL 3rd\nnew line");
+        assert_eq!(r.to_titlecase(), "École 'Quoted' O'neil 3rd\nNew Line");
     }
 
     #[test]
-    fn shrink_to_fit_01() {
-        let mut r = Rope::new();
-        for _ in 0..10 {
-            let len = r.len_chars();
-            r.insert(len / 2, "こ");
-            r.insert(len / 2, "ん");
-            r.insert(len / 2, "い");
-            r.insert(len / 2, "ち");
-            r.insert(len / 2, "は");
-            r.insert(len / 2, "、");
-            r.insert(len / 2, "み");
-            r.insert(len / 2, "ん");
-            r.insert(len / 2, "な");
-            r.insert(len / 2, "さ");
-            r.insert(len / 2, "ん");
-            r.insert(len / 2, "！");
-            r.insert(len / 2, "zopter");
-        }
-
-        let r2 = r.clone();
-        r.shrink_to_fit();
+    fn reversed_01() {
+        let r = Rope::from_str(TEXT);
+        let rev = r.reversed();
+        assert_eq!(rev, TEXT.chars().rev().collect::<String>().as_str());
+        assert_eq!(rev.chunk_size(), r.chunk_size());
+        assert_eq!(rev.reversed(), r);
+        rev.assert_integrity();
+        rev.assert_invariants();
+
+        assert_eq!(Rope::from_str("a\r\nb").reversed().len_lines(), 3);
+        assert_eq!(Rope::new().reversed(), "");
+    }
 
-        assert_eq!(r, r2);
+    #[test]
+    #[cfg(feature = "graphemes")]
+    fn reversed_graphemes_01() {
+        let text = "Hello\r\nwo\u{308}rld 🇯🇵🇺🇸!\r\n";
+        let r = Rope::from_str(text);
+        let rev = r.reversed_graphemes();
+        assert_eq!(rev, "\r\n!🇺🇸🇯🇵 dlro\u{308}w\r\nolleH");
+        assert_eq!(rev.len_lines(), r.len_lines());
+        assert_eq!(rev.reversed_graphemes(), r);
+        rev.assert_integrity();
+        rev.assert_invariants();
+    }
 
+    #[test]
+    fn swap_ranges_01() {
+        let mut r = Rope::from_str(TEXT);
+        r.swap_ranges(76..103, 0..12);
+        assert_eq!(
+            r,
+            " we're alive?  こんにちは、みんなさん！  How're you doing?  \
+             It's a fine day, isn't it?  Aren't you gladHello there!"
+        );
         r.assert_integrity();
         r.assert_invariants();
+
+        r.swap_ranges(0..27, 91..103);
+        assert_eq!(r, TEXT);
     }
 
     #[test]
-    fn byte_to_char_01() {
-        let r = Rope::from_str(TEXT);
-
-        assert_eq!(0, r.byte_to_char(0));
-        assert_eq!(1, r.byte_to_char(1));
-        assert_eq!(2, r.byte_to_char(2));
-
-        assert_eq!(91, r.byte_to_char(91));
-        assert_eq!(91, r.byte_to_char(92));
-        assert_eq!(91, r.byte_to_char(93));
+    fn swap_ranges_02() {
+        let mut r = Rope::from_str("abcdef");
+        r.swap_ranges(2..2, 4..6);
+        assert_eq!(r, "abefcd");
+        r.swap_ranges(6..6, 0..0);
+        assert_eq!(r, "abefcd");
+        r.swap_ranges(0..1, 1..2);
+        assert_eq!(r, "baefcd");
+    }
 
-        assert_eq!(92, r.byte_to_char(94));
-        assert_eq!(92, r.byte_to_char(95));
-        assert_eq!(92, r.byte_to_char(96));
+    #[test]
+    #[should_panic]
+    fn swap_ranges_03() {
+        let mut r = Rope::from_str("abcdef");
+        r.swap_ranges(0..3, 2..4);
+    }
 
-        assert_eq!(102, r.byte_to_char(124));
-        assert_eq!(102, r.byte_to_char(125));
-        assert_eq!(102, r.byte_to_char(126));
-        assert_eq!(103, r.byte_to_char(127));
+    #[test]
+    #[should_panic(expected = "overlapping")]
+    fn swap_ranges_04() {
+        let mut r = Rope::from_str("abcdef");
+        r.swap_ranges(0..3, 1..1);
     }
 
     #[test]
-    fn char_to_byte_01() {
-        let r = Rope::from_str(TEXT);
+    #[should_panic(expected = "past end")]
+    fn swap_ranges_05() {
+        let mut r = Rope::from_str("abcdef");
+        r.swap_ranges(0..100, 0..0);
+    }
 
-        assert_eq!(0, r.char_to_byte(0));
-        assert_eq!(1, r.char_to_byte(1));
-        assert_eq!(2, r.char_to_byte(2));
+    #[test]
+    fn swap_ranges_06() {
+        // Empty ranges at either end of the other range.
+        let mut r = Rope::from_str("abcdef");
+        r.swap_ranges(0..3, 0..0);
+        r.swap_ranges(3..3, 0..3);
+        r.swap_ranges(4..4, 4..4);
+        assert_eq!(r, "abcdef");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
 
-        assert_eq!(91, r.char_to_byte(91));
-        assert_eq!(94, r.char_to_byte(92));
-        assert_eq!(97, r.char_to_byte(93));
-        assert_eq!(100, r.char_to_byte(94));
+    #[test]
+    fn rotate_01() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        let start = r.line_to_char(1);
+        let mid = r.line_to_char(2);
+        let end = r.line_to_char(3);
+        r.rotate(start..end, mid);
+        assert_eq!(
+            r,
+            "Hello there!  How're you doing?\nAren't you glad \
+             we're alive?\nIt's a fine day, isn't it?\nこんにちは、みんなさん！"
+        );
+        r.assert_integrity();
+        r.assert_invariants();
 
-        assert_eq!(124, r.char_to_byte(102));
-        assert_eq!(127, r.char_to_byte(103));
+        r.rotate(0..r.len_chars(), 0);
+        r.rotate(0..r.len_chars(), r.len_chars());
+        r.rotate(start..end, start + end - mid);
+        assert_eq!(r, TEXT_LINES);
     }
 
     #[test]
-    fn char_to_line_01() {
-        let r = Rope::from_str(TEXT_LINES);
-
-        assert_eq!(0, r.char_to_line(0));
-        assert_eq!(0, r.char_to_line(1));
+    #[should_panic]
+    fn rotate_02() {
+        let mut r = Rope::from_str("abcdef");
+        r.rotate(1..3, 4);
+    }
 
-        assert_eq!(0, r.char_to_line(31));
-        assert_eq!(1, r.char_to_line(32));
-        assert_eq!(1, r.char_to_line(33));
+    #[test]
+    #[should_panic(expected = "past end")]
+    fn rotate_03() {
+        let mut r = Rope::from_str("abcdef");
+        r.rotate(0..100, 0);
+    }
 
-        assert_eq!(1, r.char_to_line(58));
-        assert_eq!(2, r.char_to_line(59));
-        assert_eq!(2, r.char_to_line(60));
+    #[test]
+    fn rotate_04() {
+        let mut r = Rope::from_str("abcdef");
+        r.rotate(2..2, 2);
+        r.rotate(1..5, 1);
+        r.rotate(1..5, 5);
+        r.rotate(0..6, 6);
+        assert_eq!(r, "abcdef");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
 
-        assert_eq!(2, r.char_to_line(87));
-        assert_eq!(3, r.char_to_line(88));
-        assert_eq!(3, r.char_to_line(89));
+    #[test]
+    fn sort_lines_01() {
+        let mut r = Rope::from_str(TEXT_LINES);
+        r.sort_lines(0..4, |a, b| a.chars().cmp(b.chars()));
+        assert_eq!(
+            r,
+            "Aren't you glad we're alive?\nHello there!  How're you doing?\n\
+             It's a fine day, isn't it?\nこんにちは、みんなさん！"
+        );
+        r.assert_integrity();
+        r.assert_invariants();
 
-        assert_eq!(4, r.char_to_line(100));
+        r.sort_lines(1..3, |a, b| b.len_chars().cmp(&a.len_chars()));
+        assert_eq!(
+            r,
+            "Aren't you glad we're alive?\nHello there!  How're you doing?\n\
+             It's a fine day, isn't it?\nこんにちは、みんなさん！"
+        );
+        r.sort_lines(0..4, |a, b| a.len_chars().cmp(&b.len_chars()));
+        assert_eq!(
+            r,
+            "こんにちは、みんなさん！\nIt's a fine day, isn't it?\n\
+             Aren't you glad we're alive?\nHello there!  How're you doing?"
+        );
+        r.assert_integrity();
+        r.assert_invariants();
     }
 
     #[test]
-    fn char_to_line_02() {
-        let r = Rope::from_str("");
-        assert_eq!(1, r.char_to_line(0));
+    fn sort_lines_02() {
+        let mut r = Rope::from_str("c\r\nb\na\u{2028}\r\n");
+        r.sort_lines(0..4, |a, b| a.chars().cmp(b.chars()));
+        assert_eq!(r, "\r\na\nb\u{2028}c\r\n");
+
+        let mut r = Rope::from_str("b\na\n");
+        r.sort_lines(1..1, |a, b| a.chars().cmp(b.chars()));
+        assert_eq!(r, "b\na\n");
+        r.sort_lines(0..2, |a, b| a.chars().cmp(b.chars()));
+        assert_eq!(r, "a\nb\n");
     }
 
     #[test]
     #[should_panic]
-    fn char_to_line_03() {
-        let r = Rope::from_str(TEXT_LINES);
-        r.char_to_line(101);
+    fn sort_lines_03() {
+        let mut r = Rope::from_str("b\na\n");
+        r.sort_lines(0..4, |a, b| a.chars().cmp(b.chars()));
     }
 
     #[test]
-    fn line_to_char_01() {
-        let r = Rope::from_str(TEXT_LINES);
-
-        assert_eq!(0, r.line_to_char(0));
-        assert_eq!(32, r.line_to_char(1));
-        assert_eq!(59, r.line_to_char(2));
-        assert_eq!(88, r.line_to_char(3));
-        assert_eq!(100, r.line_to_char(4));
+    fn sort_lines_04() {
+        // Many lines, with CRLF pairs and lone CRs landing on chunk
+        // boundaries.
+        let breaks = ["\n", "\r\n", "\r", "\u{2028}"];
+        let mut text = String::new();
+        let mut lines = Vec::new();
+        for i in 0..200 {
+            let line = format!("{}", (i * 7919) % 1000);
+            text.push_str(&line);
+            text.push_str(breaks[i % 4]);
+            lines.push(line);
+        }
+        let mut r = Rope::from_str(&text);
+        r.sort_lines(0..200, |a, b| a.chars().cmp(b.chars()));
+        lines.sort();
+
+        let mut expected = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            expected.push_str(line);
+            expected.push_str(breaks[i % 4]);
+        }
+        assert_eq!(r, expected);
+        r.assert_integrity();
+        r.assert_invariants();
     }
 
     #[test]
-    fn line_to_char_02() {
-        let r = Rope::from_str("");
-        assert_eq!(0, r.line_to_char(0));
-        assert_eq!(0, r.line_to_char(1));
+    fn take_dirty_range_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert_eq!(r.take_dirty_range(), None);
+
+        r.insert(10, "abc");
+        r.insert(50, "de");
+        assert_eq!(r.take_dirty_range(), Some(10..52));
+
+        r.remove(20..30);
+        assert_eq!(r.take_dirty_range(), Some(20..20));
+
+        // Edits before and after the edited range.
+        r.insert(20, "xyz");
+        r.remove(5..8);
+        assert_eq!(r.take_dirty_range(), Some(5..20));
+        r.insert(20, "xyz");
+        r.insert(r.len_chars(), "!");
+        assert_eq!(r.take_dirty_range(), Some(20..r.len_chars()));
+
+        // A removal overlapping the end of the edited range.
+        r.insert(30, "12345");
+        r.remove(33..40);
+        assert_eq!(r.take_dirty_range(), Some(30..33));
+
+        // No-op edits don't count.
+        r.insert(3, "");
+        r.remove(3..3);
+        assert_eq!(r.take_dirty_range(), None);
     }
 
     #[test]
-    #[should_panic]
-    fn line_to_char_03() {
-        let r = Rope::from_str(TEXT_LINES);
-        r.line_to_char(5);
+    fn take_dirty_range_02() {
+        let mut r = Rope::from_str(TEXT);
+        let mut r2 = r.split_off(50);
+        assert_eq!(r.take_dirty_range(), Some(50..50));
+        assert_eq!(r2.take_dirty_range(), None);
+
+        r.append(Rope::from_str("abc"));
+        assert_eq!(r.take_dirty_range(), Some(50..53));
+
+        r.swap_ranges(0..5, 10..12);
+        assert_eq!(r.take_dirty_range(), Some(0..12));
+
+        r.map_chars(2..4, |c| vec![c, c]);
+        assert_eq!(r.take_dirty_range(), Some(2..6));
+
+        let mut r = Rope::from_str("a\tb\nc  \nd\te\n");
+        r.expand_tabs(4);
+        assert_eq!(r.take_dirty_range(), Some(1..14));
+        r.trim_trailing_whitespace();
+        assert_eq!(r.take_dirty_range(), Some(7..7));
+        r.shrink_to_fit();
+        assert_eq!(r.take_dirty_range(), None);
+
+        let mut r = Rope::from_str(&TEXT.repeat(10));
+        r.insert(30, TEXT);
+        assert_eq!(r.take_dirty_range(), Some(30..133));
+        assert_eq!(r.sub_rope(10..20).take_dirty_range(), None);
     }
 
     #[test]
-    fn char_01() {
+    fn to_shared_str_01() {
+        let r = Rope::from_str("Hello!");
+        assert!(r.root.is_leaf());
+        assert_eq!(&*r.to_shared_str(), "Hello!");
+
         let r = Rope::from_str(TEXT);
+        assert!(!r.root.is_leaf());
+        assert_eq!(&*r.to_shared_str(), TEXT);
 
-        assert_eq!(r.char(0), 'H');
-        assert_eq!(r.char(10), 'e');
-        assert_eq!(r.char(18), 'r');
-        assert_eq!(r.char(102), '！');
+        assert_eq!(&*Rope::new().to_shared_str(), "");
     }
 
     #[test]
-    #[should_panic]
-    fn char_02() {
-        let r = Rope::from_str(TEXT);
-        r.char(103);
+    fn try_from_bytes_01() {
+        use std::convert::TryFrom;
+
+        let r = Rope::try_from(TEXT.as_bytes()).unwrap();
+        assert_eq!(r, TEXT);
+
+        let e = Rope::try_from(&b"Hello \xE3\x81\x93\xFF!"[..]).unwrap_err();
+        assert_eq!(e.valid_up_to(), 9);
+        assert_eq!(e.error_len(), Some(1));
+        assert_eq!(e.invalid_bytes(), b"\xFF");
+
+        let e = Rope::try_from(&b"Hello \xE3\x81"[..]).unwrap_err();
+        assert_eq!(e.valid_up_to(), 6);
+        assert_eq!(e.error_len(), None);
+        assert_eq!(e.invalid_bytes(), b"\xE3\x81");
     }
 
     #[test]
-    #[should_panic]
-    fn char_03() {
-        let r = Rope::from_str("");
-        r.char(0);
+    fn try_from_reader_01() {
+        let r = Rope::try_from_reader(TEXT.as_bytes()).unwrap().unwrap();
+        assert_eq!(r, TEXT);
+
+        // Invalid byte well past the first buffer's worth of data.
+        let mut bytes = TEXT.as_bytes().to_vec();
+        bytes.insert(100, 0x80);
+        let e = Rope::try_from_reader(&bytes[..]).unwrap().unwrap_err();
+        assert_eq!(e.valid_up_to(), 100);
+        assert_eq!(e.error_len(), Some(1));
+        assert_eq!(e.invalid_bytes(), b"\x80");
+
+        // Cut off in the middle of a codepoint.
+        let bytes = "Hello こんにちは".as_bytes();
+        let e = Rope::try_from_reader(&bytes[..bytes.len() - 1]).unwrap().unwrap_err();
+        assert_eq!(e.valid_up_to(), bytes.len() - 3);
+        assert_eq!(e.error_len(), None);
+        assert_eq!(e.invalid_bytes(), &bytes[(bytes.len() - 3)..(bytes.len() - 1)]);
+
+        assert!(Rope::from_reader(&bytes[..bytes.len() - 1]).is_err());
     }
 
     #[test]
-    fn line_01() {
-        let r = Rope::from_str(TEXT_LINES);
+    fn try_from_reader_02() {
+        // A reader that is interrupted before every read.
+        struct Interrupting<'a>(&'a [u8], bool);
+        impl<'a> io::Read for Interrupting<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.1 = !self.1;
+                if self.1 {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"));
+                }
+                self.0.read(buf)
+            }
+        }
 
-        assert_eq!(r.line(0), "Hello there!  How're you doing?\n");
-        assert_eq!(r.line(1), "It's a fine day, isn't it?\n");
-        assert_eq!(r.line(2), "Aren't you glad we're alive?\n");
-        assert_eq!(r.line(3), "こんにちは、みんなさん！");
+        let r = Rope::try_from_reader(Interrupting(TEXT.as_bytes(), false)).unwrap().unwrap();
+        assert_eq!(r, TEXT);
+        let r = Rope::from_reader(Interrupting(TEXT.as_bytes(), false)).unwrap();
+        assert_eq!(r, TEXT);
     }
 
     #[test]
-    fn line_02() {
-        let r = Rope::from_str("Hello there!  How're you doing?\n");
+    fn from_reader_01() {
+        // Invalid sequences at and around buffer boundaries.
+        for offset in 60..70 {
+            let mut bytes = TEXT.as_bytes()[..offset].to_vec();
+            bytes.extend_from_slice(b"\xE3\x81");
+            bytes.extend_from_slice(TEXT.as_bytes());
+
+            let e = Rope::from_reader(&bytes[..]).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+            assert_eq!(inner.valid_up_to(), offset);
+            assert_eq!(inner.error_len(), Some(2));
+            assert_eq!(inner.invalid_bytes(), b"\xE3\x81");
+        }
 
-        assert_eq!(r.line(0), "Hello there!  How're you doing?\n");
-        assert_eq!(r.line(1), "");
+        let bytes = b"Hello \xE3\x81world!";
+        let e = Rope::from_reader(&bytes[..]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+        assert_eq!(inner.valid_up_to(), 6);
+        assert_eq!(
+            e.to_string(),
+            "invalid utf-8 sequence of 2 bytes from index 6: b\"\\xE3\\x81\""
+        );
     }
 
     #[test]
-    fn line_03() {
-        let r = Rope::from_str("");
+    fn from_reader_with_policy_01() {
+        // A reader that returns a few bytes at a time, splitting code
+        // points across reads.
+        struct Trickle<'a>(&'a [u8]);
+        impl<'a> io::Read for Trickle<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
 
-        assert_eq!(r.line(0), "");
+        for &policy in &[Utf8Policy::Wait, Utf8Policy::Replace] {
+            let r = Rope::from_reader_with_policy(Trickle(TEXT.as_bytes()), policy).unwrap();
+            assert_eq!(r, TEXT);
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+        let trickle = Trickle(TEXT.as_bytes());
+        assert!(Rope::from_reader_with_policy(trickle, Utf8Policy::Error).is_err());
+        let r = Rope::from_reader_with_policy(TEXT.as_bytes(), Utf8Policy::Error).unwrap();
+        assert_eq!(r, TEXT);
+
+        let bytes = b"Hello \xE3\x81\x93\xFF\xE3\x81";
+        let e = Rope::from_reader_with_policy(Trickle(bytes), Utf8Policy::Wait).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+        assert_eq!(inner.valid_up_to(), 9);
+        assert_eq!(inner.error_len(), Some(1));
+        assert_eq!(inner.invalid_bytes(), b"\xFF");
+
+        let r = Rope::from_reader_with_policy(Trickle(bytes), Utf8Policy::Replace).unwrap();
+        assert_eq!(r, "Hello こ\u{FFFD}\u{FFFD}");
+
+        let (r, replacements) = Rope::from_reader_lossy(Trickle(bytes)).unwrap();
+        assert_eq!(r, "Hello こ\u{FFFD}\u{FFFD}");
+        let spans: Vec<_> = replacements
+            .iter()
+            .map(|r| (r.byte_offset, r.byte_len, r.char_idx))
+            .collect();
+        assert_eq!(spans, [(9, 1, 7), (10, 2, 8)]);
     }
 
     #[test]
-    #[should_panic]
-    fn line_04() {
-        let r = Rope::from_str(TEXT_LINES);
-        r.line(4);
+    fn insert_checked_01() {
+        let mut r = Rope::from_str("a\r\nb\r\n");
+        assert_eq!(
+            r.insert_checked(2, "x", BoundaryPolicy::Reject),
+            Err(BoundaryError {
+                char_idx: 2,
+                cluster: 1..3,
+            })
+        );
+        assert_eq!(r.insert_checked(5, "y", BoundaryPolicy::SnapForward), Ok(6));
+        assert_eq!(r.insert_checked(2, "x", BoundaryPolicy::SnapBackward), Ok(1));
+        assert_eq!(r.insert_checked(0, "z", BoundaryPolicy::Reject), Ok(0));
+        assert_eq!(r, "zax\r\nb\r\ny");
     }
 
     #[test]
-    fn slice_01() {
-        let r = Rope::from_str(TEXT);
-
-        let s = r.slice(0..r.len_chars());
-
-        assert_eq!(TEXT, s);
+    fn insert_checked_02() {
+        // Every CRLF in a multi-chunk rope.
+        let text = "Hello\r\nthere\r\n".repeat(20);
+        let mut r = Rope::from_str(&text);
+        let mut idx = 0;
+        while let Some(i) = r.slice(idx..).chars().position(|c| c == '\n') {
+            let lf = idx + i;
+            let result = r.insert_checked(lf, "!", BoundaryPolicy::SnapForward);
+            assert_eq!(result, Ok(lf + 1));
+            idx = lf + 2;
+        }
+        assert_eq!(r, text.replace("\r\n", "\r\n!").as_str());
+        r.assert_integrity();
+        r.assert_invariants();
     }
 
     #[test]
-    fn slice_02() {
-        let r = Rope::from_str(TEXT);
+    fn remove_checked_01() {
+        let mut r = Rope::from_str("a\r\nb\r\nc");
+        assert_eq!(
+            r.remove_checked(0..5, BoundaryPolicy::Reject),
+            Err(BoundaryError {
+                char_idx: 5,
+                cluster: 4..6,
+            })
+        );
+        assert_eq!(r, "a\r\nb\r\nc");
 
-        let s = r.slice(5..21);
+        assert_eq!(r.remove_checked(2..5, BoundaryPolicy::SnapBackward), Ok(1..4));
+        assert_eq!(r, "a\r\nc");
+        assert_eq!(r.remove_checked(2..2, BoundaryPolicy::SnapForward), Ok(3..3));
+        assert_eq!(r.remove_checked(2.., BoundaryPolicy::SnapForward), Ok(3..4));
+        assert_eq!(r, "a\r\n");
+    }
 
-        assert_eq!(&TEXT[5..21], s);
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn insert_checked_03() {
+        let mut r = Rope::from_str("cafe\u{301}!");
+        assert!(r.insert_checked(4, "x", BoundaryPolicy::Reject).is_err());
+        assert_eq!(r.insert_checked(4, "x", BoundaryPolicy::SnapForward), Ok(5));
+        assert_eq!(r, "cafe\u{301}x!");
     }
 
     #[test]
-    fn slice_03() {
+    fn conversions_with_hint_01() {
         let r = Rope::from_str(TEXT);
-
-        let s = r.slice(31..97);
-
-        assert_eq!(&TEXT[31..109], s);
+        let mut hint = PosHint::new();
+
+        // Forwards, backwards, and jumping around.
+        let order = (0..=r.len_chars())
+            .chain((0..=r.len_chars()).rev())
+            .chain((0..=r.len_chars()).map(|i| (i * 37) % (r.len_chars() + 1)));
+        for i in order {
+            assert_eq!(r.char_to_byte_with_hint(i, &mut hint), r.char_to_byte(i));
+            assert_eq!(r.char_to_line_with_hint(i, &mut hint), r.char_to_line(i));
+        }
+        for i in 0..=r.len_bytes() {
+            assert_eq!(r.byte_to_char_with_hint(i, &mut hint), r.byte_to_char(i));
+        }
     }
 
     #[test]
-    fn slice_04() {
-        let r = Rope::from_str(TEXT);
+    fn conversions_with_hint_02() {
+        let mut r = Rope::from_str(TEXT);
+        let mut hint = PosHint::new();
+        assert_eq!(r.char_to_byte_with_hint(50, &mut hint), r.char_to_byte(50));
 
-        let s = r.slice(53..53);
+        // Stale hints are ignored.
+        r.insert(0, "こんにちは");
+        assert_eq!(r.char_to_byte_with_hint(50, &mut hint), r.char_to_byte(50));
+        r.remove(10..40);
+        assert_eq!(r.char_to_byte_with_hint(50, &mut hint), r.char_to_byte(50));
 
-        assert_eq!("", s);
+        // As are hints from other ropes.
+        let r2 = Rope::from_str(TEXT);
+        assert_eq!(r2.char_to_byte_with_hint(50, &mut hint), r2.char_to_byte(50));
+        assert_eq!(r.char_to_byte_with_hint(50, &mut hint), r.char_to_byte(50));
+
+        let r3 = Rope::new();
+        assert_eq!(r3.char_to_byte_with_hint(0, &mut hint), 0);
+        assert_eq!(r3.char_to_line_with_hint(0, &mut hint), 1);
     }
 
     #[test]
-    #[should_panic]
-    fn slice_05() {
+    fn chars_to_bytes_01() {
         let r = Rope::from_str(TEXT);
-        r.slice(53..52);
+        let char_idxs: Vec<usize> = (0..=r.len_chars()).collect();
+        let byte_idxs: Vec<usize> = char_idxs.iter().map(|&i| r.char_to_byte(i)).collect();
+        assert_eq!(r.chars_to_bytes(&char_idxs), byte_idxs);
+
+        let sparse = [0, 0, 3, 50, 51, 102, 103];
+        let expected: Vec<usize> = sparse.iter().map(|&i| r.char_to_byte(i)).collect();
+        assert_eq!(r.chars_to_bytes(&sparse), expected);
+        assert_eq!(r.chars_to_bytes(&[]), Vec::<usize>::new());
     }
 
     #[test]
-    #[should_panic]
-    fn slice_06() {
+    fn bytes_to_chars_01() {
         let r = Rope::from_str(TEXT);
-        r.slice(102..104);
+        let byte_idxs: Vec<usize> = (0..=r.len_bytes()).collect();
+        let char_idxs: Vec<usize> = byte_idxs.iter().map(|&i| r.byte_to_char(i)).collect();
+        assert_eq!(r.bytes_to_chars(&byte_idxs), char_idxs);
     }
 
     #[test]
-    fn eq_rope_01() {
-        let r = Rope::from_str("");
-
-        assert_eq!(r, r);
+    #[should_panic]
+    fn chars_to_bytes_02() {
+        let r = Rope::from_str(TEXT);
+        r.chars_to_bytes(&[5, 3]);
     }
 
     #[test]
-    fn eq_rope_02() {
+    fn first_last_char_01() {
         let r = Rope::from_str(TEXT);
+        assert_eq!(r.first_char(), Some('H'));
+        assert_eq!(r.last_char(), Some('！'));
 
-        assert_eq!(r, r);
+        let s = r.slice(13..50);
+        assert_eq!(s.first_char(), Some(' '));
+        assert_eq!(s.last_char(), Some(r.char(49)));
+
+        assert_eq!(Rope::new().first_char(), None);
+        assert_eq!(r.slice(20..20).first_char(), None);
     }
 
     #[test]
-    fn eq_rope_03() {
-        let r1 = Rope::from_str(TEXT);
-        let mut r2 = r1.clone();
-        r2.remove(26..27);
-        r2.insert(26, "z");
+    fn chunks_alignment_01() {
+        use CHUNK_ALIGNMENT;
 
-        assert_ne!(r1, r2);
+        let mut r = Rope::from_str(TEXT);
+        r.insert(13, "Hi!");
+        r.remove(40..44);
+        assert!(r.chunks().count() > 1);
+        for chunk in r.chunks() {
+            assert_eq!(0, chunk.as_ptr() as usize % CHUNK_ALIGNMENT);
+        }
     }
 
     #[test]
-    fn eq_rope_04() {
-        let r = Rope::from_str("");
+    fn make_contiguous_01() {
+        let mut r = Rope::from_str(TEXT);
+        assert!(r.chunks().count() > 1);
+        let r2 = r.clone();
 
-        assert_eq!(r, "");
-        assert_eq!("", r);
+        assert_eq!(TEXT, r.make_contiguous());
+        assert_eq!(1, r.chunks().count());
+        assert_eq!(r2, r);
+        assert_eq!(r2.char_to_byte(100), r.char_to_byte(100));
+        r.assert_integrity();
+        r.assert_invariants();
+
+        r.insert(20, "Hi!");
+        r.remove(0..10);
+        assert_eq!(format!("{}Hi!{}", &TEXT[10..20], &TEXT[20..]), r);
+        r.assert_integrity();
+        r.assert_invariants();
     }
 
     #[test]
-    fn eq_rope_05() {
-        let r = Rope::from_str(TEXT);
+    fn make_contiguous_02() {
+        let mut r = Rope::new();
+        assert_eq!("", r.make_contiguous());
 
-        assert_eq!(r, TEXT);
-        assert_eq!(TEXT, r);
+        let mut r = Rope::from_str("Hi!");
+        assert_eq!("Hi!", r.make_contiguous());
     }
 
+    #[cfg(feature = "stats")]
     #[test]
-    fn eq_rope_06() {
+    fn edit_stats_01() {
+        use EditStats;
+
         let mut r = Rope::from_str(TEXT);
-        r.remove(26..27);
-        r.insert(26, "z");
+        assert_eq!(EditStats::default(), r.edit_stats());
 
-        assert_ne!(r, TEXT);
-        assert_ne!(TEXT, r);
+        // Inserting into a full leaf splits it.
+        let mut stats = r.edit_stats();
+        for _ in 0..20 {
+            r.insert(10, "a");
+        }
+        assert!(r.edit_stats().splits > stats.splits);
+        assert_eq!(0, r.edit_stats().path_copies);
+
+        // Removing text merges leaves back together.
+        stats = r.edit_stats();
+        r.remove(5..60);
+        let after = r.edit_stats();
+        assert!(after.merges + after.rebalances > stats.merges + stats.rebalances);
+
+        // Edits to shared nodes copy them.
+        let r2 = r.clone();
+        stats = r.edit_stats();
+        r.insert(30, "Hi!");
+        assert!(r.edit_stats().path_copies > stats.path_copies);
+        assert!(r.edit_stats().allocations >= r.edit_stats().path_copies);
+        assert_eq!(stats, r2.edit_stats());
+
+        r.reset_edit_stats();
+        assert_eq!(EditStats::default(), r.edit_stats());
+        assert_eq!(EditStats::default(), r.split_off(10).edit_stats());
     }
 
+    #[cfg(feature = "stats")]
     #[test]
-    fn eq_rope_07() {
-        let r = Rope::from_str(TEXT);
-        let s: String = TEXT.into();
-
-        assert_eq!(r, s);
-        assert_eq!(s, r);
+    fn edit_stats_02() {
+        // Edits made as part of a larger edit count towards the rope
+        // being edited, and recording is cleaned up afterwards.
+        let mut r = Rope::from_str("Hello");
+        r.insert(2, &TEXT.repeat(4));
+        let stats = r.edit_stats();
+        assert!(stats.allocations > 0);
+
+        let mut r2 = Rope::from_str(TEXT);
+        r2.insert(0, "Hi!");
+        assert_eq!(stats, r.edit_stats());
     }
 
     // Iterator tests are in the iter module
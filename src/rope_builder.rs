@@ -4,8 +4,13 @@ use std::sync::Arc;
 use smallvec::SmallVec;
 
 use crlf;
+use interner::LeafInterner;
 use rope::Rope;
-use tree::{Node, NodeChildren, NodeText, MAX_BYTES, MAX_CHILDREN};
+use slice::RopeSlice;
+#[cfg(feature = "stats")]
+use stats::EditStats;
+use tree::{Node, NodeChildren, NodeText, DEFAULT_MAX_SKEW, MAX_BYTES, MAX_CHILDREN,
+           MIN_CHUNK_SIZE};
 
 /// An efficient incremental `Rope` builder.
 ///
@@ -48,11 +53,34 @@ pub struct RopeBuilder {
     stack: SmallVec<[Arc<Node>; 4]>,
     buffer1: String,
     buffer2: String,
+    chunk_size: usize,
+    interner: Option<LeafInterner>,
+    prefix: Option<Rope>,
 }
 
 impl RopeBuilder {
     /// Creates a new RopeBuilder, ready for input.
     pub fn new() -> Self {
+        RopeBuilder::with_chunk_size(MAX_BYTES)
+    }
+
+    /// Creates a new RopeBuilder that builds a `Rope` with the given
+    /// target chunk size, in bytes.
+    ///
+    /// See [`Rope::with_chunk_size()`](struct.Rope.html#method.with_chunk_size)
+    /// for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is less than 8.
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        assert!(
+            chunk_size >= MIN_CHUNK_SIZE,
+            "Chunk size too small: chunk size {}, minimum {}",
+            chunk_size,
+            MIN_CHUNK_SIZE
+        );
+
         RopeBuilder {
             stack: {
                 let mut stack = SmallVec::new();
@@ -61,9 +89,19 @@ impl RopeBuilder {
             },
             buffer1: String::new(),
             buffer2: String::new(),
+            chunk_size,
+            interner: None,
+            prefix: None,
         }
     }
 
+    /// Deduplicates the built `Rope`'s leaf chunks through `interner`.
+    ///
+    /// See [`LeafInterner`](struct.LeafInterner.html) for details.
+    pub fn set_interner(&mut self, interner: &LeafInterner) {
+        self.interner = Some(interner.clone());
+    }
+
     /// Appends `chunk` to the end of the in-progress `Rope`.
     ///
     /// This method is called repeatedly to incrementally build up a
@@ -75,15 +113,59 @@ impl RopeBuilder {
         self.append_internal(chunk, false);
     }
 
+    /// Appends the text of `rope` to the end of the in-progress `Rope`.
+    ///
+    /// Large ropes have their subtrees spliced in rather than copied, so
+    /// this runs in O(log N) time for them.  Small ones are copied, like
+    /// with `append()`.
+    ///
+    /// Spliced-in subtrees are shared with `rope` as-is, and don't go
+    /// through the builder's interner.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, RopeBuilder};
+    /// let body = Rope::from_str("Hello world!\n");
+    ///
+    /// let mut builder = RopeBuilder::new();
+    /// builder.append("<p>");
+    /// builder.append_rope(&body);
+    /// builder.append("</p>");
+    ///
+    /// assert_eq!(builder.finish(), "<p>Hello world!\n</p>");
+    /// ```
+    pub fn append_rope(&mut self, rope: &Rope) {
+        self.append_slice(&rope.slice(..));
+    }
+
+    /// Appends the text of `slice` to the end of the in-progress `Rope`.
+    ///
+    /// Like `append_rope()`, large slices have their subtrees spliced in
+    /// rather than copied.
+    pub fn append_slice(&mut self, slice: &RopeSlice) {
+        if slice.len_bytes() < self.chunk_size * MAX_CHILDREN {
+            for chunk in slice.chunks() {
+                self.append(chunk);
+            }
+            return;
+        }
+
+        // Finish what's been built so far, and splice the slice onto it.
+        let mut built = self.finish_segment();
+        built.append(slice.to_rope_with_chunk_size(self.chunk_size));
+        self.prefix = Some(built);
+    }
+
     /// Finishes the build, and returns the `Rope`.
     ///
     /// Note: this method consumes the builder.  If you want to continue
     /// building other ropes with the same prefix, you can clone the builder
     /// before calling `finish()`.
     pub fn finish(mut self) -> Rope {
-        // Append the last leaf
-        self.append_internal("", true);
-        self.finish_internal()
+        let mut rope = self.finish_segment();
+        rope.clear_dirty_range();
+        rope
     }
 
     /// Builds a rope all at once from a single string slice.
@@ -98,6 +180,25 @@ impl RopeBuilder {
 
     //-----------------------------------------------------------------
 
+    // Builds everything appended so far into a `Rope`, leaving the
+    // builder empty and ready for more input.
+    fn finish_segment(&mut self) -> Rope {
+        let mut fresh = RopeBuilder::with_chunk_size(self.chunk_size);
+        fresh.interner = self.interner.clone();
+        let mut current = std::mem::replace(self, fresh);
+
+        let prefix = current.prefix.take();
+        current.append_internal("", true);
+        let segment = current.finish_internal();
+        match prefix {
+            Some(mut prefix) => {
+                prefix.append(segment);
+                prefix
+            }
+            None => segment,
+        }
+    }
+
     // Internal workings of `append()`.
     fn append_internal(&mut self, chunk: &str, last_chunk: bool) {
         let mut chunk = chunk;
@@ -113,13 +214,14 @@ impl RopeBuilder {
             match leaf_text {
                 NextText::None => break,
                 NextText::UseBuffer => {
-                    let string = NodeText::from_str(&self.buffer1);
-                    self.append_leaf_node(Arc::new(Node::Leaf(string)));
+                    let leaf = self.new_leaf(&self.buffer1);
+                    self.append_leaf_node(leaf);
                     self.buffer1.clear();
                     std::mem::swap(&mut self.buffer1, &mut self.buffer2);
                 }
                 NextText::String(s) => {
-                    self.append_leaf_node(Arc::new(Node::Leaf(NodeText::from_str(s))));
+                    let leaf = self.new_leaf(s);
+                    self.append_leaf_node(leaf);
                 }
             }
         }
@@ -142,10 +244,17 @@ impl RopeBuilder {
 
         // Get root and fix any right-side nodes with too few children.
         let mut root = self.stack.pop().unwrap();
-        Arc::make_mut(&mut root).zip_fix_right();
+        Arc::make_mut(&mut root).zip_fix_right(self.chunk_size);
 
         // Create the rope, make sure it's well-formed, and return it.
-        let mut rope = Rope { root: root };
+        let mut rope = Rope {
+            root: root,
+            chunk_size: self.chunk_size,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        };
         rope.pull_up_singular_nodes();
         return rope;
     }
@@ -157,9 +266,9 @@ impl RopeBuilder {
         last_chunk: bool,
     ) -> (NextText<'a>, &'a str) {
         if self.buffer1.is_empty() {
-            if text.len() > MAX_BYTES {
+            if text.len() > self.chunk_size {
                 // Simplest case: just chop off the end of `text`
-                let split_idx = crlf::find_good_split(MAX_BYTES, text.as_bytes(), true);
+                let split_idx = crlf::find_good_split(self.chunk_size, text.as_bytes(), true);
                 if (split_idx == 0 || split_idx == text.len()) && !last_chunk {
                     self.buffer1.push_str(text);
                     return (NextText::None, "");
@@ -172,27 +281,37 @@ impl RopeBuilder {
             } else {
                 return (NextText::String(text), "");
             }
-        } else if (text.len() + self.buffer1.len()) > MAX_BYTES {
-            if self.buffer1.len() > MAX_BYTES {
-                let split_idx = crlf::find_good_split(MAX_BYTES, self.buffer1.as_bytes(), true);
+        } else if (text.len() + self.buffer1.len()) > self.chunk_size {
+            if self.buffer1.len() > self.chunk_size {
+                let split_idx =
+                    crlf::find_good_split(self.chunk_size, self.buffer1.as_bytes(), true);
                 self.buffer2.push_str(&self.buffer1[split_idx..]);
                 self.buffer1.truncate(split_idx);
                 return (NextText::UseBuffer, text);
             } else {
-                let split_idx =
-                    crlf::find_good_split(MAX_BYTES - self.buffer1.len(), text.as_bytes(), true);
-                if split_idx <= (MAX_BYTES - self.buffer1.len())
+                let split_idx = crlf::find_good_split(
+                    self.chunk_size - self.buffer1.len(),
+                    text.as_bytes(),
+                    true,
+                );
+                if split_idx <= (self.chunk_size - self.buffer1.len())
                     && (split_idx > 0
                         || crlf::seam_is_break(self.buffer1.as_bytes(), text.as_bytes()))
                 {
                     self.buffer1.push_str(&text[..split_idx]);
                     return (NextText::UseBuffer, &text[split_idx..]);
                 } else {
-                    let split_idx = crlf::find_good_split(
+                    let mut split_idx = crlf::find_good_split(
                         self.buffer1.len() - 1,
                         self.buffer1.as_bytes(),
                         true,
                     );
+                    if split_idx == 0 {
+                        // The buffer can't be split any further, so use
+                        // all of it.  Otherwise we'd loop forever when a
+                        // single grapheme doesn't fit after it.
+                        split_idx = self.buffer1.len();
+                    }
                     self.buffer2.push_str(&self.buffer1[split_idx..]);
                     self.buffer1.truncate(split_idx);
                     return (NextText::UseBuffer, text);
@@ -207,6 +326,14 @@ impl RopeBuilder {
         }
     }
 
+    fn new_leaf(&self, text: &str) -> Arc<Node> {
+        let leaf = Arc::new(Node::Leaf(NodeText::from_str(text)));
+        match self.interner {
+            Some(ref interner) => interner.intern(leaf),
+            None => leaf,
+        }
+    }
+
     fn append_leaf_node(&mut self, leaf: Arc<Node>) {
         let last = self.stack.pop().unwrap();
         match *last {
@@ -271,6 +398,34 @@ mod tests {
                         a fine day, isn't it?\r\nAren't you glad \
                         we're alive?\r\nこんにちは、みんなさん！";
 
+    #[test]
+    fn rope_builder_append_rope_01() {
+        let big = Rope::from_str(&TEXT.repeat(20));
+        let small = Rope::from_str("small\r");
+        let big_lf = Rope::from_str(&format!("\n{}", TEXT.repeat(20)));
+
+        let mut b = RopeBuilder::new();
+        b.append("Start\r");
+        b.append_rope(&big);
+        b.append_rope(&small);
+        b.append_slice(&big.slice(5..));
+        b.append("\r");
+        b.append_rope(&big_lf);
+        b.append("\nEnd");
+        let r = b.finish();
+
+        let expected = format!(
+            "Start\r{}small\r{}\r\n{}\nEnd",
+            TEXT.repeat(20),
+            &TEXT.repeat(20)[5..],
+            TEXT.repeat(20)
+        );
+        assert_eq!(r, expected.as_str());
+        assert_eq!(r.dirty, None);
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
     #[test]
     fn rope_builder_01() {
         let mut b = RopeBuilder::new();
@@ -0,0 +1,268 @@
+use std;
+
+use iter::{Bytes, Chars, Chunks, Lines};
+use rope::Rope;
+use slice::{CharIdxRange, RopeSlice};
+use text::RopeText;
+
+/// Either a borrowed `RopeSlice` or an owned `Rope`.
+///
+/// This is the `Rope` analogue of `std::borrow::Cow`: it lets an API
+/// return text that's sometimes a view into an existing `Rope`, and
+/// sometimes freshly built, without building a new `Rope` in the borrowed
+/// case.  The read-only API is available through
+/// [`RopeText`](trait.RopeText.html), or via `as_slice()`.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, RopeCow};
+/// fn without_tabs(rope: &Rope) -> RopeCow<'_> {
+///     if rope.chars().any(|c| c == '\t') {
+///         let mut owned = rope.clone();
+///         owned.expand_tabs(4);
+///         RopeCow::Owned(owned)
+///     } else {
+///         RopeCow::Borrowed(rope.slice(..))
+///     }
+/// }
+///
+/// let rope = Rope::from_str("no tabs");
+/// assert!(without_tabs(&rope).is_borrowed());
+///
+/// let rope = Rope::from_str("\ttab");
+/// assert_eq!(without_tabs(&rope).into_owned(), "    tab");
+/// ```
+#[derive(Clone)]
+pub enum RopeCow<'a> {
+    Borrowed(RopeSlice<'a>),
+    Owned(Rope),
+}
+
+impl<'a> RopeCow<'a> {
+    /// Returns whether this is a borrowed slice.
+    pub fn is_borrowed(&self) -> bool {
+        match *self {
+            RopeCow::Borrowed(_) => true,
+            RopeCow::Owned(_) => false,
+        }
+    }
+
+    /// Returns whether this is an owned `Rope`.
+    pub fn is_owned(&self) -> bool {
+        !self.is_borrowed()
+    }
+
+    /// Returns a slice of the whole text.
+    pub fn as_slice(&self) -> RopeSlice<'_> {
+        match *self {
+            RopeCow::Borrowed(slice) => slice,
+            RopeCow::Owned(ref rope) => rope.slice(..),
+        }
+    }
+
+    /// Returns the text as an owned `Rope`, converting a borrowed slice
+    /// into one if needed.
+    ///
+    /// Converting a slice shares its data with the `Rope` it refers to, so
+    /// this runs in O(log N) time.
+    pub fn into_owned(self) -> Rope {
+        match self {
+            RopeCow::Borrowed(slice) => slice.to_rope(),
+            RopeCow::Owned(rope) => rope,
+        }
+    }
+
+    /// Returns a mutable reference to the owned `Rope`, first converting a
+    /// borrowed slice into one if needed.
+    pub fn to_mut(&mut self) -> &mut Rope {
+        if let RopeCow::Borrowed(slice) = *self {
+            *self = RopeCow::Owned(slice.to_rope());
+        }
+        match *self {
+            RopeCow::Owned(ref mut rope) => rope,
+            RopeCow::Borrowed(_) => unreachable!(),
+        }
+    }
+}
+
+impl<'a> RopeText for RopeCow<'a> {
+    #[inline]
+    fn len_bytes(&self) -> usize {
+        self.as_slice().len_bytes()
+    }
+
+    #[inline]
+    fn len_chars(&self) -> usize {
+        self.as_slice().len_chars()
+    }
+
+    #[inline]
+    fn len_lines(&self) -> usize {
+        self.as_slice().len_lines()
+    }
+
+    #[inline]
+    fn byte_to_char(&self, byte_idx: usize) -> usize {
+        self.as_slice().byte_to_char(byte_idx)
+    }
+
+    #[inline]
+    fn char_to_byte(&self, char_idx: usize) -> usize {
+        self.as_slice().char_to_byte(char_idx)
+    }
+
+    #[inline]
+    fn char_to_line(&self, char_idx: usize) -> usize {
+        self.as_slice().char_to_line(char_idx)
+    }
+
+    #[inline]
+    fn line_to_char(&self, line_idx: usize) -> usize {
+        self.as_slice().line_to_char(line_idx)
+    }
+
+    #[inline]
+    fn char(&self, char_idx: usize) -> char {
+        self.as_slice().char(char_idx)
+    }
+
+    #[inline]
+    fn line(&self, line_idx: usize) -> RopeSlice<'_> {
+        self.as_slice().line(line_idx)
+    }
+
+    #[inline]
+    fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        self.as_slice().slice(range)
+    }
+
+    #[inline]
+    fn bytes(&self) -> Bytes<'_> {
+        self.as_slice().bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Chars<'_> {
+        self.as_slice().chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Lines<'_> {
+        self.as_slice().lines()
+    }
+
+    #[inline]
+    fn chunks(&self) -> Chunks<'_> {
+        self.as_slice().chunks()
+    }
+}
+
+impl<'a> From<RopeSlice<'a>> for RopeCow<'a> {
+    #[inline]
+    fn from(slice: RopeSlice<'a>) -> Self {
+        RopeCow::Borrowed(slice)
+    }
+}
+
+impl<'a> From<&'a Rope> for RopeCow<'a> {
+    #[inline]
+    fn from(rope: &'a Rope) -> Self {
+        RopeCow::Borrowed(rope.slice(..))
+    }
+}
+
+impl<'a> From<Rope> for RopeCow<'a> {
+    #[inline]
+    fn from(rope: Rope) -> Self {
+        RopeCow::Owned(rope)
+    }
+}
+
+impl<'a> std::fmt::Debug for RopeCow<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            RopeCow::Borrowed(ref slice) => f.debug_tuple("Borrowed").field(slice).finish(),
+            RopeCow::Owned(ref rope) => f.debug_tuple("Owned").field(rope).finish(),
+        }
+    }
+}
+
+impl<'a> std::fmt::Display for RopeCow<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.as_slice(), f)
+    }
+}
+
+impl<'a, 'b> std::cmp::PartialEq<RopeCow<'b>> for RopeCow<'a> {
+    #[inline]
+    fn eq(&self, other: &RopeCow<'b>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<'a> std::cmp::Eq for RopeCow<'a> {}
+
+impl<'a, 'b> std::cmp::PartialEq<&'b str> for RopeCow<'a> {
+    #[inline]
+    fn eq(&self, other: &&'b str) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<'a> std::cmp::PartialEq<str> for RopeCow<'a> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_slice() == other
+    }
+}
+
+impl<'a> std::cmp::PartialEq<Rope> for RopeCow<'a> {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        self.as_slice() == other.slice(..)
+    }
+}
+
+impl<'a, 'b> std::cmp::PartialEq<RopeSlice<'b>> for RopeCow<'a> {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'b>) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hello there!\nHow're you doing?\r\nこんにちは！";
+
+    #[test]
+    fn rope_cow_01() {
+        let r = Rope::from_str(TEXT);
+        let borrowed = RopeCow::from(r.slice(6..));
+        let owned = RopeCow::from(r.slice(6..).to_rope());
+
+        assert!(borrowed.is_borrowed());
+        assert!(owned.is_owned());
+        assert_eq!(borrowed, owned);
+        assert_eq!(borrowed, &TEXT[6..]);
+        assert_eq!(borrowed.len_chars(), owned.len_chars());
+        assert_eq!(borrowed.line(1), "How're you doing?\r\n");
+        assert_eq!(format!("{}", owned), &TEXT[6..]);
+        assert_eq!(borrowed.into_owned(), owned.into_owned());
+    }
+
+    #[test]
+    fn rope_cow_02() {
+        let r = Rope::from_str(TEXT);
+        let mut cow = RopeCow::from(&r);
+        cow.to_mut().insert(0, ">> ");
+
+        assert!(cow.is_owned());
+        assert_eq!(cow, format!(">> {}", TEXT).as_str());
+        assert_eq!(r, TEXT);
+    }
+}
@@ -0,0 +1,469 @@
+use std;
+use std::ops::Range;
+
+use memchr::memmem;
+use smallvec::SmallVec;
+use unicode_normalization::char::decompose_canonical;
+
+/// Options controlling how text is matched during search.
+///
+/// The default options match text exactly, char for char.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, SearchOptions};
+/// let rope = Rope::from_str("Straße, STRASSE, strasse");
+/// let options = SearchOptions {
+///     unicode_case_fold: true,
+///     ..SearchOptions::default()
+/// };
+///
+/// assert_eq!(3, rope.matches_with("strasse", options).count());
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Match without regard to case, using simple per-char lowercasing.
+    pub case_insensitive: bool,
+
+    /// Match without regard to case, using full Unicode case folding.
+    /// This lets e.g. "ß" match "ss".  Implies `case_insensitive`.
+    pub unicode_case_fold: bool,
+
+    /// Match canonically equivalent text, e.g. a precomposed "é" matches
+    /// "e" followed by a combining acute accent.
+    ///
+    /// Note: decomposition is done one char at a time, so combining marks
+    /// are not canonically reordered.
+    pub normalize: bool,
+}
+
+/// Folded chars produced from a single source char.
+pub(crate) type Folded = SmallVec<[char; 4]>;
+
+impl SearchOptions {
+    /// Appends the searchable form of `c` to `out`.
+    pub(crate) fn fold(&self, c: char, out: &mut Folded) {
+        if self.normalize {
+            let opts = *self;
+            decompose_canonical(c, |d| opts.fold_case(d, out));
+        } else {
+            self.fold_case(c, out);
+        }
+    }
+
+    fn fold_case(&self, c: char, out: &mut Folded) {
+        if self.unicode_case_fold {
+            // Lowercasing the uppercase form approximates full case
+            // folding, e.g. "ß" -> "SS" -> "ss" and "ς" -> "Σ" -> "σ".
+            for u in c.to_uppercase() {
+                out.extend(u.to_lowercase());
+            }
+        } else if self.case_insensitive {
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+//==============================================================
+
+/// A search algorithm that is fed a text one chunk at a time.
+///
+/// This lets other search algorithms (regex engines, fuzzy matchers,
+/// etc.) search a `Rope` or `RopeSlice` with `search()`, which takes
+/// care of walking the chunks and of converting the matches' byte
+/// ranges to char ranges.
+///
+/// Matches may span any number of chunks, so the searcher has to keep
+/// whatever state it needs between chunks.
+///
+/// # Example
+///
+/// ```
+/// # use std::ops::Range;
+/// # use ropey::{ChunkSearcher, Rope};
+/// // Finds runs of digits.
+/// struct Digits {
+///     run_start: Option<usize>,
+/// }
+///
+/// impl ChunkSearcher for Digits {
+///     fn search_chunk(&mut self, chunk: &str, byte_idx: usize, matches: &mut Vec<Range<usize>>) {
+///         for (i, c) in chunk.char_indices() {
+///             match (c.is_ascii_digit(), self.run_start) {
+///                 (true, None) => self.run_start = Some(byte_idx + i),
+///                 (false, Some(start)) => {
+///                     matches.push(start..(byte_idx + i));
+///                     self.run_start = None;
+///                 }
+///                 _ => {}
+///             }
+///         }
+///     }
+///
+///     fn finish(&mut self, byte_idx: usize, matches: &mut Vec<Range<usize>>) {
+///         if let Some(start) = self.run_start.take() {
+///             matches.push(start..byte_idx);
+///         }
+///     }
+/// }
+///
+/// let rope = Rope::from_str("café 12, thé 3456");
+/// let runs: Vec<_> = rope.search(Digits { run_start: None }).collect();
+///
+/// assert_eq!(vec![5..7, 13..17], runs);
+/// ```
+pub trait ChunkSearcher {
+    /// Searches the next chunk of the text, which starts at byte
+    /// `byte_idx`, and pushes the byte ranges of the matches found to
+    /// `matches`.
+    ///
+    /// Byte ranges are relative to the start of the searched text, and
+    /// must be on char boundaries.  Matches must be pushed in order, but
+    /// can be pushed once the chunks after them are searched, e.g. if
+    /// more text was needed to know where they end.
+    fn search_chunk(&mut self, chunk: &str, byte_idx: usize, matches: &mut Vec<Range<usize>>);
+
+    /// Called once all the chunks have been searched, with the length
+    /// of the text in bytes, to push any remaining matches to `matches`.
+    ///
+    /// Does nothing by default.
+    fn finish(&mut self, byte_idx: usize, matches: &mut Vec<Range<usize>>) {
+        let _ = (byte_idx, matches);
+    }
+}
+
+//==============================================================
+
+/// Exact substring searcher that searches whole chunks at a time.
+///
+/// Each chunk is searched with `memchr`'s `memmem`, which uses SIMD
+/// accelerated prefiltering, so this approaches `memcpy()` speed and is
+/// much faster than feeding `Searcher` one char at a time.  Matches
+/// spanning chunk boundaries are found by keeping the last
+/// `needle.len() - 1` bytes of the text searched so far, and checking
+/// the positions in them against the start of the next chunk.
+///
+/// Since the needle is valid utf8, byte matches always start and end on
+/// char boundaries.
+#[derive(Debug, Clone)]
+pub(crate) struct ExactSearcher {
+    // Boxed, as the finder's prefilter state is several hundred bytes.
+    finder: Box<memmem::Finder<'static>>,
+    overlapping: bool,
+
+    // The last (up to) `needle.len() - 1` bytes of text, and the byte
+    // index they start at.
+    carry: Vec<u8>,
+    carry_start: usize,
+    // The byte index that the next match may start at, to keep matches
+    // from overlapping.
+    next_start: usize,
+}
+
+impl ExactSearcher {
+    pub fn new(needle: &str, overlapping: bool) -> ExactSearcher {
+        ExactSearcher {
+            finder: Box::new(memmem::Finder::new(needle).into_owned()),
+            overlapping,
+            carry: Vec::with_capacity(needle.len()),
+            carry_start: 0,
+            next_start: 0,
+        }
+    }
+
+    fn push_match(&mut self, start: usize, matches: &mut Vec<Range<usize>>) {
+        let end = start + self.finder.needle().len();
+        matches.push(start..end);
+        if !self.overlapping {
+            self.next_start = end;
+        }
+    }
+}
+
+impl ChunkSearcher for ExactSearcher {
+    fn search_chunk(&mut self, chunk: &str, byte_idx: usize, matches: &mut Vec<Range<usize>>) {
+        let n = self.finder.needle().len();
+        if n == 0 {
+            return;
+        }
+
+        // Matches starting in the carried-over bytes, which end in (or
+        // after) this chunk.  The carry is shorter than the needle, so
+        // this is cheap.
+        let carry_len = self.carry.len();
+        let head_len = chunk.len().min(n - 1);
+        self.carry.extend_from_slice(&chunk.as_bytes()[..head_len]);
+        for i in 0..carry_len {
+            let start = self.carry_start + i;
+            if start >= self.next_start && self.carry[i..].starts_with(self.finder.needle()) {
+                self.push_match(start, matches);
+            }
+        }
+
+        // Matches within this chunk.
+        let mut pos = self.next_start.saturating_sub(byte_idx);
+        while pos < chunk.len() {
+            match self.finder.find(&chunk.as_bytes()[pos..]) {
+                Some(i) => {
+                    let start = pos + i;
+                    self.push_match(byte_idx + start, matches);
+                    pos = if self.overlapping {
+                        // Continue at the next char boundary.
+                        start + chunk[start..].chars().next().unwrap().len_utf8()
+                    } else {
+                        start + n
+                    };
+                }
+                None => break,
+            }
+        }
+
+        // Keep the last `n - 1` bytes of text for the next chunk.
+        if head_len < chunk.len() {
+            self.carry.clear();
+            self.carry.extend_from_slice(&chunk.as_bytes()[(chunk.len() - head_len)..]);
+            self.carry_start = byte_idx + chunk.len() - head_len;
+        } else {
+            let drop = self.carry.len() - self.carry.len().min(n - 1);
+            self.carry.drain(..drop);
+            self.carry_start += drop;
+        }
+    }
+}
+
+//==============================================================
+
+/// Streaming Knuth-Morris-Pratt matcher over folded chars.
+///
+/// Source chars are fed in one at a time, and matches are reported as
+/// source char index ranges.  A match is only reported if it starts at
+/// the beginning and ends at the end of a source char's folded form.
+/// Matches don't overlap, unless `set_overlapping()` is used.
+#[derive(Debug, Clone)]
+pub(crate) struct Searcher {
+    options: SearchOptions,
+    needle: Vec<char>,
+    failure: Vec<usize>,
+    overlapping: bool,
+
+    // Current match state
+    matched: usize,
+    // (source char index, is first folded char of its source char) for
+    // the most recent `needle.len()` folded chars.
+    history: Vec<(usize, bool)>,
+    history_idx: usize,
+    fold_buf: Folded,
+}
+
+impl Searcher {
+    pub fn new(pattern: &str, options: SearchOptions) -> Searcher {
+        let mut needle = Vec::new();
+        let mut buf = Folded::new();
+        for c in pattern.chars() {
+            buf.clear();
+            options.fold(c, &mut buf);
+            needle.extend(buf.iter().cloned());
+        }
+
+        // Build KMP failure table.
+        let mut failure = vec![0; needle.len()];
+        let mut k = 0;
+        for i in 1..needle.len() {
+            while k > 0 && needle[k] != needle[i] {
+                k = failure[k - 1];
+            }
+            if needle[k] == needle[i] {
+                k += 1;
+            }
+            failure[i] = k;
+        }
+
+        let history = vec![(0, false); needle.len()];
+        Searcher {
+            options,
+            needle,
+            failure,
+            overlapping: false,
+            matched: 0,
+            history,
+            history_idx: 0,
+            fold_buf: Folded::new(),
+        }
+    }
+
+    /// Sets whether matches may overlap each other.
+    pub fn set_overlapping(&mut self, overlapping: bool) {
+        self.overlapping = overlapping;
+    }
+
+    /// Feeds the next source char, returning the start index of a match
+    /// that ends with it, if any.
+    pub fn push(&mut self, c: char, char_idx: usize) -> Option<usize> {
+        if self.needle.is_empty() {
+            return None;
+        }
+
+        let mut buf = std::mem::replace(&mut self.fold_buf, Folded::new());
+        buf.clear();
+        self.options.fold(c, &mut buf);
+
+        let mut result = None;
+        let last = buf.len().saturating_sub(1);
+        for (i, &fc) in buf.iter().enumerate() {
+            let m = self.needle.len();
+            self.history[self.history_idx % m] = (char_idx, i == 0);
+            self.history_idx += 1;
+
+            while self.matched > 0 && self.needle[self.matched] != fc {
+                self.matched = self.failure[self.matched - 1];
+            }
+            if self.needle[self.matched] == fc {
+                self.matched += 1;
+            }
+
+            if self.matched == m {
+                let (start, is_start) = self.history[self.history_idx % m];
+                if is_start && i == last && result.is_none() {
+                    result = Some(start);
+                    if !self.overlapping {
+                        self.matched = 0;
+                        continue;
+                    }
+                }
+                self.matched = self.failure[m - 1];
+            }
+        }
+
+        self.fold_buf = buf;
+        result
+    }
+}
+
+//==============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_all(haystack: &str, needle: &str, options: SearchOptions) -> Vec<(usize, usize)> {
+        find_all_overlapping(haystack, needle, options, false)
+    }
+
+    fn find_all_overlapping(
+        haystack: &str,
+        needle: &str,
+        options: SearchOptions,
+        overlapping: bool,
+    ) -> Vec<(usize, usize)> {
+        let mut searcher = Searcher::new(needle, options);
+        searcher.set_overlapping(overlapping);
+        let mut matches = Vec::new();
+        for (i, c) in haystack.chars().enumerate() {
+            if let Some(start) = searcher.push(c, i) {
+                matches.push((start, i + 1));
+            }
+        }
+        matches
+    }
+
+    fn find_all_exact(chunks: &[&str], needle: &str, overlapping: bool) -> Vec<Range<usize>> {
+        let mut searcher = ExactSearcher::new(needle, overlapping);
+        let mut matches = Vec::new();
+        let mut byte_idx = 0;
+        for chunk in chunks {
+            searcher.search_chunk(chunk, byte_idx, &mut matches);
+            byte_idx += chunk.len();
+        }
+        searcher.finish(byte_idx, &mut matches);
+        matches
+    }
+
+    #[test]
+    fn exact_searcher_01() {
+        let chunks = ["aaa", "a", "", "aa", "a"];
+        assert_eq!(vec![0..2, 2..4, 4..6], find_all_exact(&chunks, "aa", false));
+        assert_eq!(
+            vec![0..2, 1..3, 2..4, 3..5, 4..6, 5..7],
+            find_all_exact(&chunks, "aa", true)
+        );
+        assert_eq!(vec![0..7], find_all_exact(&chunks, "aaaaaaa", false));
+        assert_eq!(Vec::<Range<usize>>::new(), find_all_exact(&chunks, "", false));
+    }
+
+    #[test]
+    fn exact_searcher_02() {
+        // Matches spanning several chunks, and non-ASCII text.
+        let chunks = ["xこん", "に", "ちはこ", "んにち", "は"];
+        assert_eq!(
+            vec![1..16, 16..31],
+            find_all_exact(&chunks, "こんにちは", false)
+        );
+        assert_eq!(vec![7..13, 22..28], find_all_exact(&chunks, "にち", true));
+
+        let chunks = ["ab", "ab", "a"];
+        assert_eq!(vec![0..3], find_all_exact(&chunks, "aba", false));
+        assert_eq!(vec![0..3, 2..5], find_all_exact(&chunks, "aba", true));
+    }
+
+    #[test]
+    fn searcher_01() {
+        let m = find_all("aaaa", "aa", SearchOptions::default());
+        assert_eq!(vec![(0, 2), (2, 4)], m);
+    }
+
+    #[test]
+    fn searcher_overlapping_01() {
+        let m = find_all_overlapping("aaaa", "aa", SearchOptions::default(), true);
+        assert_eq!(vec![(0, 2), (1, 3), (2, 4)], m);
+
+        let m = find_all_overlapping("abababa", "aba", SearchOptions::default(), true);
+        assert_eq!(vec![(0, 3), (2, 5), (4, 7)], m);
+    }
+
+    #[test]
+    fn searcher_overlapping_02() {
+        let options = SearchOptions {
+            unicode_case_fold: true,
+            ..SearchOptions::default()
+        };
+        let m = find_all_overlapping("sßs", "ss", options, true);
+        assert_eq!(vec![(1, 2)], m);
+        let m = find_all_overlapping("sSs", "ss", options, true);
+        assert_eq!(vec![(0, 2), (1, 3)], m);
+    }
+
+    #[test]
+    fn searcher_02() {
+        let options = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        let m = find_all("Hello HELLO hello", "hELLo", options);
+        assert_eq!(vec![(0, 5), (6, 11), (12, 17)], m);
+    }
+
+    #[test]
+    fn searcher_03() {
+        // Matches may not split the folded form of a char.
+        let options = SearchOptions {
+            unicode_case_fold: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(vec![(1, 2)], find_all("aßb", "ss", options));
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all("aßb", "s", options));
+        assert_eq!(vec![(0, 4)], find_all("SSSS", "ßß", options));
+    }
+
+    #[test]
+    fn searcher_04() {
+        let options = SearchOptions {
+            normalize: true,
+            ..SearchOptions::default()
+        };
+        assert_eq!(vec![(1, 3), (4, 5)], find_all("ce\u{301}-é", "é", options));
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all("é", "e", options));
+    }
+}
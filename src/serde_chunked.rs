@@ -0,0 +1,161 @@
+//! An alternate serde representation of `Rope`, as a sequence of chunks.
+//!
+//! By default a `Rope` is serialized as a single string, which for most
+//! formats means gathering the whole text into one buffer first, and
+//! likewise on the way back in.  This module instead serializes the
+//! `Rope` as a sequence of its chunk strings, and deserializes by packing
+//! those chunks straight into a new `Rope`, so no copy of the full text
+//! is ever made.  That keeps peak memory low for very large documents.
+//!
+//! Any sequence of strings can be deserialized this way, so the chunks
+//! don't need to have come from a `Rope`.
+//!
+//! Only available with the `serde` feature.  Use it with serde's
+//! `with` attribute:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Document {
+//!     #[serde(with = "ropey::serde_chunked")]
+//!     text: Rope,
+//! }
+//! ```
+
+use std::fmt;
+
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+
+use rope::Rope;
+use rope_builder::RopeBuilder;
+
+/// Serializes `rope` as a sequence of its chunks.
+pub fn serialize<S: Serializer>(rope: &Rope, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut seq = serializer.serialize_seq(Some(rope.chunks().count()))?;
+    for chunk in rope.chunks() {
+        seq.serialize_element(chunk)?;
+    }
+    seq.end()
+}
+
+/// Deserializes a `Rope` from a sequence of strings.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rope, D::Error> {
+    deserializer.deserialize_seq(ChunksVisitor)
+}
+
+struct ChunksVisitor;
+
+impl<'de> Visitor<'de> for ChunksVisitor {
+    type Value = Rope;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of strings")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Rope, A::Error> {
+        let mut builder = RopeBuilder::new();
+        while let Some(()) = seq.next_element_seed(ChunkSeed(&mut builder))? {}
+        Ok(builder.finish())
+    }
+}
+
+/// Appends a single deserialized chunk to a `RopeBuilder`, without
+/// allocating a `String` for it where the format allows.
+struct ChunkSeed<'a>(&'a mut RopeBuilder);
+
+impl<'a, 'de> DeserializeSeed<'de> for ChunkSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'a, 'de> Visitor<'de> for ChunkSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<(), E> {
+        self.0.append(v);
+        Ok(())
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use serde::de::value::{self, SeqDeserializer};
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+    use serde_test::{assert_de_tokens, assert_tokens, Token};
+    use Rope;
+
+    #[derive(Debug, PartialEq)]
+    struct Chunked(Rope);
+
+    impl Serialize for Chunked {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Chunked {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Chunked, D::Error> {
+            super::deserialize(deserializer).map(Chunked)
+        }
+    }
+
+    #[test]
+    fn serde_chunked_01() {
+        let r = Rope::from_str("Hello there!\r\nHow're you?");
+        let chunks: Vec<&'static str> = r
+            .chunks()
+            .map(|c| &*Box::leak(c.to_string().into_boxed_str()))
+            .collect();
+        let mut tokens = vec![Token::Seq {
+            len: Some(chunks.len()),
+        }];
+        tokens.extend(chunks.iter().map(|c| Token::Str(c)));
+        tokens.push(Token::SeqEnd);
+
+        assert!(chunks.len() > 1);
+        assert_tokens(&Chunked(r), &tokens);
+    }
+
+    #[test]
+    fn serde_chunked_02() {
+        let tokens = [
+            Token::Seq { len: None },
+            Token::String("Hello there!\r"),
+            Token::BorrowedStr("\n"),
+            Token::Str(""),
+            Token::Str("How're you?"),
+            Token::SeqEnd,
+        ];
+        let r = Rope::from_str("Hello there!\r\nHow're you?");
+        assert_de_tokens(&Chunked(r.clone()), &tokens);
+
+        let chunks = vec!["Hello there!\r", "\n", "", "How're you?"];
+        let de = SeqDeserializer::<_, value::Error>::new(chunks.into_iter());
+        let r2 = super::deserialize(de).unwrap();
+        assert_eq!(r, r2);
+        r2.assert_integrity();
+        r2.assert_invariants();
+    }
+
+    #[test]
+    fn serde_chunked_03() {
+        assert_tokens(
+            &Chunked(Rope::new()),
+            &[Token::Seq { len: Some(0) }, Token::SeqEnd],
+        );
+        assert_de_tokens(
+            &Chunked(Rope::new()),
+            &[Token::Seq { len: Some(1) }, Token::Str(""), Token::SeqEnd],
+        );
+    }
+}
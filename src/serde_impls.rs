@@ -0,0 +1,72 @@
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use rope::Rope;
+use slice::RopeSlice;
+
+// A `Rope` is serialized as a single string.  See the `serde_chunked`
+// module for a representation that doesn't need the text in one piece.
+
+impl Serialize for Rope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.slice(..).serialize(serializer)
+    }
+}
+
+impl<'a> Serialize for RopeSlice<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut chunks = self.chunks();
+        match (chunks.next(), chunks.next()) {
+            (None, _) => serializer.serialize_str(""),
+            (Some(chunk), None) => serializer.serialize_str(chunk),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rope, D::Error> {
+        deserializer.deserialize_str(RopeVisitor)
+    }
+}
+
+struct RopeVisitor;
+
+impl<'de> Visitor<'de> for RopeVisitor {
+    type Value = Rope;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Rope, E> {
+        Ok(Rope::from_str(v))
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use serde_test::{assert_de_tokens, assert_ser_tokens, assert_tokens, Token};
+    use Rope;
+
+    const TEXT: &str = "Hello there!  How're you doing?\r\nIt's a fine day, isn't it?";
+
+    #[test]
+    fn serde_01() {
+        let r = Rope::from_str(TEXT);
+        assert!(r.chunks().count() > 1);
+        assert_tokens(&r, &[Token::Str(TEXT)]);
+        assert_de_tokens(&r, &[Token::String(TEXT)]);
+        assert_ser_tokens(&r.slice(6..11), &[Token::Str("there")]);
+    }
+
+    #[test]
+    fn serde_02() {
+        assert_tokens(&Rope::new(), &[Token::Str("")]);
+        assert_ser_tokens(&Rope::from_str("Hi!").slice(..), &[Token::Str("Hi!")]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use tree::{Node, NodeChildren, NodeText, MAX_CHILDREN, MIN_CHILDREN};
+use validate::InvariantViolation;
+
+/// A description of the exact layout of a `Rope`'s tree: which chunk of
+/// text goes in each leaf, and which children each internal node has.
+///
+/// This is for benchmarks and regression tests that need to reproduce a
+/// specific tree shape (e.g. minimally vs. maximally filled nodes)
+/// deterministically, rather than whatever shape a series of edits
+/// happens to produce.  Build a `Rope` from a shape with
+/// [`Rope::from_shape()`](struct.Rope.html#method.from_shape), and get
+/// the shape of an existing `Rope` with
+/// [`Rope::shape()`](struct.Rope.html#method.shape).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, TreeShape};
+/// let shape = TreeShape::Internal(vec![
+///     TreeShape::Leaf("Hello "),
+///     TreeShape::Leaf("world!"),
+/// ]);
+/// let rope = Rope::from_shape(&shape).unwrap();
+///
+/// assert_eq!(rope, "Hello world!");
+/// assert_eq!(rope.chunks().collect::<Vec<_>>(), ["Hello ", "world!"]);
+/// assert_eq!(rope.shape(), shape);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeShape<'a> {
+    /// A leaf, with its chunk of text.
+    Leaf(&'a str),
+
+    /// An internal node, with its children.
+    Internal(Vec<TreeShape<'a>>),
+}
+
+impl<'a> TreeShape<'a> {
+    /// The fewest children a non-root internal node can have.
+    pub const MIN_CHILDREN: usize = MIN_CHILDREN;
+
+    /// The most children an internal node can have.
+    pub const MAX_CHILDREN: usize = MAX_CHILDREN;
+
+    /// Creates a balanced shape with `chunks` as its leaves, giving each
+    /// internal node `fanout` children.
+    ///
+    /// Where the nodes of a level don't divide evenly, the last two nodes
+    /// of the level share the remainder, so that all nodes stay within
+    /// `MIN_CHILDREN..=MAX_CHILDREN`.  Use a `fanout` of `MIN_CHILDREN`
+    /// for a minimally filled tree and `MAX_CHILDREN` for a maximally
+    /// filled one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, TreeShape};
+    /// let chunks = vec!["Hello "; 100];
+    /// let shape = TreeShape::from_chunks(&chunks, TreeShape::MIN_CHILDREN);
+    /// let rope = Rope::from_shape(&shape).unwrap();
+    ///
+    /// assert_eq!(rope.chunks().count(), 100);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fanout` isn't within `MIN_CHILDREN..=MAX_CHILDREN`.
+    pub fn from_chunks(chunks: &[&'a str], fanout: usize) -> TreeShape<'a> {
+        assert!(
+            (MIN_CHILDREN..=MAX_CHILDREN).contains(&fanout),
+            "Invalid fanout {}: must be between {} and {}",
+            fanout,
+            MIN_CHILDREN,
+            MAX_CHILDREN
+        );
+
+        let mut level: Vec<_> = chunks.iter().map(|chunk| TreeShape::Leaf(chunk)).collect();
+        if level.is_empty() {
+            return TreeShape::Leaf("");
+        }
+
+        while level.len() > 1 {
+            let mut children = level.into_iter();
+            level = group_sizes(children.len(), fanout)
+                .into_iter()
+                .map(|count| TreeShape::Internal(children.by_ref().take(count).collect()))
+                .collect();
+        }
+
+        level.pop().unwrap()
+    }
+
+    /// Returns the shape of `node`.
+    pub(crate) fn of_node(node: &'a Node) -> TreeShape<'a> {
+        match *node {
+            Node::Leaf(ref text) => TreeShape::Leaf(text),
+            Node::Internal(ref children) => TreeShape::Internal(
+                children
+                    .nodes()
+                    .iter()
+                    .map(|child| TreeShape::of_node(child))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Builds the node described by the shape.
+    ///
+    /// Nodes with more children than fit are reported to `out`, and their
+    /// extra children are left out.
+    pub(crate) fn to_node(&self, path: &mut Vec<usize>, out: &mut Vec<InvariantViolation>) -> Node {
+        match *self {
+            TreeShape::Leaf(text) => Node::Leaf(NodeText::from_str(text)),
+            TreeShape::Internal(ref shapes) => {
+                if shapes.len() > MAX_CHILDREN {
+                    out.push(InvariantViolation::TooManyChildren {
+                        path: path.clone(),
+                        max: MAX_CHILDREN,
+                        actual: shapes.len(),
+                    });
+                }
+
+                let mut children = NodeChildren::new();
+                for (i, shape) in shapes.iter().take(MAX_CHILDREN).enumerate() {
+                    path.push(i);
+                    let node = shape.to_node(path, out);
+                    path.pop();
+                    children.push((node.text_info(), Arc::new(node)));
+                }
+                Node::Internal(children)
+            }
+        }
+    }
+}
+
+//=============================================================
+
+/// Returns the number of children of each node of a tree level with `len`
+/// children in total, giving each node `fanout` children where possible.
+///
+/// Where the children don't divide evenly, the last two nodes share the
+/// remainder, so that all nodes have at least `MIN_CHILDREN` children
+/// (unless there's only one node).
+pub(crate) fn group_sizes(len: usize, fanout: usize) -> Vec<usize> {
+    debug_assert!((MIN_CHILDREN..=MAX_CHILDREN).contains(&fanout));
+
+    let mut sizes = vec![fanout; len / fanout];
+    let rem = len % fanout;
+    if rem > 0 {
+        match sizes.pop() {
+            Some(last) if rem < MIN_CHILDREN => {
+                if last + rem <= MAX_CHILDREN {
+                    sizes.push(last + rem);
+                } else {
+                    sizes.push((last + rem).div_ceil(2));
+                    sizes.push((last + rem) / 2);
+                }
+            }
+            last => {
+                sizes.extend(last);
+                sizes.push(rem);
+            }
+        }
+    }
+    sizes
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {InvariantReport, Rope};
+
+    #[test]
+    fn from_shape_01() {
+        let shape = TreeShape::Internal(vec![
+            TreeShape::Internal(vec![
+                TreeShape::Leaf("Hello "),
+                TreeShape::Leaf("there!  "),
+                TreeShape::Leaf("How're "),
+            ]),
+            TreeShape::Internal(vec![
+                TreeShape::Leaf("you "),
+                TreeShape::Leaf("doing?"),
+                TreeShape::Leaf("\r\n"),
+            ]),
+        ]);
+        let r = Rope::from_shape(&shape).unwrap();
+        assert_eq!("Hello there!  How're you doing?\r\n", r);
+        assert_eq!(6, r.chunks().count());
+        assert_eq!(shape, r.shape());
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn from_shape_02() {
+        // Unbalanced, too few children, and a split CRLF pair.
+        let shape = TreeShape::Internal(vec![
+            TreeShape::Leaf("Hello\r"),
+            TreeShape::Internal(vec![TreeShape::Leaf("\nworld!")]),
+        ]);
+        let report = Rope::from_shape(&shape).unwrap_err();
+        assert_eq!(3, report.violations.len());
+
+        let shape = TreeShape::Internal(vec![TreeShape::Leaf("a"); MAX_CHILDREN + 1]);
+        assert_eq!(
+            Err(InvariantReport {
+                violations: vec![InvariantViolation::TooManyChildren {
+                    path: vec![],
+                    max: MAX_CHILDREN,
+                    actual: MAX_CHILDREN + 1,
+                }],
+            }),
+            Rope::from_shape(&shape).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn from_chunks_01() {
+        let chunks: Vec<String> = (0..100).map(|i| format!("{} ", i)).collect();
+        let chunks: Vec<&str> = chunks.iter().map(|c| &c[..]).collect();
+
+        for fanout in MIN_CHILDREN..(MAX_CHILDREN + 1) {
+            for n in 0..chunks.len() {
+                let shape = TreeShape::from_chunks(&chunks[..n], fanout);
+                let r = Rope::from_shape(&shape).unwrap();
+                assert_eq!(chunks[..n].concat(), r);
+                assert_eq!(n, r.chunks().count());
+                r.assert_invariants();
+            }
+        }
+    }
+
+    #[test]
+    fn from_chunks_02() {
+        let chunks = ["a"; 25];
+        let min = TreeShape::from_chunks(&chunks, MIN_CHILDREN);
+        let max = TreeShape::from_chunks(&chunks, MAX_CHILDREN);
+        let r_min = Rope::from_shape(&min).unwrap();
+        let r_max = Rope::from_shape(&max).unwrap();
+        assert!(r_min.root.depth() > r_max.root.depth());
+    }
+}
@@ -1,11 +1,26 @@
 use std;
-use std::sync::Arc;
+use std::hash::Hasher;
+use std::io::IoSlice;
 use std::ops::{Range, RangeFrom, RangeFull, RangeTo};
+use std::sync::Arc;
 
-use iter::{Bytes, Chars, Chunks, Lines};
+#[cfg(feature = "checksum")]
+use xxhash_rust::xxh64::Xxh64;
+
+use doc_stats::DocumentStats;
+use escape::EscapeControl;
+use iter::{
+    Bytes, Chars, Chunks, ClassRuns, EncodeUtf16, IndexedLines, Lines, MatchIndices, Matches,
+    SearchMatches, Split, Wrap,
+};
+use pattern::{Pattern, PatternMatcher};
+use reader::RopeReader;
 use rope::Rope;
+use search::{ChunkSearcher, SearchOptions};
+#[cfg(feature = "stats")]
+use stats::EditStats;
 use str_utils::char_idx_to_byte_idx;
-use tree::{Count, Node};
+use tree::{Count, Node, TextInfo, DEFAULT_MAX_SKEW, MAX_BYTES};
 
 /// An immutable view into part of a `Rope`.
 #[derive(Copy, Clone)]
@@ -78,6 +93,30 @@ impl<'a> RopeSlice<'a> {
         (self.end_line_break - self.start_line_break) as usize + 1
     }
 
+    /// Total number of UTF-16 code units in the `RopeSlice`, i.e. its
+    /// length if it were encoded as UTF-16.
+    ///
+    /// Runs in O(1) time for a slice of a whole `Rope`, and O(log N) time
+    /// otherwise.
+    pub fn len_utf16_cu(&self) -> usize {
+        let start = self.start_char as usize;
+        let end = self.end_char as usize;
+        if start == 0 && end == self.node.char_count() {
+            self.node.utf16_cu_count()
+        } else {
+            self.node.char_to_utf16_cu(end) - self.node.char_to_utf16_cu(start)
+        }
+    }
+
+    /// Returns a summary of the `RopeSlice`'s contents.
+    ///
+    /// See `Rope::stats()` for details.
+    ///
+    /// Runs in O(N) time.
+    pub fn stats(&self) -> DocumentStats {
+        DocumentStats::from_chunks(self.chunks())
+    }
+
     //-----------------------------------------------------------------------
     // Index conversion methods
 
@@ -99,6 +138,45 @@ impl<'a> RopeSlice<'a> {
         self.node.byte_to_char(self.start_byte as usize + byte_idx) - (self.start_char as usize)
     }
 
+    /// Returns the char index of the given byte, or `None` if `byte_idx` is out
+    /// of bounds (i.e. `byte_idx > len_bytes()`).
+    ///
+    /// The non-panicking version of `byte_to_char()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_byte_to_char(&self, byte_idx: usize) -> Option<usize> {
+        if byte_idx <= self.len_bytes() {
+            Some(self.byte_to_char(byte_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the line index of the given byte.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn byte_to_line(&self, byte_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of slice: byte index {}, slice byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        if byte_idx == self.len_bytes() {
+            self.len_lines()
+        } else {
+            self.node.byte_to_line(self.start_byte as usize + byte_idx)
+                - (self.start_line_break as usize)
+        }
+    }
+
     /// Returns the byte index of the given char.
     ///
     /// # Panics
@@ -117,6 +195,21 @@ impl<'a> RopeSlice<'a> {
         self.node.char_to_byte(self.start_char as usize + char_idx) - (self.start_byte as usize)
     }
 
+    /// Returns the byte index of the given char, or `None` if `char_idx` is out
+    /// of bounds (i.e. `char_idx > len_chars()`).
+    ///
+    /// The non-panicking version of `char_to_byte()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_char_to_byte(&self, char_idx: usize) -> Option<usize> {
+        if char_idx <= self.len_chars() {
+            Some(self.char_to_byte(char_idx))
+        } else {
+            None
+        }
+    }
+
     /// Returns the line index of the given char.
     ///
     /// # Panics
@@ -139,6 +232,48 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Returns the line index of the given char, or `None` if `char_idx` is out
+    /// of bounds (i.e. `char_idx > len_chars()`).
+    ///
+    /// The non-panicking version of `char_to_line()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_char_to_line(&self, char_idx: usize) -> Option<usize> {
+        if char_idx <= self.len_chars() {
+            Some(self.char_to_line(char_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the byte index of the start of the given line.
+    ///
+    /// Note: lines are zero-indexed.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    pub fn line_to_byte(&self, line_idx: usize) -> usize {
+        // Bounds check
+        assert!(
+            line_idx <= self.len_lines(),
+            "Attempt to index past end of slice: line index {}, slice line length {}",
+            line_idx,
+            self.len_lines()
+        );
+
+        if line_idx == self.len_lines() {
+            self.len_bytes()
+        } else {
+            self.node
+                .line_to_byte(self.start_line_break as usize + line_idx)
+                .saturating_sub(self.start_byte as usize)
+        }
+    }
+
     /// Returns the char index of the start of the given line.
     ///
     /// Note: lines are zero-indexed.
@@ -169,9 +304,45 @@ impl<'a> RopeSlice<'a> {
         }
     }
 
+    /// Returns the char index of the start of the given line, or `None` if
+    /// `line_idx` is out of bounds (i.e. `line_idx > len_lines()`).
+    ///
+    /// The non-panicking version of `line_to_char()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_line_to_char(&self, line_idx: usize) -> Option<usize> {
+        if line_idx <= self.len_lines() {
+            Some(self.line_to_char(line_idx))
+        } else {
+            None
+        }
+    }
+
     //-----------------------------------------------------------------------
     // Fetch methods
 
+    /// Returns the byte at `byte_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx >= len_bytes()`).
+    pub fn byte(&self, byte_idx: usize) -> u8 {
+        // Bounds check
+        assert!(
+            byte_idx < self.len_bytes(),
+            "Attempt to index past end of slice: byte index {}, slice byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        let byte_idx = self.start_byte + byte_idx as Count;
+        let (leaf, before, _) = Node::find_leaf(self.node, |inf| byte_idx < inf.bytes);
+        leaf.leaf_text().as_bytes()[(byte_idx - before.bytes) as usize]
+    }
+
     /// Returns the char at `char_idx`.
     ///
     /// # Panics
@@ -192,6 +363,56 @@ impl<'a> RopeSlice<'a> {
         chunk[byte_idx..].chars().nth(0).unwrap()
     }
 
+    /// Returns the char at `char_idx`, or `None` if `char_idx` is out of
+    /// bounds (i.e. `char_idx >= len_chars()`).
+    ///
+    /// The non-panicking version of `char()`.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn get_char(&self, char_idx: usize) -> Option<char> {
+        if char_idx < self.len_chars() {
+            Some(self.char(char_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the first char of the slice, or `None` if it's empty.
+    ///
+    /// Runs in O(log N) time.
+    #[inline]
+    pub fn first_char(&self) -> Option<char> {
+        if self.len_chars() == 0 {
+            None
+        } else {
+            Some(self.char(0))
+        }
+    }
+
+    /// Returns the last char of the slice, or `None` if it's empty.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!\n");
+    ///
+    /// assert_eq!(rope.slice(6..).first_char(), Some('w'));
+    /// assert_eq!(rope.slice(..5).last_char(), Some('o'));
+    /// assert_eq!(rope.slice(5..5).last_char(), None);
+    /// ```
+    #[inline]
+    pub fn last_char(&self) -> Option<char> {
+        if self.len_chars() == 0 {
+            None
+        } else {
+            Some(self.char(self.len_chars() - 1))
+        }
+    }
+
     /// Returns the line at `line_idx`.
     ///
     /// Note: lines are zero-indexed.
@@ -214,6 +435,394 @@ impl<'a> RopeSlice<'a> {
         self.slice(start..end)
     }
 
+    /// Returns the line at `line_idx`, or `None` if `line_idx` is out of
+    /// bounds (i.e. `line_idx >= len_lines()`).
+    ///
+    /// The non-panicking version of `line()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!\nBye!");
+    /// let slice = rope.slice(3..12);
+    ///
+    /// assert_eq!(slice.get_line(1).unwrap(), "world!");
+    /// assert!(slice.get_line(2).is_none());
+    /// ```
+    #[inline]
+    pub fn get_line(&self, line_idx: usize) -> Option<RopeSlice<'a>> {
+        if line_idx < self.len_lines() {
+            Some(self.line(line_idx))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the chunk containing the given byte index, clipped to the
+    /// `RopeSlice`.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// (clipped) chunk, relative to the start of the `RopeSlice`.  The
+    /// return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// One-past-the-end is valid, and returns the last chunk.  An empty
+    /// `RopeSlice` has a single empty chunk.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    /// let slice = rope.slice(3..9);
+    ///
+    /// assert_eq!(("lo\nwor", 0, 0, 0), slice.chunk_at_byte(4));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `byte_idx` is out of bounds (i.e. `byte_idx > len_bytes()`).
+    pub fn chunk_at_byte(&self, byte_idx: usize) -> (&'a str, usize, usize, usize) {
+        // Bounds check
+        assert!(
+            byte_idx <= self.len_bytes(),
+            "Attempt to index past end of slice: byte index {}, slice byte length {}",
+            byte_idx,
+            self.len_bytes()
+        );
+
+        if self.len_bytes() == 0 {
+            return ("", 0, 0, 0);
+        }
+        let byte_idx = self.start_byte + byte_idx.min(self.len_bytes() - 1) as Count;
+        self.clipped_chunk(|inf| byte_idx < inf.bytes)
+    }
+
+    /// Returns the chunk containing the given char index, clipped to the
+    /// `RopeSlice`.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// (clipped) chunk, relative to the start of the `RopeSlice`.  The
+    /// return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// One-past-the-end is valid, and returns the last chunk.  An empty
+    /// `RopeSlice` has a single empty chunk.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    /// let slice = rope.slice(3..9);
+    ///
+    /// assert_eq!(("lo\nwor", 0, 0, 0), slice.chunk_at_char(6));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx > len_chars()`).
+    pub fn chunk_at_char(&self, char_idx: usize) -> (&'a str, usize, usize, usize) {
+        // Bounds check
+        assert!(
+            char_idx <= self.len_chars(),
+            "Attempt to index past end of slice: char index {}, slice char length {}",
+            char_idx,
+            self.len_chars()
+        );
+
+        if self.len_chars() == 0 {
+            return ("", 0, 0, 0);
+        }
+        let char_idx = self.start_char + char_idx.min(self.len_chars() - 1) as Count;
+        self.clipped_chunk(|inf| char_idx < inf.chars)
+    }
+
+    /// Returns the chunk containing the given line break, clipped to the
+    /// `RopeSlice`.
+    ///
+    /// Also returns the byte, char, and line index of the start of the
+    /// (clipped) chunk, relative to the start of the `RopeSlice`.  The
+    /// return value is organized as
+    /// `(chunk, chunk_byte_idx, chunk_char_idx, chunk_line_idx)`.
+    ///
+    /// For convenience, both the start and end of the `RopeSlice` count as
+    /// line breaks: 0 gives the first chunk, `len_lines()` gives the last
+    /// one, and anything in between gives the chunk containing the line
+    /// break at the end of line `line_break_idx - 1`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello\nworld!");
+    /// let slice = rope.slice(3..9);
+    ///
+    /// assert_eq!(("lo\nwor", 0, 0, 0), slice.chunk_at_line_break(1));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_break_idx` is out of bounds (i.e. `line_break_idx > len_lines()`).
+    pub fn chunk_at_line_break(&self, line_break_idx: usize) -> (&'a str, usize, usize, usize) {
+        // Bounds check
+        assert!(
+            line_break_idx <= self.len_lines(),
+            "Attempt to index past end of slice: line break index {}, slice line length {}",
+            line_break_idx,
+            self.len_lines()
+        );
+
+        if line_break_idx == 0 {
+            self.chunk_at_byte(0)
+        } else if line_break_idx == self.len_lines() {
+            self.chunk_at_byte(self.len_bytes())
+        } else {
+            let line_break_idx = self.start_line_break + line_break_idx as Count;
+            self.clipped_chunk(|inf| line_break_idx <= inf.line_breaks)
+        }
+    }
+
+    /// Returns the chunk that `pred` leads to, as with `Node::find_leaf()`,
+    /// clipped to the slice and with its slice-relative start indices.
+    ///
+    /// `pred` must lead to a chunk that overlaps the slice.
+    fn clipped_chunk<F>(&self, pred: F) -> (&'a str, usize, usize, usize)
+    where
+        F: Fn(&TextInfo) -> bool,
+    {
+        let (leaf, before, _) = Node::find_leaf(self.node, pred);
+        let text = leaf.leaf_text();
+
+        if before.bytes < self.start_byte {
+            // Clipped at the start of the slice.
+            let start = (self.start_byte - before.bytes) as usize;
+            let end = text.len().min((self.end_byte - before.bytes) as usize);
+            (&text[start..end], 0, 0, 0)
+        } else {
+            let end = text.len().min((self.end_byte - before.bytes) as usize);
+            (
+                &text[..end],
+                (before.bytes - self.start_byte) as usize,
+                (before.chars - self.start_char) as usize,
+                (before.line_breaks - self.start_line_break) as usize,
+            )
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Search methods
+
+    /// Returns the char index range of the first match of `pattern`, or
+    /// `None` if there isn't one.
+    ///
+    /// `pattern` can be anything implementing
+    /// [`Pattern`](trait.Pattern.html), e.g. a `char` or a `&str`.
+    ///
+    /// Runs in O(N + M) time, where N is the length of the `RopeSlice` and
+    /// M is the length of `pattern`.
+    pub fn find<P: Pattern>(&self, pattern: P) -> Option<Range<usize>> {
+        self.match_indices(pattern)
+            .next()
+            .map(|(idx, text)| idx..(idx + text.len_chars()))
+    }
+
+    /// Like `find()`, but matches according to `options`.
+    pub fn find_with(&self, pattern: &str, options: SearchOptions) -> Option<Range<usize>> {
+        self.matches_with(pattern, options).next()
+    }
+
+    /// Creates an iterator over the char index ranges of the
+    /// non-overlapping matches of `pattern`.
+    ///
+    /// The text is searched a whole chunk at a time, using the same
+    /// Two-Way algorithm as `str::find()`, so this is much faster than
+    /// searching with `matches_with()` and non-default options.  An empty
+    /// pattern never matches.
+    pub fn matches(&self, pattern: &str) -> Matches<'a> {
+        self.matches_with(pattern, SearchOptions::default())
+    }
+
+    /// Like `matches()`, but matches according to `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Rope, SearchOptions};
+    /// let rope = Rope::from_str("Hello HELLO hello");
+    /// let options = SearchOptions {
+    ///     case_insensitive: true,
+    ///     ..SearchOptions::default()
+    /// };
+    /// let matches: Vec<_> = rope.slice(1..).matches_with("hello", options).collect();
+    ///
+    /// assert_eq!(vec![5..10, 11..16], matches);
+    /// ```
+    pub fn matches_with(&self, pattern: &str, options: SearchOptions) -> Matches<'a> {
+        Matches::new(*self, pattern, options, false)
+    }
+
+    /// Creates an iterator over the char index ranges of all matches of
+    /// `pattern`, including ones that overlap each other.
+    ///
+    /// Matches are yielded in order of their start.  Text is streamed
+    /// through the matcher, so matches spanning chunk boundaries are
+    /// found like any other.  An empty pattern never matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("banana");
+    /// let matches: Vec<_> = rope.slice(..).matches_overlapping("ana").collect();
+    ///
+    /// assert_eq!(vec![1..4, 3..6], matches);
+    /// ```
+    pub fn matches_overlapping(&self, pattern: &str) -> Matches<'a> {
+        self.matches_overlapping_with(pattern, SearchOptions::default())
+    }
+
+    /// Like `matches_overlapping()`, but matches according to `options`.
+    pub fn matches_overlapping_with(&self, pattern: &str, options: SearchOptions) -> Matches<'a> {
+        Matches::new(*self, pattern, options, true)
+    }
+
+    /// Creates an iterator over the matches found by `searcher`, which is
+    /// fed the `RopeSlice`'s chunks in order.
+    ///
+    /// Yields the char index range of each match.  This is how other
+    /// search algorithms, e.g. regex engines, can search a `RopeSlice`.
+    /// See [`ChunkSearcher`](trait.ChunkSearcher.html) for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `searcher` yields a byte range that is out of bounds.
+    pub fn search<S: ChunkSearcher>(&self, searcher: S) -> SearchMatches<'a, S> {
+        SearchMatches::new(*self, searcher)
+    }
+
+    /// Creates an iterator over the non-overlapping matches of `pattern`,
+    /// yielding the char index and text of each.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("a1b22c333");
+    /// let digits: Vec<_> = rope
+    ///     .slice(1..)
+    ///     .match_indices(|c: char| c.is_ascii_digit())
+    ///     .map(|(idx, text)| (idx, text.to_string()))
+    ///     .collect();
+    ///
+    /// assert_eq!(digits[..3], [(0, "1".into()), (2, "2".into()), (3, "2".into())]);
+    /// ```
+    pub fn match_indices<P: Pattern>(&self, pattern: P) -> MatchIndices<'a, P::Matcher> {
+        MatchIndices::new(*self, pattern.into_matcher())
+    }
+
+    /// Creates an iterator over the pieces of the `RopeSlice` between
+    /// the non-overlapping matches of `pattern`.
+    ///
+    /// Like `str::split()`, this always yields at least one (possibly
+    /// empty) piece, and one more than there are matches.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("one, two,,three");
+    /// let pieces: Vec<_> = rope.slice(..).split(',').collect();
+    ///
+    /// assert_eq!(pieces, ["one", " two", "", "three"]);
+    /// ```
+    pub fn split<P: Pattern>(&self, pattern: P) -> Split<'a, P::Matcher> {
+        Split::new(*self, pattern.into_matcher())
+    }
+
+    /// Returns a sub-slice with all leading and trailing matches of
+    /// `pattern` removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("xxHello!xyx");
+    ///
+    /// assert_eq!("Hello!xy", rope.slice(..).trim_matches('x'));
+    /// assert_eq!("Hello!", rope.slice(..).trim_matches(&['x', 'y'][..]));
+    /// ```
+    pub fn trim_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'a> {
+        let mut matcher = pattern.into_matcher();
+        let start = self.trim_start_idx(&mut matcher);
+        let rest = self.slice(start..);
+        let end = rest.trim_end_idx(&mut matcher);
+        rest.slice(..end)
+    }
+
+    /// Returns a sub-slice with all leading matches of `pattern` removed.
+    pub fn trim_start_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'a> {
+        let start = self.trim_start_idx(&mut pattern.into_matcher());
+        self.slice(start..)
+    }
+
+    /// Returns a sub-slice with all trailing matches of `pattern` removed.
+    pub fn trim_end_matches<P: Pattern>(&self, pattern: P) -> RopeSlice<'a> {
+        let end = self.trim_end_idx(&mut pattern.into_matcher());
+        self.slice(..end)
+    }
+
+    /// Returns the char index of the end of the leading matches of
+    /// `matcher`.
+    fn trim_start_idx<M: PatternMatcher>(&self, matcher: &mut M) -> usize {
+        let max_len = matcher.max_len();
+        let mut start = 0;
+        for (idx, c) in self.chars().enumerate() {
+            match matcher.next_match(c, idx) {
+                Some(match_start) if match_start == start => start = idx + 1,
+                Some(_) => break,
+                None => {
+                    if max_len.is_some_and(|len| idx + 1 - start >= len) {
+                        break;
+                    }
+                }
+            }
+        }
+        start
+    }
+
+    /// Returns the char index of the start of the trailing matches of
+    /// `matcher`.
+    fn trim_end_idx<M: PatternMatcher>(&self, matcher: &mut M) -> usize {
+        let max_len = matcher.max_len();
+        let mut end = self.len_chars();
+        let mut idx = end;
+        'chunks: while idx > 0 {
+            let (chunk, _, chunk_char_idx, _) = self.chunk_at_char(idx - 1);
+            let byte_end = char_idx_to_byte_idx(chunk, idx - chunk_char_idx);
+            for c in chunk[..byte_end].chars().rev() {
+                idx -= 1;
+                match matcher.next_match_back(c, idx) {
+                    Some(match_end) if match_end == end => end = idx,
+                    Some(_) => break 'chunks,
+                    None => {
+                        if max_len.is_some_and(|len| end - idx >= len) {
+                            break 'chunks;
+                        }
+                    }
+                }
+            }
+        }
+        end
+    }
+
     //-----------------------------------------------------------------------
     // Slicing
 
@@ -246,6 +855,76 @@ impl<'a> RopeSlice<'a> {
         )
     }
 
+    /// Returns a sub-slice of the `RopeSlice`, or `None` if the start of
+    /// the range is greater than the end, or the end is out of bounds
+    /// (i.e. `end > len_chars()`).
+    ///
+    /// The non-panicking version of `slice()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// let slice = rope.slice(6..);
+    ///
+    /// assert_eq!(slice.get_slice(..5).unwrap(), "world");
+    /// assert!(slice.get_slice(..7).is_none());
+    /// ```
+    pub fn get_slice<R: CharIdxRange>(&self, range: R) -> Option<Self> {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_chars());
+
+        if start <= end && end <= self.len_chars() {
+            Some(RopeSlice::new_with_range(
+                self.node,
+                self.start_char as usize + start,
+                self.start_char as usize + end,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a sub-slice of the `RopeSlice` in the given byte index range.
+    ///
+    /// Uses range syntax, e.g. `2..7`, `2..`, etc.  Both ends of the range
+    /// must be on char boundaries.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the end
+    /// is out of bounds (i.e. `end > len_bytes()`), or if either end isn't
+    /// on a char boundary.
+    pub fn byte_slice<R: CharIdxRange>(&self, range: R) -> Self {
+        let start = range.start().unwrap_or(0);
+        let end = range.end().unwrap_or_else(|| self.len_bytes());
+
+        // Bounds check
+        assert!(start <= end);
+        assert!(
+            end <= self.len_bytes(),
+            "Attempt to slice past end of RopeSlice: slice end {}, RopeSlice byte length {}",
+            end,
+            self.len_bytes()
+        );
+
+        // A byte is on a char boundary exactly when it's the first byte
+        // of the char it's in.
+        let start_char = self.byte_to_char(start);
+        let end_char = self.byte_to_char(end);
+        assert!(
+            self.char_to_byte(start_char) == start && self.char_to_byte(end_char) == end,
+            "Attempt to slice RopeSlice at a non-char boundary: byte range {}..{}",
+            start,
+            end
+        );
+
+        self.slice(start_char..end_char)
+    }
+
     //-----------------------------------------------------------------------
     // Iterator methods
 
@@ -259,16 +938,140 @@ impl<'a> RopeSlice<'a> {
         Chars::new_with_range(self.node, self.start_char as usize, self.end_char as usize)
     }
 
+    /// Creates an iterator over the text of the `RopeSlice` encoded as
+    /// UTF-16 code units.
+    pub fn encode_utf16(&self) -> EncodeUtf16<'a> {
+        EncodeUtf16::new_with_range(self.node, self.start_char as usize, self.end_char as usize)
+    }
+
     /// Creates an iterator over the lines of the `RopeSlice`.
     pub fn lines(&self) -> Lines<'a> {
         Lines::new_with_range(self.node, self.start_char as usize, self.end_char as usize)
     }
 
+    /// Creates an iterator over the lines of the `RopeSlice`, along with
+    /// the index and byte range of each line.
+    ///
+    /// Line indices and byte ranges are relative to the start of the
+    /// `RopeSlice`.
+    pub fn indexed_lines(&self) -> IndexedLines<'a> {
+        IndexedLines::new(self.lines())
+    }
+
     /// Creates an iterator over the chunks of the `RopeSlice`.
     pub fn chunks(&self) -> Chunks<'a> {
         Chunks::new_with_range(self.node, self.start_char as usize, self.end_char as usize)
     }
 
+    /// Creates an iterator over the visual rows of the `RopeSlice` when
+    /// soft-wrapped to `width` columns.
+    ///
+    /// Each row is yielded as a char index range relative to the start of
+    /// the slice.  Rows are broken at whichever char would overflow the
+    /// width, without regard for word boundaries.  Column widths follow
+    /// Unicode display width, and tabs advance to the next multiple of
+    /// `tab_width`.
+    ///
+    /// This is intended to be used on a single line (e.g. the result of
+    /// `Rope::line()`), but line breaks in the slice are respected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!\n");
+    /// let rows: Vec<_> = rope.line(0).wrap(5, 4).collect();
+    ///
+    /// assert_eq!(vec![0..5, 5..10, 10..13], rows);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `tab_width` is zero.
+    pub fn wrap(&self, width: usize, tab_width: usize) -> Wrap<'a> {
+        Wrap::new(self.chars(), width, tab_width, false)
+    }
+
+    /// Like `wrap()`, but prefers to break rows after whitespace.
+    ///
+    /// Whitespace is allowed to hang past the end of a row, and words are
+    /// only broken mid-word if they don't fit on a row by themselves.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// let rows: Vec<_> = rope.slice(..).wrap_words(8, 4).collect();
+    ///
+    /// assert_eq!(vec![0..6, 6..12], rows);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` or `tab_width` is zero.
+    pub fn wrap_words(&self, width: usize, tab_width: usize) -> Wrap<'a> {
+        Wrap::new(self.chars(), width, tab_width, true)
+    }
+
+    /// Creates an iterator over the maximal runs of chars for which
+    /// `classify` returns equal values.
+    ///
+    /// Each run is yielded as its char index range, relative to the start
+    /// of the slice, along with its class.  The text is scanned chunk by
+    /// chunk, so this is much cheaper than classifying chars one at a time
+    /// via `char()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hi  there!");
+    /// let runs: Vec<_> = rope.slice(..).class_runs(char::is_whitespace).collect();
+    ///
+    /// assert_eq!(vec![(0..2, false), (2..4, true), (4..10, false)], runs);
+    /// ```
+    pub fn class_runs<F, K>(&self, classify: F) -> ClassRuns<'a, F, K>
+    where
+        F: FnMut(char) -> K,
+        K: PartialEq,
+    {
+        ClassRuns::new(self.chunks(), classify)
+    }
+
+    /// Creates an `io::Read`/`io::BufRead` adapter over the bytes of the
+    /// `RopeSlice`.
+    pub fn reader(&self) -> RopeReader<'a> {
+        RopeReader::new(*self)
+    }
+
+    /// Appends the `RopeSlice`'s chunks to `slices` as `IoSlice`s, for use
+    /// with vectored writes.
+    ///
+    /// This doesn't copy any text: the `IoSlice`s refer directly to the
+    /// `Rope`'s internal buffers.  Empty chunks are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::io::Write;
+    /// # use ropey::Rope;
+    /// let rope = Rope::from_str("Hello world!");
+    /// let mut slices = Vec::new();
+    /// rope.slice(6..).as_io_slices(&mut slices);
+    ///
+    /// let mut out = Vec::new();
+    /// out.write_vectored(&slices).unwrap();
+    /// assert_eq!(b"world!", &out[..]);
+    /// ```
+    pub fn as_io_slices(&self, slices: &mut Vec<IoSlice<'a>>) {
+        slices.extend(
+            self.chunks()
+                .filter(|chunk| !chunk.is_empty())
+                .map(|chunk| IoSlice::new(chunk.as_bytes())),
+        );
+    }
+
     //-----------------------------------------------------------------------
     // Conversion methods
 
@@ -282,9 +1085,21 @@ impl<'a> RopeSlice<'a> {
     }
 
     /// Creates a new `Rope` from the contents of the `RopeSlice`.
+    ///
+    /// The new `Rope` has the default chunk size.  If the slice is of a
+    /// `Rope` with a larger chunk size, its text is re-split to fit.
     pub fn to_rope(&self) -> Rope {
+        self.to_rope_with_chunk_size(MAX_BYTES)
+    }
+
+    pub(crate) fn to_rope_with_chunk_size(self, chunk_size: usize) -> Rope {
         let mut rope = Rope {
             root: Arc::clone(self.node),
+            chunk_size,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
         };
 
         // Chop off right end if needed
@@ -297,9 +1112,86 @@ impl<'a> RopeSlice<'a> {
             rope = rope.split_off(self.start_char as usize);
         }
 
+        // Re-split the chunks if they're from a rope with a larger chunk
+        // size.
+        if !rope.root.is_leaf() && rope.root.has_oversized_leaf(chunk_size) {
+            rope = rope.rechunked(chunk_size);
+        }
+
         // Return the rope
+        rope.clear_dirty_range();
         rope
     }
+
+    /// Returns a `Display` adaptor that writes the `RopeSlice` with
+    /// control chars, the byte order mark, and bidi formatting chars
+    /// rendered as visible escapes.
+    ///
+    /// See [`EscapeControl`](struct.EscapeControl.html) for details.
+    pub fn escape_control(&self) -> EscapeControl<'a> {
+        EscapeControl::new(*self)
+    }
+
+    //-----------------------------------------------------------------------
+    // Hashing methods
+
+    /// Feeds the contents of the `RopeSlice` into `hasher`, one chunk at a
+    /// time.
+    ///
+    /// This passes the text's bytes to `hasher.write()` without any
+    /// framing, so for hashers that process their input as a byte stream
+    /// the result is the same as hashing the equivalent `&[u8]`, regardless
+    /// of how the text is chunked.
+    ///
+    /// Runs in O(N) time.
+    pub fn hash_into<H: Hasher>(&self, hasher: &mut H) {
+        for chunk in self.chunks() {
+            hasher.write(chunk.as_bytes());
+        }
+    }
+
+    /// Returns a checksum of the `RopeSlice`'s contents.
+    ///
+    /// See `Rope::checksum()` for the stability guarantees.
+    ///
+    /// Only available with the `checksum` feature.
+    ///
+    /// Runs in O(N) time.
+    #[cfg(feature = "checksum")]
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = Xxh64::new(0);
+        for chunk in self.chunks() {
+            hasher.update(chunk.as_bytes());
+        }
+        hasher.digest()
+    }
+
+    /// Feeds the contents of the `RopeSlice` into a `digest`-crate
+    /// hasher, one chunk at a time.
+    ///
+    /// Only available with the `digest` feature.
+    ///
+    /// Runs in O(N) time.
+    #[cfg(feature = "digest")]
+    pub fn update_digest<D: digest::Update>(&self, digest: &mut D) {
+        for chunk in self.chunks() {
+            digest.update(chunk.as_bytes());
+        }
+    }
+
+    //-----------------------------------------------------------------------
+    // Comparison methods
+
+    /// Returns whether the `RopeSlice` has the same text as `other`,
+    /// treating LF, CRLF and CR line endings as equivalent.
+    ///
+    /// See `Rope::eq_ignore_line_endings()` for details.
+    ///
+    /// Runs in O(N) time.
+    pub fn eq_ignore_line_endings(&self, other: &RopeSlice) -> bool {
+        cmp_chunks(LfChunks::new(self.chunks()), LfChunks::new(other.chunks()))
+            == std::cmp::Ordering::Equal
+    }
 }
 
 //==============================================================
@@ -383,70 +1275,240 @@ impl<'a, 'b> std::cmp::PartialEq<&'b str> for RopeSlice<'a> {
             idx += chunk.len();
         }
 
-        return true;
+        return true;
+    }
+}
+
+impl<'a, 'b> std::cmp::PartialEq<RopeSlice<'a>> for &'b str {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        other == self
+    }
+}
+
+impl<'a> std::cmp::PartialEq<str> for RopeSlice<'a> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        std::cmp::PartialEq::<&str>::eq(self, &other)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for str {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        std::cmp::PartialEq::<&str>::eq(other, &self)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<String> for RopeSlice<'a> {
+    #[inline]
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for String {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, 'b> std::cmp::PartialEq<std::borrow::Cow<'b, str>> for RopeSlice<'a> {
+    #[inline]
+    fn eq(&self, other: &std::borrow::Cow<'b, str>) -> bool {
+        *self == **other
+    }
+}
+
+impl<'a, 'b> std::cmp::PartialEq<RopeSlice<'a>> for std::borrow::Cow<'b, str> {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        **self == *other
+    }
+}
+
+impl<'a> std::cmp::PartialEq<Rope> for RopeSlice<'a> {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        *self == other.slice(..)
+    }
+}
+
+impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for Rope {
+    #[inline]
+    fn eq(&self, other: &RopeSlice<'a>) -> bool {
+        self.slice(..) == *other
+    }
+}
+
+impl<'a> std::cmp::Eq for RopeSlice<'a> {}
+
+impl<'a> std::cmp::Ord for RopeSlice<'a> {
+    #[inline]
+    fn cmp(&self, other: &RopeSlice<'a>) -> std::cmp::Ordering {
+        cmp_chunks(self.chunks(), other.chunks())
     }
 }
 
-impl<'a, 'b> std::cmp::PartialEq<RopeSlice<'a>> for &'b str {
+impl<'a, 'b> std::cmp::PartialOrd<RopeSlice<'b>> for RopeSlice<'a> {
     #[inline]
-    fn eq(&self, other: &RopeSlice<'a>) -> bool {
-        other == self
+    fn partial_cmp(&self, other: &RopeSlice<'b>) -> Option<std::cmp::Ordering> {
+        Some(cmp_chunks(self.chunks(), other.chunks()))
     }
 }
 
-impl<'a> std::cmp::PartialEq<str> for RopeSlice<'a> {
+impl<'a, 'b> std::cmp::PartialOrd<&'b str> for RopeSlice<'a> {
     #[inline]
-    fn eq(&self, other: &str) -> bool {
-        std::cmp::PartialEq::<&str>::eq(self, &other)
+    fn partial_cmp(&self, other: &&'b str) -> Option<std::cmp::Ordering> {
+        Some(cmp_chunks(self.chunks(), std::iter::once(*other)))
     }
 }
 
-impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for str {
+impl<'a> std::cmp::PartialOrd<RopeSlice<'a>> for &str {
     #[inline]
-    fn eq(&self, other: &RopeSlice<'a>) -> bool {
-        std::cmp::PartialEq::<&str>::eq(other, &self)
+    fn partial_cmp(&self, other: &RopeSlice<'a>) -> Option<std::cmp::Ordering> {
+        Some(cmp_chunks(std::iter::once(*self), other.chunks()))
     }
 }
 
-impl<'a> std::cmp::PartialEq<String> for RopeSlice<'a> {
+impl<'a> std::cmp::PartialOrd<str> for RopeSlice<'a> {
     #[inline]
-    fn eq(&self, other: &String) -> bool {
-        self == other.as_str()
+    fn partial_cmp(&self, other: &str) -> Option<std::cmp::Ordering> {
+        Some(cmp_chunks(self.chunks(), std::iter::once(other)))
     }
 }
 
-impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for String {
+impl<'a> std::cmp::PartialOrd<RopeSlice<'a>> for str {
     #[inline]
-    fn eq(&self, other: &RopeSlice<'a>) -> bool {
-        self.as_str() == other
+    fn partial_cmp(&self, other: &RopeSlice<'a>) -> Option<std::cmp::Ordering> {
+        Some(cmp_chunks(std::iter::once(self), other.chunks()))
     }
 }
 
-impl<'a, 'b> std::cmp::PartialEq<std::borrow::Cow<'b, str>> for RopeSlice<'a> {
+impl<'a> std::cmp::PartialOrd<String> for RopeSlice<'a> {
     #[inline]
-    fn eq(&self, other: &std::borrow::Cow<'b, str>) -> bool {
-        *self == **other
+    fn partial_cmp(&self, other: &String) -> Option<std::cmp::Ordering> {
+        self.partial_cmp(other.as_str())
     }
 }
 
-impl<'a, 'b> std::cmp::PartialEq<RopeSlice<'a>> for std::borrow::Cow<'b, str> {
+impl<'a> std::cmp::PartialOrd<RopeSlice<'a>> for String {
     #[inline]
-    fn eq(&self, other: &RopeSlice<'a>) -> bool {
-        **self == *other
+    fn partial_cmp(&self, other: &RopeSlice<'a>) -> Option<std::cmp::Ordering> {
+        self.as_str().partial_cmp(other)
     }
 }
 
-impl<'a> std::cmp::PartialEq<Rope> for RopeSlice<'a> {
+impl<'a> std::cmp::PartialOrd<Rope> for RopeSlice<'a> {
     #[inline]
-    fn eq(&self, other: &Rope) -> bool {
-        *self == other.slice(..)
+    fn partial_cmp(&self, other: &Rope) -> Option<std::cmp::Ordering> {
+        self.partial_cmp(&other.slice(..))
     }
 }
 
-impl<'a> std::cmp::PartialEq<RopeSlice<'a>> for Rope {
+impl<'a> std::cmp::PartialOrd<RopeSlice<'a>> for Rope {
     #[inline]
-    fn eq(&self, other: &RopeSlice<'a>) -> bool {
-        self.slice(..) == *other
+    fn partial_cmp(&self, other: &RopeSlice<'a>) -> Option<std::cmp::Ordering> {
+        self.slice(..).partial_cmp(other)
+    }
+}
+
+/// Lexicographically compares the texts made up of two sequences of
+/// chunks, which may be split at different points.
+///
+/// Like `str`'s ordering, this compares bytes, which for utf8 is the same
+/// as comparing chars.
+pub(crate) fn cmp_chunks<'a, 'b, A, B>(mut itr_1: A, mut itr_2: B) -> std::cmp::Ordering
+where
+    A: Iterator<Item = &'a str>,
+    B: Iterator<Item = &'b str>,
+{
+    let mut chunk1: &[u8] = &[];
+    let mut chunk2: &[u8] = &[];
+    loop {
+        while chunk1.is_empty() {
+            match itr_1.next() {
+                Some(chunk) => chunk1 = chunk.as_bytes(),
+                None => break,
+            }
+        }
+        while chunk2.is_empty() {
+            match itr_2.next() {
+                Some(chunk) => chunk2 = chunk.as_bytes(),
+                None => break,
+            }
+        }
+
+        match (chunk1.is_empty(), chunk2.is_empty()) {
+            (true, true) => return std::cmp::Ordering::Equal,
+            (true, false) => return std::cmp::Ordering::Less,
+            (false, true) => return std::cmp::Ordering::Greater,
+            (false, false) => {}
+        }
+
+        let len = chunk1.len().min(chunk2.len());
+        match chunk1[..len].cmp(&chunk2[..len]) {
+            std::cmp::Ordering::Equal => {
+                chunk1 = &chunk1[len..];
+                chunk2 = &chunk2[len..];
+            }
+            ordering => return ordering,
+        }
+    }
+}
+
+/// Adapts a sequence of chunks to have LF line endings, by yielding each
+/// CRLF or CR as a separate "\n" chunk.
+struct LfChunks<'a, I> {
+    chunks: I,
+    rest: &'a str,
+    // Whether the last chunk yielded was for a CR, whose LF (if any)
+    // still needs to be skipped.
+    after_cr: bool,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> LfChunks<'a, I> {
+    fn new(chunks: I) -> LfChunks<'a, I> {
+        LfChunks {
+            chunks,
+            rest: "",
+            after_cr: false,
+        }
+    }
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for LfChunks<'a, I> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            while self.rest.is_empty() {
+                self.rest = self.chunks.next()?;
+            }
+            if self.after_cr {
+                self.after_cr = false;
+                if self.rest.starts_with('\n') {
+                    self.rest = &self.rest[1..];
+                    continue;
+                }
+            }
+            break;
+        }
+
+        match self.rest.bytes().position(|b| b == b'\r') {
+            Some(0) => {
+                self.rest = &self.rest[1..];
+                self.after_cr = true;
+                Some("\n")
+            }
+            Some(i) => {
+                let chunk = &self.rest[..i];
+                self.rest = &self.rest[i..];
+                Some(chunk)
+            }
+            None => Some(std::mem::take(&mut self.rest)),
+        }
     }
 }
 
@@ -552,6 +1614,20 @@ mod tests {
         assert_eq!(s.len_lines(), 1);
     }
 
+    #[test]
+    fn len_utf16_cu_01() {
+        let text = "a\u{1F600}せ".repeat(20);
+        let r = Rope::from_str(&text);
+        assert_eq!(r.slice(..).len_utf16_cu(), 80);
+
+        for start in 0..r.len_chars() {
+            for &end in &[start, start + 1, (start + 7).min(60), 60] {
+                let s = r.slice(start..end);
+                assert_eq!(s.len_utf16_cu(), s.to_string().encode_utf16().count());
+            }
+        }
+    }
+
     #[test]
     fn byte_to_char_01() {
         let r = Rope::from_str(TEXT);
@@ -594,6 +1670,27 @@ mod tests {
         assert_eq!(36, s.char_to_byte(14));
     }
 
+    #[test]
+    fn byte_to_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        for byte_idx in 0..=s.len_bytes() {
+            let char_idx = s.byte_to_char(byte_idx);
+            assert_eq!(s.char_to_line(char_idx), s.byte_to_line(byte_idx));
+        }
+        assert_eq!(1, r.slice(43..43).byte_to_line(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_to_line_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        s.byte_to_line(s.len_bytes() + 1);
+    }
+
     #[test]
     fn char_to_line_01() {
         let r = Rope::from_str(TEXT_LINES);
@@ -633,6 +1730,30 @@ mod tests {
         s.char_to_line(63);
     }
 
+    #[test]
+    fn line_to_byte_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        for line_idx in 0..=s.len_lines() {
+            let char_idx = s.line_to_char(line_idx);
+            assert_eq!(s.char_to_byte(char_idx), s.line_to_byte(line_idx));
+        }
+
+        let s = r.slice(43..43);
+        assert_eq!(0, s.line_to_byte(0));
+        assert_eq!(0, s.line_to_byte(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn line_to_byte_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        s.line_to_byte(4);
+    }
+
     #[test]
     fn line_to_char_01() {
         let r = Rope::from_str(TEXT_LINES);
@@ -662,6 +1783,51 @@ mod tests {
         s.line_to_char(4);
     }
 
+    #[test]
+    fn get_index_conversions_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(88..102);
+
+        assert_eq!(Some(3), s.get_byte_to_char(5));
+        assert_eq!(Some(14), s.get_byte_to_char(36));
+        assert_eq!(None, s.get_byte_to_char(37));
+
+        assert_eq!(Some(6), s.get_char_to_byte(4));
+        assert_eq!(Some(36), s.get_char_to_byte(14));
+        assert_eq!(None, s.get_char_to_byte(15));
+
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        assert_eq!(Some(1), s.get_char_to_line(25));
+        assert_eq!(Some(3), s.get_char_to_line(62));
+        assert_eq!(None, s.get_char_to_line(63));
+
+        assert_eq!(Some(54), s.get_line_to_char(2));
+        assert_eq!(Some(62), s.get_line_to_char(3));
+        assert_eq!(None, s.get_line_to_char(4));
+    }
+
+    #[test]
+    fn byte_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(34..100);
+        let text = &TEXT[34..118];
+
+        assert_eq!(s.len_bytes(), text.len());
+        for (i, &b) in text.as_bytes().iter().enumerate() {
+            assert_eq!(s.byte(i), b);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_02() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(34..100);
+        s.byte(84);
+    }
+
     #[test]
     fn char_01() {
         let r = Rope::from_str(TEXT);
@@ -693,6 +1859,17 @@ mod tests {
         s.char(0);
     }
 
+    #[test]
+    fn get_char_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(34..100);
+
+        assert_eq!(s.get_char(0), Some('t'));
+        assert_eq!(s.get_char(65), Some('な'));
+        assert_eq!(s.get_char(66), None);
+        assert_eq!(r.slice(43..43).get_char(0), None);
+    }
+
     #[test]
     fn line_01() {
         let r = Rope::from_str(TEXT_LINES);
@@ -731,6 +1908,223 @@ mod tests {
         s.line(3);
     }
 
+    #[test]
+    fn get_line_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        assert_eq!(s.get_line(1).unwrap(), "Aren't you glad we're alive?\n");
+        assert_eq!(s.get_line(2).unwrap(), "こんにちは、みん");
+        assert!(s.get_line(3).is_none());
+        assert_eq!(r.slice(43..43).get_line(0).unwrap(), "");
+    }
+
+    #[test]
+    fn chunk_at_char_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        let mut text = String::new();
+        let mut last_chunk_start = None;
+        for i in 0..(s.len_chars() + 1) {
+            let (chunk, b, c, l) = s.chunk_at_char(i);
+            let chunk_chars = chunk.chars().count();
+            assert!(c <= i && (i < c + chunk_chars || i == s.len_chars()));
+            assert_eq!(chunk, s.slice(c..(c + chunk_chars)));
+            assert_eq!(b, s.char_to_byte(c));
+            assert_eq!(l, s.char_to_line(c));
+
+            if last_chunk_start != Some(c) {
+                text.push_str(chunk);
+                last_chunk_start = Some(c);
+            }
+        }
+        assert_eq!(text, s);
+    }
+
+    #[test]
+    fn chunk_at_char_02() {
+        let r = Rope::from_str(TEXT);
+        assert_eq!(("", 0, 0, 0), r.slice(43..43).chunk_at_char(0));
+        assert_eq!(("", 0, 0, 0), r.slice(..0).chunk_at_char(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunk_at_char_03() {
+        let r = Rope::from_str(TEXT);
+        r.slice(34..96).chunk_at_char(63);
+    }
+
+    #[test]
+    fn chunk_at_byte_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(88..102);
+
+        for i in 0..(s.len_bytes() + 1) {
+            let (chunk, b, c, l) = s.chunk_at_byte(i);
+            assert!(b <= i && (i < b + chunk.len() || i == s.len_bytes()));
+            assert_eq!(chunk, s.slice(c..s.byte_to_char(b + chunk.len())));
+            assert_eq!(c, s.byte_to_char(b));
+            assert_eq!(0, l);
+        }
+        assert_eq!(("", 0, 0, 0), r.slice(3..3).chunk_at_byte(0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunk_at_byte_02() {
+        let r = Rope::from_str(TEXT);
+        r.slice(88..102).chunk_at_byte(40);
+    }
+
+    #[test]
+    fn chunk_at_line_break_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let s = r.slice(34..96);
+
+        assert_eq!(s.chunk_at_line_break(0), s.chunk_at_char(0));
+        assert_eq!(s.chunk_at_line_break(3), s.chunk_at_char(62));
+        for i in 1..s.len_lines() {
+            // The line break at the end of line `i - 1`.
+            let char_idx = s.line_to_char(i) - 1;
+            assert_eq!(s.chunk_at_line_break(i), s.chunk_at_char(char_idx));
+        }
+        assert_eq!(("", 0, 0, 0), r.slice(43..43).chunk_at_line_break(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunk_at_line_break_02() {
+        let r = Rope::from_str(TEXT_LINES);
+        r.slice(34..96).chunk_at_line_break(4);
+    }
+
+    #[test]
+    fn find_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(5..);
+
+        assert_eq!(Some(7..26), s.find("  How're you doing?"));
+        assert_eq!(Some(89..92), s.find(&"ちは、".to_string()));
+        assert_eq!(Some(0..1), s.find(' '));
+        assert_eq!(Some(12..13), s.find(&['z', '\''][..]));
+        assert_eq!(Some(86..87), s.find(|c: char| !c.is_ascii()));
+        assert_eq!(None, s.find('Z'));
+        assert_eq!(None, s.find(""));
+    }
+
+    #[test]
+    fn search_01() {
+        use std::ops::Range;
+        use ChunkSearcher;
+
+        // Naive substring search, carrying the end of each chunk over to
+        // the next one.
+        struct Naive {
+            needle: &'static str,
+            carry: String,
+            carry_idx: usize,
+            next_byte_idx: usize,
+        }
+
+        impl ChunkSearcher for Naive {
+            fn search_chunk(&mut self, chunk: &str, byte_idx: usize, out: &mut Vec<Range<usize>>) {
+                assert_eq!(self.next_byte_idx, byte_idx);
+                self.next_byte_idx += chunk.len();
+                self.carry.push_str(chunk);
+                let mut from = 0;
+                while let Some(i) = self.carry[from..].find(self.needle) {
+                    let start = self.carry_idx + from + i;
+                    out.push(start..(start + self.needle.len()));
+                    from += i + self.needle.len();
+                }
+                let mut keep = from.max(self.carry.len().saturating_sub(self.needle.len()));
+                while !self.carry.is_char_boundary(keep) {
+                    keep += 1;
+                }
+                self.carry.drain(..keep);
+                self.carry_idx += keep;
+            }
+        }
+
+        let r = Rope::from_str(&TEXT_LINES.repeat(3));
+        let s = r.slice(7..290);
+        for &needle in &["o", "you", "ちは、", "?\nAren't", "missing"] {
+            let searcher = Naive {
+                needle,
+                carry: String::new(),
+                carry_idx: 0,
+                next_byte_idx: 0,
+            };
+            let found: Vec<_> = s.search(searcher).collect();
+            let expected: Vec<_> = s.matches(needle).collect();
+            assert_eq!(expected, found);
+        }
+    }
+
+    #[test]
+    fn match_indices_01() {
+        let r = Rope::from_str(&"ab, ".repeat(20));
+        let s = r.slice(1..);
+        let m: Vec<_> = s.match_indices(", ").map(|(i, t)| (i, t.to_string())).collect();
+        assert_eq!(20, m.len());
+        for (n, &(idx, ref text)) in m.iter().enumerate() {
+            assert_eq!(1 + n * 4, idx);
+            assert_eq!(", ", text);
+        }
+        assert_eq!(0, s.match_indices('x').count());
+    }
+
+    #[test]
+    fn split_01() {
+        let r = Rope::from_str(TEXT_LINES);
+        let pieces: Vec<String> = r.split('\n').map(|p| p.to_string()).collect();
+        let expected: Vec<&str> = TEXT_LINES.split('\n').collect();
+        assert_eq!(expected, pieces);
+
+        let pieces: Vec<String> = r.split("  ").map(|p| p.to_string()).collect();
+        let expected: Vec<&str> = TEXT_LINES.split("  ").collect();
+        assert_eq!(expected, pieces);
+
+        let r = Rope::from_str("");
+        assert_eq!(vec![""], r.split(',').collect::<Vec<_>>());
+        let r = Rope::from_str(",a,");
+        assert_eq!(vec!["", "a", ""], r.split(',').collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn trim_matches_01() {
+        let text = format!("{}{}{}", "ab".repeat(10), TEXT, "ba".repeat(10));
+        let r = Rope::from_str(&text);
+        assert!(r.chunks().count() > 3);
+
+        assert_eq!(text.trim_matches(&['a', 'b'][..]), r.trim_matches(&['a', 'b'][..]));
+        assert_eq!(text.trim_start_matches("ab"), r.trim_start_matches("ab"));
+        assert_eq!(text.trim_end_matches("ab"), r.trim_end_matches("ab"));
+        assert_eq!(text.trim_end_matches("ba"), r.trim_end_matches("ba"));
+        assert_eq!(text.trim_matches('a'), r.trim_matches('a'));
+        assert_eq!(text, r.trim_matches(""));
+
+        let r = Rope::from_str("aaaa");
+        assert_eq!("", r.trim_matches('a'));
+        assert_eq!("", r.trim_start_matches("aa"));
+        assert_eq!("a", r.slice(1..).trim_end_matches("aa"));
+    }
+
+    #[test]
+    fn trim_matches_02() {
+        // Trimming stops as soon as no more matches are possible.
+        let r = Rope::from_str(TEXT);
+        let mut seen = 0;
+        let trimmed = r.trim_start_matches(|c: char| {
+            seen += 1;
+            c == 'H'
+        });
+        assert_eq!(&TEXT[1..], trimmed);
+        assert_eq!(2, seen);
+    }
+
     #[test]
     fn slice_01() {
         let r = Rope::from_str(TEXT);
@@ -789,6 +2183,41 @@ mod tests {
         s.slice(37..39);
     }
 
+    #[test]
+    fn get_slice_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(5..43);
+
+        assert_eq!(s.get_slice(3..19).unwrap(), &TEXT[8..24]);
+        assert_eq!(s.get_slice(38..).unwrap(), "");
+        assert!(s.get_slice(21..20).is_none());
+        assert!(s.get_slice(37..39).is_none());
+        assert!(s.get_slice(39..).is_none());
+    }
+
+    #[test]
+    fn byte_slice_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(88..102);
+
+        // ?  こんにちは、みんなさん
+
+        assert_eq!(s.byte_slice(..), s);
+        assert_eq!(s.byte_slice(3..12), "こんに");
+        assert_eq!(s.byte_slice(2..), &TEXT[90..124]);
+        assert_eq!(s.byte_slice(36..), "");
+        assert_eq!(s.byte_slice(3..12).byte_slice(3..6), "ん");
+    }
+
+    #[test]
+    #[should_panic]
+    fn byte_slice_02() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(88..102);
+
+        s.byte_slice(3..11);
+    }
+
     #[test]
     fn eq_str_01() {
         let r = Rope::from_str(TEXT);
@@ -871,6 +2300,75 @@ mod tests {
         assert_eq!(s, s);
     }
 
+    #[test]
+    fn cmp_rope_slice_01() {
+        let r = Rope::from_str(TEXT);
+        let s1 = r.slice(43..97);
+        let s2 = r.slice(43..97);
+        let s3 = r.slice(43..96);
+        let s4 = r.slice(44..97);
+
+        assert_eq!(s1.cmp(&s2), std::cmp::Ordering::Equal);
+        assert!(s3 < s1);
+        assert!(s1 > s3);
+        assert_eq!(s1 < s4, TEXT[43..] < TEXT[44..]);
+        assert_eq!(r.slice(0..0).cmp(&r.slice(5..5)), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn cmp_str_01() {
+        let r = Rope::from_str(TEXT);
+        let slice = r.slice(..);
+        let s: String = TEXT.into();
+
+        assert!(slice <= TEXT && slice >= TEXT);
+        assert!(slice <= s && s <= slice);
+        assert!(slice < "Hello there!  How're you doing?  It's a fine day, isn't it?  Z");
+        assert!("Hello there!  How're you doing?  It's a fine day, isn't it?  A" < slice);
+        assert!(slice > *"Hello");
+        assert!(*"Hello" < slice);
+        assert!(r.slice(..5) < "Hello there");
+        assert!(r.slice(..5) < r);
+        assert!(r > r.slice(..5));
+    }
+
+    #[test]
+    fn eq_ignore_line_endings_01() {
+        // CRLF pairs, some of them split across chunks.
+        let text_lf = "Hello\nthere!\n\nこんにちは\n".repeat(4);
+        let text_crlf = text_lf.replace('\n', "\r\n");
+        let text_cr = text_lf.replace('\n', "\r");
+        let r_lf = Rope::from_str(&text_lf);
+        let r_crlf = Rope::from_str(&format!("x{}", text_crlf));
+        let r_cr = Rope::from_str(&text_cr);
+
+        let s_lf = r_lf.slice(..);
+        let s_crlf = r_crlf.slice(1..);
+        let s_cr = r_cr.slice(..);
+        assert!(s_lf.eq_ignore_line_endings(&s_crlf));
+        assert!(s_crlf.eq_ignore_line_endings(&s_cr));
+        assert!(s_cr.eq_ignore_line_endings(&s_lf));
+        assert!(r_crlf.slice(1..7).eq_ignore_line_endings(&r_lf.slice(..6)));
+        assert!(r_crlf.slice(1..6).eq_ignore_line_endings(&r_lf.slice(..5)));
+        assert!(r_crlf.slice(..0).eq_ignore_line_endings(&r_lf.slice(..0)));
+    }
+
+    #[test]
+    fn eq_ignore_line_endings_02() {
+        let r1 = Rope::from_str("a\r\n\nb");
+        let r2 = Rope::from_str("a\n\r\nb");
+        let r3 = Rope::from_str("a\r\rb");
+        let r4 = Rope::from_str("a\nb");
+        let r5 = Rope::from_str("a\n\n\u{2028}b");
+
+        assert!(r1.eq_ignore_line_endings(&r2.slice(..)));
+        assert!(r2.eq_ignore_line_endings(&r3.slice(..)));
+        assert!(!r1.eq_ignore_line_endings(&r4.slice(..)));
+        assert!(!r4.eq_ignore_line_endings(&r1.slice(..)));
+        assert!(!r1.eq_ignore_line_endings(&r5.slice(..)));
+        assert!(!r1.eq_ignore_line_endings(&r1.slice(..4)));
+    }
+
     #[test]
     fn to_rope_01() {
         let r1 = Rope::from_str(TEXT);
@@ -0,0 +1,249 @@
+use std;
+use std::ops::Deref;
+
+use iter::{Bytes, Chars, Chunks, Lines};
+use rope::Rope;
+use slice::{CharIdxRange, RopeSlice};
+use text::RopeText;
+
+/// An immutable snapshot of a `Rope`'s text.
+///
+/// Snapshots are meant to be handed off to other threads (e.g. for search
+/// or syntax highlighting) while the original `Rope` keeps being edited.
+/// Taking a snapshot is cheap, since it shares its data with the `Rope`,
+/// and edits to the `Rope` afterwards don't affect it.
+///
+/// `RopeSnapshot` is `Send + Sync`, and derefs to `Rope`, so the full
+/// read-only `Rope` API is available on it.  There's no way to edit the
+/// text through a snapshot: use `to_rope()` to get an editable copy.
+///
+/// Created by [`Rope::snapshot()`](struct.Rope.html#method.snapshot).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// use std::thread;
+///
+/// let mut rope = Rope::from_str("Hello world!");
+/// let snapshot = rope.snapshot();
+///
+/// let handle = thread::spawn(move || snapshot.chars().filter(|c| *c == 'o').count());
+/// rope.insert(0, "Oh, ");
+///
+/// assert_eq!(handle.join().unwrap(), 2);
+/// assert_eq!(rope, "Oh, Hello world!");
+/// ```
+#[derive(Clone)]
+pub struct RopeSnapshot {
+    rope: Rope,
+}
+
+impl RopeSnapshot {
+    pub(crate) fn new(rope: &Rope) -> RopeSnapshot {
+        let mut rope = rope.clone();
+        rope.clear_dirty_range();
+        RopeSnapshot { rope }
+    }
+
+    /// Returns a slice of the whole text.
+    #[inline]
+    pub fn as_slice(&self) -> RopeSlice<'_> {
+        self.rope.slice(..)
+    }
+
+    /// Returns an editable copy of the text.
+    ///
+    /// The copy shares its data with the snapshot, so this runs in O(1)
+    /// time.
+    #[inline]
+    pub fn to_rope(&self) -> Rope {
+        self.rope.clone()
+    }
+}
+
+impl Deref for RopeSnapshot {
+    type Target = Rope;
+
+    #[inline]
+    fn deref(&self) -> &Rope {
+        &self.rope
+    }
+}
+
+impl RopeText for RopeSnapshot {
+    #[inline]
+    fn len_bytes(&self) -> usize {
+        self.rope.len_bytes()
+    }
+
+    #[inline]
+    fn len_chars(&self) -> usize {
+        self.rope.len_chars()
+    }
+
+    #[inline]
+    fn len_lines(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    #[inline]
+    fn byte_to_char(&self, byte_idx: usize) -> usize {
+        self.rope.byte_to_char(byte_idx)
+    }
+
+    #[inline]
+    fn char_to_byte(&self, char_idx: usize) -> usize {
+        self.rope.char_to_byte(char_idx)
+    }
+
+    #[inline]
+    fn char_to_line(&self, char_idx: usize) -> usize {
+        self.rope.char_to_line(char_idx)
+    }
+
+    #[inline]
+    fn line_to_char(&self, line_idx: usize) -> usize {
+        self.rope.line_to_char(line_idx)
+    }
+
+    #[inline]
+    fn char(&self, char_idx: usize) -> char {
+        self.rope.char(char_idx)
+    }
+
+    #[inline]
+    fn line(&self, line_idx: usize) -> RopeSlice<'_> {
+        self.rope.line(line_idx)
+    }
+
+    #[inline]
+    fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        self.rope.slice(range)
+    }
+
+    #[inline]
+    fn bytes(&self) -> Bytes<'_> {
+        self.rope.bytes()
+    }
+
+    #[inline]
+    fn chars(&self) -> Chars<'_> {
+        self.rope.chars()
+    }
+
+    #[inline]
+    fn lines(&self) -> Lines<'_> {
+        self.rope.lines()
+    }
+
+    #[inline]
+    fn chunks(&self) -> Chunks<'_> {
+        self.rope.chunks()
+    }
+}
+
+impl From<RopeSnapshot> for Rope {
+    #[inline]
+    fn from(snapshot: RopeSnapshot) -> Self {
+        snapshot.rope
+    }
+}
+
+impl std::fmt::Debug for RopeSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("RopeSnapshot").field(&self.rope).finish()
+    }
+}
+
+impl std::fmt::Display for RopeSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.rope, f)
+    }
+}
+
+impl std::cmp::PartialEq<RopeSnapshot> for RopeSnapshot {
+    #[inline]
+    fn eq(&self, other: &RopeSnapshot) -> bool {
+        self.rope == other.rope
+    }
+}
+
+impl std::cmp::Eq for RopeSnapshot {}
+
+impl<'a> std::cmp::PartialEq<&'a str> for RopeSnapshot {
+    #[inline]
+    fn eq(&self, other: &&'a str) -> bool {
+        self.rope == *other
+    }
+}
+
+impl std::cmp::PartialEq<str> for RopeSnapshot {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.rope == *other
+    }
+}
+
+impl std::cmp::PartialEq<Rope> for RopeSnapshot {
+    #[inline]
+    fn eq(&self, other: &Rope) -> bool {
+        self.rope == *other
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    const TEXT: &str = "Hello there!\nHow're you doing?\r\nこんにちは！";
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn snapshot_01() {
+        assert_send_sync::<RopeSnapshot>();
+
+        let mut r = Rope::from_str(TEXT);
+        let snapshot = r.snapshot();
+        r.remove(0..13);
+        r.insert(0, "Hi!\n");
+
+        assert_eq!(snapshot, TEXT);
+        assert_eq!(snapshot.len_lines(), 3);
+        assert_eq!(snapshot.line(1), "How're you doing?\r\n");
+        assert_eq!(r, "Hi!\nHow're you doing?\r\nこんにちは！");
+    }
+
+    #[test]
+    fn snapshot_02() {
+        let r = Rope::from_str(TEXT);
+        let snapshot = r.snapshot();
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let snapshot = snapshot.clone();
+                thread::spawn(move || snapshot.line(i % 3).to_string())
+            })
+            .collect();
+        let lines: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(lines[0], "Hello there!\n");
+        assert_eq!(lines[1], "How're you doing?\r\n");
+        assert_eq!(lines[2], "こんにちは！");
+        assert_eq!(lines[3], "Hello there!\n");
+    }
+
+    #[test]
+    fn snapshot_03() {
+        let snapshot = Rope::from_str(TEXT).snapshot();
+        let mut r = snapshot.to_rope();
+        r.insert(0, "Oh, ");
+
+        assert_eq!(snapshot, TEXT);
+        assert_eq!(r.len_chars(), snapshot.len_chars() + 4);
+    }
+}
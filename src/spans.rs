@@ -0,0 +1,733 @@
+use std;
+use std::ops::Range;
+
+use edit::Edit;
+use iter::Runs;
+use rope::Rope;
+
+/// A node of the balanced (AVL) tree that `Spans` stores its runs in.
+///
+/// Each node holds one run, and caches the total length and height of
+/// its subtree.
+#[derive(Debug, Clone)]
+pub(crate) struct SpanNode<T> {
+    pub(crate) run: (usize, T),
+    pub(crate) left: SpanTree<T>,
+    pub(crate) right: SpanTree<T>,
+    len: usize,
+    height: usize,
+}
+
+pub(crate) type SpanTree<T> = Option<Box<SpanNode<T>>>;
+
+/// A sequence of value runs over a text, such as styles or annotations.
+///
+/// `Spans` divides the char indices `0..len()` into runs, each of which
+/// has a single value of type `T`.  It is meant to be kept alongside a
+/// `Rope` and updated with the same edits, so that e.g. syntax highlighting
+/// follows the text it applies to.  Adjacent runs with equal values are
+/// always merged.
+///
+/// Runs are stored in a balanced tree, so finding the run at an index
+/// takes O(log N) time, and an edit takes O((M + 1) log N) time, where N
+/// is the number of runs and M is the number of runs the edit adds.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, Spans};
+/// let mut rope = Rope::from_str("Hello world!");
+/// let mut spans = Spans::new(rope.len_chars(), "plain");
+/// spans.set(6..11, "bold");
+///
+/// // Edit the text and the spans together.
+/// rope.insert(6, "big ");
+/// spans.edit(6..6, 4);
+///
+/// let runs: Vec<_> = spans.iter().map(|(r, v)| (r, *v)).collect();
+/// assert_eq!(vec![(0..10, "plain"), (10..15, "bold"), (15..16, "plain")], runs);
+/// assert_eq!("world", rope.slice(10..15));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spans<T> {
+    root: SpanTree<T>,
+}
+
+impl<T: Clone + PartialEq> Spans<T> {
+    /// Creates a `Spans` of length `len`, with `value` throughout.
+    pub fn new(len: usize, value: T) -> Self {
+        Spans::from_runs(vec![(len, value)])
+    }
+
+    /// Total length of the `Spans`, in chars.
+    ///
+    /// Runs in O(1) time.
+    pub fn len(&self) -> usize {
+        tree_len(&self.root)
+    }
+
+    /// Returns whether the `Spans` has zero length.
+    ///
+    /// Runs in O(1) time.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the value at `char_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len()`).
+    pub fn value_at(&self, char_idx: usize) -> &T {
+        self.run_at(char_idx).1
+    }
+
+    /// Returns the char range and value of the run containing `char_idx`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `char_idx` is out of bounds (i.e. `char_idx >= len()`).
+    pub fn run_at(&self, char_idx: usize) -> (Range<usize>, &T) {
+        // Bounds check
+        assert!(
+            char_idx < self.len(),
+            "Attempt to index past end of Spans: char index {}, Spans length {}",
+            char_idx,
+            self.len()
+        );
+
+        let mut node = self.root.as_ref().unwrap();
+        let mut pos = 0;
+        loop {
+            let start = pos + tree_len(&node.left);
+            let end = start + node.run.0;
+            if char_idx < start {
+                node = node.left.as_ref().unwrap();
+            } else if char_idx < end {
+                return (start..end, &node.run.1);
+            } else {
+                pos = end;
+                node = node.right.as_ref().unwrap();
+            }
+        }
+    }
+
+    /// Sets the value of the chars in `range` to `value`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len()`).
+    pub fn set(&mut self, range: Range<usize>, value: T) {
+        let len = range.end.saturating_sub(range.start);
+        self.splice(range, vec![(len, value)]);
+    }
+
+    /// Updates the `Spans` for a text edit that replaced the chars in
+    /// `range` with `new_len` new chars.
+    ///
+    /// The new chars take on the value of the char just before `range`, or
+    /// of the first char if `range` is at the start.  Use `edit_with()` to
+    /// specify the value instead.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, if the end
+    /// is out of bounds (i.e. `end > len()`), or if `new_len` is non-zero
+    /// and the `Spans` is empty.
+    pub fn edit(&mut self, range: Range<usize>, new_len: usize) {
+        if new_len == 0 {
+            self.splice(range, Vec::new());
+        } else {
+            let value = self.value_at(range.start.saturating_sub(1)).clone();
+            self.splice(range, vec![(new_len, value)]);
+        }
+    }
+
+    /// Updates the `Spans` for a text edit that replaced the chars in
+    /// `range` with `new_len` new chars, which get `value`.
+    ///
+    /// Runs in O(log N) time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len()`).
+    pub fn edit_with(&mut self, range: Range<usize>, new_len: usize, value: T) {
+        self.splice(range, vec![(new_len, value)]);
+    }
+
+    /// Creates a new `Spans` from the runs in `range`.
+    ///
+    /// Runs in O(M + log N) time, where M is the number of runs in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or if the
+    /// end is out of bounds (i.e. `end > len()`).
+    pub fn slice(&self, range: Range<usize>) -> Spans<T> {
+        self.check_range(&range);
+
+        let runs = Runs::new(&self.root, range.start)
+            .take_while(|(r, _)| r.start < range.end)
+            .map(|(r, value)| {
+                let len = r.end.min(range.end) - r.start.max(range.start);
+                (len, value.clone())
+            })
+            .collect();
+        Spans::from_runs(runs)
+    }
+
+    /// Returns the range of lines of `rope` that need re-highlighting
+    /// after `edit`, where `self` holds the highlighting from before the
+    /// edit and `rope` is the text after it.
+    ///
+    /// The range covers every line touched by the edit.  Multi-line
+    /// constructs, such as block comments or strings, are accounted for
+    /// with `is_multiline`: if the edit is inside or at the edge of a run
+    /// whose value `is_multiline` returns true for, the range is extended
+    /// to cover the whole run.
+    ///
+    /// An edit can also create or end a multi-line construct, which only
+    /// the highlighter can know about.  The highlighter should keep going
+    /// past the end of the returned range until its state at the end of a
+    /// line matches what it was before the edit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::{Edit, Rope, Spans};
+    /// let mut rope = Rope::from_str("a\n/* x\ny\nz */\nb\nc\n");
+    /// let mut spans = Spans::new(rope.len_chars(), "code");
+    /// spans.set(2..14, "comment");
+    ///
+    /// // Edit the "y" inside the comment.
+    /// rope.insert(8, "yy");
+    /// let edit = Edit::insert(8, 2);
+    ///
+    /// let lines = spans.rehighlight_lines(&rope, &edit, |v| *v == "comment");
+    /// assert_eq!(1..4, lines);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `edit` doesn't fit both `self` and `rope`.
+    pub fn rehighlight_lines<F>(
+        &self,
+        rope: &Rope,
+        edit: &Edit,
+        mut is_multiline: F,
+    ) -> Range<usize>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let len = self.len();
+        assert!(edit.start <= edit.old_end && edit.old_end <= len);
+        assert_eq!(
+            len - (edit.old_end - edit.start) + (edit.new_end - edit.start),
+            rope.len_chars(),
+            "Edit doesn't match the Spans and Rope lengths"
+        );
+
+        // Extend over multi-line runs at either end of the edit.
+        let mut start = edit.start;
+        let mut end = edit.new_end;
+        if len > 0 {
+            let (run, value) = self.run_at(edit.start.saturating_sub(1));
+            if is_multiline(value) {
+                start = start.min(run.start);
+                if run.end >= edit.old_end {
+                    end = end.max(edit.shift(run.end));
+                }
+            }
+            let (run, value) = self.run_at(edit.old_end.min(len - 1));
+            if is_multiline(value) && run.end >= edit.old_end {
+                start = start.min(run.start);
+                end = end.max(edit.shift(run.end));
+            }
+        }
+
+        // The line of the last char in the range, or of `start` if the
+        // range is empty, e.g. for a removal that joined two lines.
+        let last = if end > start { end - 1 } else { start };
+        let line_of = |char_idx: usize| rope.char_to_line(char_idx).min(rope.len_lines() - 1);
+        line_of(start)..(line_of(last) + 1)
+    }
+
+    /// Creates an iterator over the runs of the `Spans`, yielding each
+    /// run's char range and value.
+    pub fn iter(&self) -> Runs<'_, T> {
+        Runs::new(&self.root, 0)
+    }
+
+    //-----------------------------------------------------------------------
+    // Internal utilities
+
+    fn from_runs(runs: Vec<(usize, T)>) -> Self {
+        let mut merged = Vec::with_capacity(runs.len());
+        for (len, value) in runs {
+            push_run(&mut merged, len, value);
+        }
+        let count = merged.len();
+        Spans {
+            root: build(&mut merged.into_iter(), count),
+        }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        // Bounds check
+        assert!(range.start <= range.end);
+        assert!(
+            range.end <= self.len(),
+            "Attempt to edit past end of Spans: range end {}, Spans length {}",
+            range.end,
+            self.len()
+        );
+    }
+
+    /// Replaces the runs over `range` with `insert`.
+    fn splice(&mut self, range: Range<usize>, insert: Vec<(usize, T)>) {
+        self.check_range(&range);
+
+        let (left, rest) = split(self.root.take(), range.start);
+        let (_, right) = split(rest, range.end - range.start);
+
+        // Take out the runs on either side of the range, so that they can
+        // be merged with the new runs if they have the same value.
+        let (mut root, before) = pop_last(left);
+        let (right, after) = pop_first(right);
+        let mut runs = Vec::with_capacity(insert.len() + 2);
+        for (len, value) in before.into_iter().chain(insert).chain(after) {
+            push_run(&mut runs, len, value);
+        }
+
+        for run in runs {
+            root = Some(join(root, run, None));
+        }
+        self.root = concat(root, right);
+    }
+}
+
+impl<T: Clone + PartialEq> std::cmp::PartialEq for Spans<T> {
+    fn eq(&self, other: &Spans<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+//===========================================================
+
+/// Pushes a run onto `runs`, merging it with the last run if they have
+/// the same value.  Empty runs are skipped.
+fn push_run<T: PartialEq>(runs: &mut Vec<(usize, T)>, len: usize, value: T) {
+    if len == 0 {
+        return;
+    }
+    if let Some(last) = runs.last_mut() {
+        if last.1 == value {
+            last.0 += len;
+            return;
+        }
+    }
+    runs.push((len, value));
+}
+
+/// Total length of the runs in `tree`.
+#[inline]
+pub(crate) fn tree_len<T>(tree: &SpanTree<T>) -> usize {
+    tree.as_ref().map_or(0, |node| node.len)
+}
+
+/// Height of `tree`, where an empty tree has a height of zero.
+#[inline]
+fn height<T>(tree: &SpanTree<T>) -> usize {
+    tree.as_ref().map_or(0, |node| node.height)
+}
+
+/// Creates a node, without rebalancing.
+fn node<T>(left: SpanTree<T>, run: (usize, T), right: SpanTree<T>) -> Box<SpanNode<T>> {
+    Box::new(SpanNode {
+        len: tree_len(&left) + run.0 + tree_len(&right),
+        height: height(&left).max(height(&right)) + 1,
+        run,
+        left,
+        right,
+    })
+}
+
+fn rotate_left<T>(n: SpanNode<T>) -> Box<SpanNode<T>> {
+    let SpanNode {
+        run, left, right, ..
+    } = n;
+    let SpanNode {
+        run: r_run,
+        left: r_left,
+        right: r_right,
+        ..
+    } = *right.unwrap();
+    node(Some(node(left, run, r_left)), r_run, r_right)
+}
+
+fn rotate_right<T>(n: SpanNode<T>) -> Box<SpanNode<T>> {
+    let SpanNode {
+        run, left, right, ..
+    } = n;
+    let SpanNode {
+        run: l_run,
+        left: l_left,
+        right: l_right,
+        ..
+    } = *left.unwrap();
+    node(l_left, l_run, Some(node(l_right, run, right)))
+}
+
+/// Joins two balanced trees with `run` between them into a balanced tree.
+///
+/// Runs in O(|height(left) - height(right)| + 1) time.
+fn join<T>(left: SpanTree<T>, run: (usize, T), right: SpanTree<T>) -> Box<SpanNode<T>> {
+    let (l_height, r_height) = (height(&left), height(&right));
+    if l_height > r_height + 1 {
+        join_right(*left.unwrap(), run, right)
+    } else if r_height > l_height + 1 {
+        join_left(left, run, *right.unwrap())
+    } else {
+        node(left, run, right)
+    }
+}
+
+/// `join()` for when `left` is more than one level taller than `right`.
+fn join_right<T>(left: SpanNode<T>, run: (usize, T), right: SpanTree<T>) -> Box<SpanNode<T>> {
+    let SpanNode {
+        run: l_run,
+        left: l_left,
+        right: l_right,
+        ..
+    } = left;
+    let l_left_height = height(&l_left);
+    if height(&l_right) <= height(&right) + 1 {
+        let new_right = node(l_right, run, right);
+        if new_right.height <= l_left_height + 1 {
+            node(l_left, l_run, Some(new_right))
+        } else {
+            rotate_left(*node(l_left, l_run, Some(rotate_right(*new_right))))
+        }
+    } else {
+        let new_right = join_right(*l_right.unwrap(), run, right);
+        let new_right_height = new_right.height;
+        let new_node = node(l_left, l_run, Some(new_right));
+        if new_right_height <= l_left_height + 1 {
+            new_node
+        } else {
+            rotate_left(*new_node)
+        }
+    }
+}
+
+/// `join()` for when `right` is more than one level taller than `left`.
+fn join_left<T>(left: SpanTree<T>, run: (usize, T), right: SpanNode<T>) -> Box<SpanNode<T>> {
+    let SpanNode {
+        run: r_run,
+        left: r_left,
+        right: r_right,
+        ..
+    } = right;
+    let r_right_height = height(&r_right);
+    if height(&r_left) <= height(&left) + 1 {
+        let new_left = node(left, run, r_left);
+        if new_left.height <= r_right_height + 1 {
+            node(Some(new_left), r_run, r_right)
+        } else {
+            rotate_right(*node(Some(rotate_left(*new_left)), r_run, r_right))
+        }
+    } else {
+        let new_left = join_left(left, run, *r_left.unwrap());
+        let new_left_height = new_left.height;
+        let new_node = node(Some(new_left), r_run, r_right);
+        if new_left_height <= r_right_height + 1 {
+            new_node
+        } else {
+            rotate_right(*new_node)
+        }
+    }
+}
+
+/// Concatenates two balanced trees.
+fn concat<T>(left: SpanTree<T>, right: SpanTree<T>) -> SpanTree<T> {
+    match pop_last(left) {
+        (left, Some(run)) => Some(join(left, run, right)),
+        (_, None) => right,
+    }
+}
+
+/// Splits `tree` at `char_idx`, cutting the run that spans it (if any)
+/// in two.
+///
+/// Runs in O(log N) time.
+fn split<T: Clone>(tree: SpanTree<T>, char_idx: usize) -> (SpanTree<T>, SpanTree<T>) {
+    let n = match tree {
+        Some(n) => n,
+        None => return (None, None),
+    };
+    let SpanNode {
+        run, left, right, ..
+    } = *n;
+    let start = tree_len(&left);
+    let end = start + run.0;
+    if char_idx <= start {
+        let (l_left, l_right) = split(left, char_idx);
+        (l_left, Some(join(l_right, run, right)))
+    } else if char_idx >= end {
+        let (r_left, r_right) = split(right, char_idx - end);
+        (Some(join(left, run, r_left)), r_right)
+    } else {
+        let (len, value) = run;
+        let cut = char_idx - start;
+        (
+            Some(join(left, (cut, value.clone()), None)),
+            Some(join(None, (len - cut, value), right)),
+        )
+    }
+}
+
+/// Removes the last run from `tree`, returning the rest of the tree and
+/// the run.
+fn pop_last<T>(tree: SpanTree<T>) -> (SpanTree<T>, Option<(usize, T)>) {
+    let n = match tree {
+        Some(n) => n,
+        None => return (None, None),
+    };
+    let SpanNode {
+        run, left, right, ..
+    } = *n;
+    if right.is_none() {
+        return (left, Some(run));
+    }
+    let (right, last) = pop_last(right);
+    (Some(join(left, run, right)), last)
+}
+
+/// Removes the first run from `tree`, returning the rest of the tree and
+/// the run.
+fn pop_first<T>(tree: SpanTree<T>) -> (SpanTree<T>, Option<(usize, T)>) {
+    let n = match tree {
+        Some(n) => n,
+        None => return (None, None),
+    };
+    let SpanNode {
+        run, left, right, ..
+    } = *n;
+    if left.is_none() {
+        return (right, Some(run));
+    }
+    let (left, first) = pop_first(left);
+    (Some(join(left, run, right)), first)
+}
+
+/// Builds a perfectly balanced tree from the next `count` runs.
+fn build<T, I>(runs: &mut I, count: usize) -> SpanTree<T>
+where
+    I: Iterator<Item = (usize, T)>,
+{
+    if count == 0 {
+        return None;
+    }
+    let l_count = count / 2;
+    let left = build(runs, l_count);
+    let run = runs.next().unwrap();
+    let right = build(runs, count - l_count - 1);
+    Some(node(left, run, right))
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(spans: &Spans<u32>) -> Vec<(Range<usize>, u32)> {
+        spans.iter().map(|(r, v)| (r, *v)).collect()
+    }
+
+    /// Checks the cached lengths and heights, and the balance, of `tree`,
+    /// returning its length and height.
+    fn check_tree(tree: &SpanTree<u32>) -> (usize, usize) {
+        match *tree {
+            Some(ref node) => {
+                let (l_len, l_height) = check_tree(&node.left);
+                let (r_len, r_height) = check_tree(&node.right);
+                assert!(node.run.0 > 0);
+                assert!(l_height <= r_height + 1 && r_height <= l_height + 1);
+                assert_eq!(l_len + node.run.0 + r_len, node.len);
+                assert_eq!(l_height.max(r_height) + 1, node.height);
+                (node.len, node.height)
+            }
+            None => (0, 0),
+        }
+    }
+
+    fn assert_tree(spans: &Spans<u32>) {
+        check_tree(&spans.root);
+        let r = runs(spans);
+        for pair in r.windows(2) {
+            assert!(pair[0].1 != pair[1].1);
+        }
+    }
+
+    #[test]
+    fn set_01() {
+        let mut spans = Spans::new(20, 0);
+        spans.set(5..10, 1);
+        spans.set(8..12, 2);
+        spans.set(0..5, 1);
+        assert_tree(&spans);
+        assert_eq!(vec![(0..8, 1), (8..12, 2), (12..20, 0)], runs(&spans));
+        assert_eq!(&1, spans.value_at(7));
+        assert_eq!(&2, spans.value_at(8));
+        assert_eq!(&0, spans.value_at(19));
+    }
+
+    #[test]
+    fn set_02() {
+        // Enough runs to need a deep tree.
+        let mut spans = Spans::new(100, 0);
+        for i in 0..50 {
+            spans.set((i * 2)..(i * 2 + 1), i as u32 + 1);
+        }
+        assert_tree(&spans);
+        assert!(spans.root.as_ref().unwrap().height > 2);
+        assert_eq!(100, runs(&spans).len());
+
+        spans.set(3..97, 0);
+        assert_tree(&spans);
+        assert_eq!(
+            vec![(0..1, 1), (1..2, 0), (2..3, 2), (3..98, 0), (98..99, 50), (99..100, 0)],
+            runs(&spans)
+        );
+    }
+
+    #[test]
+    fn edit_01() {
+        let mut spans = Spans::new(10, 0);
+        spans.set(3..6, 1);
+        spans.edit(6..6, 4);
+        spans.edit(0..0, 2);
+        assert_eq!(vec![(0..5, 0), (5..12, 1), (12..16, 0)], runs(&spans));
+
+        spans.edit(4..13, 0);
+        assert_tree(&spans);
+        assert_eq!(vec![(0..7, 0)], runs(&spans));
+
+        spans.edit_with(2..3, 1, 5);
+        assert_eq!(vec![(0..2, 0), (2..3, 5), (3..7, 0)], runs(&spans));
+        assert_eq!(7, spans.len());
+    }
+
+    #[test]
+    fn edit_02() {
+        let mut spans = Spans::new(0, 0);
+        spans.edit_with(0..0, 3, 7);
+        spans.edit(3..3, 2);
+        assert_eq!(vec![(0..5, 7)], runs(&spans));
+        spans.edit(0..5, 0);
+        assert!(spans.is_empty());
+        assert_eq!(0, spans.iter().count());
+    }
+
+    #[test]
+    fn edit_03() {
+        // Compare against a value per char, for a pseudo-random sequence of
+        // sets and edits.
+        let mut spans = Spans::new(50, 0);
+        let mut model = vec![0u32; 50];
+        let mut seed = 12345u32;
+        let mut rand = |n: usize| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as usize % n
+        };
+        for i in 0..500 {
+            let start = rand(model.len() + 1);
+            let end = start + rand(model.len() - start + 1).min(8);
+            let value = rand(4) as u32;
+            if i % 2 == 0 {
+                spans.set(start..end, value);
+                for v in &mut model[start..end] {
+                    *v = value;
+                }
+            } else {
+                let new_len = rand(6);
+                spans.edit_with(start..end, new_len, value);
+                model.splice(start..end, std::iter::repeat(value).take(new_len));
+            }
+            assert_tree(&spans);
+            assert_eq!(model.len(), spans.len());
+        }
+        for (r, v) in runs(&spans) {
+            assert!(model[r].iter().all(|m| *m == v));
+        }
+    }
+
+    #[test]
+    fn slice_01() {
+        let mut spans = Spans::new(30, 0);
+        for i in 0..10 {
+            spans.set((i * 3)..(i * 3 + 1), 1);
+        }
+        let s = spans.slice(4..11);
+        assert_tree(&s);
+        assert_eq!(vec![(0..2, 0), (2..3, 1), (3..5, 0), (5..6, 1), (6..7, 0)], runs(&s));
+        assert_eq!(Spans::new(0, 0), spans.slice(5..5));
+    }
+
+    #[test]
+    fn run_at_01() {
+        let mut spans = Spans::new(20, 0);
+        for i in 0..5 {
+            spans.set((i * 4)..(i * 4 + 2), 1);
+        }
+        assert_eq!((0..2, &1), spans.run_at(1));
+        assert_eq!((14..16, &0), spans.run_at(14));
+        assert_eq!((18..20, &0), spans.run_at(19));
+    }
+
+    #[test]
+    fn rehighlight_lines_01() {
+        let text = "a\nb /* c\nd\ne */ f\ng\nh\n";
+        let mut rope = Rope::from_str(text);
+        let mut spans = Spans::new(rope.len_chars(), false);
+        spans.set(4..16, true);
+
+        // An edit on a single line.
+        rope.insert(0, "xx");
+        assert_eq!(0..1, spans.rehighlight_lines(&rope, &Edit::insert(0, 2), |v| *v));
+        spans.edit(0..0, 2);
+
+        // Removing the char right after a multi-line run.
+        rope.remove(18..19);
+        assert_eq!(1..4, spans.rehighlight_lines(&rope, &Edit::remove(18..19), |v| *v));
+        spans.edit(18..19, 0);
+
+        // Inserting a line, outside of any multi-line run.
+        rope.insert(21, "i\n");
+        assert_eq!(5..6, spans.rehighlight_lines(&rope, &Edit::insert(21, 2), |v| *v));
+
+        // A removal at the very end.
+        let mut rope = Rope::from_str(text);
+        let spans = Spans::new(rope.len_chars(), false);
+        rope.remove(20..22);
+        assert_eq!(5..6, spans.rehighlight_lines(&rope, &Edit::remove(20..22), |v| *v));
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_03() {
+        let mut spans = Spans::new(10, 0);
+        spans.set(5..11, 1);
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(feature = "stats")]
+use std::cell::Cell;
+use std::sync::Arc;
+
+use rope::Rope;
+use tree::Node;
+
+/// Counts of the tree maintenance work done by a `Rope`'s edits.
+///
+/// This is meant for investigating the performance of edit patterns,
+/// e.g. to find out whether a slow workload is spending its time
+/// splitting and merging nodes, or copying nodes that are shared with
+/// clones of the `Rope`.
+///
+/// Only available with the `stats` feature.  See
+/// [`Rope::edit_stats()`](struct.Rope.html#method.edit_stats).
+#[cfg(feature = "stats")]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct EditStats {
+    /// Nodes that overflowed and were split in two.
+    pub splits: u64,
+    /// Pairs of underfull sibling nodes that were merged into one.
+    pub merges: u64,
+    /// Nodes allocated, including by splits and path copies.
+    pub allocations: u64,
+    /// Nodes that had to be copied before being edited, because they
+    /// were shared with another `Rope` (e.g. a clone).
+    pub path_copies: u64,
+    /// Other changes to the shape of the tree: text or children moved
+    /// between siblings, leaves compacted, and the tree growing or
+    /// shrinking in height.
+    pub rebalances: u64,
+}
+
+/// A tree maintenance event, as counted by `EditStats`.
+#[derive(Copy, Clone)]
+pub(crate) enum Event {
+    Split,
+    Merge,
+    Allocation,
+    PathCopy,
+    Rebalance,
+}
+
+// The stats of the edit currently being recorded on this thread, if any.
+#[cfg(feature = "stats")]
+thread_local!(static RECORDING: Cell<Option<EditStats>> = const { Cell::new(None) });
+
+/// Counts `event` towards the edit currently being recorded.
+///
+/// Splits and path copies also count as an allocation.  Does nothing
+/// without the `stats` feature.
+#[inline(always)]
+pub(crate) fn count(event: Event) {
+    #[cfg(feature = "stats")]
+    RECORDING.with(|recording| {
+        if let Some(mut stats) = recording.get() {
+            match event {
+                Event::Split => {
+                    stats.splits += 1;
+                    stats.allocations += 1;
+                }
+                Event::Merge => stats.merges += 1,
+                Event::Allocation => stats.allocations += 1,
+                Event::PathCopy => {
+                    stats.path_copies += 1;
+                    stats.allocations += 1;
+                }
+                Event::Rebalance => stats.rebalances += 1,
+            }
+            recording.set(Some(stats));
+        }
+    });
+    #[cfg(not(feature = "stats"))]
+    let _ = event;
+}
+
+/// `Arc::make_mut()` for tree nodes, counting the path copies it makes.
+#[inline(always)]
+pub(crate) fn make_mut(node: &mut Arc<Node>) -> &mut Node {
+    // Weak references (e.g. from a `WeakRope`) also force a copy.
+    if cfg!(feature = "stats") && (Arc::strong_count(node) > 1 || Arc::weak_count(node) > 0) {
+        count(Event::PathCopy);
+    }
+    Arc::make_mut(node)
+}
+
+/// Records the events counted during an edit, for adding to the edited
+/// `Rope`'s stats.
+///
+/// Recordings don't nest: edits made as part of another edit (e.g. the
+/// `split_off()` and `append()` done by a large `insert()`) are counted
+/// towards the outer one.
+pub(crate) struct Recording {
+    #[cfg(feature = "stats")]
+    active: bool,
+}
+
+impl Recording {
+    #[inline(always)]
+    pub fn start() -> Recording {
+        #[cfg(feature = "stats")]
+        {
+            let active = RECORDING.with(|recording| {
+                if recording.get().is_none() {
+                    recording.set(Some(EditStats::default()));
+                    true
+                } else {
+                    false
+                }
+            });
+            Recording { active }
+        }
+        #[cfg(not(feature = "stats"))]
+        Recording {}
+    }
+
+    /// Adds the recorded events to `rope`'s stats.
+    #[inline(always)]
+    pub fn finish(self, rope: &mut Rope) {
+        #[cfg(feature = "stats")]
+        {
+            if self.active {
+                let stats = RECORDING.with(|recording| recording.get()).unwrap();
+                rope.stats.splits += stats.splits;
+                rope.stats.merges += stats.merges;
+                rope.stats.allocations += stats.allocations;
+                rope.stats.path_copies += stats.path_copies;
+                rope.stats.rebalances += stats.rebalances;
+            }
+        }
+        #[cfg(not(feature = "stats"))]
+        let _ = rope;
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Drop for Recording {
+    fn drop(&mut self) {
+        if self.active {
+            RECORDING.with(|recording| recording.set(None));
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use std;
 
+#[cfg(feature = "graphemes")]
+use unicode_segmentation::UnicodeSegmentation;
+
 /// Uses bit-fiddling magic to count utf8 chars really quickly.
 /// We actually count the number of non-starting utf8 bytes, since
 /// they have a consistent starting two-bit pattern.  We then
@@ -59,14 +62,18 @@ pub fn count_chars(text: &str) -> usize {
 /// - u{2029}        (Paragraph Separator)
 #[inline]
 pub fn count_line_breaks(text: &str) -> usize {
-    // TODO: right now this checks the high byte for the large line break codepoints
-    // when determining whether to skip the full check.  This penalizes texts that use
-    // a lot of code points in those ranges.  We should check the low bytes instead, to
-    // better distribute the penalty.
+    // The large line break code points are detected by their last byte
+    // (0x85 for u{0085}, 0xA8/0xA9 for u{2028}/u{2029}) rather than their
+    // lead byte.  Lead bytes 0xC2 and 0xE2 start every Latin-1 Supplement
+    // and General Punctuation char, so texts using many of those would
+    // otherwise constantly fall out of the fast loop, whereas the last
+    // bytes are spread across many unrelated code points.
+    const ONEMASK: usize = usize::MAX / 0xFF;
     let tsize: usize = std::mem::size_of::<usize>();
 
     let len = text.len();
-    let mut ptr = text.as_ptr();
+    let start_ptr = text.as_ptr();
+    let mut ptr = start_ptr;
     let end_ptr = unsafe { ptr.offset(len as isize) };
     let mut count = 0;
 
@@ -88,26 +95,18 @@ pub fn count_line_breaks(text: &str) -> usize {
 
                 count += 1;
             }
-            // Handle u{0085}
-            else if byte == 0xC2 {
-                ptr = unsafe { ptr.offset(1) };
-                if ptr < end_ptr && unsafe { *ptr } == 0x85 {
-                    count += 1;
-                }
-            }
-            // Handle u{2028} and u{2029}
-            else if byte == 0xE2 {
-                let next1 = unsafe { ptr.offset(1) };
-                let next2 = unsafe { ptr.offset(2) };
-                if next1 < end_ptr && next2 < end_ptr && unsafe { *next1 } == 0x80 && (unsafe {
-                    *next2
-                }
-                    >> 1)
-                    == 0x54
-                {
-                    count += 1;
-                }
-                ptr = unsafe { ptr.offset(2) };
+            // Handle u{0085}, u{2028} and u{2029}, by looking back for the
+            // rest of their bytes.
+            else if byte == 0x85 || (byte & 0xFE) == 0xA8 {
+                let offset = ptr as usize - start_ptr as usize;
+                let is_break = if byte == 0x85 {
+                    offset >= 1 && unsafe { *ptr.offset(-1) } == 0xC2
+                } else {
+                    offset >= 2
+                        && unsafe { *ptr.offset(-1) } == 0x80
+                        && unsafe { *ptr.offset(-2) } == 0xE2
+                };
+                count += is_break as usize;
             }
 
             ptr = unsafe { ptr.offset(1) };
@@ -120,8 +119,10 @@ pub fn count_line_breaks(text: &str) -> usize {
                 let n = unsafe { *(ptr as *const usize) };
 
                 // If there's a possibility that there might be a line-ending, stop
-                // and do the full check.
-                if has_bytes_less_than(n, 0x0E) || has_byte(n, 0xC2) || has_byte(n, 0xE2) {
+                // and do the full check.  Setting the low bit of every byte
+                // lets 0xA8 and 0xA9 be checked for at once.
+                if has_bytes_less_than(n, 0x0E) || has_byte(n, 0x85) || has_byte(n | ONEMASK, 0xA9)
+                {
                     break;
                 }
 
@@ -133,6 +134,14 @@ pub fn count_line_breaks(text: &str) -> usize {
     count
 }
 
+/// Counts the chars outside the Basic Multilingual Plane, i.e. those
+/// that take a surrogate pair in UTF-16.  These are exactly the chars
+/// that take four bytes in utf8.
+#[inline]
+pub fn count_utf16_surrogates(text: &str) -> usize {
+    text.as_bytes().iter().filter(|&&byte| byte >= 0xF0).count()
+}
+
 #[inline]
 pub fn byte_idx_to_char_idx(text: &str, byte_idx: usize) -> usize {
     if byte_idx == 0 {
@@ -229,6 +238,78 @@ pub fn line_idx_to_char_idx(text: &str, line_idx: usize) -> usize {
     byte_idx_to_char_idx(text, line_idx_to_byte_idx(text, line_idx))
 }
 
+#[inline]
+pub fn char_idx_to_utf16_cu_idx(text: &str, char_idx: usize) -> usize {
+    let byte_idx = char_idx_to_byte_idx(text, char_idx);
+    char_idx + count_utf16_surrogates(&text[..byte_idx])
+}
+
+/// Returns the index of the char containing the given UTF-16 code unit,
+/// or the char count if `utf16_cu_idx` is one-past-the-end.
+#[inline]
+pub fn utf16_cu_idx_to_char_idx(text: &str, utf16_cu_idx: usize) -> usize {
+    let mut utf16_cu_count = 0;
+    for (char_idx, c) in text.chars().enumerate() {
+        utf16_cu_count += c.len_utf16();
+        if utf16_cu_count > utf16_cu_idx {
+            return char_idx;
+        }
+    }
+    count_chars(text)
+}
+
+#[cfg(feature = "graphemes")]
+#[inline]
+pub fn count_graphemes(text: &str) -> usize {
+    if text.is_ascii() {
+        // Fast path: in ASCII text every char is its own grapheme, except
+        // for CRLF pairs.
+        let crlf_count = text.as_bytes()
+            .windows(2)
+            .filter(|pair| pair == b"\r\n")
+            .count();
+        text.len() - crlf_count
+    } else {
+        text.graphemes(true).count()
+    }
+}
+
+/// Returns the index of the grapheme containing `char_idx`, or the grapheme
+/// count if `char_idx` is one-past-the-end.
+#[cfg(feature = "graphemes")]
+#[inline]
+pub fn char_idx_to_grapheme_idx(text: &str, char_idx: usize) -> usize {
+    let byte_idx = char_idx_to_byte_idx(text, char_idx);
+    let starts = text.grapheme_indices(true)
+        .take_while(|&(i, _)| i <= byte_idx)
+        .count();
+    if byte_idx == text.len() {
+        starts
+    } else {
+        starts - 1
+    }
+}
+
+#[cfg(feature = "graphemes")]
+#[inline]
+pub fn grapheme_idx_to_char_idx(text: &str, grapheme_idx: usize) -> usize {
+    let byte_idx = text.grapheme_indices(true)
+        .nth(grapheme_idx)
+        .map(|(i, _)| i)
+        .unwrap_or_else(|| text.len());
+    byte_idx_to_char_idx(text, byte_idx)
+}
+
+/// Returns whether `c` is a line break char.  See `LineBreakIter` for the
+/// recognized line breaks; CRLF is two line break chars.
+#[inline]
+pub fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\u{000A}' | '\u{000B}' | '\u{000C}' | '\u{000D}' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
 #[inline(always)]
 pub fn has_bytes_less_than(word: usize, n: u8) -> bool {
     const ONEMASK: usize = std::usize::MAX / 0xFF;
@@ -349,6 +430,21 @@ mod tests {
         assert_eq!(None, itr.next());
     }
 
+    #[test]
+    fn utf16_cu_01() {
+        let text = "a\u{1F600}せ\u{10FFFF}\u{FFFF}";
+        assert_eq!(2, count_utf16_surrogates(text));
+        assert_eq!(text.encode_utf16().count(), count_chars(text) + 2);
+
+        let starts = [0, 1, 3, 4, 6, 7];
+        for (char_idx, &utf16_cu_idx) in starts.iter().enumerate() {
+            assert_eq!(utf16_cu_idx, char_idx_to_utf16_cu_idx(text, char_idx));
+            assert_eq!(char_idx, utf16_cu_idx_to_char_idx(text, utf16_cu_idx));
+        }
+        assert_eq!(1, utf16_cu_idx_to_char_idx(text, 2));
+        assert_eq!(3, utf16_cu_idx_to_char_idx(text, 5));
+    }
+
     #[test]
     fn count_line_breaks_01() {
         let text = "\u{000A}Hello\u{000D}\u{000A}\u{000D}せ\u{000B}か\u{000C}い\u{0085}. \
@@ -363,6 +459,24 @@ mod tests {
         assert_eq!(count_line_breaks(text), LineBreakIter::new(text).count());
     }
 
+    #[test]
+    fn count_line_breaks_03() {
+        // Lots of chars sharing the lead or last bytes of the large line
+        // breaks, with line breaks at every alignment.
+        let mut text = String::new();
+        for i in 0..64 {
+            text.push_str("«é» “—” Å\u{A8}\u{129}");
+            text.push_str(&"x".repeat(i % 9));
+            text.push(['\u{0085}', '\u{2028}', '\u{2029}', '\n'][i % 4]);
+        }
+        assert_eq!(count_line_breaks(&text), 64);
+        assert_eq!(count_line_breaks(&text), LineBreakIter::new(&text).count());
+        for i in 0..16 {
+            let text = &text[text.char_indices().nth(i).unwrap().0..];
+            assert_eq!(count_line_breaks(text), LineBreakIter::new(text).count());
+        }
+    }
+
     #[test]
     fn byte_idx_to_char_idx_01() {
         let text = "Hello せかい!";
@@ -0,0 +1,105 @@
+use std;
+
+use unicode_width::UnicodeWidthChar;
+
+use rewrite::ChunkRewriter;
+use str_utils::is_line_break;
+
+/// Display width of `c` when placed at column `col`.
+fn char_width(c: char, col: usize, tab_width: usize) -> usize {
+    if c == '\t' {
+        tab_width - (col % tab_width)
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
+/// Replaces tabs with spaces up to the next tab stop.
+pub(crate) struct ExpandTabs {
+    tab_width: usize,
+    col: usize,
+}
+
+impl ExpandTabs {
+    pub fn new(tab_width: usize) -> ExpandTabs {
+        assert!(tab_width > 0, "Tab width must be non-zero.");
+        ExpandTabs { tab_width, col: 0 }
+    }
+}
+
+impl ChunkRewriter for ExpandTabs {
+    fn rewrite(&mut self, chunk: &str, out: &mut String) {
+        for c in chunk.chars() {
+            if is_line_break(c) {
+                out.push(c);
+                self.col = 0;
+            } else if c == '\t' {
+                let width = char_width(c, self.col, self.tab_width);
+                out.extend(std::iter::repeat_n(' ', width));
+                self.col += width;
+            } else {
+                out.push(c);
+                self.col += char_width(c, self.col, self.tab_width);
+            }
+        }
+    }
+}
+
+/// Replaces runs of two or more spaces that end at a tab stop with tabs.
+pub(crate) struct CollapseSpaces {
+    tab_width: usize,
+    col: usize,
+    pending_spaces: usize,
+}
+
+impl CollapseSpaces {
+    pub fn new(tab_width: usize) -> CollapseSpaces {
+        assert!(tab_width > 0, "Tab width must be non-zero.");
+        CollapseSpaces {
+            tab_width,
+            col: 0,
+            pending_spaces: 0,
+        }
+    }
+
+    fn flush_spaces(&mut self, out: &mut String) {
+        out.extend(std::iter::repeat_n(' ', self.pending_spaces));
+        self.pending_spaces = 0;
+    }
+}
+
+impl ChunkRewriter for CollapseSpaces {
+    fn rewrite(&mut self, chunk: &str, out: &mut String) {
+        for c in chunk.chars() {
+            if c == ' ' {
+                self.pending_spaces += 1;
+                self.col += 1;
+                if self.col.is_multiple_of(self.tab_width) {
+                    if self.pending_spaces > 1 {
+                        out.push('\t');
+                        self.pending_spaces = 0;
+                    } else {
+                        self.flush_spaces(out);
+                    }
+                }
+            } else if c == '\t' {
+                // Spaces right before a tab are covered by the tab anyway.
+                self.pending_spaces = 0;
+                out.push(c);
+                self.col += char_width(c, self.col, self.tab_width);
+            } else {
+                self.flush_spaces(out);
+                out.push(c);
+                if is_line_break(c) {
+                    self.col = 0;
+                } else {
+                    self.col += char_width(c, self.col, self.tab_width);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, out: &mut String) {
+        self.flush_spaces(out);
+    }
+}
@@ -0,0 +1,245 @@
+use iter::{Bytes, Chars, Chunks, Lines};
+use rope::Rope;
+use slice::{CharIdxRange, RopeSlice};
+
+/// The read-only text API shared by `Rope` and `RopeSlice`.
+///
+/// This lets code accept either one through a single generic parameter.
+/// Each method behaves the same as the inherent method of the same name.
+///
+/// # Example
+///
+/// ```
+/// # use ropey::{Rope, RopeText};
+/// fn count_blank_lines<T: RopeText>(text: &T) -> usize {
+///     text.lines().filter(|line| line.chars().all(char::is_whitespace)).count()
+/// }
+///
+/// let rope = Rope::from_str("a\n\nb\n  \nc");
+///
+/// assert_eq!(count_blank_lines(&rope), 2);
+/// assert_eq!(count_blank_lines(&rope.slice(..4)), 1);
+/// ```
+pub trait RopeText {
+    /// Total number of bytes in the text.
+    fn len_bytes(&self) -> usize;
+
+    /// Total number of chars in the text.
+    fn len_chars(&self) -> usize;
+
+    /// Total number of lines in the text.
+    fn len_lines(&self) -> usize;
+
+    /// Returns the char index of the given byte.
+    fn byte_to_char(&self, byte_idx: usize) -> usize;
+
+    /// Returns the byte index of the given char.
+    fn char_to_byte(&self, char_idx: usize) -> usize;
+
+    /// Returns the line index of the given char.
+    fn char_to_line(&self, char_idx: usize) -> usize;
+
+    /// Returns the char index of the start of the given line.
+    fn line_to_char(&self, line_idx: usize) -> usize;
+
+    /// Returns the char at `char_idx`.
+    fn char(&self, char_idx: usize) -> char;
+
+    /// Returns the line at `line_idx`.
+    fn line(&self, line_idx: usize) -> RopeSlice<'_>;
+
+    /// Gets an immutable slice of the text.
+    fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_>;
+
+    /// Creates an iterator over the bytes of the text.
+    fn bytes(&self) -> Bytes<'_>;
+
+    /// Creates an iterator over the chars of the text.
+    fn chars(&self) -> Chars<'_>;
+
+    /// Creates an iterator over the lines of the text.
+    fn lines(&self) -> Lines<'_>;
+
+    /// Creates an iterator over the chunks of the text.
+    fn chunks(&self) -> Chunks<'_>;
+}
+
+impl RopeText for Rope {
+    #[inline]
+    fn len_bytes(&self) -> usize {
+        Rope::len_bytes(self)
+    }
+
+    #[inline]
+    fn len_chars(&self) -> usize {
+        Rope::len_chars(self)
+    }
+
+    #[inline]
+    fn len_lines(&self) -> usize {
+        Rope::len_lines(self)
+    }
+
+    #[inline]
+    fn byte_to_char(&self, byte_idx: usize) -> usize {
+        Rope::byte_to_char(self, byte_idx)
+    }
+
+    #[inline]
+    fn char_to_byte(&self, char_idx: usize) -> usize {
+        Rope::char_to_byte(self, char_idx)
+    }
+
+    #[inline]
+    fn char_to_line(&self, char_idx: usize) -> usize {
+        Rope::char_to_line(self, char_idx)
+    }
+
+    #[inline]
+    fn line_to_char(&self, line_idx: usize) -> usize {
+        Rope::line_to_char(self, line_idx)
+    }
+
+    #[inline]
+    fn char(&self, char_idx: usize) -> char {
+        Rope::char(self, char_idx)
+    }
+
+    #[inline]
+    fn line(&self, line_idx: usize) -> RopeSlice<'_> {
+        Rope::line(self, line_idx)
+    }
+
+    #[inline]
+    fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        Rope::slice(self, range)
+    }
+
+    #[inline]
+    fn bytes(&self) -> Bytes<'_> {
+        Rope::bytes(self)
+    }
+
+    #[inline]
+    fn chars(&self) -> Chars<'_> {
+        Rope::chars(self)
+    }
+
+    #[inline]
+    fn lines(&self) -> Lines<'_> {
+        Rope::lines(self)
+    }
+
+    #[inline]
+    fn chunks(&self) -> Chunks<'_> {
+        Rope::chunks(self)
+    }
+}
+
+impl<'a> RopeText for RopeSlice<'a> {
+    #[inline]
+    fn len_bytes(&self) -> usize {
+        RopeSlice::len_bytes(self)
+    }
+
+    #[inline]
+    fn len_chars(&self) -> usize {
+        RopeSlice::len_chars(self)
+    }
+
+    #[inline]
+    fn len_lines(&self) -> usize {
+        RopeSlice::len_lines(self)
+    }
+
+    #[inline]
+    fn byte_to_char(&self, byte_idx: usize) -> usize {
+        RopeSlice::byte_to_char(self, byte_idx)
+    }
+
+    #[inline]
+    fn char_to_byte(&self, char_idx: usize) -> usize {
+        RopeSlice::char_to_byte(self, char_idx)
+    }
+
+    #[inline]
+    fn char_to_line(&self, char_idx: usize) -> usize {
+        RopeSlice::char_to_line(self, char_idx)
+    }
+
+    #[inline]
+    fn line_to_char(&self, line_idx: usize) -> usize {
+        RopeSlice::line_to_char(self, line_idx)
+    }
+
+    #[inline]
+    fn char(&self, char_idx: usize) -> char {
+        RopeSlice::char(self, char_idx)
+    }
+
+    #[inline]
+    fn line(&self, line_idx: usize) -> RopeSlice<'_> {
+        RopeSlice::line(self, line_idx)
+    }
+
+    #[inline]
+    fn slice<R: CharIdxRange>(&self, range: R) -> RopeSlice<'_> {
+        RopeSlice::slice(self, range)
+    }
+
+    #[inline]
+    fn bytes(&self) -> Bytes<'_> {
+        RopeSlice::bytes(self)
+    }
+
+    #[inline]
+    fn chars(&self) -> Chars<'_> {
+        RopeSlice::chars(self)
+    }
+
+    #[inline]
+    fn lines(&self) -> Lines<'_> {
+        RopeSlice::lines(self)
+    }
+
+    #[inline]
+    fn chunks(&self) -> Chunks<'_> {
+        RopeSlice::chunks(self)
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hello there!\nHow're you doing?\r\nこんにちは！";
+
+    fn summary<T: RopeText>(text: &T) -> (usize, usize, usize, String, usize) {
+        let last_line = text.line(text.len_lines() - 1).to_string();
+        let chunk_bytes = text.chunks().map(|chunk| chunk.len()).sum();
+        (
+            text.len_bytes(),
+            text.len_chars(),
+            text.len_lines(),
+            last_line,
+            chunk_bytes,
+        )
+    }
+
+    #[test]
+    fn rope_text_01() {
+        let r = Rope::from_str(TEXT);
+        let s = r.slice(6..);
+
+        assert_eq!(summary(&r), (50, 38, 3, "こんにちは！".into(), 50));
+        assert_eq!(summary(&s), (44, 32, 3, "こんにちは！".into(), 44));
+        assert_eq!(RopeText::slice(&s, 7..10), "How");
+        assert_eq!(RopeText::char_to_line(&s, 7), 1);
+        assert_eq!(RopeText::line_to_char(&s, 2), 26);
+        assert_eq!(RopeText::char(&s, 0), 't');
+        assert_eq!(RopeText::chars(&s).count(), 32);
+        assert_eq!(RopeText::bytes(&r).count(), 50);
+    }
+}
@@ -0,0 +1,189 @@
+use smallvec::{Array, SmallVec};
+
+use tree::INLINE_BYTES;
+
+/// The byte buffer that a `NodeText` keeps its text in.
+///
+/// `NodeText` does all of the text handling (utf8 and CRLF/grapheme
+/// boundaries, splitting, etc.) on top of this, so other kinds of leaf
+/// storage (interned handles, pages of a memory-mapped file, etc.) only
+/// need to provide the raw buffer operations below.
+///
+/// # Safety
+///
+/// `NodeText` relies on the buffer behaving like a `Vec<u8>`:
+/// `as_mut_ptr()` must point to at least `capacity()` bytes, the start of
+/// which are the ones returned by `as_bytes()`, and `reserve()` and
+/// `compact()` must preserve those.
+pub(crate) unsafe trait LeafStorage: Clone + Default {
+    /// Creates an empty buffer with room for at least `capacity` bytes.
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// The bytes stored in the buffer.
+    fn as_bytes(&self) -> &[u8];
+
+    /// Size of the buffer, whether inline or heap-allocated.
+    fn capacity(&self) -> usize;
+
+    /// Whether the buffer's memory is allocated separately from the leaf
+    /// itself.
+    fn spilled(&self) -> bool;
+
+    /// Makes room for at least `additional` more bytes.
+    fn reserve(&mut self, additional: usize);
+
+    /// Sets the number of stored bytes, without touching the buffer.
+    ///
+    /// `len` must be at most `capacity()`, and the first `len` bytes must
+    /// be initialized.
+    unsafe fn set_len(&mut self, len: usize);
+
+    /// Pointer to the start of the buffer, for writing.
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Gives back buffer space that's no longer needed, where that's
+    /// cheap to do.
+    ///
+    /// Called after text is removed from the leaf.
+    fn compact(&mut self);
+}
+
+//=======================================================================
+
+/// The default leaf storage: an inline buffer of `INLINE_BYTES` length,
+/// aligned to `CHUNK_ALIGNMENT`, which spills to the heap when needed.
+#[derive(Clone, Default)]
+pub(crate) struct InlineStorage {
+    buffer: SmallVec<BackingArray>,
+}
+
+unsafe impl LeafStorage for InlineStorage {
+    #[inline(always)]
+    fn with_capacity(capacity: usize) -> Self {
+        InlineStorage {
+            buffer: SmallVec::with_capacity(capacity),
+        }
+    }
+
+    #[inline(always)]
+    fn as_bytes(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    #[inline(always)]
+    fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    #[inline(always)]
+    fn spilled(&self) -> bool {
+        self.buffer.spilled()
+    }
+
+    #[inline(always)]
+    fn reserve(&mut self, additional: usize) {
+        self.buffer.reserve(additional);
+    }
+
+    #[inline(always)]
+    unsafe fn set_len(&mut self, len: usize) {
+        self.buffer.set_len(len);
+    }
+
+    #[inline(always)]
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buffer.as_mut_ptr()
+    }
+
+    /// Re-inlines the data if it's been heap allocated but can
+    /// fit inline.
+    #[inline(always)]
+    fn compact(&mut self) {
+        if self.buffer.spilled() && (self.buffer.len() <= self.buffer.inline_size()) {
+            self.buffer.shrink_to_fit();
+        }
+    }
+}
+
+/// The backing internal buffer for `InlineStorage`.
+///
+/// Its alignment must match `tree::CHUNK_ALIGNMENT`.
+#[derive(Copy, Clone)]
+#[repr(C, align(16))]
+pub(crate) struct BackingArray([u8; INLINE_BYTES]);
+unsafe impl Array for BackingArray {
+    type Item = u8;
+    fn size() -> usize {
+        INLINE_BYTES
+    }
+    fn ptr(&self) -> *const u8 {
+        &self.0[0]
+    }
+    fn ptr_mut(&mut self) -> *mut u8 {
+        &mut self.0[0]
+    }
+}
+
+//=======================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std;
+    use tree::{NodeText, CHUNK_ALIGNMENT};
+
+    /// A minimal storage backend, to check that `NodeText` doesn't depend
+    /// on anything specific to `InlineStorage`.
+    #[derive(Clone, Default)]
+    struct VecStorage(Vec<u8>);
+
+    unsafe impl LeafStorage for VecStorage {
+        fn with_capacity(capacity: usize) -> Self {
+            VecStorage(Vec::with_capacity(capacity))
+        }
+        fn as_bytes(&self) -> &[u8] {
+            &self.0
+        }
+        fn capacity(&self) -> usize {
+            self.0.capacity()
+        }
+        fn spilled(&self) -> bool {
+            true
+        }
+        fn reserve(&mut self, additional: usize) {
+            self.0.reserve(additional);
+        }
+        unsafe fn set_len(&mut self, len: usize) {
+            self.0.set_len(len);
+        }
+        fn as_mut_ptr(&mut self) -> *mut u8 {
+            self.0.as_mut_ptr()
+        }
+        fn compact(&mut self) {}
+    }
+
+    #[test]
+    fn alignment_01() {
+        assert_eq!(CHUNK_ALIGNMENT, std::mem::align_of::<BackingArray>());
+
+        let mut s = InlineStorage::with_capacity(4);
+        assert!(!s.spilled());
+        assert_eq!(0, s.as_mut_ptr() as usize % CHUNK_ALIGNMENT);
+    }
+
+    #[test]
+    fn other_storage_01() {
+        let mut s = NodeText::<VecStorage>::from_str("Hello!");
+        s.insert_str(5, " world");
+        assert_eq!("Hello world!", s);
+
+        let right = s.split_off(6);
+        assert_eq!("Hello ", s);
+        assert_eq!("world!", right);
+
+        s.remove_range(0, 2);
+        s.truncate_front(1);
+        s.push_str("there");
+        assert_eq!("lo there", s);
+    }
+}
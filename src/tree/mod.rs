@@ -1,3 +1,4 @@
+mod leaf_storage;
 mod node;
 mod node_children;
 mod node_text;
@@ -25,15 +26,73 @@ const TARGET_NODE_SIZE: usize = 768 - (PTR_SIZE * 2);
 // non-testing, they're determined by TARGET_NODE_SIZE, above.
 #[cfg(test)]
 pub(crate) const MAX_CHILDREN: usize = 5;
+// Each child takes a pointer and its text info.
 #[cfg(not(test))]
-pub(crate) const MAX_CHILDREN: usize = (TARGET_NODE_SIZE - 1) / 32;
+pub(crate) const MAX_CHILDREN: usize = (TARGET_NODE_SIZE - 1) / (PTR_SIZE + size_of::<TextInfo>());
 pub(crate) const MIN_CHILDREN: usize = MAX_CHILDREN - (MAX_CHILDREN / 2);
 
 #[cfg(test)]
 pub(crate) const MAX_BYTES: usize = 8;
+// The leaf buffer's length and inline/spilled tag each get padded out to
+// `CHUNK_ALIGNMENT` in front of the inline buffer, and the buffer itself
+// is padded to a multiple of it.
 #[cfg(not(test))]
-pub(crate) const MAX_BYTES: usize = TARGET_NODE_SIZE - 1 - (PTR_SIZE * 2);
-pub(crate) const MIN_BYTES: usize = MAX_BYTES - (MAX_BYTES / 2);
+pub(crate) const MAX_BYTES: usize =
+    (TARGET_NODE_SIZE - (CHUNK_ALIGNMENT * 2)) & !(CHUNK_ALIGNMENT - 1);
+
+// The default for how many levels taller than the minimum for its length
+// a `Rope`'s tree may get before it's automatically rebalanced.
+pub(crate) const DEFAULT_MAX_SKEW: usize = 3;
+
+// The size of a leaf's inline text buffer.  This is just `MAX_BYTES`,
+// except in tests, where it's rounded up so that the aligned buffer has no
+// padding (which `SmallVec` doesn't allow).
+pub(crate) const INLINE_BYTES: usize = (MAX_BYTES + CHUNK_ALIGNMENT - 1) & !(CHUNK_ALIGNMENT - 1);
+
+/// The alignment, in bytes, that `Rope` chunks are stored at.
+///
+/// Every chunk yielded by [`Rope::chunks()`](struct.Rope.html#method.chunks)
+/// starts at an address that's a multiple of this, as long as the chunk
+/// fits in its leaf's inline buffer.  That's true of all chunks of a `Rope`
+/// with the default chunk size, barring unusually long graphemes, so SIMD
+/// scanners can skip their unaligned prologue for them.  Chunks that don't
+/// fit (e.g. with a larger chunk size) are heap-allocated, and only have
+/// the alignment that the global allocator happens to give them.
+///
+/// Chunks of a `RopeSlice` can start in the middle of a leaf, and so have
+/// no alignment guarantee.
+pub const CHUNK_ALIGNMENT: usize = 16;
+
+// The smallest allowed per-rope chunk size.  Leaves need to be able to hold
+// at least a CRLF pair plus a full code point, with some slack.
+pub(crate) const MIN_CHUNK_SIZE: usize = 8;
+
+// Leaf nodes are sized at run-time, between `min_bytes()` and the
+// per-rope chunk size.  `MAX_BYTES` is just the default chunk size and
+// the size of a leaf's inline buffer.
+#[inline(always)]
+pub(crate) fn min_bytes(max_bytes: usize) -> usize {
+    max_bytes - (max_bytes / 2)
+}
 
 // Type used for storing tree metadata, such as byte and char length.
 pub(crate) type Count = u64;
+
+/// The unit that an index into the tree is given in, for descents that
+/// can be done by either.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Metric {
+    Bytes,
+    Chars,
+}
+
+impl Metric {
+    /// The length of the text described by `info`, in this unit.
+    #[inline(always)]
+    pub(crate) fn of(self, info: &TextInfo) -> usize {
+        match self {
+            Metric::Bytes => info.bytes as usize,
+            Metric::Chars => info.chars as usize,
+        }
+    }
+}
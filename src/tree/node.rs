@@ -1,11 +1,18 @@
 use std;
+use std::fmt;
 use std::sync::Arc;
 
+use crlf;
 use str_utils::{byte_idx_to_char_idx, byte_idx_to_line_idx, char_idx_to_byte_idx,
-                char_idx_to_line_idx, line_idx_to_byte_idx, line_idx_to_char_idx};
-use tree::{Count, NodeChildren, NodeText, TextInfo, MAX_BYTES, MAX_CHILDREN, MIN_BYTES,
+                char_idx_to_line_idx, char_idx_to_utf16_cu_idx, line_idx_to_byte_idx,
+                line_idx_to_char_idx, utf16_cu_idx_to_char_idx};
+use stats::{self, Event};
+use tree::{min_bytes, Count, Metric, NodeChildren, NodeText, TextInfo, MAX_CHILDREN,
            MIN_CHILDREN};
 use tree::node_text::fix_segment_seam;
+use validate::InvariantViolation;
+#[cfg(feature = "graphemes")]
+use str_utils::{char_idx_to_grapheme_idx, grapheme_idx_to_char_idx};
 
 #[derive(Debug, Clone)]
 pub(crate) enum Node {
@@ -34,7 +41,18 @@ impl Node {
         self.text_info().line_breaks as usize
     }
 
-    /// Edits nodes in range `start_idx..end_idx`.
+    /// Total number of UTF-16 code units in the Rope.
+    pub fn utf16_cu_count(&self) -> usize {
+        self.text_info().utf16_cus() as usize
+    }
+
+    #[cfg(feature = "graphemes")]
+    pub fn grapheme_count(&self) -> usize {
+        self.text_info().graphemes as usize
+    }
+
+    /// Edits nodes in range `start_idx..end_idx`, which is in `metric`
+    /// units.
     ///
     /// Nodes completely subsumed by the range will be removed except the
     /// leftmost node even if it is subsumed, and the remaining 1 or 2 leaf
@@ -53,48 +71,56 @@ impl Node {
     /// 2. An optional new leaf node to the right of the selected leaf node,
     ///    along with its text info.
     ///
+    /// With `Metric::Bytes`, both ends of the range must be on char
+    /// boundaries.  That's checked in the leaves they're in, before
+    /// anything is edited, so that the tree is left untouched if it
+    /// panics.
+    ///
     /// WARNING: does not correctly handle all text being removed.  That
     /// should be special-cased in calling code.
-    pub fn edit_char_range<F>(
+    pub fn edit_range<F>(
         &mut self,
+        metric: Metric,
         start_idx: usize,
         end_idx: usize,
+        max_bytes: usize,
         mut edit: F,
     ) -> (TextInfo, Option<(TextInfo, Arc<Node>)>)
     where
         F: FnMut(TextInfo, TextInfo, &mut NodeText) -> (TextInfo, Option<(TextInfo, NodeText)>),
     {
         debug_assert!(start_idx <= end_idx);
-        debug_assert!(end_idx <= self.text_info().chars as usize);
+        debug_assert!(end_idx <= metric.of(&self.text_info()));
 
-        match *self {
-            Node::Leaf(_) => {
-                let cur_info = self.text_info();
-                self.edit_char_range_internal(
-                    start_idx,
-                    end_idx,
-                    TextInfo::new(),
-                    cur_info,
-                    &mut edit,
-                )
-            }
-            Node::Internal(_) => self.edit_char_range_internal(
-                start_idx,
-                end_idx,
-                TextInfo::new(),
-                TextInfo::new(),
-                &mut edit,
-            ),
-        }
+        let cur_info = match *self {
+            Node::Leaf(_) => self.text_info(),
+            Node::Internal(_) => TextInfo::new(),
+        };
+        self.edit_range_internal(
+            metric,
+            start_idx,
+            end_idx,
+            max_bytes,
+            TextInfo::new(),
+            cur_info,
+            false,
+            &mut edit,
+        )
     }
 
-    // Internal implementation of edit_char_range(), above.
-    fn edit_char_range_internal<F>(
+    // Internal implementation of edit_range(), above.  `ends_checked` is
+    // whether the ends of the range are already known to be on char
+    // boundaries.
+    #[allow(clippy::too_many_arguments)]
+    fn edit_range_internal<F>(
         &mut self,
+        metric: Metric,
         start_idx: usize,
         end_idx: usize,
+        max_bytes: usize,
         acc_info: TextInfo,
         cur_info: TextInfo,
+        ends_checked: bool,
         edit: &mut F,
     ) -> (TextInfo, Option<(TextInfo, Arc<Node>)>)
     where
@@ -103,9 +129,16 @@ impl Node {
         match *self {
             // If it's a leaf
             Node::Leaf(ref mut cur_text) => {
+                if metric == Metric::Bytes && !ends_checked {
+                    for &idx in &[start_idx, end_idx] {
+                        assert_char_boundary(cur_text.is_char_boundary(idx), &acc_info, idx);
+                    }
+                }
+
                 let (info, residual) = edit(acc_info, cur_info, cur_text);
 
                 if let Some((r_info, r_text)) = residual {
+                    stats::count(Event::Split);
                     (info, Some((r_info, Arc::new(Node::Leaf(r_text)))))
                 } else {
                     (info, None)
@@ -117,17 +150,21 @@ impl Node {
                 // Shared code for handling children.
                 let mut handle_child = |children: &mut NodeChildren,
                                         child_i: usize,
-                                        c_acc_info: TextInfo|
+                                        c_acc_info: TextInfo,
+                                        ends_checked: bool|
                  -> Option<Arc<Node>> {
                     // Recurse into child
                     let tmp_info = children.info()[child_i];
-                    let tmp_chars = children.info()[child_i].chars as usize;
-                    let (new_info, residual) = Arc::make_mut(&mut children.nodes_mut()[child_i])
-                        .edit_char_range_internal(
-                            start_idx - (c_acc_info.chars as usize).min(start_idx),
-                            (end_idx - c_acc_info.chars as usize).min(tmp_chars),
+                    let c_acc_idx = metric.of(&c_acc_info);
+                    let (new_info, residual) = stats::make_mut(&mut children.nodes_mut()[child_i])
+                        .edit_range_internal(
+                            metric,
+                            start_idx - c_acc_idx.min(start_idx),
+                            (end_idx - c_acc_idx).min(metric.of(&tmp_info)),
+                            max_bytes,
                             acc_info + c_acc_info,
                             tmp_info,
+                            ends_checked,
                             edit,
                         );
 
@@ -148,6 +185,7 @@ impl Node {
                             }
                             // The new node won't fit!  Must split.
                             else {
+                                stats::count(Event::Split);
                                 return Some(Arc::new(Node::Internal(
                                     children.insert_split(child_i + 1, (info, node)),
                                 )));
@@ -164,23 +202,26 @@ impl Node {
                                    child_i: usize|
                  -> bool {
                     if child_i < children.len() {
-                        if children.len() > 1 && children.nodes()[child_i].is_undersized() {
+                        if children.len() > 1 && children.nodes()[child_i].is_undersized(max_bytes)
+                        {
                             if child_i == 0 {
-                                children.merge_distribute(child_i, child_i + 1)
+                                children.merge_distribute(child_i, child_i + 1, max_bytes)
                             } else {
-                                children.merge_distribute(child_i - 1, child_i)
+                                children.merge_distribute(child_i - 1, child_i, max_bytes)
                             }
                         } else {
                             false
                         }
                     } else if let Some(ref mut node) = *split_node {
-                        let r_children = Arc::make_mut(node).children();
+                        let r_children = stats::make_mut(node).children();
                         let child_i = child_i - children.len();
-                        if r_children.len() > 1 && r_children.nodes()[child_i].is_undersized() {
+                        if r_children.len() > 1
+                            && r_children.nodes()[child_i].is_undersized(max_bytes)
+                        {
                             if child_i == 0 {
-                                r_children.merge_distribute(child_i, child_i + 1)
+                                r_children.merge_distribute(child_i, child_i + 1, max_bytes)
                             } else {
-                                r_children.merge_distribute(child_i - 1, child_i)
+                                r_children.merge_distribute(child_i - 1, child_i, max_bytes)
                             }
                         } else {
                             false
@@ -193,28 +234,28 @@ impl Node {
                 // Compact leaf children if we're close to maximum leaf
                 // fragmentation.
                 if children.is_full() && children.nodes()[0].is_leaf()
-                    && (children.combined_info().bytes as usize) < (MAX_BYTES * (MIN_CHILDREN + 1))
+                    && (children.combined_info().bytes as usize) < (max_bytes * (MIN_CHILDREN + 1))
                 {
-                    children.compact_leaves();
+                    children.compact_leaves(max_bytes);
                 }
 
                 // Early-out optimization, to make simple insertion faster
                 if start_idx == end_idx {
-                    let (child_i, child_acc_info) = children.search_char_idx(start_idx);
-                    let residual = handle_child(children, child_i, child_acc_info);
+                    let (child_i, child_acc_info) = children.search_idx(metric, start_idx);
+                    let residual = handle_child(children, child_i, child_acc_info, ends_checked);
                     return (
                         children.combined_info(),
                         residual.map(|c| (c.text_info(), c)),
                     );
                 }
 
-                // Get child info for the two char indices
+                // Get child info for the two indices
                 let ((l_child_i, l_acc_info), (r_child_i, r_acc_info)) =
-                    children.search_char_idx_range(start_idx, end_idx);
+                    children.search_idx_range(metric, start_idx, end_idx);
 
                 // Both indices point into the same child
                 if l_child_i == r_child_i {
-                    let mut residual = handle_child(children, l_child_i, l_acc_info);
+                    let mut residual = handle_child(children, l_child_i, l_acc_info, ends_checked);
                     merge_child(children, &mut residual, l_child_i);
 
                     return (
@@ -227,9 +268,7 @@ impl Node {
                     // Calculate the start..end range of nodes to be removed.
                     let r_child_exists: bool;
                     let start_i = l_child_i + 1;
-                    let end_i = if (r_acc_info.chars + children.info()[r_child_i].chars) as usize
-                        == end_idx
-                    {
+                    let end_i = if metric.of(&(r_acc_info + children.info()[r_child_i])) == end_idx {
                         r_child_exists = false;
                         r_child_i + 1
                     } else {
@@ -237,6 +276,18 @@ impl Node {
                         r_child_i
                     };
 
+                    // The children between the two ends are about to be
+                    // removed, before the leaves at the ends are reached,
+                    // so check the ends here instead.
+                    if metric == Metric::Bytes && !ends_checked {
+                        let ends = [(l_child_i, l_acc_info, start_idx), (r_child_i, r_acc_info, end_idx)];
+                        for &(child_i, c_acc_info, idx) in &ends {
+                            let local_idx = idx - c_acc_info.bytes as usize;
+                            let is_boundary = children.nodes()[child_i].is_char_boundary(local_idx);
+                            assert_char_boundary(is_boundary, &acc_info, idx);
+                        }
+                    }
+
                     // Remove the children
                     for _ in start_i..end_i {
                         children.remove(start_i);
@@ -246,7 +297,7 @@ impl Node {
 
                     // Handle right child
                     let mut split_children = if r_child_exists {
-                        handle_child(children, l_child_i + 1, r_acc_info)
+                        handle_child(children, l_child_i + 1, r_acc_info, true)
                     } else {
                         None
                     };
@@ -254,15 +305,16 @@ impl Node {
                     // Handle left child
                     if split_children.is_none() {
                         // We have to check because merging may have
-                        split_children = handle_child(children, l_child_i, l_acc_info);
+                        split_children = handle_child(children, l_child_i, l_acc_info, true);
                     } else if l_child_i < children.len() {
-                        let tmp = handle_child(children, l_child_i, l_acc_info);
+                        let tmp = handle_child(children, l_child_i, l_acc_info, true);
                         debug_assert!(tmp.is_none());
                     } else if let Some(ref mut r_children) = split_children {
                         let tmp = handle_child(
-                            Arc::make_mut(r_children).children(),
+                            stats::make_mut(r_children).children(),
                             l_child_i - children.len(),
                             l_acc_info,
+                            true,
                         );
                         debug_assert!(tmp.is_none());
                     }
@@ -302,14 +354,16 @@ impl Node {
                 }
                 Node::Internal(ref mut children_l) => {
                     let mut other = other;
-                    if let Node::Internal(ref mut children_r) = *Arc::make_mut(&mut other) {
+                    if let Node::Internal(ref mut children_r) = *stats::make_mut(&mut other) {
                         if (children_l.len() + children_r.len()) <= MAX_CHILDREN {
                             for _ in 0..children_r.len() {
                                 children_l.push(children_r.remove(0));
                             }
+                            stats::count(Event::Merge);
                             return None;
                         } else {
                             children_l.distribute_with(children_r);
+                            stats::count(Event::Rebalance);
                             // Return lower down, to avoid borrow-checker.
                         }
                     } else {
@@ -320,8 +374,8 @@ impl Node {
             }
         } else if let Node::Internal(ref mut children) = *self {
             let last_i = children.len() - 1;
-            let residual =
-                Arc::make_mut(&mut children.nodes_mut()[last_i]).append_at_depth(other, depth - 1);
+            let residual = stats::make_mut(&mut children.nodes_mut()[last_i])
+                .append_at_depth(other, depth - 1);
             children.update_child_info(last_i);
             if let Some(extra_node) = residual {
                 if children.len() < MAX_CHILDREN {
@@ -329,6 +383,7 @@ impl Node {
                     return None;
                 } else {
                     let r_children = children.push_split((extra_node.text_info(), extra_node));
+                    stats::count(Event::Split);
                     return Some(Arc::new(Node::Internal(r_children)));
                 }
             } else {
@@ -351,14 +406,16 @@ impl Node {
                 }
                 Node::Internal(ref mut children_r) => {
                     let mut other = other;
-                    if let Node::Internal(ref mut children_l) = *Arc::make_mut(&mut other) {
+                    if let Node::Internal(ref mut children_l) = *stats::make_mut(&mut other) {
                         if (children_l.len() + children_r.len()) <= MAX_CHILDREN {
                             for _ in 0..children_l.len() {
                                 children_r.insert(0, children_l.pop());
                             }
+                            stats::count(Event::Merge);
                             return None;
                         } else {
                             children_l.distribute_with(children_r);
+                            stats::count(Event::Rebalance);
                             // Return lower down, to avoid borrow-checker.
                         }
                     } else {
@@ -369,7 +426,7 @@ impl Node {
             }
         } else if let Node::Internal(ref mut children) = *self {
             let residual =
-                Arc::make_mut(&mut children.nodes_mut()[0]).prepend_at_depth(other, depth - 1);
+                stats::make_mut(&mut children.nodes_mut()[0]).prepend_at_depth(other, depth - 1);
             children.update_child_info(0);
             if let Some(extra_node) = residual {
                 if children.len() < MAX_CHILDREN {
@@ -379,6 +436,7 @@ impl Node {
                     let mut r_children =
                         children.insert_split(0, (extra_node.text_info(), extra_node));
                     std::mem::swap(children, &mut r_children);
+                    stats::count(Event::Split);
                     return Some(Arc::new(Node::Internal(r_children)));
                 }
             } else {
@@ -411,10 +469,11 @@ impl Node {
                     let mut r_children = children.split_off(child_i + 1);
 
                     // Recurse
-                    let r_node = Arc::make_mut(&mut children.nodes_mut()[child_i])
+                    let r_node = stats::make_mut(&mut children.nodes_mut()[child_i])
                         .split(char_idx - acc_info.chars as usize);
 
                     r_children.insert(0, (r_node.text_info(), Arc::new(r_node)));
+                    stats::count(Event::Allocation);
 
                     children.update_child_info(child_i);
                     r_children.update_child_info(0);
@@ -481,6 +540,69 @@ impl Node {
         }
     }
 
+    /// Returns the line index of the given char, along with its column
+    /// (its char offset from the start of the line).
+    ///
+    /// Done in a single descent, except that when the line starts in an
+    /// earlier leaf, the nearest subtree with a line break before the char
+    /// is descended into to find where.
+    pub fn char_to_line_col(&self, char_idx: usize) -> (usize, usize) {
+        let mut node = self;
+        let mut node_start = 0; // Char index of the start of `node`
+        let mut line_idx = 0;
+        // The nearest subtree before `node` with a line break in it, along
+        // with the char index of its end.
+        let mut prev_break: Option<(&Node, usize)> = None;
+
+        loop {
+            match *node {
+                Node::Leaf(ref text) => {
+                    let rel_idx = char_idx - node_start;
+                    let leaf_line = char_idx_to_line_idx(text, rel_idx);
+                    let line_start = if leaf_line > 0 {
+                        node_start + line_idx_to_char_idx(text, leaf_line)
+                    } else if let Some((prev, prev_end)) = prev_break {
+                        prev_end - prev.chars_after_last_line_break()
+                    } else {
+                        0
+                    };
+                    return (line_idx + leaf_line, char_idx - line_start);
+                }
+                Node::Internal(ref children) => {
+                    let (child_i, acc_info) = children
+                        .search_combine_info(|inf| (char_idx - node_start) as Count <= inf.chars);
+
+                    let info = &children.info()[..child_i];
+                    if let Some(i) = info.iter().rposition(|inf| inf.line_breaks > 0) {
+                        let end: Count = info[..=i].iter().map(|inf| inf.chars).sum();
+                        prev_break = Some((&children.nodes()[i], node_start + end as usize));
+                    }
+
+                    line_idx += acc_info.line_breaks as usize;
+                    node_start += acc_info.chars as usize;
+                    node = &children.nodes()[child_i];
+                }
+            }
+        }
+    }
+
+    /// Returns the number of chars after the last line break in the
+    /// node, which must contain one.
+    fn chars_after_last_line_break(&self) -> usize {
+        match *self {
+            Node::Leaf(ref text) => {
+                let info = self.text_info();
+                (info.chars as usize) - line_idx_to_char_idx(text, info.line_breaks as usize)
+            }
+            Node::Internal(ref children) => {
+                let info = children.info();
+                let i = info.iter().rposition(|inf| inf.line_breaks > 0).unwrap();
+                let after: Count = info[(i + 1)..].iter().map(|inf| inf.chars).sum();
+                after as usize + children.nodes()[i].chars_after_last_line_break()
+            }
+        }
+    }
+
     /// Returns the byte index of the start of the given line.
     pub fn line_to_byte(&self, line_idx: usize) -> usize {
         match *self {
@@ -496,6 +618,60 @@ impl Node {
         }
     }
 
+    /// Returns the byte and char indices of the start of the given line.
+    pub fn line_to_byte_char(&self, line_idx: usize) -> (usize, usize) {
+        match *self {
+            Node::Leaf(ref text) => {
+                let byte_idx = line_idx_to_byte_idx(text, line_idx);
+                (byte_idx, byte_idx_to_char_idx(text, byte_idx))
+            }
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| line_idx as Count <= inf.line_breaks);
+
+                let (bytes, chars) = children.nodes()[child_i]
+                    .line_to_byte_char(line_idx - acc_info.line_breaks as usize);
+                (acc_info.bytes as usize + bytes, acc_info.chars as usize + chars)
+            }
+        }
+    }
+
+    /// Returns the length in bytes and chars of the given line, including
+    /// its line break.  The line must end in a line break, i.e. `line_idx`
+    /// must be less than the number of line breaks.
+    ///
+    /// The starts of the line and the next one are found in a single
+    /// descent, down to the node where they're in different children.
+    pub fn line_len(&self, line_idx: usize) -> (usize, usize) {
+        match *self {
+            Node::Leaf(ref text) => {
+                let start = line_idx_to_byte_idx(text, line_idx);
+                let end = line_idx_to_byte_idx(text, line_idx + 1);
+                (end - start, byte_idx_to_char_idx(&text[start..], end - start))
+            }
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| line_idx as Count <= inf.line_breaks);
+                let local_idx = line_idx - acc_info.line_breaks as usize;
+                if local_idx < children.info()[child_i].line_breaks as usize {
+                    return children.nodes()[child_i].line_len(local_idx);
+                }
+
+                // The line continues past the end of the child, so find
+                // its start and end separately from here.
+                let (end_i, end_acc_info) =
+                    children.search_combine_info(|inf| (line_idx as Count) < inf.line_breaks);
+                let start = children.nodes()[child_i].line_to_byte_char(local_idx);
+                let end = children.nodes()[end_i]
+                    .line_to_byte_char(line_idx + 1 - end_acc_info.line_breaks as usize);
+                (
+                    (end_acc_info.bytes - acc_info.bytes) as usize + end.0 - start.0,
+                    (end_acc_info.chars - acc_info.chars) as usize + end.1 - start.1,
+                )
+            }
+        }
+    }
+
     /// Returns the char index of the start of the given line.
     pub fn line_to_char(&self, line_idx: usize) -> usize {
         match *self {
@@ -511,6 +687,66 @@ impl Node {
         }
     }
 
+    /// Returns the UTF-16 code unit index of the given char.
+    pub fn char_to_utf16_cu(&self, char_idx: usize) -> usize {
+        match *self {
+            Node::Leaf(ref text) => char_idx_to_utf16_cu_idx(text, char_idx),
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| char_idx as Count <= inf.chars);
+
+                acc_info.utf16_cus() as usize
+                    + children.nodes()[child_i].char_to_utf16_cu(char_idx - acc_info.chars as usize)
+            }
+        }
+    }
+
+    /// Returns the index of the char containing the given UTF-16 code unit.
+    pub fn utf16_cu_to_char(&self, utf16_cu_idx: usize) -> usize {
+        match *self {
+            Node::Leaf(ref text) => utf16_cu_idx_to_char_idx(text, utf16_cu_idx),
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| (utf16_cu_idx as Count) < inf.utf16_cus());
+
+                acc_info.chars as usize
+                    + children.nodes()[child_i]
+                        .utf16_cu_to_char(utf16_cu_idx - acc_info.utf16_cus() as usize)
+            }
+        }
+    }
+
+    /// Returns the grapheme index of the given char.
+    #[cfg(feature = "graphemes")]
+    pub fn char_to_grapheme(&self, char_idx: usize) -> usize {
+        match *self {
+            Node::Leaf(ref text) => char_idx_to_grapheme_idx(text, char_idx),
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| char_idx as Count <= inf.chars);
+
+                acc_info.graphemes as usize
+                    + children.nodes()[child_i].char_to_grapheme(char_idx - acc_info.chars as usize)
+            }
+        }
+    }
+
+    /// Returns the char index of the start of the given grapheme.
+    #[cfg(feature = "graphemes")]
+    pub fn grapheme_to_char(&self, grapheme_idx: usize) -> usize {
+        match *self {
+            Node::Leaf(ref text) => grapheme_idx_to_char_idx(text, grapheme_idx),
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) =
+                    children.search_combine_info(|inf| grapheme_idx as Count <= inf.graphemes);
+
+                acc_info.chars as usize
+                    + children.nodes()[child_i]
+                        .grapheme_to_char(grapheme_idx - acc_info.graphemes as usize)
+            }
+        }
+    }
+
     pub fn text_info(&self) -> TextInfo {
         match *self {
             Node::Leaf(ref text) => TextInfo::from_str(text),
@@ -551,6 +787,27 @@ impl Node {
         }
     }
 
+    /// Returns the text of the leaf containing `byte_idx`, along with the
+    /// byte index of its start, copying it and the nodes above it first
+    /// if they're shared.
+    ///
+    /// One-past-the-end is valid, and returns the last leaf.  The caller
+    /// must keep the text's info unchanged.
+    pub fn leaf_text_mut_at_byte(node: &mut Arc<Node>, byte_idx: usize) -> (&mut NodeText, usize) {
+        let mut node = node;
+        let mut start = 0;
+        loop {
+            match *stats::make_mut(node) {
+                Node::Leaf(ref mut text) => return (text, start),
+                Node::Internal(ref mut children) => {
+                    let (child_i, acc_info) = children.search_byte_idx(byte_idx - start);
+                    start += acc_info.bytes as usize;
+                    node = &mut children.nodes_mut()[child_i];
+                }
+            }
+        }
+    }
+
     pub fn is_leaf(&self) -> bool {
         match *self {
             Node::Leaf(_) => true,
@@ -558,13 +815,44 @@ impl Node {
         }
     }
 
-    pub fn is_undersized(&self) -> bool {
+    pub fn is_undersized(&self, max_bytes: usize) -> bool {
         match *self {
-            Node::Leaf(ref text) => text.len() < MIN_BYTES,
+            Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
             Node::Internal(ref children) => children.len() < MIN_CHILDREN,
         }
     }
 
+    /// Whether this is a leaf longer than `max_bytes`, not counting its
+    /// first and last segments (CRLF pairs, or graphemes with the
+    /// `graphemes` feature).
+    ///
+    /// Those are left out because fixing the seam between two leaves can
+    /// move a segment onto a full leaf, and a single segment may be longer
+    /// than `max_bytes` anyway.
+    pub fn is_oversized(&self, max_bytes: usize) -> bool {
+        match *self {
+            Node::Leaf(ref text) => {
+                let bytes = text.as_bytes();
+                let start = crlf::next_break(0, bytes);
+                let end = crlf::prev_break(bytes.len(), bytes);
+                end > start && end - start > max_bytes
+            }
+            Node::Internal(_) => false,
+        }
+    }
+
+    /// Whether any of the leaves in the tree is oversized, as with
+    /// `is_oversized()`.
+    pub fn has_oversized_leaf(&self, max_bytes: usize) -> bool {
+        match *self {
+            Node::Leaf(_) => self.is_oversized(max_bytes),
+            Node::Internal(ref children) => children
+                .nodes()
+                .iter()
+                .any(|node| node.has_oversized_leaf(max_bytes)),
+        }
+    }
+
     /// How many nodes deep the tree is.
     ///
     /// This counts root and leafs.  For example, a single leaf node
@@ -588,6 +876,134 @@ impl Node {
         }
     }
 
+    /// Converts the sorted indices `idxs` from one metric to another in a
+    /// single traversal of the tree, appending the results to `out`.
+    ///
+    /// `metric_in` and `metric_out` pick the metrics out of a `TextInfo`,
+    /// `convert` converts an index within a leaf, and `base` is the offset
+    /// of this node in both metrics.  Each index is converted in the same
+    /// leaf that the single-index conversion methods would use.
+    pub fn convert_sorted<I, O, C>(
+        &self,
+        idxs: &[usize],
+        base: (usize, usize),
+        metric_in: &I,
+        metric_out: &O,
+        convert: &C,
+        out: &mut Vec<usize>,
+    ) where
+        I: Fn(&TextInfo) -> usize,
+        O: Fn(&TextInfo) -> usize,
+        C: Fn(&str, usize) -> usize,
+    {
+        let (base_in, base_out) = base;
+        match *self {
+            Node::Leaf(ref text) => {
+                for &idx in idxs {
+                    out.push(base_out + convert(text, idx - base_in));
+                }
+            }
+            Node::Internal(ref children) => {
+                let mut rest = idxs;
+                let mut acc = TextInfo::new();
+                let last = children.len() - 1;
+                for (i, (info, child)) in children.info().iter().zip(children.nodes()).enumerate() {
+                    if rest.is_empty() {
+                        break;
+                    }
+                    let end = base_in + metric_in(&(acc + *info));
+                    let n = if i == last {
+                        rest.len()
+                    } else {
+                        rest.iter().take_while(|&&idx| idx <= end).count()
+                    };
+                    if n > 0 {
+                        child.convert_sorted(
+                            &rest[..n],
+                            (base_in + metric_in(&acc), base_out + metric_out(&acc)),
+                            metric_in,
+                            metric_out,
+                            convert,
+                            out,
+                        );
+                        rest = &rest[n..];
+                    }
+                    acc += *info;
+                }
+            }
+        }
+    }
+
+    /// Appends the non-empty leaves of `node` to `out` in order, along
+    /// with their text info, merging each leaf into the previous one where
+    /// they fit within `max_bytes` together.
+    ///
+    /// `info` is the text info of `node`.  Leaves that aren't merged are
+    /// shared with `node` rather than copied.
+    pub fn collect_leaves(
+        node: &Arc<Node>,
+        info: TextInfo,
+        max_bytes: usize,
+        out: &mut Vec<(TextInfo, Arc<Node>)>,
+    ) {
+        match **node {
+            Node::Leaf(ref text) => {
+                if text.is_empty() {
+                    return;
+                }
+                if let Some(&mut (ref mut last_info, ref mut last)) = out.last_mut() {
+                    if last.leaf_text().len() + text.len() <= max_bytes {
+                        let last_text = stats::make_mut(last).leaf_text_mut();
+                        last_text.push_str(text);
+                        *last_info = TextInfo::from_str(last_text);
+                        stats::count(Event::Merge);
+                        return;
+                    }
+                }
+                out.push((info, Arc::clone(node)));
+            }
+            Node::Internal(ref children) => {
+                for (&info, child) in children.iter() {
+                    Node::collect_leaves(child, info, max_bytes, out);
+                }
+            }
+        }
+    }
+
+    /// Returns the leaf that the index conversion methods would descend
+    /// to, along with the text info of everything before it and of the
+    /// leaf itself.
+    ///
+    /// As with `NodeChildren::search_combine_info()`, `pred` is given the
+    /// text info of everything up to and including each child, and the
+    /// first child it's true for is descended into.
+    pub fn find_leaf<F>(node: &Arc<Node>, pred: F) -> (&Arc<Node>, TextInfo, TextInfo)
+    where
+        F: Fn(&TextInfo) -> bool,
+    {
+        let mut node = node;
+        let mut before = TextInfo::new();
+        let mut info = None;
+        while let Node::Internal(ref children) = **node {
+            let (child_i, acc_info) = children.search_combine_info(|inf| pred(&(before + *inf)));
+            before += acc_info;
+            info = Some(children.info()[child_i]);
+            node = &children.nodes()[child_i];
+        }
+        (node, before, info.unwrap_or_else(|| node.text_info()))
+    }
+
+    /// Returns whether `byte_idx` is on a char boundary.
+    pub fn is_char_boundary(&self, byte_idx: usize) -> bool {
+        match *self {
+            Node::Leaf(ref text) => text.is_char_boundary(byte_idx),
+            Node::Internal(ref children) => {
+                let (child_i, acc_info) = children.search_byte_idx(byte_idx);
+                children.nodes()[child_i].is_char_boundary(byte_idx - acc_info.bytes as usize)
+            }
+        }
+    }
+
     /// Debugging tool to make sure that all of the meta-data of the
     /// tree is consistent with the actual data.
     pub fn assert_integrity(&self) {
@@ -642,6 +1058,210 @@ impl Node {
         }
     }
 
+    /// Writes an indented, human readable dump of the tree, with the byte,
+    /// char, and line ranges and fill percentage of each node.
+    ///
+    /// `acc_info` is the text info of everything before this node, and
+    /// `max_bytes` is the chunk size of the rope, which leaf fill is
+    /// relative to.
+    pub fn fmt_tree(
+        &self,
+        f: &mut fmt::Formatter,
+        indent: usize,
+        acc_info: TextInfo,
+        max_bytes: usize,
+    ) -> fmt::Result {
+        let end_info = acc_info + self.text_info();
+        write!(f, "{:1$}", "", indent * 4)?;
+        match *self {
+            Node::Leaf(ref text) => write!(
+                f,
+                "Leaf ({}/{} bytes, {}%)",
+                text.len(),
+                max_bytes,
+                text.len() * 100 / max_bytes
+            )?,
+            Node::Internal(ref children) => write!(
+                f,
+                "Internal ({}/{} children, {}%)",
+                children.len(),
+                MAX_CHILDREN,
+                children.len() * 100 / MAX_CHILDREN
+            )?,
+        }
+        write!(
+            f,
+            " bytes {}..{}, chars {}..{}, lines {}..{}",
+            acc_info.bytes,
+            end_info.bytes,
+            acc_info.chars,
+            end_info.chars,
+            acc_info.line_breaks,
+            end_info.line_breaks
+        )?;
+
+        match *self {
+            Node::Leaf(ref text) => writeln!(f, ": {:?}", &text[..]),
+            Node::Internal(ref children) => {
+                writeln!(f)?;
+                let mut acc_info = acc_info;
+                for (info, node) in children.iter() {
+                    node.fmt_tree(f, indent + 1, acc_info, max_bytes)?;
+                    acc_info += *info;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Non-panicking counterpart to `assert_integrity()`, `assert_balance()`
+    /// and `assert_node_size()`, which records all violations in `out`.
+    ///
+    /// `path` is the path to this node, `leaf_depth` is the depth of the
+    /// first leaf encountered so far, if any, and `max_bytes` and
+    /// `max_children` are the largest a leaf and an internal node may be.
+    pub fn validate(
+        &self,
+        path: &mut Vec<usize>,
+        leaf_depth: &mut Option<usize>,
+        max_bytes: usize,
+        max_children: usize,
+        out: &mut Vec<InvariantViolation>,
+    ) {
+        match *self {
+            Node::Leaf(ref text) => {
+                // Balance
+                let depth = path.len() + 1;
+                match *leaf_depth {
+                    None => *leaf_depth = Some(depth),
+                    Some(expected) if expected != depth => {
+                        out.push(InvariantViolation::UnbalancedDepth {
+                            path: path.clone(),
+                            expected,
+                            actual: depth,
+                        });
+                    }
+                    Some(_) => {}
+                }
+
+                // Leaf size
+                if !path.is_empty() && text.is_empty() {
+                    out.push(InvariantViolation::EmptyLeaf { path: path.clone() });
+                }
+                if !path.is_empty() && self.is_oversized(max_bytes) {
+                    out.push(InvariantViolation::OversizedLeaf {
+                        path: path.clone(),
+                        max: max_bytes,
+                        actual: text.len(),
+                    });
+                }
+            }
+            Node::Internal(ref children) => {
+                // Child count
+                let min = if path.is_empty() { 2 } else { MIN_CHILDREN };
+                if children.len() < min {
+                    out.push(InvariantViolation::TooFewChildren {
+                        path: path.clone(),
+                        min,
+                        actual: children.len(),
+                    });
+                } else if children.len() > max_children {
+                    out.push(InvariantViolation::TooManyChildren {
+                        path: path.clone(),
+                        max: max_children,
+                        actual: children.len(),
+                    });
+                }
+
+                for (i, (info, node)) in children.iter().enumerate() {
+                    path.push(i);
+
+                    // Meta-data
+                    let actual = node.text_info();
+                    if *info != actual {
+                        out.push(InvariantViolation::InfoMismatch {
+                            path: path.clone(),
+                            recorded: (*info).into(),
+                            actual: actual.into(),
+                        });
+                    }
+
+                    node.validate(path, leaf_depth, max_bytes, max_children, out);
+                    path.pop();
+                }
+            }
+        }
+    }
+
+    /// Returns the number of leading bytes that two trees have in common,
+    /// and whether the trees are entirely equal.
+    ///
+    /// Subtrees shared between the two trees are skipped without looking
+    /// at their contents.  Stops at the first point where the trees are
+    /// structured differently, so the result can be an underestimate.
+    pub fn shared_prefix_bytes(a: &Arc<Node>, b: &Arc<Node>) -> (usize, bool) {
+        if Arc::ptr_eq(a, b) {
+            return (a.byte_count(), true);
+        }
+        match (&**a, &**b) {
+            (Node::Leaf(text_a), Node::Leaf(text_b)) => {
+                let n = text_a
+                    .bytes()
+                    .zip(text_b.bytes())
+                    .take_while(|&(x, y)| x == y)
+                    .count();
+                (n, n == text_a.len() && n == text_b.len())
+            }
+            (Node::Internal(children_a), Node::Internal(children_b)) => {
+                let mut total = 0;
+                for (node_a, node_b) in children_a.nodes().iter().zip(children_b.nodes()) {
+                    let (n, equal) = Node::shared_prefix_bytes(node_a, node_b);
+                    total += n;
+                    if !equal {
+                        return (total, false);
+                    }
+                }
+                (total, children_a.len() == children_b.len())
+            }
+            _ => (0, false),
+        }
+    }
+
+    /// Counterpart to `shared_prefix_bytes()` for trailing bytes.
+    pub fn shared_suffix_bytes(a: &Arc<Node>, b: &Arc<Node>) -> (usize, bool) {
+        if Arc::ptr_eq(a, b) {
+            return (a.byte_count(), true);
+        }
+        match (&**a, &**b) {
+            (Node::Leaf(text_a), Node::Leaf(text_b)) => {
+                let n = text_a
+                    .bytes()
+                    .rev()
+                    .zip(text_b.bytes().rev())
+                    .take_while(|&(x, y)| x == y)
+                    .count();
+                (n, n == text_a.len() && n == text_b.len())
+            }
+            (Node::Internal(children_a), Node::Internal(children_b)) => {
+                let mut total = 0;
+                for (node_a, node_b) in children_a
+                    .nodes()
+                    .iter()
+                    .rev()
+                    .zip(children_b.nodes().iter().rev())
+                {
+                    let (n, equal) = Node::shared_suffix_bytes(node_a, node_b);
+                    total += n;
+                    if !equal {
+                        return (total, false);
+                    }
+                }
+                (total, children_a.len() == children_b.len())
+            }
+            _ => (0, false),
+        }
+    }
+
     /// Checks to make sure that a boundary between leaf nodes (given as a byte
     /// position in the rope) doesn't split a grapheme, and fixes it if it does.
     ///
@@ -672,12 +1292,12 @@ impl Node {
             Node::Internal(ref mut children) => {
                 if byte_pos == 0 {
                     // Special-case 1
-                    return Arc::make_mut(&mut children.nodes_mut()[0])
+                    return stats::make_mut(&mut children.nodes_mut()[0])
                         .fix_grapheme_seam(byte_pos, must_be_boundary);
                 } else if byte_pos == children.combined_info().bytes {
                     // Special-case 2
                     let (info, nodes) = children.data_mut();
-                    return Arc::make_mut(nodes.last_mut().unwrap())
+                    return stats::make_mut(nodes.last_mut().unwrap())
                         .fix_grapheme_seam(info.last().unwrap().bytes, must_be_boundary);
                 } else {
                     // Find the child to navigate into
@@ -703,8 +1323,8 @@ impl Node {
                             let (mut l_child, mut r_child) =
                                 children.get_two_mut(l_child_i, l_child_i + 1);
                             let l_child_bytes = l_child.0.bytes;
-                            let l_child = Arc::make_mut(&mut l_child.1);
-                            let r_child = Arc::make_mut(&mut r_child.1);
+                            let l_child = stats::make_mut(&mut l_child.1);
+                            let r_child = stats::make_mut(&mut r_child.1);
                             fix_segment_seam(
                                 l_child
                                     .fix_grapheme_seam(l_child_bytes, must_be_boundary)
@@ -730,7 +1350,7 @@ impl Node {
                         // WARNING: we use raw pointers to work around the borrow
                         // checker here, so be careful when modifying this code!
                         {
-                            let raw_text = Arc::make_mut(&mut children.nodes_mut()[child_i])
+                            let raw_text = stats::make_mut(&mut children.nodes_mut()[child_i])
                                 .fix_grapheme_seam(pos_in_child, must_be_boundary)
                                 .map(|text| text as *mut NodeText);
 
@@ -761,7 +1381,7 @@ impl Node {
         match *self {
             Node::Leaf(_) => {}
             Node::Internal(ref mut children) => {
-                Arc::make_mut(&mut children.nodes_mut()[0]).fix_info_left();
+                stats::make_mut(&mut children.nodes_mut()[0]).fix_info_left();
                 children.update_child_info(0);
                 if children.info()[0].bytes == 0 {
                     children.remove(0);
@@ -777,7 +1397,7 @@ impl Node {
             Node::Leaf(_) => {}
             Node::Internal(ref mut children) => {
                 let idx = children.len() - 1;
-                Arc::make_mut(&mut children.nodes_mut()[idx]).fix_info_right();
+                stats::make_mut(&mut children.nodes_mut()[idx]).fix_info_right();
                 children.update_child_info(idx);
                 if children.info()[idx].bytes == 0 {
                     children.remove(idx);
@@ -790,20 +1410,20 @@ impl Node {
     ///
     /// Returns whether it did anything or not that would affect the
     /// parent.
-    pub fn zip_fix_left(&mut self) -> bool {
+    pub fn zip_fix_left(&mut self, max_bytes: usize) -> bool {
         if let Node::Internal(ref mut children) = *self {
             let mut did_stuff = false;
             loop {
                 let do_merge = (children.len() > 1) && match *children.nodes()[0] {
-                    Node::Leaf(ref text) => text.len() < MIN_BYTES,
+                    Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
                     Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
                 };
 
                 if do_merge {
-                    did_stuff |= children.merge_distribute(0, 1);
+                    did_stuff |= children.merge_distribute(0, 1, max_bytes);
                 }
 
-                if !Arc::make_mut(&mut children.nodes_mut()[0]).zip_fix_left() {
+                if !stats::make_mut(&mut children.nodes_mut()[0]).zip_fix_left(max_bytes) {
                     break;
                 }
             }
@@ -817,21 +1437,23 @@ impl Node {
     ///
     /// Returns whether it did anything or not that would affect the
     /// parent. True: did stuff, false: didn't do stuff
-    pub fn zip_fix_right(&mut self) -> bool {
+    pub fn zip_fix_right(&mut self, max_bytes: usize) -> bool {
         if let Node::Internal(ref mut children) = *self {
             let mut did_stuff = false;
             loop {
                 let last_i = children.len() - 1;
                 let do_merge = (children.len() > 1) && match *children.nodes()[last_i] {
-                    Node::Leaf(ref text) => text.len() < MIN_BYTES,
+                    Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
                     Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
                 };
 
                 if do_merge {
-                    did_stuff |= children.merge_distribute(last_i - 1, last_i);
+                    did_stuff |= children.merge_distribute(last_i - 1, last_i, max_bytes);
                 }
 
-                if !Arc::make_mut(&mut children.nodes_mut().last_mut().unwrap()).zip_fix_right() {
+                if !stats::make_mut(&mut children.nodes_mut().last_mut().unwrap())
+                    .zip_fix_right(max_bytes)
+                {
                     break;
                 }
             }
@@ -845,7 +1467,7 @@ impl Node {
     ///
     /// Returns whether it did anything or not that would affect the
     /// parent. True: did stuff, false: didn't do stuff
-    pub fn zip_fix(&mut self, char_idx: usize) -> bool {
+    pub fn zip_fix(&mut self, char_idx: usize, max_bytes: usize) -> bool {
         if let Node::Internal(ref mut children) = *self {
             let mut did_stuff = false;
             loop {
@@ -857,28 +1479,29 @@ impl Node {
 
                     if end_info.chars as usize == char_idx && (child_i + 1) < children.len() {
                         let do_merge = match *children.nodes()[child_i] {
-                            Node::Leaf(ref text) => text.len() < MIN_BYTES,
+                            Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
                             Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
                         }
                             || match *children.nodes()[child_i + 1] {
-                                Node::Leaf(ref text) => text.len() < MIN_BYTES,
+                                Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
                                 Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
                             };
 
                         if do_merge {
-                            did_stuff |= children.merge_distribute(child_i, child_i + 1);
+                            did_stuff |= children.merge_distribute(child_i, child_i + 1, max_bytes);
                         }
                     } else {
                         let do_merge = match *children.nodes()[child_i] {
-                            Node::Leaf(ref text) => text.len() < MIN_BYTES,
+                            Node::Leaf(ref text) => text.len() < min_bytes(max_bytes),
                             Node::Internal(ref children2) => children2.len() < MIN_CHILDREN,
                         };
 
                         if do_merge {
                             if child_i == 0 {
-                                did_stuff |= children.merge_distribute(0, 1);
+                                did_stuff |= children.merge_distribute(0, 1, max_bytes);
                             } else {
-                                did_stuff |= children.merge_distribute(child_i - 1, child_i);
+                                did_stuff |=
+                                    children.merge_distribute(child_i - 1, child_i, max_bytes);
                             }
                         }
                     }
@@ -890,13 +1513,16 @@ impl Node {
 
                 if end_info.chars as usize == char_idx && (child_i + 1) < children.len() {
                     let tmp = children.info()[child_i].chars as usize;
-                    let effect_1 = Arc::make_mut(&mut children.nodes_mut()[child_i]).zip_fix(tmp);
-                    let effect_2 = Arc::make_mut(&mut children.nodes_mut()[child_i + 1]).zip_fix(0);
+                    let effect_1 =
+                        stats::make_mut(&mut children.nodes_mut()[child_i]).zip_fix(tmp, max_bytes);
+                    let effect_2 =
+                        stats::make_mut(&mut children.nodes_mut()[child_i + 1])
+                            .zip_fix(0, max_bytes);
                     if (!effect_1) && (!effect_2) {
                         break;
                     }
-                } else if !Arc::make_mut(&mut children.nodes_mut()[child_i])
-                    .zip_fix(char_idx - start_info.chars as usize)
+                } else if !stats::make_mut(&mut children.nodes_mut()[child_i])
+                    .zip_fix(char_idx - start_info.chars as usize, max_bytes)
                 {
                     break;
                 }
@@ -909,10 +1535,24 @@ impl Node {
     }
 }
 
+/// Panics for `edit_range()` if an end of the range isn't on a char
+/// boundary.  `byte_idx` is relative to the node that `acc_info` is the
+/// text info of everything before.
+fn assert_char_boundary(is_boundary: bool, acc_info: &TextInfo, byte_idx: usize) {
+    assert!(
+        is_boundary,
+        "Attempt to edit Rope at a non-char boundary: byte index {}",
+        acc_info.bytes as usize + byte_idx
+    );
+}
+
 //===========================================================================
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "stats")]
+    use stats::EditStats;
+    use tree::DEFAULT_MAX_SKEW;
     use Rope;
 
     // 133 chars, 209 bytes
@@ -943,6 +1583,35 @@ mod tests {
         assert_eq!(133, r.line_to_char(3));
     }
 
+    #[test]
+    fn validate_01() {
+        use std::sync::Arc;
+        use super::Node;
+        use tree::{NodeChildren, NodeText, MAX_BYTES, MAX_CHILDREN};
+        use validate::InvariantViolation;
+
+        let mut children = NodeChildren::new();
+        for _ in 0..3 {
+            let leaf = Node::Leaf(NodeText::from_str("abc"));
+            children.push((leaf.text_info(), Arc::new(leaf)));
+        }
+        let node = Node::Internal(children);
+
+        let mut out = Vec::new();
+        node.validate(&mut Vec::new(), &mut None, MAX_BYTES, MAX_CHILDREN, &mut out);
+        assert_eq!(Vec::<InvariantViolation>::new(), out);
+
+        node.validate(&mut Vec::new(), &mut None, MAX_BYTES, 2, &mut out);
+        assert_eq!(
+            vec![InvariantViolation::TooManyChildren {
+                path: vec![],
+                max: 2,
+                actual: 3,
+            }],
+            out
+        );
+    }
+
     #[test]
     fn crlf_corner_case_01() {
         use std::iter;
@@ -963,6 +1632,11 @@ mod tests {
         let root = Node::Internal(children);
         let mut rope = Rope {
             root: Arc::new(root),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
         };
         assert_eq!(rope.char(0), '\n');
         assert_eq!(rope.len_chars(), MAX_BYTES * 2 - 1);
@@ -991,6 +1665,11 @@ mod tests {
         let root = Node::Internal(children);
         let mut rope = Rope {
             root: Arc::new(root),
+            chunk_size: MAX_BYTES,
+            max_skew: Some(DEFAULT_MAX_SKEW),
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
         };
         assert_eq!(rope.char(0), '\r');
         assert_eq!(rope.len_chars(), MAX_BYTES * 2 - 1);
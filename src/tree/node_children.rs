@@ -4,9 +4,10 @@ use std::slice;
 use std::sync::Arc;
 
 use crlf;
+use stats::{self, Event};
 use tree;
-use tree::{Node, MAX_BYTES};
-use tree::TextInfo;
+use tree::Node;
+use tree::{Metric, TextInfo};
 
 const MAX_LEN: usize = tree::MAX_CHILDREN;
 
@@ -92,17 +93,17 @@ impl NodeChildren {
     ///
     /// - True: merge was successful.
     /// - False: merge failed, equidistributed instead.
-    pub fn merge_distribute(&mut self, idx1: usize, idx2: usize) -> bool {
+    pub fn merge_distribute(&mut self, idx1: usize, idx2: usize, max_bytes: usize) -> bool {
         assert!(idx1 < idx2);
         assert!(idx2 < self.len());
         let remove_right = {
             let ((_, node1), (_, node2)) = self.get_two_mut(idx1, idx2);
-            let node1 = Arc::make_mut(node1);
-            let node2 = Arc::make_mut(node2);
+            let node1 = stats::make_mut(node1);
+            let node2 = stats::make_mut(node2);
             match *node1 {
                 Node::Leaf(ref mut text1) => {
                     if let Node::Leaf(ref mut text2) = *node2 {
-                        if (text1.len() + text2.len()) <= tree::MAX_BYTES {
+                        if (text1.len() + text2.len()) <= max_bytes {
                             text1.push_str(text2);
                             true
                         } else {
@@ -136,10 +137,12 @@ impl NodeChildren {
         if remove_right {
             self.remove(idx2);
             self.update_child_info(idx1);
+            stats::count(Event::Merge);
             return true;
         } else {
             self.update_child_info(idx1);
             self.update_child_info(idx2);
+            stats::count(Event::Rebalance);
             return false;
         }
     }
@@ -158,7 +161,7 @@ impl NodeChildren {
 
     /// If the children are leaf nodes, compacts them to take up the fewest
     /// nodes.
-    pub fn compact_leaves(&mut self) {
+    pub fn compact_leaves(&mut self, max_bytes: usize) {
         if !self.nodes()[0].is_leaf() || self.len() < 2 {
             return;
         }
@@ -166,26 +169,28 @@ impl NodeChildren {
         let mut i = 1;
         while i < self.len() {
             if (self.nodes()[i - 1].leaf_text().len() + self.nodes()[i].leaf_text().len())
-                <= MAX_BYTES
+                <= max_bytes
             {
                 // Scope to contain borrows
                 {
                     let ((_, node_l), (_, node_r)) = self.get_two_mut(i - 1, i);
-                    let text_l = Arc::make_mut(node_l).leaf_text_mut();
+                    let text_l = stats::make_mut(node_l).leaf_text_mut();
                     let text_r = node_r.leaf_text();
                     text_l.push_str(text_r);
                 }
                 self.remove(i);
-            } else if self.nodes()[i - 1].leaf_text().len() < MAX_BYTES {
+                stats::count(Event::Merge);
+            } else if self.nodes()[i - 1].leaf_text().len() < max_bytes {
                 // Scope to contain borrows
                 {
                     let ((_, node_l), (_, node_r)) = self.get_two_mut(i - 1, i);
-                    let text_l = Arc::make_mut(node_l).leaf_text_mut();
-                    let text_r = Arc::make_mut(node_r).leaf_text_mut();
-                    let split_idx_r = crlf::prev_break(MAX_BYTES - text_l.len(), text_r.as_bytes());
+                    let text_l = stats::make_mut(node_l).leaf_text_mut();
+                    let text_r = stats::make_mut(node_r).leaf_text_mut();
+                    let split_idx_r = crlf::prev_break(max_bytes - text_l.len(), text_r.as_bytes());
                     text_l.push_str(&text_r[..split_idx_r]);
                     text_r.truncate_front(split_idx_r);
                 }
+                stats::count(Event::Rebalance);
                 i += 1;
             } else {
                 i += 1;
@@ -304,26 +309,7 @@ impl NodeChildren {
     ///
     /// One-past-the end is valid, and will return the last child.
     pub fn search_byte_idx(&self, byte_idx: usize) -> (usize, TextInfo) {
-        debug_assert!(self.len() > 0);
-
-        let mut accum = TextInfo::new();
-        let mut idx = 0;
-        for info in self.info()[0..(self.len() - 1)].iter() {
-            let next_accum = accum + *info;
-            if byte_idx < next_accum.bytes as usize {
-                break;
-            }
-            accum = next_accum;
-            idx += 1;
-        }
-
-        #[cfg(any(test, debug_assertions))]
-        assert!(
-            byte_idx <= (accum.bytes + self.info()[idx].bytes) as usize,
-            "Index out of bounds."
-        );
-
-        (idx, accum)
+        self.search_idx(Metric::Bytes, byte_idx)
     }
 
     /// Returns the child index and left-side-accumulated text info of the
@@ -331,34 +317,44 @@ impl NodeChildren {
     ///
     /// One-past-the end is valid, and will return the last child.
     pub fn search_char_idx(&self, char_idx: usize) -> (usize, TextInfo) {
+        self.search_idx(Metric::Chars, char_idx)
+    }
+
+    /// Returns the child index and left-side-accumulated text info of the
+    /// child that contains the given index, in `metric` units.
+    ///
+    /// One-past-the end is valid, and will return the last child.
+    pub fn search_idx(&self, metric: Metric, idx: usize) -> (usize, TextInfo) {
         debug_assert!(self.len() > 0);
 
         let mut accum = TextInfo::new();
-        let mut idx = 0;
+        let mut child_i = 0;
         for info in self.info()[0..(self.len() - 1)].iter() {
             let next_accum = accum + *info;
-            if char_idx < next_accum.chars as usize {
+            if idx < metric.of(&next_accum) {
                 break;
             }
             accum = next_accum;
-            idx += 1;
+            child_i += 1;
         }
 
         #[cfg(any(test, debug_assertions))]
         assert!(
-            char_idx <= (accum.chars + self.info()[idx].chars) as usize,
+            idx <= metric.of(&(accum + self.info()[child_i])),
             "Index out of bounds."
         );
 
-        (idx, accum)
+        (child_i, accum)
     }
 
-    /// Returns the child indices at the start and end of the given char
-    /// range, and returns their left-side-accumulated text info as well.
+    /// Returns the child indices at the start and end of the given range,
+    /// in `metric` units, and returns their left-side-accumulated text
+    /// info as well.
     ///
     /// One-past-the end is valid, and corresponds to the last child.
-    pub fn search_char_idx_range(
+    pub fn search_idx_range(
         &self,
+        metric: Metric,
         start_idx: usize,
         end_idx: usize,
     ) -> ((usize, TextInfo), (usize, TextInfo)) {
@@ -371,7 +367,7 @@ impl NodeChildren {
         // Find left child and info
         for info in self.info()[..(self.len() - 1)].iter() {
             let next_accum = accum + *info;
-            if start_idx < next_accum.chars as usize {
+            if start_idx < metric.of(&next_accum) {
                 break;
             }
             accum = next_accum;
@@ -383,7 +379,7 @@ impl NodeChildren {
         // Find right child and info
         for info in self.info()[idx..(self.len() - 1)].iter() {
             let next_accum = accum + *info;
-            if end_idx <= next_accum.chars as usize {
+            if end_idx <= metric.of(&next_accum) {
                 break;
             }
             accum = next_accum;
@@ -392,7 +388,7 @@ impl NodeChildren {
 
         #[cfg(any(test, debug_assertions))]
         assert!(
-            end_idx <= (accum.chars + self.info()[idx].chars) as usize,
+            end_idx <= metric.of(&(accum + self.info()[idx])),
             "Index out of bounds."
         );
 
@@ -704,7 +700,7 @@ mod tests {
     }
 
     #[test]
-    fn search_char_idx_range_01() {
+    fn search_idx_range_01() {
         let mut children = NodeChildren::new();
         children.push((
             TextInfo::new(),
@@ -723,10 +719,10 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        let at_0_0 = children.search_char_idx_range(0, 0);
-        let at_6_6 = children.search_char_idx_range(6, 6);
-        let at_12_12 = children.search_char_idx_range(12, 12);
-        let at_18_18 = children.search_char_idx_range(18, 18);
+        let at_0_0 = children.search_idx_range(Metric::Chars, 0, 0);
+        let at_6_6 = children.search_idx_range(Metric::Chars, 6, 6);
+        let at_12_12 = children.search_idx_range(Metric::Chars, 12, 12);
+        let at_18_18 = children.search_idx_range(Metric::Chars, 18, 18);
 
         assert_eq!(0, (at_0_0.0).0);
         assert_eq!(0, (at_0_0.1).0);
@@ -748,9 +744,9 @@ mod tests {
         assert_eq!(12, (at_18_18.0).1.chars);
         assert_eq!(12, (at_18_18.1).1.chars);
 
-        let at_0_6 = children.search_char_idx_range(0, 6);
-        let at_6_12 = children.search_char_idx_range(6, 12);
-        let at_12_18 = children.search_char_idx_range(12, 18);
+        let at_0_6 = children.search_idx_range(Metric::Chars, 0, 6);
+        let at_6_12 = children.search_idx_range(Metric::Chars, 6, 12);
+        let at_12_18 = children.search_idx_range(Metric::Chars, 12, 18);
 
         assert_eq!(0, (at_0_6.0).0);
         assert_eq!(0, (at_0_6.1).0);
@@ -767,8 +763,8 @@ mod tests {
         assert_eq!(12, (at_12_18.0).1.chars);
         assert_eq!(12, (at_12_18.1).1.chars);
 
-        let at_5_7 = children.search_char_idx_range(5, 7);
-        let at_11_13 = children.search_char_idx_range(11, 13);
+        let at_5_7 = children.search_idx_range(Metric::Chars, 5, 7);
+        let at_11_13 = children.search_idx_range(Metric::Chars, 11, 13);
 
         assert_eq!(0, (at_5_7.0).0);
         assert_eq!(1, (at_5_7.1).0);
@@ -783,7 +779,7 @@ mod tests {
 
     #[test]
     #[should_panic]
-    fn search_char_idx_range_02() {
+    fn search_idx_range_02() {
         let mut children = NodeChildren::new();
         children.push((
             TextInfo::new(),
@@ -802,6 +798,6 @@ mod tests {
         children.update_child_info(1);
         children.update_child_info(2);
 
-        children.search_char_idx_range(18, 19);
+        children.search_idx_range(Metric::Chars, 18, 19);
     }
 }
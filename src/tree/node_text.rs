@@ -6,29 +6,29 @@ use std::ptr;
 use std::str;
 
 use crlf;
-use smallvec::{Array, SmallVec};
 use str_utils::{char_idx_to_byte_idx, count_chars};
-use tree::MAX_BYTES;
+use tree::leaf_storage::{InlineStorage, LeafStorage};
 
 // TODO: handle corner-case when fixing CRLF seams, and then uncomment
 // all of the `MAX_BYTES` asserts to make sure that no allocations are
-// happening.  Finally, remove SmallVec in favor of a simple byte array
-// + len.
+// happening.  Finally, remove SmallVec from `InlineStorage` in favor of
+// a simple byte array + len.
 
-/// A custom small string, with an internal buffer of `tree::MAX_BYTES`
-/// length.  Has a bunch of methods on it that are useful for the rope
-/// tree.
+/// A custom small string, stored in a `LeafStorage` buffer.  With the
+/// default `InlineStorage`, that's an internal buffer of
+/// `tree::INLINE_BYTES` length, which spills to the heap when needed.
+/// Has a bunch of methods on it that are useful for the rope tree.
 #[derive(Clone, Default)]
-pub(crate) struct NodeText {
-    buffer: SmallVec<BackingArray>,
+pub(crate) struct NodeText<S: LeafStorage = InlineStorage> {
+    buffer: S,
 }
 
-impl NodeText {
+impl<S: LeafStorage> NodeText<S> {
     /// Creates a new empty `NodeText`
     #[inline(always)]
     pub fn new() -> Self {
         NodeText {
-            buffer: SmallVec::new(),
+            buffer: S::default(),
         }
     }
 
@@ -37,13 +37,16 @@ impl NodeText {
     #[inline(always)]
     pub fn with_capacity(capacity: usize) -> Self {
         NodeText {
-            buffer: SmallVec::with_capacity(capacity),
+            buffer: S::with_capacity(capacity),
         }
     }
 
     /// Creates a new `NodeText` with the same contents as the given `&str`.
+    ///
+    /// Ropes with a chunk size larger than `MAX_BYTES` (or long graphemes)
+    /// will produce text that spills out of the inline buffer, which is
+    /// fine.
     pub fn from_str(string: &str) -> Self {
-        debug_assert!(string.len() <= MAX_BYTES);
         let mut nodetext = NodeText::with_capacity(string.len());
         unsafe { nodetext.insert_bytes(0, string.as_bytes()) };
         nodetext
@@ -73,9 +76,10 @@ impl NodeText {
     /// Inserts a `&str` and splits the resulting string in half, returning
     /// the right half.
     ///
-    /// Only splits on code point boundaries and will never split CRLF pairs,
-    /// so if the whole string is a single code point or CRLF pair, the split
-    /// will fail and the returned string will be empty.
+    /// Only splits on code point boundaries and will never split CRLF pairs
+    /// (or any grapheme, with the `graphemes` feature), so if the whole
+    /// string is a single code point or CRLF pair, the split will fail and
+    /// the returned string will be empty.
     pub fn insert_str_split(&mut self, idx: usize, string: &str) -> Self {
         debug_assert!(self.is_char_boundary(idx));
         // debug_assert!((self.len() + string.len()) <= (MAX_BYTES * 2 - 4));
@@ -90,6 +94,7 @@ impl NodeText {
         // We first copy the bytes in the area of the proposed split point into
         // a small 8-byte buffer.  We then use that buffer to look for the
         // real split point.
+        #[cfg(not(feature = "graphemes"))]
         let split_idx = {
             let mut buf = [0u8; 8];
             let start = mid_idx - 4.min(mid_idx);
@@ -107,6 +112,17 @@ impl NodeText {
             crlf::nearest_internal_break(mid_idx - start, &buf[..(end - start)]) + start
         };
 
+        // Graphemes can be arbitrarily long and need surrounding context,
+        // so with the `graphemes` feature we look at the full text instead.
+        #[cfg(feature = "graphemes")]
+        let split_idx = {
+            let mut buf = String::with_capacity(tot_len);
+            buf.push_str(&self[..a]);
+            buf.push_str(string);
+            buf.push_str(&self[a..]);
+            crlf::nearest_internal_break(mid_idx, buf.as_bytes())
+        };
+
         // debug_assert!(split_idx <= MAX_BYTES);
 
         let mut right = NodeText::new();
@@ -158,8 +174,8 @@ impl NodeText {
     pub fn truncate(&mut self, idx: usize) {
         assert!(self.is_char_boundary(idx));
         debug_assert!(idx <= self.len());
-        self.buffer.truncate(idx);
-        self.inline_if_possible();
+        unsafe { self.buffer.set_len(idx) };
+        self.buffer.compact();
     }
 
     /// Drops the text before byte index `idx`, shifting the
@@ -187,7 +203,7 @@ impl NodeText {
         unsafe {
             self.remove_bytes(start, end);
         }
-        self.inline_if_possible();
+        self.buffer.compact();
     }
 
     pub fn remove_char_range(&mut self, start: usize, end: usize) {
@@ -198,7 +214,7 @@ impl NodeText {
         let byte_end = char_idx_to_byte_idx(&self[start..], end - start);
 
         unsafe { self.remove_bytes(byte_start, byte_end) }
-        self.inline_if_possible();
+        self.buffer.compact();
     }
 
     /// Splits the `NodeText` at `idx`.
@@ -212,17 +228,17 @@ impl NodeText {
         assert!(self.is_char_boundary(idx));
         assert!(idx <= self.len());
         let len = self.len();
-        let mut other = NodeText::with_capacity(len - idx);
+        let mut other = NodeText::<S>::with_capacity(len - idx);
         unsafe {
             ptr::copy_nonoverlapping(
-                self.buffer.as_ptr().offset(idx as isize),
+                self.buffer.as_bytes().as_ptr().offset(idx as isize),
                 other.buffer.as_mut_ptr().offset(0),
                 len - idx,
             );
             self.buffer.set_len(idx);
             other.buffer.set_len(len - idx);
         }
-        self.inline_if_possible();
+        self.buffer.compact();
         other
     }
 
@@ -235,9 +251,10 @@ impl NodeText {
         self.split_off(byte_idx)
     }
 
-    #[inline(always)]
-    pub fn shrink_to_fit(&mut self) {
-        self.buffer.shrink_to_fit();
+    /// Returns the size of the string's buffer, whether inline or
+    /// heap-allocated.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
     }
 
     /// Returns whether the string has spilled out into a heap-allocated
@@ -247,10 +264,19 @@ impl NodeText {
     }
 
     #[inline(always)]
-    pub unsafe fn as_mut_smallvec(&mut self) -> &mut SmallVec<BackingArray> {
+    pub unsafe fn as_mut_storage(&mut self) -> &mut S {
         &mut self.buffer
     }
 
+    /// The text's bytes, for changing in place.
+    ///
+    /// The caller must keep the bytes valid utf8.
+    #[inline(always)]
+    pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let len = self.len();
+        std::slice::from_raw_parts_mut(self.buffer.as_mut_ptr(), len)
+    }
+
     #[inline(always)]
     unsafe fn insert_bytes(&mut self, idx: usize, bytes: &[u8]) {
         assert!(idx <= self.len());
@@ -260,16 +286,13 @@ impl NodeText {
         let amt = bytes.len();
         self.buffer.reserve(amt);
 
+        let buf = self.buffer.as_mut_ptr();
         ptr::copy(
-            self.buffer.as_ptr().offset(idx as isize),
-            self.buffer.as_mut_ptr().offset((idx + amt) as isize),
+            buf.offset(idx as isize),
+            buf.offset((idx + amt) as isize),
             len - idx,
         );
-        ptr::copy(
-            bytes.as_ptr(),
-            self.buffer.as_mut_ptr().offset(idx as isize),
-            amt,
-        );
+        ptr::copy(bytes.as_ptr(), buf.offset(idx as isize), amt);
         self.buffer.set_len(len + amt);
     }
 
@@ -279,86 +302,78 @@ impl NodeText {
         assert!(end <= self.len());
         let len = self.len();
         let amt = end - start;
+        let buf = self.buffer.as_mut_ptr();
         ptr::copy(
-            self.buffer.as_ptr().offset(end as isize),
-            self.buffer.as_mut_ptr().offset(start as isize),
+            buf.offset(end as isize),
+            buf.offset(start as isize),
             len - end,
         );
         self.buffer.set_len(len - amt);
 
-        self.inline_if_possible();
-    }
-
-    /// Re-inlines the data if it's been heap allocated but can
-    /// fit inline.
-    #[inline(always)]
-    fn inline_if_possible(&mut self) {
-        if self.buffer.spilled() && (self.buffer.len() <= self.buffer.inline_size()) {
-            self.buffer.shrink_to_fit();
-        }
+        self.buffer.compact();
     }
 }
 
-impl std::cmp::PartialEq for NodeText {
+impl<S: LeafStorage> std::cmp::PartialEq for NodeText<S> {
     fn eq(&self, other: &Self) -> bool {
         let (s1, s2): (&str, &str) = (self, other);
         s1 == s2
     }
 }
 
-impl<'a> PartialEq<NodeText> for &'a str {
-    fn eq(&self, other: &NodeText) -> bool {
+impl<'a, S: LeafStorage> PartialEq<NodeText<S>> for &'a str {
+    fn eq(&self, other: &NodeText<S>) -> bool {
         *self == (other as &str)
     }
 }
 
-impl<'a> PartialEq<&'a str> for NodeText {
+impl<'a, S: LeafStorage> PartialEq<&'a str> for NodeText<S> {
     fn eq(&self, other: &&'a str) -> bool {
         (self as &str) == *other
     }
 }
 
-impl std::fmt::Display for NodeText {
+impl<S: LeafStorage> std::fmt::Display for NodeText<S> {
     fn fmt(&self, fm: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         NodeText::deref(self).fmt(fm)
     }
 }
 
-impl std::fmt::Debug for NodeText {
+impl<S: LeafStorage> std::fmt::Debug for NodeText<S> {
     fn fmt(&self, fm: &mut std::fmt::Formatter) -> std::fmt::Result {
         NodeText::deref(self).fmt(fm)
     }
 }
 
-impl<'a> From<&'a str> for NodeText {
+impl<'a, S: LeafStorage> From<&'a str> for NodeText<S> {
     fn from(s: &str) -> Self {
         Self::from_str(s)
     }
 }
 
-impl Deref for NodeText {
+impl<S: LeafStorage> Deref for NodeText<S> {
     type Target = str;
 
     fn deref(&self) -> &str {
         // NodeText's methods don't allow `buffer` to become invalid utf8,
         // so this is safe.
-        unsafe { str::from_utf8_unchecked(self.buffer.as_ref()) }
+        unsafe { str::from_utf8_unchecked(self.buffer.as_bytes()) }
     }
 }
 
-impl AsRef<str> for NodeText {
+impl<S: LeafStorage> AsRef<str> for NodeText<S> {
     fn as_ref(&self) -> &str {
         // NodeText's methods don't allow `buffer` to become invalid utf8,
         // so this is safe.
-        unsafe { str::from_utf8_unchecked(self.buffer.as_ref()) }
+        unsafe { str::from_utf8_unchecked(self.buffer.as_bytes()) }
     }
 }
 
-impl Borrow<str> for NodeText {
+impl<S: LeafStorage> Borrow<str> for NodeText<S> {
     fn borrow(&self) -> &str {
         // NodeText's methods don't allow `buffer` to become invalid utf8,
         // so this is safe.
-        unsafe { str::from_utf8_unchecked(self.buffer.as_ref()) }
+        unsafe { str::from_utf8_unchecked(self.buffer.as_bytes()) }
     }
 }
 
@@ -368,7 +383,7 @@ impl Borrow<str> for NodeText {
 ///
 /// Note: this will leave one of the strings empty if the entire composite string
 /// is one big grapheme.
-pub(crate) fn fix_segment_seam(l: &mut NodeText, r: &mut NodeText) {
+pub(crate) fn fix_segment_seam<S: LeafStorage>(l: &mut NodeText<S>, r: &mut NodeText<S>) {
     // Early out, if there's nothing to do.
     if crlf::seam_is_break(l.as_bytes(), r.as_bytes()) {
         return;
@@ -403,31 +418,13 @@ pub(crate) fn fix_segment_seam(l: &mut NodeText, r: &mut NodeText) {
 
 //=======================================================================
 
-/// The backing internal buffer for `NodeText`.
-#[derive(Copy, Clone)]
-pub(crate) struct BackingArray([u8; MAX_BYTES]);
-unsafe impl Array for BackingArray {
-    type Item = u8;
-    fn size() -> usize {
-        MAX_BYTES
-    }
-    fn ptr(&self) -> *const u8 {
-        &self.0[0]
-    }
-    fn ptr_mut(&mut self) -> *mut u8 {
-        &mut self.0[0]
-    }
-}
-
-//=======================================================================
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn remove_bytes_01() {
-        let mut s = NodeText::new();
+        let mut s: NodeText = NodeText::new();
         s.push_str("Hello!");
         unsafe {
             s.remove_bytes(2, 4);
@@ -1,6 +1,8 @@
 use std::ops::{Add, AddAssign, Sub, SubAssign};
 
-use str_utils::{count_chars, count_line_breaks};
+#[cfg(feature = "graphemes")]
+use str_utils::count_graphemes;
+use str_utils::{count_chars, count_line_breaks, count_utf16_surrogates};
 use tree::Count;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -8,6 +10,11 @@ pub struct TextInfo {
     pub(crate) bytes: Count,
     pub(crate) chars: Count,
     pub(crate) line_breaks: Count,
+    // Chars outside the Basic Multilingual Plane, which are each two
+    // UTF-16 code units (a surrogate pair) long.
+    pub(crate) utf16_surrogates: Count,
+    #[cfg(feature = "graphemes")]
+    pub(crate) graphemes: Count,
 }
 
 impl TextInfo {
@@ -17,15 +24,27 @@ impl TextInfo {
             bytes: 0,
             chars: 0,
             line_breaks: 0,
+            utf16_surrogates: 0,
+            #[cfg(feature = "graphemes")]
+            graphemes: 0,
         }
     }
 
+    /// The length of the text in UTF-16 code units.
+    #[inline]
+    pub fn utf16_cus(&self) -> Count {
+        self.chars + self.utf16_surrogates
+    }
+
     #[inline]
     pub fn from_str(text: &str) -> TextInfo {
         TextInfo {
             bytes: text.len() as Count,
             chars: count_chars(text) as Count,
             line_breaks: count_line_breaks(text) as Count,
+            utf16_surrogates: count_utf16_surrogates(text) as Count,
+            #[cfg(feature = "graphemes")]
+            graphemes: count_graphemes(text) as Count,
         }
     }
 }
@@ -38,6 +57,9 @@ impl Add for TextInfo {
             bytes: self.bytes + rhs.bytes,
             chars: self.chars + rhs.chars,
             line_breaks: self.line_breaks + rhs.line_breaks,
+            utf16_surrogates: self.utf16_surrogates + rhs.utf16_surrogates,
+            #[cfg(feature = "graphemes")]
+            graphemes: self.graphemes + rhs.graphemes,
         }
     }
 }
@@ -57,6 +79,9 @@ impl Sub for TextInfo {
             bytes: self.bytes - rhs.bytes,
             chars: self.chars - rhs.chars,
             line_breaks: self.line_breaks - rhs.line_breaks,
+            utf16_surrogates: self.utf16_surrogates - rhs.utf16_surrogates,
+            #[cfg(feature = "graphemes")]
+            graphemes: self.graphemes - rhs.graphemes,
         }
     }
 }
@@ -0,0 +1,66 @@
+use edit::Edit;
+use rewrite::ChunkRewriter;
+use str_utils::is_line_break;
+
+/// Removes spaces and tabs from the end of every line, recording an `Edit`
+/// for each line that changed.
+pub(crate) struct TrimTrailing {
+    pending: String,
+    pending_chars: usize,
+    out_chars: usize,
+    pub edits: Vec<Edit>,
+}
+
+impl TrimTrailing {
+    pub fn new() -> TrimTrailing {
+        TrimTrailing {
+            pending: String::new(),
+            pending_chars: 0,
+            out_chars: 0,
+            edits: Vec::new(),
+        }
+    }
+
+    /// Drops the held back whitespace, which ends a line.
+    fn drop_pending(&mut self) {
+        if self.pending_chars > 0 {
+            self.edits.push(Edit::remove(
+                self.out_chars..(self.out_chars + self.pending_chars),
+            ));
+            self.pending.clear();
+            self.pending_chars = 0;
+        }
+    }
+
+    /// Writes out the held back whitespace, which turned out not to be
+    /// trailing.
+    fn flush_pending(&mut self, out: &mut String) {
+        out.push_str(&self.pending);
+        self.out_chars += self.pending_chars;
+        self.pending.clear();
+        self.pending_chars = 0;
+    }
+}
+
+impl ChunkRewriter for TrimTrailing {
+    fn rewrite(&mut self, chunk: &str, out: &mut String) {
+        for c in chunk.chars() {
+            if c == ' ' || c == '\t' {
+                self.pending.push(c);
+                self.pending_chars += 1;
+                continue;
+            }
+            if is_line_break(c) {
+                self.drop_pending();
+            } else {
+                self.flush_pending(out);
+            }
+            out.push(c);
+            self.out_chars += 1;
+        }
+    }
+
+    fn finish(&mut self, _out: &mut String) {
+        self.drop_pending();
+    }
+}
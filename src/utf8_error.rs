@@ -0,0 +1,132 @@
+use std;
+use std::fmt;
+
+/// An error for byte data that isn't valid utf8.
+///
+/// Like `std::str::Utf8Error`, but with offsets relative to the start of
+/// the whole input rather than a single buffer, and with a copy of the
+/// offending bytes.  Returned by `Rope::try_from_reader()` and
+/// `Rope::try_from(&[u8])`, and as the inner error of the `InvalidData`
+/// errors from `Rope::from_reader()` and `RopeWriter`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Utf8Error {
+    valid_up_to: usize,
+    error_len: Option<usize>,
+    // The offending bytes.  Invalid and incomplete sequences are never
+    // more than 3 bytes long.
+    bytes: [u8; 4],
+    bytes_len: u8,
+}
+
+impl Utf8Error {
+    /// Creates an error for the sequence `bytes`, found at `valid_up_to`.
+    pub(crate) fn new(valid_up_to: usize, error_len: Option<usize>, bytes: &[u8]) -> Utf8Error {
+        let bytes = &bytes[..bytes.len().min(4)];
+        let mut error = Utf8Error {
+            valid_up_to,
+            error_len,
+            bytes: [0; 4],
+            bytes_len: bytes.len() as u8,
+        };
+        error.bytes[..bytes.len()].copy_from_slice(bytes);
+        error
+    }
+
+    /// Offsets a `std::str::Utf8Error` for `input`, which was found
+    /// `offset` bytes into the whole input.
+    pub(crate) fn from_std(error: std::str::Utf8Error, offset: usize, input: &[u8]) -> Utf8Error {
+        let start = error.valid_up_to();
+        let end = match error.error_len() {
+            Some(len) => start + len,
+            None => input.len(),
+        };
+        Utf8Error::new(offset + start, error.error_len(), &input[start..end])
+    }
+
+    /// Returns the byte offset of the invalid sequence.  All the input up
+    /// to this offset is valid utf8.
+    #[inline]
+    pub fn valid_up_to(&self) -> usize {
+        self.valid_up_to
+    }
+
+    /// Returns the length in bytes of the invalid sequence, or `None` if
+    /// the input ended in the middle of an otherwise valid sequence.
+    #[inline]
+    pub fn error_len(&self) -> Option<usize> {
+        self.error_len
+    }
+
+    /// Returns the offending bytes: the invalid sequence, or the
+    /// incomplete sequence that the input ended with.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ropey::Rope;
+    /// let data: &[u8] = b"Hello\xC3(world!";
+    /// let error = Rope::try_from_reader(data).unwrap().unwrap_err();
+    /// assert_eq!(error.valid_up_to(), 5);
+    /// assert_eq!(error.invalid_bytes(), b"\xC3");
+    /// ```
+    #[inline]
+    pub fn invalid_bytes(&self) -> &[u8] {
+        &self.bytes[..self.bytes_len as usize]
+    }
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.error_len {
+            Some(len) => write!(
+                f,
+                "invalid utf-8 sequence of {} bytes from index {}",
+                len, self.valid_up_to
+            )?,
+            None => write!(
+                f,
+                "incomplete utf-8 byte sequence from index {}",
+                self.valid_up_to
+            )?,
+        }
+        write!(f, ": b\"")?;
+        for byte in self.invalid_bytes() {
+            write!(f, "\\x{:02X}", byte)?;
+        }
+        write!(f, "\"")
+    }
+}
+
+impl std::error::Error for Utf8Error {}
+
+//=============================================================
+
+/// How streaming input handles utf8 that is invalid or cut off.
+///
+/// Reads and writes from pipes, sockets, decompressors, etc. routinely
+/// end in the middle of a multi-byte code point.  Used by
+/// `Rope::from_reader_with_policy()` and `Rope::writer_with_policy()`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Utf8Policy {
+    /// Hold back a code point cut off at the end of a read or write until
+    /// more input completes it.  Invalid sequences, and input that ends
+    /// in the middle of a code point, fail with a `Utf8Error`.
+    Wait,
+
+    /// Like `Wait`, but replace invalid sequences, and input that ends in
+    /// the middle of a code point, with U+FFFD REPLACEMENT CHARACTER, in
+    /// the same way as `String::from_utf8_lossy()`.
+    Replace,
+
+    /// Don't hold anything back: a code point cut off at the end of a
+    /// read or write fails immediately with a `Utf8Error`, as do invalid
+    /// sequences.
+    Error,
+}
+
+impl std::default::Default for Utf8Policy {
+    #[inline]
+    fn default() -> Self {
+        Utf8Policy::Wait
+    }
+}
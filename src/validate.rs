@@ -0,0 +1,159 @@
+use std;
+use std::fmt;
+
+use tree::TextInfo;
+
+/// Text metrics of a node, as recorded in or computed from the tree.
+///
+/// Used by [`InvariantViolation`](enum.InvariantViolation.html) to report
+/// meta-data mismatches.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextStats {
+    pub bytes: usize,
+    pub chars: usize,
+    pub line_breaks: usize,
+    pub utf16_surrogates: usize,
+    #[cfg(feature = "graphemes")]
+    pub graphemes: usize,
+}
+
+impl From<TextInfo> for TextStats {
+    fn from(info: TextInfo) -> TextStats {
+        TextStats {
+            bytes: info.bytes as usize,
+            chars: info.chars as usize,
+            line_breaks: info.line_breaks as usize,
+            utf16_surrogates: info.utf16_surrogates as usize,
+            #[cfg(feature = "graphemes")]
+            graphemes: info.graphemes as usize,
+        }
+    }
+}
+
+/// A single broken invariant of a `Rope`'s internal tree.
+///
+/// Nodes are identified by their path from the root: the child index taken
+/// at each level.  An empty path is the root itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The text info recorded for a node by its parent doesn't match the
+    /// node's actual contents.
+    InfoMismatch {
+        path: Vec<usize>,
+        recorded: TextStats,
+        actual: TextStats,
+    },
+
+    /// A leaf is at a different depth than the first leaf of the tree.
+    UnbalancedDepth {
+        path: Vec<usize>,
+        expected: usize,
+        actual: usize,
+    },
+
+    /// A non-root internal node has fewer than the minimum number of
+    /// children, or the root has only one child.
+    TooFewChildren {
+        path: Vec<usize>,
+        min: usize,
+        actual: usize,
+    },
+
+    /// An internal node has more than the maximum number of children.
+    TooManyChildren {
+        path: Vec<usize>,
+        max: usize,
+        actual: usize,
+    },
+
+    /// A non-root leaf is longer than the `Rope`'s chunk size, not counting
+    /// the graphemes (or CRLF pairs) at its ends, which may not fit.
+    OversizedLeaf {
+        path: Vec<usize>,
+        max: usize,
+        actual: usize,
+    },
+
+    /// A non-root leaf is empty.
+    EmptyLeaf { path: Vec<usize> },
+
+    /// The seam between two chunks, at the given byte index, splits a CRLF
+    /// pair (or a grapheme, with the `graphemes` feature).
+    SplitSeam { byte_idx: usize },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvariantViolation::InfoMismatch {
+                ref path,
+                ref recorded,
+                ref actual,
+            } => write!(
+                f,
+                "node {:?}: recorded text info {:?} doesn't match actual {:?}",
+                path, recorded, actual
+            ),
+            InvariantViolation::UnbalancedDepth {
+                ref path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "leaf {:?}: at depth {}, expected depth {}",
+                path, actual, expected
+            ),
+            InvariantViolation::TooFewChildren {
+                ref path,
+                min,
+                actual,
+            } => write!(
+                f,
+                "node {:?}: has {} children, minimum is {}",
+                path, actual, min
+            ),
+            InvariantViolation::TooManyChildren {
+                ref path,
+                max,
+                actual,
+            } => write!(
+                f,
+                "node {:?}: has {} children, maximum is {}",
+                path, actual, max
+            ),
+            InvariantViolation::OversizedLeaf {
+                ref path,
+                max,
+                actual,
+            } => write!(
+                f,
+                "leaf {:?}: has {} bytes, chunk size is {}",
+                path, actual, max
+            ),
+            InvariantViolation::EmptyLeaf { ref path } => write!(f, "leaf {:?}: is empty", path),
+            InvariantViolation::SplitSeam { byte_idx } => {
+                write!(f, "chunk seam at byte {}: splits a grapheme", byte_idx)
+            }
+        }
+    }
+}
+
+/// A report of all the broken invariants found in a `Rope`.
+///
+/// Returned by [`Rope::validate()`](struct.Rope.html#method.validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvariantReport {
+    pub violations: Vec<InvariantViolation>,
+}
+
+impl fmt::Display for InvariantReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} rope invariant violation(s)", self.violations.len())?;
+        for violation in &self.violations {
+            write!(f, "\n- {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvariantReport {}
@@ -0,0 +1,114 @@
+use std;
+use std::sync::{Arc, Weak};
+
+use rope::Rope;
+#[cfg(feature = "stats")]
+use stats::EditStats;
+use tree::Node;
+
+/// A weak reference to a snapshot of a `Rope`.
+///
+/// A `WeakRope` doesn't keep the text it refers to alive.  It can be
+/// upgraded back into a `Rope` for as long as some `Rope` still holds
+/// that exact snapshot of the text, i.e. the `Rope` it was created from
+/// or a clone of it, unedited since.  This lets caches refer to old
+/// versions of a document without holding on to their memory.
+///
+/// Created by [`Rope::downgrade()`](struct.Rope.html#method.downgrade).
+///
+/// # Example
+///
+/// ```
+/// # use ropey::Rope;
+/// let mut rope = Rope::from_str("Hello world!");
+/// let weak = rope.downgrade();
+/// assert_eq!(weak.upgrade().unwrap(), "Hello world!");
+///
+/// rope.insert(0, "Oh, ");
+/// assert!(weak.upgrade().is_none());
+/// ```
+#[derive(Clone)]
+pub struct WeakRope {
+    root: Weak<Node>,
+    chunk_size: usize,
+    max_skew: Option<usize>,
+}
+
+impl WeakRope {
+    pub(crate) fn new(rope: &Rope) -> WeakRope {
+        WeakRope {
+            root: Arc::downgrade(&rope.root),
+            chunk_size: rope.chunk_size,
+            max_skew: rope.max_skew,
+        }
+    }
+
+    /// Returns the snapshot as a `Rope`, or `None` if it no longer exists.
+    ///
+    /// Runs in O(1) time.
+    pub fn upgrade(&self) -> Option<Rope> {
+        self.root.upgrade().map(|root| Rope {
+            root,
+            chunk_size: self.chunk_size,
+            max_skew: self.max_skew,
+            dirty: None,
+            #[cfg(feature = "stats")]
+            stats: EditStats::default(),
+        })
+    }
+
+    /// Returns whether the snapshot still exists, i.e. whether `upgrade()`
+    /// would succeed.
+    pub fn is_alive(&self) -> bool {
+        self.root.strong_count() > 0
+    }
+}
+
+impl std::fmt::Debug for WeakRope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_alive() {
+            f.write_str("WeakRope(..)")
+        } else {
+            f.write_str("WeakRope(<dropped>)")
+        }
+    }
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hello there!  How're you doing?  It's a fine day, isn't it?";
+
+    #[test]
+    fn weak_rope_01() {
+        let r = Rope::from_str(TEXT);
+        let weak = r.downgrade();
+        let mut r2 = r.clone();
+        drop(r);
+
+        // Still alive through the clone.
+        assert!(weak.is_alive());
+        assert_eq!(weak.upgrade().unwrap(), TEXT);
+        assert_eq!(weak.upgrade().unwrap().chunk_size(), r2.chunk_size());
+
+        // Editing the last holder of the snapshot drops it.
+        r2.remove(0..6);
+        assert!(!weak.is_alive());
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn weak_rope_02() {
+        let r = Rope::from_str(TEXT);
+        let weak = r.downgrade();
+        let upgraded = weak.upgrade().unwrap();
+        drop(r);
+
+        assert_eq!(upgraded, TEXT);
+        drop(upgraded);
+        assert!(weak.upgrade().is_none());
+    }
+}
@@ -0,0 +1,344 @@
+use std;
+use std::io;
+
+use decoder::Replacement;
+use rope::Rope;
+use utf8_error::{Utf8Error, Utf8Policy};
+
+/// An `io::Write` adapter that appends to the end of a `Rope`.
+///
+/// Writes don't need to line up with code point boundaries: a code point
+/// cut off at the end of one write is held back until a later write
+/// completes it.  Writing invalid utf8 fails with an error of kind
+/// `InvalidData`, whose inner error is a
+/// [`Utf8Error`](struct.Utf8Error.html) giving the offset of the invalid
+/// sequence among all the bytes written.  Other ways of handling split
+/// and invalid utf8 can be picked with a
+/// [`Utf8Policy`](enum.Utf8Policy.html).
+///
+/// Created by [`Rope::writer()`](struct.Rope.html#method.writer) and
+/// [`Rope::writer_with_policy()`](struct.Rope.html#method.writer_with_policy).
+///
+/// # Example
+///
+/// ```
+/// # use std::io::Write;
+/// # use ropey::Rope;
+/// let mut rope = Rope::from_str("Numbers: ");
+/// {
+///     let mut writer = rope.writer();
+///     for i in 1..4 {
+///         write!(writer, "{} ", i).unwrap();
+///     }
+///     writer.finish().unwrap();
+/// }
+///
+/// assert_eq!(rope, "Numbers: 1 2 3 ");
+/// ```
+pub struct RopeWriter<'a> {
+    rope: &'a mut Rope,
+    pending: [u8; 4],
+    pending_len: usize,
+    bytes_written: usize,
+    policy: Utf8Policy,
+    replacements: Vec<Replacement>,
+}
+
+impl<'a> RopeWriter<'a> {
+    pub(crate) fn new(rope: &'a mut Rope, policy: Utf8Policy) -> RopeWriter<'a> {
+        RopeWriter {
+            rope,
+            pending: [0; 4],
+            pending_len: 0,
+            bytes_written: 0,
+            policy,
+            replacements: Vec::new(),
+        }
+    }
+
+    /// Returns the replacements made so far with `Utf8Policy::Replace`.
+    ///
+    /// The replacements' byte offsets are relative to the first byte
+    /// written, and their char indices are in the whole `Rope`.
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    /// Finishes writing, and returns all the replacements made with
+    /// `Utf8Policy::Replace`.
+    ///
+    /// Dropping the writer also finishes it, but this reports whether the
+    /// written bytes ended in the middle of a code point, which is then
+    /// discarded.  With `Utf8Policy::Replace` the cut off code point is
+    /// instead replaced with U+FFFD, and this never fails.
+    pub fn finish(mut self) -> io::Result<Vec<Replacement>> {
+        if self.pending_len > 0 {
+            let (start, len) = (self.bytes_written - self.pending_len, self.pending_len);
+            if self.policy == Utf8Policy::Replace {
+                self.pending_len = 0;
+                self.replace(start, len);
+            } else {
+                let bytes = &self.pending[..len];
+                return Err(invalid_data(Utf8Error::new(start, None, bytes)));
+            }
+        }
+        Ok(self.replacements)
+    }
+
+    fn append(&mut self, text: &str) {
+        if !text.is_empty() {
+            let len = self.rope.len_chars();
+            self.rope.insert(len, text);
+        }
+    }
+
+    /// Appends `bytes`, which start at byte `offset` of the input, for
+    /// `Utf8Policy::Replace`, replacing invalid sequences and holding back
+    /// a code point cut off at the end.
+    fn append_lossy(&mut self, mut bytes: &[u8], mut offset: usize) {
+        loop {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => {
+                    self.append(text);
+                    return;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    self.append(unsafe { std::str::from_utf8_unchecked(&bytes[..valid]) });
+                    match e.error_len() {
+                        Some(n) => {
+                            self.replace(offset + valid, n);
+                            bytes = &bytes[(valid + n)..];
+                            offset += valid + n;
+                        }
+                        None => {
+                            let tail = &bytes[valid..];
+                            self.pending[..tail.len()].copy_from_slice(tail);
+                            self.pending_len = tail.len();
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn replace(&mut self, byte_offset: usize, byte_len: usize) {
+        self.replacements.push(Replacement {
+            byte_offset,
+            byte_len,
+            char_idx: self.rope.len_chars(),
+        });
+        self.append("\u{FFFD}");
+    }
+}
+
+impl<'a> io::Write for RopeWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut consumed = 0;
+
+        // First complete a code point left over from the last write, one
+        // byte at a time.
+        while self.pending_len > 0 && consumed < buf.len() {
+            let mut pending = self.pending;
+            let len = self.pending_len + 1;
+            pending[len - 1] = buf[consumed];
+            match std::str::from_utf8(&pending[..len]) {
+                Ok(text) => {
+                    self.pending_len = 0;
+                    self.append(text);
+                }
+                Err(e) => {
+                    if let Some(n) = e.error_len() {
+                        if self.policy == Utf8Policy::Replace {
+                            // Replace the invalid prefix, and start over
+                            // on the bytes after it.
+                            let start = self.bytes_written - self.pending_len;
+                            self.pending_len = 0;
+                            self.replace(start, n);
+                            self.append_lossy(&pending[n..len], start + n);
+                            consumed += 1;
+                            self.bytes_written += 1;
+                            continue;
+                        }
+                        if consumed > 0 {
+                            return Ok(consumed);
+                        }
+                        let offset = self.bytes_written - self.pending_len;
+                        return Err(invalid_data(Utf8Error::new(offset, Some(n), &pending[..n])));
+                    }
+                    self.pending = pending;
+                    self.pending_len = len;
+                }
+            }
+            consumed += 1;
+            self.bytes_written += 1;
+        }
+
+        let rest = &buf[consumed..];
+        if self.policy == Utf8Policy::Replace {
+            let start = self.bytes_written;
+            self.append_lossy(rest, start);
+            self.bytes_written += rest.len();
+            return Ok(buf.len());
+        }
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                self.append(text);
+                self.bytes_written += rest.len();
+                Ok(buf.len())
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                self.append(unsafe { std::str::from_utf8_unchecked(&rest[..valid]) });
+                self.bytes_written += valid;
+                match e.error_len() {
+                    Some(n) => {
+                        if consumed + valid > 0 {
+                            Ok(consumed + valid)
+                        } else {
+                            let offset = self.bytes_written;
+                            let bytes = &rest[valid..(valid + n)];
+                            Err(invalid_data(Utf8Error::new(offset, Some(n), bytes)))
+                        }
+                    }
+                    None if self.policy == Utf8Policy::Error => {
+                        if consumed + valid > 0 {
+                            Ok(consumed + valid)
+                        } else {
+                            let offset = self.bytes_written;
+                            Err(invalid_data(Utf8Error::new(offset, None, &rest[valid..])))
+                        }
+                    }
+                    None => {
+                        let tail = &rest[valid..];
+                        self.pending[..tail.len()].copy_from_slice(tail);
+                        self.pending_len = tail.len();
+                        self.bytes_written += tail.len();
+                        Ok(buf.len())
+                    }
+                }
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn invalid_data(error: Utf8Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+//===========================================================
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use Rope;
+    use {Utf8Error, Utf8Policy};
+
+    // 124 bytes, 100 chars, 4 lines
+    const TEXT_LINES: &str = "Hello there!  How're you doing?\nIt's \
+                              a fine day, isn't it?\nAren't you glad \
+                              we're alive?\nこんにちは、みんなさん！";
+
+    #[test]
+    fn write_01() {
+        let bytes = TEXT_LINES.as_bytes();
+        for size in 1..8 {
+            let mut r = Rope::new();
+            {
+                let mut writer = r.writer();
+                for piece in bytes.chunks(size) {
+                    writer.write_all(piece).unwrap();
+                }
+                writer.finish().unwrap();
+            }
+            assert_eq!(r, TEXT_LINES);
+            r.assert_integrity();
+            r.assert_invariants();
+        }
+    }
+
+    #[test]
+    fn write_02() {
+        let mut r = Rope::from_str("abc");
+        let mut writer = r.writer();
+
+        assert_eq!(writer.write(b"de\xE3").unwrap(), 3);
+        assert_eq!(writer.write(b"\x81\x93f\xFFg").unwrap(), 3);
+        let e = writer.write(b"\xFFg").unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+        assert_eq!(inner.valid_up_to(), 6);
+        assert_eq!(inner.error_len(), Some(1));
+        assert_eq!(inner.invalid_bytes(), b"\xFF");
+
+        assert_eq!(writer.write(b"\xE3\x81").unwrap(), 2);
+        let e = writer.write(b"g").unwrap_err();
+        let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+        assert_eq!(inner.valid_up_to(), 6);
+        assert_eq!(inner.error_len(), Some(2));
+        assert_eq!(inner.invalid_bytes(), b"\xE3\x81");
+        assert!(writer.finish().is_err());
+
+        assert_eq!(r, "abcdeこf");
+    }
+
+    #[test]
+    fn write_03() {
+        // Split code points are still put back together.
+        let bytes = TEXT_LINES.as_bytes();
+        for size in 1..8 {
+            let mut r = Rope::new();
+            {
+                let mut writer = r.writer_with_policy(Utf8Policy::Replace);
+                for piece in bytes.chunks(size) {
+                    writer.write_all(piece).unwrap();
+                }
+                writer.finish().unwrap();
+            }
+            assert_eq!(r, TEXT_LINES);
+        }
+
+        let mut r = Rope::from_str("abc");
+        {
+            let mut writer = r.writer_with_policy(Utf8Policy::Replace);
+            assert_eq!(writer.write(b"d\xE3").unwrap(), 2);
+            assert_eq!(writer.write(b"\x81e\xFF").unwrap(), 3);
+            assert_eq!(writer.write(b"\xE3\x81\x93\xF0").unwrap(), 4);
+            assert_eq!(writer.replacements().len(), 2);
+
+            let replacements = writer.finish().unwrap();
+            let spans: Vec<_> = replacements
+                .iter()
+                .map(|r| (r.byte_offset, r.byte_len, r.char_idx))
+                .collect();
+            assert_eq!(spans, [(1, 2, 4), (4, 1, 6), (8, 1, 8)]);
+        }
+        assert_eq!(r, "abcd\u{FFFD}e\u{FFFD}こ\u{FFFD}");
+        r.assert_integrity();
+        r.assert_invariants();
+    }
+
+    #[test]
+    fn write_04() {
+        let mut r = Rope::from_str("abc");
+        {
+            let mut writer = r.writer_with_policy(Utf8Policy::Error);
+            assert_eq!(writer.write(b"de\xE3\x81").unwrap(), 2);
+            let e = writer.write(b"\xE3\x81").unwrap_err();
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+            let inner = e.get_ref().unwrap().downcast_ref::<Utf8Error>().unwrap();
+            assert_eq!(inner.valid_up_to(), 2);
+            assert_eq!(inner.error_len(), None);
+            assert_eq!(inner.invalid_bytes(), b"\xE3\x81");
+
+            assert_eq!(writer.write(b"\xE3\x81\x93").unwrap(), 3);
+            writer.finish().unwrap();
+        }
+        assert_eq!(r, "abcdeこ");
+    }
+}